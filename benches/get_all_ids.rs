@@ -0,0 +1,103 @@
+//! The ids of a large list with [Client::get_all_ids] and with [Client::get], against a mock
+//! server so it is the client that is measured.  `cargo bench --bench get_all_ids`
+use cwmanage::Client;
+use serde_json::{json, Value};
+use std::time::Instant;
+
+/// the records in the list
+const RECORDS: usize = 5_000;
+
+/// connectwise's page size when the query doesn't have one
+const DEFAULT_PAGE_SIZE: usize = 25;
+
+/// the value of `key` in the query of a request
+fn query_value(req: &mockito::Request, key: &str) -> Option<String> {
+    let url = url::Url::parse(&format!("http://localhost{}", req.path_and_query())).ok()?;
+    url.query_pairs()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.to_string())
+}
+
+/// the `pageid` and size of the page asked for
+fn page(req: &mockito::Request) -> (usize, usize) {
+    let page = query_value(req, "pageid").and_then(|p| p.parse().ok());
+    let size = query_value(req, "pagesize").and_then(|s| s.parse().ok());
+    (page.unwrap_or(1), size.unwrap_or(DEFAULT_PAGE_SIZE))
+}
+
+/// a ticket the way connectwise returns it, or only its id
+fn ticket(id: usize, id_only: bool) -> Value {
+    match id_only {
+        true => json!({"id": id}),
+        false => json!({
+            "id": id,
+            "summary": format!("ticket {} summary that is about this long", id),
+            "recordType": "ServiceTicket",
+            "board": {"id": 1, "name": "Service", "_info": {"board_href": "https://example.com"}},
+            "status": {"id": 16, "name": "New", "_info": {"status_href": "https://example.com"}},
+            "company": {"id": 250, "identifier": "myco", "name": "My Company"},
+            "contact": {"id": 7, "name": "Jane Doe"},
+            "priority": {"id": 4, "name": "Priority 3 - Normal Response", "sort": 6},
+            "severity": "Medium",
+            "impact": "Medium",
+            "closedFlag": false,
+            "actualHours": 1.5,
+            "customFields": [{"id": 1, "caption": "Serial", "type": "Text", "value": null}],
+            "_info": {"lastUpdated": "2022-07-14T15:00:00Z", "updatedBy": "zach"},
+        }),
+    }
+}
+
+fn time(name: &str, run: impl Fn() -> usize) {
+    let started = Instant::now();
+    assert_eq!(run(), RECORDS);
+    println!("{:<40} {:>8.1?}", name, started.elapsed());
+}
+
+fn main() {
+    let mut server = mockito::Server::new();
+    let url = server.url();
+    let _tickets = server
+        .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+        .match_query(mockito::Matcher::Any)
+        .with_header_from_request("link", move |req| {
+            let (page, size) = page(req);
+            match page * size < RECORDS {
+                true => format!(
+                    "<{}/v4_6_release/apis/3.0/service/tickets?pageId={}>; rel=\"next\"",
+                    url,
+                    page + 1
+                ),
+                false => String::new(),
+            }
+        })
+        .with_body_from_request(|req| {
+            let (page, size) = page(req);
+            let id_only = query_value(req, "fields").as_deref() == Some("id");
+            let records: Vec<Value> = ((page - 1) * size + 1..=(page * size).min(RECORDS))
+                .map(|id| ticket(id, id_only))
+                .collect();
+            serde_json::to_vec(&records).unwrap()
+        })
+        .expect_at_least(1)
+        .create();
+    let client = Client::new(
+        "myco".to_string(),
+        "public".to_string(),
+        "private".to_string(),
+        "clientid".to_string(),
+    )
+    .api_url(server.url())
+    .build();
+
+    println!("the ids of {} tickets", RECORDS);
+    time("get", || client.get("/service/tickets", &[]).unwrap().len());
+    time("get, fields=id and pagesize=1000", || {
+        let query = [("fields", "id"), ("pagesize", "1000")];
+        let records = client.get("/service/tickets", &query).unwrap();
+        records.iter().filter_map(|r| r["id"].as_i64()).count()
+    });
+    time("get_all_ids", || {
+        client.get_all_ids("/service/tickets", "").unwrap().len()
+    });
+}
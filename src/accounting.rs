@@ -0,0 +1,304 @@
+//! The accounting interface: unposted invoices and the batches that mark them exported.  See
+//! [Client::create_accounting_batch]
+use crate::Client;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const BATCHES_PATH: &str = "/finance/accounting/batches";
+
+/// An invoice that hasn't been exported to accounting yet
+/// (`/finance/accounting/unpostedinvoices`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnpostedInvoice {
+    /// the id of the unposted record, this is what goes in a batch
+    pub id: i64,
+    /// the invoice number (`INV-1001`)
+    #[serde(default)]
+    pub invoice_number: String,
+    /// the kind of invoice (`Standard`, `Agreement`, `CreditMemo`, ...)
+    #[serde(default)]
+    pub invoice_type: String,
+    /// the id of the company billed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company_id: Option<i64>,
+    /// the name of the company billed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company_name: Option<String>,
+    /// the date of the invoice (`2022-07-14T00:00:00Z`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice_date: Option<String>,
+    /// the invoice total
+    #[serde(default)]
+    pub total: f64,
+}
+
+#[cfg(feature = "chrono")]
+impl UnpostedInvoice {
+    /// [UnpostedInvoice::invoice_date] as a date, see [crate::dates].  `None` when it isn't set (or isn't a date)
+    pub fn invoice_date_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.invoice_date.as_deref())
+    }
+}
+
+/// An accounting batch (`/finance/accounting/batches`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountingBatch {
+    /// the id of the batch
+    pub id: i64,
+    /// the name of the batch
+    #[serde(default)]
+    pub batch_identifier: String,
+    /// true if the batch exported invoices
+    #[serde(default)]
+    pub export_invoices_flag: bool,
+    /// true if the batch exported expenses
+    #[serde(default)]
+    pub export_expenses_flag: bool,
+    /// true if the batch exported products
+    #[serde(default)]
+    pub export_products_flag: bool,
+    /// true once the batch is closed
+    #[serde(default)]
+    pub closed_flag: bool,
+}
+
+/// What goes into a batch made with [Client::create_accounting_batch].  Nothing is included
+/// unless it is asked for, and at least one of invoices, expenses and products has to be
+///
+/// ```
+/// use cwmanage::ExportSettings;
+///
+/// let settings = ExportSettings::new("2022-07").invoices(true).dry_run(true);
+/// assert_eq!(
+///     settings.body(&[1, 2])["exportInvoicesFlag"],
+///     serde_json::json!(true)
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ExportSettings {
+    batch_identifier: String,
+    invoices: bool,
+    expenses: bool,
+    products: bool,
+    dry_run: bool,
+}
+
+impl ExportSettings {
+    /// a batch called `batch_identifier` with nothing included yet
+    pub fn new(batch_identifier: &str) -> ExportSettings {
+        ExportSettings {
+            batch_identifier: batch_identifier.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// export the invoices
+    pub fn invoices(mut self, invoices: bool) -> ExportSettings {
+        self.invoices = invoices;
+        self
+    }
+
+    /// export the expenses
+    pub fn expenses(mut self, expenses: bool) -> ExportSettings {
+        self.expenses = expenses;
+        self
+    }
+
+    /// export the products
+    pub fn products(mut self, products: bool) -> ExportSettings {
+        self.products = products;
+        self
+    }
+
+    /// don't create the batch, only check the settings and work out the body that would be
+    /// sent (see [BatchResult::DryRun])
+    pub fn dry_run(mut self, dry_run: bool) -> ExportSettings {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// the body that creates the batch for these records
+    pub fn body(&self, ids: &[i64]) -> Value {
+        json!({
+            "batchIdentifier": self.batch_identifier,
+            "exportInvoicesFlag": self.invoices,
+            "exportExpensesFlag": self.expenses,
+            "exportProductsFlag": self.products,
+            "processedRecordIds": ids,
+        })
+    }
+
+    fn check(&self, ids: &[i64]) -> Result<()> {
+        if self.batch_identifier.trim().is_empty() {
+            return Err(anyhow!("the batch needs a batch identifier"));
+        }
+        if !(self.invoices || self.expenses || self.products) {
+            return Err(anyhow!(
+                "the batch exports nothing, include invoices, expenses or products"
+            ));
+        }
+        if ids.is_empty() {
+            return Err(anyhow!("no records given for the batch"));
+        }
+        Ok(())
+    }
+}
+
+/// What [Client::create_accounting_batch] did
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchResult {
+    /// the batch that was created, check it matches what you expected
+    Created(AccountingBatch),
+    /// a dry run, nothing was sent.  This is the body that would have been
+    DryRun(Value),
+}
+
+impl Client {
+    /// Gets the invoices waiting to be exported, `query` is passed on as with [Client::get]
+    pub fn list_unposted_invoices(&self, query: &[(&str, &str)]) -> Result<Vec<UnpostedInvoice>> {
+        self.get_decoded("/finance/accounting/unpostedinvoices", query)
+    }
+
+    /// Creates an accounting batch, marking the records (ids from
+    /// [Client::list_unposted_invoices]) as exported, and returns the batch.  With
+    /// [ExportSettings::dry_run] the settings are checked but nothing is sent
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{BatchResult, Client, ExportSettings};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ids: Vec<i64> = client
+    ///     .list_unposted_invoices(&[("", "")])
+    ///     .unwrap()
+    ///     .iter()
+    ///     .map(|i| i.id)
+    ///     .collect();
+    /// let settings = ExportSettings::new("2022-07").invoices(true);
+    /// match client.create_accounting_batch(&ids, &settings).unwrap() {
+    ///     BatchResult::Created(batch) => println!("created batch {}", batch.id),
+    ///     BatchResult::DryRun(body) => println!("would send {}", body),
+    /// }
+    /// ```
+    pub fn create_accounting_batch(
+        &self,
+        ids: &[i64],
+        settings: &ExportSettings,
+    ) -> Result<BatchResult> {
+        settings.check(ids)?;
+        let body = settings.body(ids);
+        if settings.dry_run {
+            return Ok(BatchResult::DryRun(body));
+        }
+
+        let batch = self.post(BATCHES_PATH, body.to_string())?;
+        Ok(BatchResult::Created(self.decode(BATCHES_PATH, batch)?))
+    }
+
+    /// Gets an accounting batch
+    pub fn get_batch(&self, id: i64) -> Result<AccountingBatch> {
+        let path = format!("{}/{}", BATCHES_PATH, id);
+        self.decode(&path, self.get_single(&path, &[])?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_create_accounting_batch() {
+        let mut mock = MockCw::new();
+        let create = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/finance/accounting/batches")
+            .match_body(Matcher::Json(json!({
+                "batchIdentifier": "2022-07",
+                "exportInvoicesFlag": true,
+                "exportExpensesFlag": false,
+                "exportProductsFlag": true,
+                "processedRecordIds": [11, 12]
+            })))
+            .with_body(
+                r#"{"id": 3, "batchIdentifier": "2022-07", "exportInvoicesFlag": true,
+                    "exportProductsFlag": true}"#,
+            )
+            .expect(1)
+            .create();
+        let client = mock.client();
+        let settings = ExportSettings::new("2022-07").invoices(true).products(true);
+
+        let dry_run = client
+            .create_accounting_batch(&[11, 12], &settings.clone().dry_run(true))
+            .unwrap();
+        let created = client
+            .create_accounting_batch(&[11, 12], &settings)
+            .unwrap();
+
+        create.assert();
+        assert_eq!(dry_run, BatchResult::DryRun(settings.body(&[11, 12])));
+        match created {
+            BatchResult::Created(batch) => {
+                assert_eq!(batch.id, 3);
+                assert!(batch.export_products_flag);
+                assert!(!batch.export_expenses_flag);
+            }
+            other => panic!("expected a batch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_batch_settings_checked() {
+        let mut mock = MockCw::new();
+        let nothing = mock.server().mock("POST", Matcher::Any).expect(0).create();
+        let client = mock.client();
+
+        assert!(client
+            .create_accounting_batch(&[1], &ExportSettings::new("2022-07"))
+            .is_err());
+        assert!(client
+            .create_accounting_batch(&[], &ExportSettings::new("2022-07").invoices(true))
+            .is_err());
+        assert!(client
+            .create_accounting_batch(&[1], &ExportSettings::new(" ").expenses(true))
+            .is_err());
+        nothing.assert();
+    }
+
+    #[test]
+    fn test_list_unposted_invoices() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock(
+                "GET",
+                "/v4_6_release/apis/3.0/finance/accounting/unpostedinvoices",
+            )
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 11, "invoiceNumber": "INV-1001", "invoiceType": "Standard",
+                     "companyId": 250, "total": 199.5}]"#,
+            )
+            .create();
+
+        let invoices = mock.client().list_unposted_invoices(&[("", "")]).unwrap();
+
+        assert_eq!(invoices[0].invoice_number, "INV-1001");
+        assert_eq!(invoices[0].total, 199.5);
+    }
+}
@@ -0,0 +1,236 @@
+//! Sales activities and the status and type lookups they need.  See [Client::create_activity]
+use crate::reference::find_by_name;
+use crate::{Client, IdOrName};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+const STATUSES_PATH: &str = "/sales/activities/statuses";
+const TYPES_PATH: &str = "/sales/activities/types";
+
+/// An activity status (`/sales/activities/statuses`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityStatus {
+    /// the id of the status
+    pub id: i64,
+    /// the name of the status (`Open`, `Closed`)
+    #[serde(default)]
+    pub name: String,
+    /// true for the status new activities get
+    #[serde(default)]
+    pub default_flag: bool,
+    /// true if the status can no longer be used
+    #[serde(default)]
+    pub inactive_flag: bool,
+    /// true if an activity with this status is closed
+    #[serde(default)]
+    pub closed_flag: bool,
+}
+
+/// An activity type (`/sales/activities/types`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityType {
+    /// the id of the type
+    pub id: i64,
+    /// the name of the type (`Call`, `Quote`)
+    #[serde(default)]
+    pub name: String,
+    /// true for the type new activities get
+    #[serde(default)]
+    pub default_flag: bool,
+    /// true if the type can no longer be used
+    #[serde(default)]
+    pub inactive_flag: bool,
+}
+
+/// A sales activity to create with [Client::create_activity].  The status and type can be
+/// given by name, they are looked up when the activity is created
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NewActivity {
+    /// the subject of the activity
+    pub name: String,
+    /// the status, by default the instance's default
+    pub status: Option<IdOrName>,
+    /// the type, by default the instance's default
+    pub activity_type: Option<IdOrName>,
+    /// the company the activity is for
+    pub company_id: Option<i64>,
+    /// the contact the activity is for
+    pub contact_id: Option<i64>,
+    /// the identifier of the member it is assigned to (`zpeters`)
+    pub assign_to: Option<String>,
+    /// the notes
+    pub notes: Option<String>,
+}
+
+impl Client {
+    /// Gets every activity status, inactive ones included
+    pub fn get_activity_statuses(&self) -> Result<Vec<ActivityStatus>> {
+        self.get_decoded(STATUSES_PATH, &[("", "")])
+    }
+
+    /// Gets every activity type, inactive ones included
+    pub fn get_activity_types(&self) -> Result<Vec<ActivityType>> {
+        self.get_decoded(TYPES_PATH, &[("", "")])
+    }
+
+    /// Finds the active status called `name`, ignoring case.  [crate::Error::UnknownName] if
+    /// there isn't one
+    pub fn find_activity_status(&self, name: &str) -> Result<ActivityStatus> {
+        let statuses: Vec<ActivityStatus> = self
+            .get_activity_statuses()?
+            .into_iter()
+            .filter(|s| !s.inactive_flag)
+            .collect();
+        find_by_name(&statuses, STATUSES_PATH, name, |s| &s.name).cloned()
+    }
+
+    /// Finds the active type called `name`, ignoring case.  [crate::Error::UnknownName] if
+    /// there isn't one
+    pub fn find_activity_type(&self, name: &str) -> Result<ActivityType> {
+        let types: Vec<ActivityType> = self
+            .get_activity_types()?
+            .into_iter()
+            .filter(|t| !t.inactive_flag)
+            .collect();
+        find_by_name(&types, TYPES_PATH, name, |t| &t.name).cloned()
+    }
+
+    /// Creates a sales activity, looking up the status and type first if they were given by
+    /// name, and returns it
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, NewActivity};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let activity = NewActivity {
+    ///     name: "Follow up on quote".to_string(),
+    ///     status: Some("open".into()),
+    ///     activity_type: Some("Call".into()),
+    ///     company_id: Some(250),
+    ///     ..Default::default()
+    /// };
+    /// let created = client.create_activity(&activity).unwrap();
+    /// println!("created activity {}", created["id"]);
+    /// ```
+    pub fn create_activity(&self, activity: &NewActivity) -> Result<Value> {
+        let mut body = Map::new();
+        body.insert("name".to_string(), json!(activity.name));
+        if let Some(status) = &activity.status {
+            let id = match status {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => self.find_activity_status(name)?.id,
+            };
+            body.insert("status".to_string(), json!({ "id": id }));
+        }
+        if let Some(activity_type) = &activity.activity_type {
+            let id = match activity_type {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => self.find_activity_type(name)?.id,
+            };
+            body.insert("type".to_string(), json!({ "id": id }));
+        }
+        if let Some(company_id) = activity.company_id {
+            body.insert("company".to_string(), json!({ "id": company_id }));
+        }
+        if let Some(contact_id) = activity.contact_id {
+            body.insert("contact".to_string(), json!({ "id": contact_id }));
+        }
+        if let Some(assign_to) = &activity.assign_to {
+            body.insert("assignTo".to_string(), json!({ "identifier": assign_to }));
+        }
+        if let Some(notes) = &activity.notes {
+            body.insert("notes".to_string(), json!(notes));
+        }
+
+        self.post("/sales/activities", Value::Object(body).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    fn mock_lookups(server: &mut mockito::Server) -> Vec<mockito::Mock> {
+        vec![
+            server
+                .mock("GET", "/v4_6_release/apis/3.0/sales/activities/statuses")
+                .match_query(Matcher::Any)
+                .with_body(
+                    r#"[{"id": 1, "name": "Open", "defaultFlag": true},
+                        {"id": 2, "name": "Closed", "closedFlag": true},
+                        {"id": 3, "name": "Pending", "inactiveFlag": true}]"#,
+                )
+                .create(),
+            server
+                .mock("GET", "/v4_6_release/apis/3.0/sales/activities/types")
+                .match_query(Matcher::Any)
+                .with_body(r#"[{"id": 7, "name": "Call"}, {"id": 8, "name": "Quote"}]"#)
+                .create(),
+        ]
+    }
+
+    #[test]
+    fn test_create_activity_by_name() {
+        let mut mock = MockCw::new();
+        let _lookups = mock_lookups(mock.server());
+        let create = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/sales/activities")
+            .match_body(Matcher::Json(json!({
+                "name": "Follow up",
+                "status": {"id": 2},
+                "type": {"id": 8},
+                "company": {"id": 250}
+            })))
+            .with_body(r#"{"id": 100, "name": "Follow up"}"#)
+            .create();
+        let activity = NewActivity {
+            name: "Follow up".to_string(),
+            status: Some(" closed ".into()),
+            activity_type: Some(IdOrName::Id(8)),
+            company_id: Some(250),
+            ..Default::default()
+        };
+
+        let created = mock.client().create_activity(&activity).unwrap();
+
+        create.assert();
+        assert_eq!(created["id"], 100);
+    }
+
+    #[test]
+    fn test_find_activity_status_skips_inactive() {
+        let mut mock = MockCw::new();
+        let _lookups = mock_lookups(mock.server());
+        let client = mock.client();
+
+        assert_eq!(client.find_activity_type("CALL").unwrap().id, 7);
+        assert_eq!(client.get_activity_statuses().unwrap().len(), 3);
+        let err = client.find_activity_status("pending").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::UnknownName {
+                path: "/sales/activities/statuses".to_string(),
+                name: "pending".to_string(),
+                valid: vec!["Open".to_string(), "Closed".to_string()],
+            })
+        );
+    }
+}
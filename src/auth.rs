@@ -0,0 +1,117 @@
+//! The `Authorization: Basic` header, encoded as RFC 7617 says for `charset="UTF-8"`
+use crate::Error;
+use icu_normalizer::ComposingNormalizerBorrowed;
+
+/// The `Basic ...` value for the credentials.  The user is `{company_id}+{public_key}` and
+/// the password the private key, each normalized to NFC (so `é` is the same bytes however it
+/// was typed) and encoded as UTF-8 before the base64.  [Error::InvalidCredentials] when the
+/// user has a `:` or whitespace, or any part has a control character
+pub(crate) fn basic_auth(
+    company_id: &str,
+    public_key: &str,
+    private_key: &str,
+) -> Result<String, Error> {
+    let nfc = ComposingNormalizerBorrowed::new_nfc();
+    let company_id = nfc.normalize(company_id);
+    let public_key = nfc.normalize(public_key);
+    let private_key = nfc.normalize(private_key);
+
+    for (field, value) in [("company id", &company_id), ("public key", &public_key)].iter() {
+        check(field, value, &[':'])?;
+        if value.chars().any(char::is_whitespace) {
+            return Err(invalid(field, "it can't have spaces"));
+        }
+    }
+    check("private key", &private_key, &[])?;
+
+    let encoded = base64::encode(format!("{}+{}:{}", company_id, public_key, private_key));
+    Ok(format!("Basic {}", encoded))
+}
+
+/// rejects control characters, and the `forbidden` ones
+fn check(field: &str, value: &str, forbidden: &[char]) -> Result<(), Error> {
+    if value.chars().any(char::is_control) {
+        return Err(invalid(field, "it has a control character"));
+    }
+    if let Some(c) = value.chars().find(|c| forbidden.contains(c)) {
+        return Err(invalid(field, &format!("it can't have a {:?}", c)));
+    }
+    Ok(())
+}
+
+fn invalid(field: &str, reason: &str) -> Error {
+    Error::InvalidCredentials {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_basic_auth_utf8() {
+        assert_eq!(
+            basic_auth("myco", "pub", "priv").unwrap(),
+            "Basic bXljbytwdWI6cHJpdg=="
+        );
+        // the composed and decomposed é are the same header
+        assert_eq!(
+            basic_auth("caf\u{e9}", "pub", "priv").unwrap(),
+            "Basic Y2Fmw6krcHViOnByaXY="
+        );
+        assert_eq!(
+            basic_auth("cafe\u{301}", "pub", "priv").unwrap(),
+            "Basic Y2Fmw6krcHViOnByaXY="
+        );
+        // a : is fine in the password
+        assert_eq!(
+            basic_auth("myco", "\u{1f980}", "p:ss").unwrap(),
+            "Basic bXljbyvwn6aAOnA6c3M="
+        );
+    }
+
+    #[test]
+    fn test_basic_auth_invalid() {
+        assert_eq!(
+            basic_auth("my co", "pub", "priv").unwrap_err().to_string(),
+            "invalid company id: it can't have spaces"
+        );
+        assert_eq!(
+            basic_auth("myco", "pu:b", "priv").unwrap_err(),
+            Error::InvalidCredentials {
+                field: "public key".to_string(),
+                reason: "it can't have a ':'".to_string(),
+            }
+        );
+        assert_eq!(
+            basic_auth("myco", "pub", "pr\niv").unwrap_err().to_string(),
+            "invalid private key: it has a control character"
+        );
+        assert!(basic_auth("myco\t", "pub", "priv").is_err());
+    }
+
+    #[test]
+    fn test_invalid_credentials_not_sent() {
+        let mut server = mockito::Server::new();
+        let nothing = server.mock("GET", mockito::Matcher::Any).expect(0).create();
+        let client = crate::Client::new(
+            " myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
+
+        let err = client.get_single("/system/info", &[]).unwrap_err();
+
+        nothing.assert();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidCredentials { .. })
+        ));
+    }
+}
@@ -0,0 +1,405 @@
+//! When a member is working and when they are free.  See [Client::get_member_availability]
+use crate::{Client, CwValueExt};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc, Weekday};
+use serde_json::Value;
+
+/// A span of time in utc, `start` up to (not including) `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    /// when it starts
+    pub start: DateTime<Utc>,
+    /// when it ends
+    pub end: DateTime<Utc>,
+}
+
+/// A member's time between two points, from [Client::get_member_availability].  Each list is
+/// sorted, has no overlaps and is cut to the window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Availability {
+    /// the member
+    pub member_id: i64,
+    /// the start of the window
+    pub from: DateTime<Utc>,
+    /// the end of the window
+    pub to: DateTime<Utc>,
+    /// the working hours of the member's calendar, less the holidays
+    pub working: Vec<Interval>,
+    /// the member's schedule entries
+    pub busy: Vec<Interval>,
+    /// the working hours that aren't busy
+    pub free: Vec<Interval>,
+}
+
+impl Availability {
+    /// true if the member is free for all of `start` to `end`
+    pub fn is_free(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        self.free.iter().any(|f| f.start <= start && end <= f.end)
+    }
+}
+
+/// sorts the intervals and joins the ones that overlap or touch, empty ones are dropped
+fn merge(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.retain(|i| i.start < i.end);
+    intervals.sort_by_key(|i| i.start);
+    let mut merged: Vec<Interval> = Vec::new();
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+/// the parts of the merged intervals `from` that aren't in the merged intervals `remove`
+fn subtract(from: &[Interval], remove: &[Interval]) -> Vec<Interval> {
+    let mut left = Vec::new();
+    for interval in from {
+        let mut start = interval.start;
+        for r in remove
+            .iter()
+            .filter(|r| r.end > interval.start && r.start < interval.end)
+        {
+            if r.start > start {
+                left.push(Interval {
+                    start,
+                    end: r.start,
+                });
+            }
+            start = start.max(r.end);
+        }
+        if start < interval.end {
+            left.push(Interval {
+                start,
+                end: interval.end,
+            });
+        }
+    }
+    left
+}
+
+/// the intervals cut to `from` - `to`
+fn clip(intervals: Vec<Interval>, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Interval> {
+    let clipped = intervals.into_iter().map(|i| Interval {
+        start: i.start.max(from),
+        end: i.end.min(to),
+    });
+    merge(clipped.collect())
+}
+
+/// the calendar field prefix of a day (`monday`)
+fn day_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+/// a time of day as connectwise sends it (`08:00:00`)
+fn parse_time(value: &Value, key: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.get_str(key)?, "%H:%M:%S").ok()
+}
+
+/// a date (`2023-12-25`, or the date of `2023-12-25T00:00:00Z`)
+fn parse_date(value: &Value, key: &str) -> Option<NaiveDate> {
+    let date = value.get_str(key)?;
+    NaiveDate::parse_from_str(date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// a time in utc (`2023-03-01T14:00:00Z`)
+fn parse_utc(value: &Value, key: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.get_str(key)?)
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+impl Client {
+    /// Gets when a member works and when they are free between `from` and `to`
+    ///
+    /// The working hours are the days of the member's calendar (`/schedule/calendars`) less
+    /// the holidays of the calendar's holiday list.  They are in the instance's local time and
+    /// are converted with [Client::to_utc], so a day the clocks change is still right.  Hours
+    /// that end before they start (`22:00` to `06:00`) run into the next day.  The busy times
+    /// are the member's schedule entries, overlapping entries are joined
+    ///
+    /// # Example
+    /// ```no_run
+    /// use chrono::{Duration, Utc};
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let now = Utc::now();
+    /// let week = client
+    ///     .get_member_availability(123, now, now + Duration::days(7))
+    ///     .unwrap();
+    /// for free in week.free {
+    ///     println!("free {} to {}", free.start, free.end);
+    /// }
+    /// ```
+    pub fn get_member_availability(
+        &self,
+        member_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Availability> {
+        if to <= from {
+            return Err(anyhow!(
+                "the window ends ({}) before it starts ({})",
+                to,
+                from
+            ));
+        }
+        let member_path = format!("/system/members/{}", member_id);
+        let member = self.get_single(&member_path, &[("fields", "id,calendar")])?;
+        let calendar_id = member["calendar"]["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("member {} has no calendar", member_id))?;
+        let calendar_path = format!("/schedule/calendars/{}", calendar_id);
+        let calendar = self.get_single(&calendar_path, &[])?;
+        let holidays = match calendar["holidayList"]["id"].as_i64() {
+            Some(list) => {
+                let path = format!("/schedule/holidayLists/{}/holidays", list);
+                self.get(&path, &[("", "")])?
+            }
+            None => Vec::new(),
+        };
+
+        let working = self.working_hours(&calendar, from, to)?;
+        let holidays = self.holiday_hours(&holidays)?;
+        let working = clip(subtract(&working, &holidays), from, to);
+
+        let conditions = format!(
+            "member/id = {} AND dateStart < [{}] AND dateEnd > [{}]",
+            member_id,
+            to.format("%Y-%m-%dT%H:%M:%SZ"),
+            from.format("%Y-%m-%dT%H:%M:%SZ")
+        );
+        let entries = self.get(
+            "/schedule/entries",
+            &[
+                ("conditions", &conditions),
+                ("fields", "id,dateStart,dateEnd"),
+            ],
+        )?;
+        let busy = entries
+            .iter()
+            .filter_map(|e| {
+                Some(Interval {
+                    start: parse_utc(e, "dateStart")?,
+                    end: parse_utc(e, "dateEnd")?,
+                })
+            })
+            .collect();
+        let busy = clip(busy, from, to);
+        let free = subtract(&working, &busy);
+
+        Ok(Availability {
+            member_id,
+            from,
+            to,
+            working,
+            busy,
+            free,
+        })
+    }
+
+    /// the working hours of a calendar on each local day that could touch `from` - `to`
+    fn working_hours(
+        &self,
+        calendar: &Value,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Interval>> {
+        let tz = self.timezone()?;
+        // a day before, for hours that run past midnight into the window
+        let mut day = from.with_timezone(&tz).date_naive() - Duration::days(1);
+        let last = to.with_timezone(&tz).date_naive();
+        let mut hours = Vec::new();
+        while day <= last {
+            let name = day_name(day.weekday());
+            let start = parse_time(calendar, &format!("{}StartTime", name));
+            let end = parse_time(calendar, &format!("{}EndTime", name));
+            if let (Some(start), Some(end)) = (start, end) {
+                let end_day = match end <= start {
+                    true => day + Duration::days(1),
+                    false => day,
+                };
+                hours.push(Interval {
+                    start: self.to_utc(day.and_time(start))?,
+                    end: self.to_utc(end_day.and_time(end))?,
+                });
+            }
+            day += Duration::days(1);
+        }
+        Ok(merge(hours))
+    }
+
+    /// the times of a holiday list, a whole local day for an all day holiday
+    fn holiday_hours(&self, holidays: &[Value]) -> Result<Vec<Interval>> {
+        let mut hours = Vec::new();
+        for holiday in holidays {
+            let date = match parse_date(holiday, "date") {
+                Some(date) => date,
+                None => continue,
+            };
+            let times = (
+                parse_time(holiday, "timeStart"),
+                parse_time(holiday, "timeEnd"),
+            );
+            let (start, end) = match (holiday["allDayFlag"].as_bool(), times) {
+                (Some(false), (Some(start), Some(end))) => {
+                    (date.and_time(start), date.and_time(end))
+                }
+                _ => (
+                    date.and_time(NaiveTime::MIN),
+                    (date + Duration::days(1)).and_time(NaiveTime::MIN),
+                ),
+            };
+            hours.push(Interval {
+                start: self.to_utc(start)?,
+                end: self.to_utc(end)?,
+            });
+        }
+        Ok(merge(hours))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 3, day, hour, minute, 0).unwrap()
+    }
+
+    fn interval(start: DateTime<Utc>, end: DateTime<Utc>) -> Interval {
+        Interval { start, end }
+    }
+
+    #[test]
+    fn test_get_member_availability() {
+        let mut server = mockito::Server::new();
+        let base = "/v4_6_release/apis/3.0";
+        let _info = server
+            .mock("GET", format!("{}/system/info", base).as_str())
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"{"version": "v2022.1", "isCloud": true,
+                    "serverTimeZone": "Central Standard Time"}"#,
+            )
+            .create();
+        let _member = server
+            .mock("GET", format!("{}/system/members/5", base).as_str())
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 5, "calendar": {"id": 2, "name": "Day shift"}}"#)
+            .create();
+        // wednesday 1 march and thursday 2 march 2023, central is utc-6
+        let _calendar = server
+            .mock("GET", format!("{}/schedule/calendars/2", base).as_str())
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"{"id": 2, "wednesdayStartTime": "08:00:00", "wednesdayEndTime": "17:00:00",
+                    "thursdayStartTime": "22:00:00", "thursdayEndTime": "02:00:00",
+                    "holidayList": {"id": 3}}"#,
+            )
+            .create();
+        let _holidays = server
+            .mock(
+                "GET",
+                format!("{}/schedule/holidayLists/3/holidays", base).as_str(),
+            )
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "date": "2023-03-01T00:00:00Z", "allDayFlag": false,
+                     "timeStart": "12:00:00", "timeEnd": "13:00:00"}]"#,
+            )
+            .create();
+        let entries = server
+            .mock("GET", format!("{}/schedule/entries", base).as_str())
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "member/id = 5 AND dateStart < [2023-03-03T12:00:00Z] \
+                 AND dateEnd > [2023-03-01T00:00:00Z]"
+                    .to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 1, "dateStart": "2023-03-01T15:00:00Z", "dateEnd": "2023-03-01T16:00:00Z"},
+                    {"id": 2, "dateStart": "2023-03-01T15:30:00Z", "dateEnd": "2023-03-01T16:30:00Z"},
+                    {"id": 3, "dateStart": "2023-03-03T05:00:00Z", "dateEnd": "2023-03-03T06:00:00Z"}]"#,
+            )
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
+
+        let week = client
+            .get_member_availability(5, utc(1, 0, 0), utc(3, 12, 0))
+            .unwrap();
+
+        entries.assert();
+        assert_eq!(
+            week.working,
+            vec![
+                interval(utc(1, 14, 0), utc(1, 18, 0)),
+                interval(utc(1, 19, 0), utc(1, 23, 0)),
+                interval(utc(3, 4, 0), utc(3, 8, 0)),
+            ]
+        );
+        assert_eq!(
+            week.busy,
+            vec![
+                interval(utc(1, 15, 0), utc(1, 16, 30)),
+                interval(utc(3, 5, 0), utc(3, 6, 0)),
+            ]
+        );
+        assert_eq!(
+            week.free,
+            vec![
+                interval(utc(1, 14, 0), utc(1, 15, 0)),
+                interval(utc(1, 16, 30), utc(1, 18, 0)),
+                interval(utc(1, 19, 0), utc(1, 23, 0)),
+                interval(utc(3, 4, 0), utc(3, 5, 0)),
+                interval(utc(3, 6, 0), utc(3, 8, 0)),
+            ]
+        );
+        assert!(week.is_free(utc(1, 20, 0), utc(1, 21, 0)));
+        assert!(!week.is_free(utc(1, 15, 30), utc(1, 17, 0)));
+    }
+
+    #[test]
+    fn test_merge_and_subtract() {
+        let merged = merge(vec![
+            interval(utc(1, 10, 0), utc(1, 11, 0)),
+            interval(utc(1, 9, 0), utc(1, 10, 0)),
+            interval(utc(1, 12, 0), utc(1, 12, 0)),
+        ]);
+        assert_eq!(merged, vec![interval(utc(1, 9, 0), utc(1, 11, 0))]);
+        assert_eq!(
+            subtract(&merged, &[interval(utc(1, 8, 0), utc(1, 9, 30))]),
+            vec![interval(utc(1, 9, 30), utc(1, 11, 0))]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! `cw`, one-off queries against the connectwise api from the command line (the `cli`
+//! feature)
+//!
+//! ```text
+//! cw get /service/tickets --conditions "status/name = 'New'" --fields id,summary
+//! cw get-single /service/tickets/123
+//! cw post /service/tickets '{"summary": "printer on fire", "board": {"id": 1}}'
+//! cw patch /service/tickets/123 replace summary '"printer no longer on fire"'
+//! cw count /service/tickets --conditions "closedFlag = false"
+//! ```
+//!
+//! The credentials are read from the environment, see [cwmanage::Client::from_env]
+use anyhow::Result;
+use cwmanage::export::{to_csv, CsvOptions};
+use cwmanage::{Client, Error, PatchOp};
+use serde_json::Value;
+use std::io::Read;
+use std::process::ExitCode;
+
+const COMMANDS: &[&str] = &["get", "get-single", "post", "patch", "count"];
+
+const USAGE: &str = "usage: cw <command> <path> [options]
+
+commands:
+  get <path>                         a list, every page of it
+  get-single <path>                  a single record
+  post <path> <json|->               create a record, `-` reads the body from stdin
+  patch <path> <op> <field> <value>  change one field, the value is json (a bare word is a string)
+  count <path>                       how many records match
+
+options:
+  --fields <fields>          only these fields (`id,summary,status/name`)
+  --conditions <conditions>  only the records matching (`status/name = 'New'`)
+  --orderby <orderby>        the order of a list (`id desc`)
+  --pagesize <n>             records per page
+  --format <format>          pretty (the default), compact or csv (get only)
+
+credentials come from CWMANAGE_COMPANY_ID, CWMANAGE_PUBLIC_KEY, CWMANAGE_PRIVATE_KEY and
+CWMANAGE_CLIENT_ID, and CWMANAGE_API_URL when it is set
+
+exit codes:
+  0 ok, 1 other errors, 2 usage, 3 not allowed (401, 403), 4 not found, 5 invalid request";
+
+/// The command line was wrong
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn usage(message: String) -> anyhow::Error {
+    UsageError(message).into()
+}
+
+/// What went wrong, as the exit code
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Failure {
+    Other = 1,
+    Usage = 2,
+    Auth = 3,
+    NotFound = 4,
+    Invalid = 5,
+}
+
+impl Failure {
+    fn of(err: &anyhow::Error) -> Failure {
+        if err.downcast_ref::<UsageError>().is_some() {
+            return Failure::Usage;
+        }
+        match err.downcast_ref::<Error>() {
+            Some(Error::Forbidden { .. }) => Failure::Auth,
+            Some(Error::Api { status, .. }) => match status {
+                401 | 403 => Failure::Auth,
+                404 => Failure::NotFound,
+                400 | 409 | 422 => Failure::Invalid,
+                _ => Failure::Other,
+            },
+            Some(Error::NotFound { .. }) => Failure::NotFound,
+            Some(
+                Error::InvalidPath { .. }
+                | Error::InvalidQuery { .. }
+                | Error::InvalidFieldPath { .. }
+                | Error::ExpectedList { .. }
+                | Error::ExpectedObject { .. },
+            ) => Failure::Invalid,
+            _ => Failure::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Pretty,
+    Compact,
+    Csv,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    query: Vec<(&'static str, String)>,
+    format: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args> {
+    let mut parsed = Args::default();
+    while let Some(arg) = args.next() {
+        let key = match arg.as_str() {
+            "--fields" => "fields",
+            "--conditions" => "conditions",
+            "--orderby" => "orderBy",
+            "--pagesize" => "pageSize",
+            "--format" => "format",
+            "-h" | "--help" => {
+                parsed.command = "help".to_string();
+                return Ok(parsed);
+            }
+            a if a.starts_with("--") => return Err(usage(format!("unknown option {}", a))),
+            _ if parsed.command.is_empty() => {
+                parsed.command = arg;
+                continue;
+            }
+            _ => {
+                parsed.positional.push(arg);
+                continue;
+            }
+        };
+        let value = args
+            .next()
+            .ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+        match key {
+            "format" => parsed.format = Some(value),
+            key => parsed.query.push((key, value)),
+        }
+    }
+    if parsed.command.is_empty() {
+        return Err(usage(USAGE.to_string()));
+    }
+    Ok(parsed)
+}
+
+fn print(value: &Value, format: Format) -> Result<()> {
+    match format {
+        Format::Pretty => println!("{}", serde_json::to_string_pretty(value)?),
+        Format::Compact => println!("{}", serde_json::to_string(value)?),
+        Format::Csv => unreachable!("csv is checked before the request"),
+    }
+    Ok(())
+}
+
+fn run(args: &Args) -> Result<()> {
+    let format = match args.format.as_deref() {
+        None | Some("pretty") => Format::Pretty,
+        Some("compact") => Format::Compact,
+        Some("csv") => Format::Csv,
+        Some(other) => return Err(usage(format!("unknown format {:?}", other))),
+    };
+    let positional = |count: usize, names: &str| -> Result<&[String]> {
+        match args.positional.len() == count {
+            true => Ok(&args.positional),
+            false => Err(usage(format!("usage: cw {} {}", args.command, names))),
+        }
+    };
+    let query: Vec<(&str, &str)> = args.query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    if args.command == "help" {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+    if !COMMANDS.contains(&args.command.as_str()) {
+        return Err(usage(format!(
+            "unknown command {:?}\n\n{}",
+            args.command, USAGE
+        )));
+    }
+    if format == Format::Csv && args.command != "get" {
+        return Err(usage("csv is only for lists, use it with get".to_string()));
+    }
+    let client = Client::from_env()?;
+
+    match args.command.as_str() {
+        "get" => {
+            let path = &positional(1, "<path>")?[0];
+            let records = client.get(path, &query)?;
+            match format {
+                Format::Csv => to_csv(&records, std::io::stdout(), CsvOptions::default())?,
+                format => print(&Value::Array(records), format)?,
+            }
+        }
+        "get-single" => {
+            let path = &positional(1, "<path>")?[0];
+            print(&client.get_single(path, &query)?, format)?;
+        }
+        "post" => {
+            let args = positional(2, "<path> <json|->")?;
+            let body = match args[1].as_str() {
+                "-" => {
+                    let mut body = String::new();
+                    std::io::stdin().read_to_string(&mut body)?;
+                    body
+                }
+                body => body.to_string(),
+            };
+            serde_json::from_str::<Value>(&body)
+                .map_err(|e| usage(format!("the body isn't json: {}", e)))?;
+            print(&client.post(&args[0], body)?, format)?;
+        }
+        "patch" => {
+            let args = positional(4, "<path> <op> <field> <value>")?;
+            let op: PatchOp = args[1].parse().map_err(|e| usage(format!("{}", e)))?;
+            let value = serde_json::from_str(&args[3])
+                .unwrap_or_else(|_| Value::String(args[3].to_string()));
+            print(&client.patch(&args[0], op, &args[2], value)?, format)?;
+        }
+        "count" => {
+            let path = &positional(1, "<path>")?[0];
+            let count =
+                client.get_single(&format!("{}/count", path.trim_end_matches('/')), &query)?;
+            print(&count["count"], format)?;
+        }
+        _ => unreachable!("checked against COMMANDS"),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(Failure::Usage as u8);
+        }
+    };
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(Failure::of(&e) as u8)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn args(line: &[&str]) -> Result<Args> {
+        parse_args(line.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args(&[
+            "get",
+            "/service/tickets",
+            "--conditions",
+            "id > 1",
+            "--pagesize",
+            "50",
+            "--format",
+            "csv",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            parsed,
+            Args {
+                command: "get".to_string(),
+                positional: vec!["/service/tickets".to_string()],
+                query: vec![
+                    ("conditions", "id > 1".to_string()),
+                    ("pageSize", "50".to_string())
+                ],
+                format: Some("csv".to_string()),
+            }
+        );
+        assert_eq!(args(&["--help"]).unwrap().command, "help");
+        for bad in [
+            &["get", "/x", "--fields"][..],
+            &["get", "--limit", "1"],
+            &[],
+        ] {
+            assert_eq!(Failure::of(&args(bad).unwrap_err()), Failure::Usage);
+        }
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let forbidden: anyhow::Error = Error::Forbidden {
+            path: "/finance/agreements".to_string(),
+            error: Default::default(),
+        }
+        .into();
+        let unauthorized: anyhow::Error = Error::Api {
+            path: "/service/tickets".to_string(),
+            status: 401,
+            error: Default::default(),
+        }
+        .into();
+        let not_found: anyhow::Error = Error::NotFound {
+            path: "/service/tickets/1".to_string(),
+        }
+        .into();
+        let invalid: anyhow::Error = Error::Api {
+            path: "/service/tickets".to_string(),
+            status: 400,
+            error: Default::default(),
+        }
+        .into();
+
+        assert_eq!(Failure::of(&forbidden), Failure::Auth);
+        assert_eq!(Failure::of(&unauthorized), Failure::Auth);
+        assert_eq!(Failure::of(&not_found), Failure::NotFound);
+        assert_eq!(Failure::of(&invalid), Failure::Invalid);
+        assert_eq!(
+            Failure::of(&anyhow::anyhow!("connection reset")),
+            Failure::Other
+        );
+    }
+}
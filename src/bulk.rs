@@ -0,0 +1,950 @@
+//! Applying the same change to many records, or a batch of different writes.  See
+//! [Client::patch_bulk], [Client::delete_bulk] and [Client::apply_batch]
+use crate::{Client, Error, PatchOperation};
+use anyhow::Result;
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How many times a record is retried after the api rate limits us before it is reported as
+/// failed
+const RATE_LIMIT_RETRIES: u32 = 5;
+
+/// How long to wait after a 429 that didn't include a `Retry-After`, multiplied by the attempt
+const RATE_LIMIT_WAIT: Duration = Duration::from_secs(2);
+
+type ProgressFn = dyn Fn(usize, usize) + Send + Sync;
+
+/// Options for [Client::patch_bulk_with]
+#[derive(Clone)]
+pub struct BulkOptions {
+    concurrency: usize,
+    dry_run: bool,
+    progress: Option<Arc<ProgressFn>>,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        BulkOptions {
+            concurrency: 1,
+            dry_run: false,
+            progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for BulkOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BulkOptions")
+            .field("concurrency", &self.concurrency)
+            .field("dry_run", &self.dry_run)
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+impl BulkOptions {
+    /// how many requests are sent at the same time, by default 1.  0 is treated as 1
+    pub fn concurrency(mut self, concurrency: usize) -> BulkOptions {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// don't send anything, only work out the path for each id.  The report lists every id
+    /// that would have been sent as succeeded
+    pub fn dry_run(mut self, dry_run: bool) -> BulkOptions {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// called with `(done, total)` after each id is finished, successfully or not
+    pub fn on_progress(mut self, progress: impl Fn(usize, usize) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+}
+
+/// Options for [Client::delete_bulk]
+#[derive(Clone, Debug, Default)]
+pub struct BulkDeleteOptions {
+    bulk: BulkOptions,
+    missing_ok: bool,
+}
+
+impl BulkDeleteOptions {
+    /// how many requests are sent at the same time, by default 1.  0 is treated as 1
+    pub fn concurrency(mut self, concurrency: usize) -> BulkDeleteOptions {
+        self.bulk = self.bulk.concurrency(concurrency);
+        self
+    }
+
+    /// don't delete anything, only look each record up.  The report lists the ids that would
+    /// have been deleted as succeeded and the ones that can't be read as failed
+    pub fn dry_run(mut self, dry_run: bool) -> BulkDeleteOptions {
+        self.bulk = self.bulk.dry_run(dry_run);
+        self
+    }
+
+    /// count a record that is already gone (a 404) as deleted, so a cleanup can be run again
+    pub fn missing_ok(mut self, missing_ok: bool) -> BulkDeleteOptions {
+        self.missing_ok = missing_ok;
+        self
+    }
+
+    /// called with `(done, total)` after each id is finished, successfully or not
+    pub fn on_progress(mut self, progress: impl Fn(usize, usize) + Send + Sync + 'static) -> Self {
+        self.bulk = self.bulk.on_progress(progress);
+        self
+    }
+}
+
+/// Options for [Client::apply_batch_with]
+#[derive(Clone, Debug, Default)]
+pub struct BatchOptions {
+    bulk: BulkOptions,
+    stop_on_error: bool,
+}
+
+impl BatchOptions {
+    /// how many requests are sent at the same time, by default 1.  0 is treated as 1
+    pub fn concurrency(mut self, concurrency: usize) -> BatchOptions {
+        self.bulk = self.bulk.concurrency(concurrency);
+        self
+    }
+
+    /// don't write anything.  The record a patch or delete is for is checked to exist and a
+    /// post only has its path checked, the report lists the ones that pass as succeeded
+    pub fn dry_run(mut self, dry_run: bool) -> BatchOptions {
+        self.bulk = self.bulk.dry_run(dry_run);
+        self
+    }
+
+    /// once one operation fails no more are started, the ones after it are
+    /// [BatchOutcome::Skipped].  With a concurrency above 1 the ones already sent still finish
+    pub fn stop_on_error(mut self, stop_on_error: bool) -> BatchOptions {
+        self.stop_on_error = stop_on_error;
+        self
+    }
+
+    /// called with `(done, total)` after each operation is finished, successfully or not
+    pub fn on_progress(mut self, progress: impl Fn(usize, usize) + Send + Sync + 'static) -> Self {
+        self.bulk = self.bulk.on_progress(progress);
+        self
+    }
+}
+
+/// One write of a [Client::apply_batch]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    /// creates a record, see [Client::post]
+    Post {
+        /// where to post (`/sales/activities`)
+        path: String,
+        /// the record
+        body: Value,
+    },
+    /// changes a record, see [Client::patch_ops]
+    Patch {
+        /// the record (`/service/tickets/123`)
+        path: String,
+        /// the changes
+        ops: Vec<PatchOperation>,
+    },
+    /// deletes a record
+    Delete {
+        /// the record (`/schedule/entries/456`)
+        path: String,
+    },
+}
+
+impl BatchOp {
+    /// the path of the operation
+    pub fn path(&self) -> &str {
+        match self {
+            BatchOp::Post { path, .. } | BatchOp::Patch { path, .. } | BatchOp::Delete { path } => {
+                path
+            }
+        }
+    }
+}
+
+/// What happened to one [BatchOp]
+#[derive(Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "lowercase")]
+pub enum BatchOutcome {
+    /// it was written (or with a dry run, would have been sent)
+    Succeeded {
+        /// the created or changed record, `None` for a delete or a dry run
+        record: Option<Value>,
+    },
+    /// it failed
+    Failed {
+        /// why it failed, use [anyhow::Error::downcast_ref] to get the [Error]
+        #[serde(serialize_with = "error_message")]
+        error: anyhow::Error,
+    },
+    /// it wasn't sent because an operation before it failed, see [BatchOptions::stop_on_error]
+    Skipped,
+}
+
+/// A [BatchOp] and what happened to it
+#[derive(Debug, Serialize)]
+pub struct BatchEntry {
+    /// the operation
+    pub op: BatchOp,
+    /// what happened to it
+    #[serde(flatten)]
+    pub outcome: BatchOutcome,
+}
+
+/// The outcome of a [Client::apply_batch], each operation in the order given.  It serializes
+/// (for an audit log) with each failure's error as its message
+#[derive(Debug, Default, Serialize)]
+pub struct BatchReport {
+    /// every operation and what happened to it
+    pub entries: Vec<BatchEntry>,
+    /// true if this was a dry run and nothing was written
+    pub dry_run: bool,
+}
+
+impl BatchReport {
+    /// true if every operation succeeded
+    pub fn is_success(&self) -> bool {
+        self.entries
+            .iter()
+            .all(|e| matches!(e.outcome, BatchOutcome::Succeeded { .. }))
+    }
+
+    /// the operations that failed, with their errors
+    pub fn failed(&self) -> impl Iterator<Item = (&BatchOp, &anyhow::Error)> {
+        self.entries.iter().filter_map(|e| match &e.outcome {
+            BatchOutcome::Failed { error } => Some((&e.op, error)),
+            _ => None,
+        })
+    }
+}
+
+/// The outcome of a bulk operation.  One failed id never stops the others.  It serializes
+/// (for an audit log) with each failure's error as its message
+#[derive(Debug, Default, Serialize)]
+pub struct BulkReport {
+    /// ids that were changed (or with a dry run, would have been sent), in the order given
+    pub succeeded: Vec<i64>,
+    /// ids that failed, in the order given
+    pub failed: Vec<BulkFailure>,
+    /// ids that weren't sent because the client was cancelled (see [Client::cancel_on]), in
+    /// the order given
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub not_started: Vec<i64>,
+    /// true if this was a dry run and nothing was sent
+    pub dry_run: bool,
+}
+
+impl BulkReport {
+    /// true if no id failed and none were left when cancelled
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty() && self.not_started.is_empty()
+    }
+}
+
+/// An id that failed in a bulk operation
+#[derive(Debug, Serialize)]
+pub struct BulkFailure {
+    /// the id of the record
+    pub id: i64,
+    /// why it failed, use [anyhow::Error::downcast_ref] to get the [Error]
+    #[serde(serialize_with = "error_message")]
+    pub error: anyhow::Error,
+}
+
+/// the error with its causes, `outer: inner`
+fn error_message<S: Serializer>(error: &anyhow::Error, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_args!("{:#}", error))
+}
+
+impl Client {
+    /// Applies the same patch operations to many records.  `path_template` is the path of one
+    /// record with `{id}` where the id goes (`/service/tickets/{id}`).  Up to `concurrency`
+    /// requests are sent at once.  Every id is tried, the failures are collected in the
+    /// report rather than stopping the batch.  When the api rate limits us (a 429) the whole
+    /// batch waits and the record is tried again
+    ///
+    /// Use [Client::patch_bulk_with] for a dry run or progress reporting
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, PatchOp, PatchOperation};
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ops = [PatchOperation::new(PatchOp::Replace, "status/id", json!(42))];
+    /// let report = client.patch_bulk("/service/tickets/{id}", &[101, 102, 103], &ops, 4);
+    /// for failure in report.failed.iter() {
+    ///     println!("{} failed: {}", failure.id, failure.error);
+    /// }
+    /// ```
+    pub fn patch_bulk(
+        &self,
+        path_template: &str,
+        ids: &[i64],
+        ops: &[PatchOperation],
+        concurrency: usize,
+    ) -> BulkReport {
+        self.patch_bulk_with(
+            path_template,
+            ids,
+            ops,
+            BulkOptions::default().concurrency(concurrency),
+        )
+    }
+
+    /// Same as [Client::patch_bulk] with [BulkOptions] for a dry run or progress reporting
+    pub fn patch_bulk_with(
+        &self,
+        path_template: &str,
+        ids: &[i64],
+        ops: &[PatchOperation],
+        options: BulkOptions,
+    ) -> BulkReport {
+        run_bulk(ids, &options, |id| {
+            let path = bulk_path(path_template, id)?;
+            if options.dry_run {
+                return Ok(());
+            }
+            self.patch_ops(&path, ops).map(|_| ())
+        })
+    }
+}
+
+impl Client {
+    /// Deletes many records.  `path_template` is the path of one record with `{id}` where the
+    /// id goes (`/schedule/entries/{id}`).  As with [Client::patch_bulk] up to `concurrency`
+    /// requests are sent at once, every id is tried and a rate limit slows the batch down
+    /// rather than failing it.  A record that is already gone is a failure
+    /// ([Error::NotFound]) unless [BulkDeleteOptions::missing_ok] is set
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{BulkDeleteOptions, Client};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let options = BulkDeleteOptions::default()
+    ///     .concurrency(4)
+    ///     .missing_ok(true)
+    ///     .dry_run(true);
+    /// let report = client.delete_bulk("/schedule/entries/{id}", &[101, 102, 103], options);
+    /// println!("{}", serde_json::to_string(&report).unwrap());
+    /// ```
+    pub fn delete_bulk(
+        &self,
+        path_template: &str,
+        ids: &[i64],
+        options: BulkDeleteOptions,
+    ) -> BulkReport {
+        run_bulk(ids, &options.bulk, |id| {
+            let path = bulk_path(path_template, id)?;
+            let result = match options.bulk.dry_run {
+                true => self.get_single(&path, &[("fields", "id")]).map(|_| ()),
+                false => self.delete(&path),
+            };
+            match result {
+                Err(e) if options.missing_ok && is_not_found(&e) => Ok(()),
+                result => result,
+            }
+        })
+    }
+}
+
+impl Client {
+    /// Applies a batch of different writes (posts, patches and deletes), one at a time.  Every
+    /// operation is tried and a rate limit slows the batch down rather than failing it, as
+    /// with [Client::patch_bulk].  A read only client ([Client::read_only]) sends nothing and
+    /// every operation fails with [Error::ReadOnly]
+    ///
+    /// Use [Client::apply_batch_with] to send more at once, stop on the first failure or for a
+    /// dry run
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{BatchOp, BatchOptions, Client, PatchOp, PatchOperation};
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ops = vec![
+    ///     BatchOp::Patch {
+    ///         path: "/service/tickets/101".to_string(),
+    ///         ops: vec![PatchOperation::new(PatchOp::Replace, "status/id", json!(42))],
+    ///     },
+    ///     BatchOp::Post {
+    ///         path: "/sales/activities".to_string(),
+    ///         body: json!({"name": "follow up", "company": {"id": 250}}),
+    ///     },
+    ///     BatchOp::Delete {
+    ///         path: "/schedule/entries/7".to_string(),
+    ///     },
+    /// ];
+    /// let options = BatchOptions::default().concurrency(4).stop_on_error(true);
+    /// let report = client.apply_batch_with(ops, options);
+    /// println!("{}", serde_json::to_string(&report).unwrap());
+    /// ```
+    pub fn apply_batch(&self, ops: Vec<BatchOp>) -> BatchReport {
+        self.apply_batch_with(ops, BatchOptions::default())
+    }
+
+    /// Same as [Client::apply_batch] with [BatchOptions]
+    pub fn apply_batch_with(&self, ops: Vec<BatchOp>, options: BatchOptions) -> BatchReport {
+        let dry_run = options.bulk.dry_run;
+        let outcomes = run_each(&ops, &options.bulk, options.stop_on_error, |op| {
+            match (op, dry_run) {
+                (BatchOp::Post { path, .. }, true) => self.url_for(path, &[]).map(|_| None),
+                (BatchOp::Patch { path, .. }, true) | (BatchOp::Delete { path }, true) => {
+                    self.get_single(path, &[("fields", "id")]).map(|_| None)
+                }
+                (BatchOp::Post { path, body }, false) => {
+                    self.post(path, body.to_string()).map(Some)
+                }
+                (BatchOp::Patch { path, ops }, false) => self.patch_ops(path, ops).map(Some),
+                (BatchOp::Delete { path }, false) => self.delete(path).map(|_| None),
+            }
+        });
+
+        let entries = ops
+            .into_iter()
+            .zip(outcomes)
+            .map(|(op, outcome)| BatchEntry {
+                op,
+                outcome: match outcome {
+                    Some(Ok(record)) => BatchOutcome::Succeeded { record },
+                    Some(Err(error)) => BatchOutcome::Failed { error },
+                    None => BatchOutcome::Skipped,
+                },
+            })
+            .collect();
+        BatchReport { entries, dry_run }
+    }
+}
+
+fn is_not_found(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<Error>(), Some(Error::NotFound { .. }))
+}
+
+/// puts the id into a path template
+pub(crate) fn bulk_path(path_template: &str, id: i64) -> Result<String> {
+    match path_template.contains("{id}") {
+        true => Ok(path_template.replace("{id}", &id.to_string())),
+        false => Err(Error::InvalidPath {
+            path: path_template.to_string(),
+            reason: "the path template has no {id}".to_string(),
+        }
+        .into()),
+    }
+}
+
+/// runs `f` for every id on `options.concurrency` threads, waiting and retrying when rate
+/// limited
+pub(crate) fn run_bulk<F>(ids: &[i64], options: &BulkOptions, f: F) -> BulkReport
+where
+    F: Fn(i64) -> Result<()> + Sync,
+{
+    let outcomes = run_each(ids, options, false, |id| f(*id));
+
+    let mut report = BulkReport {
+        dry_run: options.dry_run,
+        ..Default::default()
+    };
+    for (id, outcome) in ids.iter().zip(outcomes) {
+        match outcome {
+            Some(Ok(())) => report.succeeded.push(*id),
+            Some(Err(error)) => report.failed.push(BulkFailure { id: *id, error }),
+            None => report.not_started.push(*id),
+        }
+    }
+    report
+}
+
+/// runs `f` for every item on `options.concurrency` threads, waiting and retrying when rate
+/// limited.  The outcomes are in the order of the items, `None` for the ones not started
+/// because an earlier one failed and `stop_on_error` is set, or one was [Error::Cancelled]
+pub(crate) fn run_each<T, R, F>(
+    items: &[T],
+    options: &BulkOptions,
+    stop_on_error: bool,
+    f: F,
+) -> Vec<Option<Result<R>>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    let paused_until: Mutex<Option<Instant>> = Mutex::new(None);
+    let outcomes: Mutex<Vec<(usize, Result<R>)>> = Mutex::new(Vec::with_capacity(items.len()));
+
+    let worker = || loop {
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        let item = match items.get(index) {
+            Some(item) => item,
+            None => break,
+        };
+
+        let mut attempt = 0;
+        let outcome = loop {
+            wait_for_pause(&paused_until);
+            attempt += 1;
+            let result = f(item);
+            let retry_after = match result.as_ref().err().and_then(|e| e.downcast_ref()) {
+                Some(Error::RateLimited { retry_after }) if attempt <= RATE_LIMIT_RETRIES => {
+                    retry_after.unwrap_or(RATE_LIMIT_WAIT * attempt)
+                }
+                _ => break result,
+            };
+            let until = Instant::now() + retry_after;
+            let mut paused = paused_until.lock().unwrap();
+            if paused.is_none_or(|p| p < until) {
+                *paused = Some(until);
+            }
+        };
+
+        let cancelled = matches!(
+            outcome.as_ref().err().and_then(|e| e.downcast_ref()),
+            Some(Error::Cancelled { .. })
+        );
+        if cancelled || (stop_on_error && outcome.is_err()) {
+            stopped.store(true, Ordering::SeqCst);
+        }
+        outcomes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((index, outcome));
+        let finished = done.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Some(progress) = &options.progress {
+            progress(finished, items.len());
+        }
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 1..options.concurrency.min(items.len()) {
+            scope.spawn(worker);
+        }
+        worker();
+    });
+
+    let mut ordered: Vec<Option<Result<R>>> = items.iter().map(|_| None).collect();
+    // a worker that panicked poisons the lock, the outcomes it holds are still good
+    let outcomes = outcomes
+        .into_inner()
+        .unwrap_or_else(PoisonError::into_inner);
+    for (index, outcome) in outcomes {
+        ordered[index] = Some(outcome);
+    }
+    ordered
+}
+
+fn wait_for_pause(paused_until: &Mutex<Option<Instant>>) {
+    let until = *paused_until.lock().unwrap();
+    if let Some(until) = until {
+        let now = Instant::now();
+        if until > now {
+            std::thread::sleep(until - now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use crate::PatchOp;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::sync::atomic::AtomicUsize;
+
+    fn ticket_path(id: i64) -> String {
+        format!("/v4_6_release/apis/3.0/service/tickets/{}", id)
+    }
+
+    #[test]
+    fn test_patch_bulk() {
+        let mut mock = MockCw::new();
+        let body = Matcher::Json(json!([{"op": "replace", "path": "status/id", "value": 42}]));
+        let ok = [1, 3, 4].map(|id| {
+            mock.server()
+                .mock("PATCH", ticket_path(id).as_str())
+                .match_body(body.clone())
+                .with_body(json!({"id": id}).to_string())
+                .expect(1)
+                .create()
+        });
+        let bad = mock
+            .server()
+            .mock("PATCH", ticket_path(2).as_str())
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "status is closed"}"#)
+            .expect(1)
+            .create();
+        let ops = [PatchOperation::new(
+            PatchOp::Replace,
+            "status/id",
+            json!(42),
+        )];
+
+        let report = mock
+            .client()
+            .patch_bulk("/service/tickets/{id}", &[1, 2, 3, 4], &ops, 3);
+
+        ok.iter().for_each(|m| m.assert());
+        bad.assert();
+        assert_eq!(report.succeeded, vec![1, 3, 4]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].id, 2);
+        assert!(!report.is_success());
+    }
+
+    #[test]
+    fn test_patch_bulk_rate_limited() {
+        let mut mock = MockCw::new();
+        let limited = mock
+            .server()
+            .mock("PATCH", ticket_path(1).as_str())
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(2)
+            .create();
+        let ok = mock
+            .server()
+            .mock("PATCH", ticket_path(1).as_str())
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+        let ops = [PatchOperation::new(PatchOp::Replace, "summary", json!("x"))];
+
+        let report = mock
+            .client()
+            .patch_bulk("/service/tickets/{id}", &[1], &ops, 1);
+
+        limited.assert();
+        ok.assert();
+        assert_eq!(report.succeeded, vec![1]);
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn test_patch_bulk_dry_run() {
+        let mut mock = MockCw::new();
+        let nothing = mock.server().mock("PATCH", Matcher::Any).expect(0).create();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let options = BulkOptions::default()
+            .dry_run(true)
+            .on_progress(move |_done, total| {
+                assert_eq!(total, 2);
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        let ops = [PatchOperation::new(PatchOp::Replace, "summary", json!("x"))];
+
+        let report = mock
+            .client()
+            .patch_bulk_with("/service/tickets/{id}", &[1, 2], &ops, options);
+
+        nothing.assert();
+        assert!(report.dry_run);
+        assert_eq!(report.succeeded, vec![1, 2]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_delete_bulk() {
+        let mut mock = MockCw::new();
+        let path = |id: i64| format!("/v4_6_release/apis/3.0/schedule/entries/{}", id);
+        let deleted = mock
+            .server()
+            .mock("DELETE", path(1).as_str())
+            .with_status(204)
+            .expect(1)
+            .create();
+        let _gone = mock
+            .server()
+            .mock("DELETE", path(2).as_str())
+            .with_status(404)
+            .create();
+        let limited = mock
+            .server()
+            .mock("DELETE", path(3).as_str())
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create();
+        let later = mock
+            .server()
+            .mock("DELETE", path(3).as_str())
+            .with_status(204)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let report = client.delete_bulk(
+            "/schedule/entries/{id}",
+            &[1, 2, 3],
+            BulkDeleteOptions::default().concurrency(2),
+        );
+        let again = client.delete_bulk(
+            "/schedule/entries/{id}",
+            &[2],
+            BulkDeleteOptions::default().missing_ok(true),
+        );
+
+        deleted.assert();
+        limited.assert();
+        later.assert();
+        assert_eq!(report.succeeded, vec![1, 3]);
+        assert_eq!(report.failed[0].id, 2);
+        assert!(again.is_success());
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "succeeded": [1, 3],
+                "failed": [{"id": 2, "error": "not found: /schedule/entries/2"}],
+                "dry_run": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_delete_bulk_dry_run() {
+        let mut mock = MockCw::new();
+        let nothing = mock
+            .server()
+            .mock("DELETE", Matcher::Any)
+            .expect(0)
+            .create();
+        let _found = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/schedule/entries/1")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 1}"#)
+            .create();
+        let _missing = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/schedule/entries/2")
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .create();
+
+        let report = mock.client().delete_bulk(
+            "/schedule/entries/{id}",
+            &[1, 2],
+            BulkDeleteOptions::default().dry_run(true),
+        );
+
+        nothing.assert();
+        assert!(report.dry_run);
+        assert_eq!(report.succeeded, vec![1]);
+        assert_eq!(report.failed[0].id, 2);
+    }
+
+    fn batch() -> Vec<BatchOp> {
+        vec![
+            BatchOp::Patch {
+                path: "/service/tickets/1".to_string(),
+                ops: vec![PatchOperation::new(
+                    PatchOp::Replace,
+                    "status/id",
+                    json!(42),
+                )],
+            },
+            BatchOp::Post {
+                path: "/sales/activities".to_string(),
+                body: json!({"name": "follow up"}),
+            },
+            BatchOp::Delete {
+                path: "/schedule/entries/7".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_apply_batch() {
+        let mut mock = MockCw::new();
+        let patched = mock
+            .server()
+            .mock("PATCH", ticket_path(1).as_str())
+            .match_body(Matcher::Json(
+                json!([{"op": "replace", "path": "status/id", "value": 42}]),
+            ))
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+        let posted = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/sales/activities")
+            .match_body(Matcher::Json(json!({"name": "follow up"})))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "company is required"}"#)
+            .expect(1)
+            .create();
+        let deleted = mock
+            .server()
+            .mock("DELETE", "/v4_6_release/apis/3.0/schedule/entries/7")
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        let report = mock
+            .client()
+            .apply_batch_with(batch(), BatchOptions::default().concurrency(2));
+
+        patched.assert();
+        posted.assert();
+        deleted.assert();
+        assert!(!report.is_success());
+        let (op, error) = report.failed().next().unwrap();
+        assert_eq!(op.path(), "/sales/activities");
+        assert!(matches!(
+            error.downcast_ref::<Error>(),
+            Some(Error::Api { status: 400, .. })
+        ));
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "entries": [
+                    {
+                        "op": {
+                            "op": "patch",
+                            "path": "/service/tickets/1",
+                            "ops": [{"op": "replace", "path": "status/id", "value": 42}]
+                        },
+                        "outcome": "succeeded",
+                        "record": {"id": 1}
+                    },
+                    {
+                        "op": {"op": "post", "path": "/sales/activities", "body": {"name": "follow up"}},
+                        "outcome": "failed",
+                        "error": format!("{:#}", error)
+                    },
+                    {
+                        "op": {"op": "delete", "path": "/schedule/entries/7"},
+                        "outcome": "succeeded",
+                        "record": null
+                    }
+                ],
+                "dry_run": false
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_batch_stop_on_error() {
+        let mut mock = MockCw::new();
+        let _patched = mock
+            .server()
+            .mock("PATCH", ticket_path(1).as_str())
+            .with_status(404)
+            .expect(1)
+            .create();
+        let nothing_else = mock.server().mock("POST", Matcher::Any).expect(0).create();
+
+        let report = mock
+            .client()
+            .apply_batch_with(batch(), BatchOptions::default().stop_on_error(true));
+
+        nothing_else.assert();
+        assert!(matches!(
+            report.entries[0].outcome,
+            BatchOutcome::Failed { .. }
+        ));
+        assert!(matches!(report.entries[1].outcome, BatchOutcome::Skipped));
+        assert!(matches!(report.entries[2].outcome, BatchOutcome::Skipped));
+    }
+
+    #[test]
+    fn test_apply_batch_read_only_dry_run() {
+        let mut mock = MockCw::new();
+        let writes = ["POST", "PATCH", "DELETE"]
+            .map(|method| mock.server().mock(method, Matcher::Any).expect(0).create());
+        let _ticket = mock
+            .server()
+            .mock("GET", ticket_path(1).as_str())
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 1}"#)
+            .create();
+        let _missing = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/schedule/entries/7")
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .create();
+        let client = mock.client().read_only(true);
+
+        let report = client.apply_batch(batch());
+        assert!(report.entries.iter().all(|e| matches!(
+            &e.outcome,
+            BatchOutcome::Failed { error } if matches!(error.downcast_ref(), Some(Error::ReadOnly))
+        )));
+
+        // a dry run sends nothing, so a read only client can preview a batch
+        let report = client.apply_batch_with(batch(), BatchOptions::default().dry_run(true));
+        writes.iter().for_each(|m| m.assert());
+        assert!(report.dry_run);
+        assert!(matches!(
+            report.entries[0].outcome,
+            BatchOutcome::Succeeded { record: None }
+        ));
+        assert!(matches!(
+            report.entries[1].outcome,
+            BatchOutcome::Succeeded { record: None }
+        ));
+        let (op, error) = report.failed().next().unwrap();
+        assert_eq!(op.path(), "/schedule/entries/7");
+        assert!(matches!(error.downcast_ref(), Some(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_bulk_path() {
+        assert_eq!(
+            bulk_path("/service/tickets/{id}/notes", 7).unwrap(),
+            "/service/tickets/7/notes"
+        );
+        let err = bulk_path("/service/tickets", 7).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidPath { .. })
+        ));
+    }
+}
@@ -0,0 +1,249 @@
+//! Parsing the callbacks (webhooks) connectwise posts to your own service
+//!
+//! ```
+//! use cwmanage::callbacks::{Action, CallbackPayload};
+//!
+//! let body = r#"{
+//!     "FromUrl": "na.myconnectwise.net",
+//!     "CompanyId": "myco",
+//!     "MemberId": "zpeters",
+//!     "Action": "updated",
+//!     "Type": "ticket",
+//!     "ID": 123,
+//!     "Entity": "{\"id\":123,\"summary\":\"Printer on fire\"}"
+//! }"#;
+//! let payload: CallbackPayload = serde_json::from_str(body).unwrap();
+//!
+//! assert_eq!(payload.action, Action::Updated);
+//! assert_eq!(payload.entity["summary"], "Printer on fire");
+//! ```
+use crate::{Client, Error};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use url::Url;
+
+/// What happened to the record
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    /// the record was created
+    Added,
+    /// the record was changed
+    Updated,
+    /// the record was deleted, there is no entity
+    Deleted,
+    /// an action this crate doesn't know about
+    #[serde(other)]
+    Unknown,
+}
+
+/// The body of a callback.  Connectwise sends the record (`Entity`) as a json string inside of
+/// the json, it is decoded into [CallbackPayload::entity]
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CallbackPayload {
+    /// the connectwise instance that sent the callback
+    pub from_url: String,
+    /// the company id (short name) of the instance
+    pub company_id: String,
+    /// the member whose change triggered the callback
+    #[serde(default)]
+    pub member_id: String,
+    /// what happened
+    pub action: Action,
+    /// the kind of record (`ticket`, `company`, ...)
+    #[serde(rename = "Type")]
+    pub callback_type: String,
+    /// the id of the record
+    #[serde(rename = "ID")]
+    pub id: i64,
+    /// the record, `Value::Null` when it was deleted
+    #[serde(default, deserialize_with = "decode_entity")]
+    pub entity: Value,
+}
+
+impl CallbackPayload {
+    /// Converts the entity into your own type
+    ///
+    /// ```
+    /// use cwmanage::callbacks::CallbackPayload;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Ticket {
+    ///     id: i64,
+    ///     summary: String,
+    /// }
+    ///
+    /// let body = r#"{"FromUrl": "na.myconnectwise.net", "CompanyId": "myco", "Action": "added",
+    ///     "Type": "ticket", "ID": 1, "Entity": "{\"id\":1,\"summary\":\"new laptop\"}"}"#;
+    /// let payload: CallbackPayload = serde_json::from_str(body).unwrap();
+    /// let ticket: Ticket = payload.entity_as().unwrap();
+    /// assert_eq!(ticket.summary, "new laptop");
+    /// ```
+    pub fn entity_as<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_value(self.entity.clone())?)
+    }
+}
+
+/// the entity is normally a string of json, accept an object too in case that changes
+fn decode_entity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::String(s) if s.trim().is_empty() => Ok(Value::Null),
+        Value::String(s) => serde_json::from_str(&s).map_err(serde::de::Error::custom),
+        other => Ok(other),
+    }
+}
+
+impl Client {
+    /// Checks a callback came from the instance (and company) this client is set up for, so a
+    /// callback from another tenant isn't acted on.  The `api-` prefix of cloud hosts is
+    /// ignored, as is case.  Returns [Error::ForeignCallback] when it doesn't match
+    ///
+    /// ```
+    /// use cwmanage::callbacks::CallbackPayload;
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let body = r#"{"FromUrl": "https://eu.myconnectwise.net", "CompanyId": "myco",
+    ///     "Action": "deleted", "Type": "ticket", "ID": 1}"#;
+    /// let payload: CallbackPayload = serde_json::from_str(body).unwrap();
+    ///
+    /// assert!(client.verify_callback(&payload).is_err());
+    /// ```
+    pub fn verify_callback(&self, payload: &CallbackPayload) -> Result<()> {
+        let ours = callback_host(&self.api_url);
+        let theirs = callback_host(&payload.from_url);
+
+        match ours.is_some()
+            && ours == theirs
+            && payload.company_id.eq_ignore_ascii_case(&self.company_id)
+        {
+            true => Ok(()),
+            false => Err(Error::ForeignCallback {
+                from_url: payload.from_url.to_string(),
+                company_id: payload.company_id.to_string(),
+            }
+            .into()),
+        }
+    }
+}
+
+/// the host of a url that may or may not have a scheme, lowercased and without `api-`
+fn callback_host(url: &str) -> Option<String> {
+    let url = match url.contains("://") {
+        true => Url::parse(url).ok()?,
+        false => Url::parse(&format!("https://{}", url)).ok()?,
+    };
+    let host = url.host_str()?.to_lowercase();
+    match host.strip_prefix("api-") {
+        Some(host) => Some(host.to_string()),
+        None => Some(host),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    const TICKET_UPDATED: &str = include_str!("../tests/fixtures/callbacks/ticket_updated.json");
+    const COMPANY_ADDED: &str = include_str!("../tests/fixtures/callbacks/company_added.json");
+    const TICKET_DELETED: &str = include_str!("../tests/fixtures/callbacks/ticket_deleted.json");
+
+    #[test]
+    fn test_ticket_updated() {
+        let payload: CallbackPayload = serde_json::from_str(TICKET_UPDATED).unwrap();
+
+        assert_eq!(payload.action, Action::Updated);
+        assert_eq!(payload.callback_type, "ticket");
+        assert_eq!(payload.id, 123);
+        assert_eq!(payload.member_id, "zpeters");
+        assert_eq!(
+            payload.entity["board"],
+            json!({"id": 1, "name": "Help Desk"})
+        );
+    }
+
+    #[test]
+    fn test_company_added_entity_as() {
+        #[derive(Debug, Deserialize)]
+        struct Company {
+            id: i64,
+            identifier: String,
+        }
+
+        let payload: CallbackPayload = serde_json::from_str(COMPANY_ADDED).unwrap();
+        let company: Company = payload.entity_as().unwrap();
+
+        assert_eq!(payload.action, Action::Added);
+        assert_eq!(company.id, 250);
+        assert_eq!(company.identifier, "ACME");
+    }
+
+    #[test]
+    fn test_ticket_deleted() {
+        let payload: CallbackPayload = serde_json::from_str(TICKET_DELETED).unwrap();
+
+        assert_eq!(payload.action, Action::Deleted);
+        assert_eq!(payload.entity, Value::Null);
+    }
+
+    #[test]
+    fn test_entity_object_and_unknown_action() {
+        let payload: CallbackPayload = serde_json::from_value(json!({
+            "FromUrl": "na.myconnectwise.net",
+            "CompanyId": "myco",
+            "Action": "merged",
+            "Type": "company",
+            "ID": 1,
+            "Entity": {"id": 1}
+        }))
+        .unwrap();
+
+        assert_eq!(payload.action, Action::Unknown);
+        assert_eq!(payload.entity, json!({"id": 1}));
+    }
+
+    #[test]
+    fn test_verify_callback() {
+        let ticket: CallbackPayload = serde_json::from_str(TICKET_UPDATED).unwrap();
+        let company: CallbackPayload = serde_json::from_str(COMPANY_ADDED).unwrap();
+        let mock = MockCw::new();
+        let client = |api_url: &str| mock.client().api_url(api_url.to_string());
+
+        assert!(client("na.myconnectwise.net")
+            .verify_callback(&ticket)
+            .is_ok());
+        assert!(client("api-na.myconnectwise.net")
+            .verify_callback(&company)
+            .is_ok());
+
+        let err = client("eu.myconnectwise.net")
+            .verify_callback(&ticket)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::ForeignCallback {
+                from_url: "na.myconnectwise.net".to_string(),
+                company_id: "myco".to_string(),
+            })
+        );
+
+        let mut other_company = ticket.clone();
+        other_company.company_id = "otherco".to_string();
+        assert!(client("na.myconnectwise.net")
+            .verify_callback(&other_company)
+            .is_err());
+    }
+}
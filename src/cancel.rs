@@ -0,0 +1,189 @@
+//! Stopping multi request operations from another thread, see [Client::cancel_on]
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// how often a retry backoff checks whether it was cancelled
+const CANCEL_POLL: Duration = Duration::from_millis(20);
+
+/// A flag shared by its clones that cancels the requests of the clients it is set on, see
+/// [Client::cancel_on](crate::Client::cancel_on).  Once cancelled it stays cancelled
+///
+/// ```
+/// use cwmanage::CancelToken;
+///
+/// let token = CancelToken::new();
+/// let clone = token.clone();
+/// clone.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// A token that isn't cancelled
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    /// Cancels the token and its clones
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// true once [CancelToken::cancel] was called on the token or a clone
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// sleeps for `wait`, waking early when cancelled.  true if it was
+    pub(crate) fn sleep(&self, wait: Duration) -> bool {
+        let until = Instant::now() + wait;
+        loop {
+            if self.is_cancelled() {
+                return true;
+            }
+            let left = until.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return false;
+            }
+            std::thread::sleep(left.min(CANCEL_POLL));
+        }
+    }
+}
+
+/// Tokens are equal when they are clones of each other
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for CancelToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CancelToken")
+            .field(&self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw, API_PREFIX};
+    use crate::{BulkDeleteOptions, Error};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::Value;
+
+    fn is_cancelled(e: &anyhow::Error) -> bool {
+        matches!(e.downcast_ref(), Some(Error::Cancelled { .. }))
+    }
+
+    #[test]
+    fn test_cancel_between_pages() {
+        let mut mock = MockCw::new();
+        let next = format!(
+            "<{}{}/service/tickets?pageId=2>; rel=\"next\"",
+            mock.url(),
+            API_PREFIX
+        );
+        let path = format!("{}/service/tickets", API_PREFIX);
+        let first = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body(Value::Array(vec![fixtures::ticket(1)]).to_string())
+            .expect(1)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_body("[]")
+            .expect(0)
+            .create();
+        let token = CancelToken::new();
+        let client = mock.client().cancel_on(token.clone());
+
+        let mut pages = client.pages("/service/tickets", &[]);
+        assert_eq!(pages.next().unwrap().unwrap().len(), 1);
+        token.cancel();
+        let err = pages.next().unwrap().unwrap_err();
+        assert!(pages.next().is_none());
+
+        assert!(is_cancelled(&err));
+        assert_eq!(
+            err.to_string(),
+            "cancelled, didn't GET /v4_6_release/apis/3.0/service/tickets"
+        );
+        assert!(is_cancelled(
+            &client.get("/service/tickets", &[]).unwrap_err()
+        ));
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn test_cancel_during_backoff() {
+        let mut mock = MockCw::new();
+        let unavailable = mock
+            .server()
+            .mock("GET", format!("{}/system/info", API_PREFIX).as_str())
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .expect(1)
+            .create();
+        let token = CancelToken::new();
+        let client = mock
+            .client()
+            .max_retries(3)
+            .retry_backoff(Duration::from_secs(30))
+            .cancel_on(token.clone());
+
+        let started = Instant::now();
+        let err = std::thread::scope(|scope| {
+            let call = scope.spawn(|| client.get_single("/system/info", &[]));
+            // cancel once the first attempt failed, while waiting to retry
+            while !unavailable.matched() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            token.cancel();
+            call.join().unwrap().unwrap_err()
+        });
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(is_cancelled(&err));
+        unavailable.assert();
+    }
+
+    #[test]
+    fn test_cancel_bulk() {
+        let mut mock = MockCw::new();
+        let deletes = mock
+            .server()
+            .mock("DELETE", Matcher::Any)
+            .with_status(204)
+            .expect(0)
+            .create();
+        let token = CancelToken::new();
+        token.cancel();
+        let client = mock.client().cancel_on(token);
+
+        let report = client.delete_bulk(
+            "/service/tickets/{id}",
+            &[1, 2, 3],
+            BulkDeleteOptions::default().concurrency(2),
+        );
+
+        deletes.assert();
+        assert!(!report.is_success());
+        assert!(report.succeeded.is_empty());
+        // the workers stop at the first cancelled id
+        assert!(report.failed.len() <= 2);
+        assert!(report.failed.iter().all(|f| is_cancelled(&f.error)));
+        assert_eq!(report.failed.len() + report.not_started.len(), 3);
+    }
+}
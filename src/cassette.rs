@@ -0,0 +1,305 @@
+//! Record and replay of api traffic so tests can run without a connectwise instance.
+//! See [crate::Client::cassette]
+use crate::RawResponse;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Response headers that are never written to a cassette
+const SKIPPED_HEADERS: &[&str] = &["authorization", "set-cookie"];
+
+/// How a cassette file is used
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CassetteMode {
+    /// Requests go to the api as normal and each request and its response is written to the
+    /// cassette (replacing what was there).  Request headers, including `Authorization`, are
+    /// never written
+    Record,
+    /// Requests are answered from the cassette and nothing is sent.  Requests are matched on
+    /// method, path and query (the order of the query doesn't matter).  A request that isn't in
+    /// the cassette is an error showing what was recorded for the same method and path
+    Replay,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Interaction {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    response: RecordedResponse,
+}
+
+impl Interaction {
+    fn describe(&self) -> String {
+        let query: Vec<String> = self
+            .query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        format!("{} {}?{}", self.method, self.path, query.join("&"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    interactions: Vec<Interaction>,
+    used: Vec<bool>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    state: Mutex<Option<State>>,
+}
+
+impl PartialEq for Cassette {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path && self.mode == other.mode
+    }
+}
+
+impl Cassette {
+    pub(crate) fn new(path: PathBuf, mode: CassetteMode) -> Cassette {
+        Cassette {
+            path,
+            mode,
+            state: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn execute(
+        &self,
+        http: &reqwest::blocking::Client,
+        req: reqwest::blocking::Request,
+    ) -> Result<RawResponse> {
+        let mut query: Vec<(String, String)> = req
+            .url()
+            .query_pairs()
+            .into_owned()
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+        query.sort();
+        let mut interaction = Interaction {
+            method: req.method().to_string(),
+            path: req.url().path().to_string(),
+            query,
+            body: req
+                .body()
+                .and_then(|b| b.as_bytes())
+                .map(|b| String::from_utf8_lossy(b).to_string()),
+            response: RecordedResponse {
+                status: 0,
+                headers: BTreeMap::new(),
+                body: String::new(),
+            },
+        };
+
+        let mut guard = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("cassette lock poisoned"))?;
+        if guard.is_none() {
+            *guard = Some(self.load()?);
+        }
+        let state = guard.as_mut().expect("cassette state is loaded");
+
+        match self.mode {
+            CassetteMode::Record => {
+                let res = crate::execute(http, req)?;
+                interaction.response = RecordedResponse {
+                    status: res.status.as_u16(),
+                    headers: res
+                        .headers
+                        .iter()
+                        .filter(|(k, _)| !SKIPPED_HEADERS.contains(&k.as_str()))
+                        .map(|(k, v)| {
+                            (
+                                k.to_string(),
+                                String::from_utf8_lossy(v.as_bytes()).to_string(),
+                            )
+                        })
+                        .collect(),
+                    body: res.body.clone(),
+                };
+                state.interactions.push(interaction);
+                self.save(&state.interactions)?;
+                Ok(res)
+            }
+            CassetteMode::Replay => {
+                let found = state.interactions.iter().enumerate().position(|(i, r)| {
+                    !state.used[i]
+                        && r.method == interaction.method
+                        && r.path == interaction.path
+                        && r.query == interaction.query
+                });
+                match found {
+                    Some(i) => {
+                        state.used[i] = true;
+                        replayed(&state.interactions[i].response)
+                    }
+                    None => Err(self.mismatch(state, &interaction)),
+                }
+            }
+        }
+    }
+
+    fn load(&self) -> Result<State> {
+        match self.mode {
+            CassetteMode::Record => Ok(State::default()),
+            CassetteMode::Replay => {
+                let raw = fs::read_to_string(&self.path)
+                    .map_err(|e| anyhow!("cannot read cassette {:?}: {}", self.path, e))?;
+                let interactions: Vec<Interaction> = serde_json::from_str(&raw)
+                    .map_err(|e| anyhow!("cannot parse cassette {:?}: {}", self.path, e))?;
+                Ok(State {
+                    used: vec![false; interactions.len()],
+                    interactions,
+                })
+            }
+        }
+    }
+
+    fn save(&self, interactions: &[Interaction]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(interactions)?)?;
+        Ok(())
+    }
+
+    fn mismatch(&self, state: &State, interaction: &Interaction) -> anyhow::Error {
+        let mut message = format!(
+            "no recorded response in {:?} for\n+ {}\n",
+            self.path,
+            interaction.describe()
+        );
+        let candidates: Vec<String> = state
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.method == interaction.method && r.path == interaction.path)
+            .map(|(i, r)| match state.used[i] {
+                true => format!("- {} (already used)", r.describe()),
+                false => format!("- {}", r.describe()),
+            })
+            .collect();
+        match candidates.is_empty() {
+            true => message.push_str("nothing was recorded for this method and path"),
+            false => {
+                message.push_str("recorded for this method and path:\n");
+                message.push_str(&candidates.join("\n"));
+            }
+        }
+        anyhow!(message)
+    }
+}
+
+fn replayed(recorded: &RecordedResponse) -> Result<RawResponse> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    for (k, v) in recorded.headers.iter() {
+        headers.insert(
+            reqwest::header::HeaderName::from_bytes(k.as_bytes())?,
+            reqwest::header::HeaderValue::from_str(v)?,
+        );
+    }
+    Ok(RawResponse {
+        status: reqwest::StatusCode::from_u16(recorded.status)?,
+        headers,
+        body: recorded.body.clone(),
+        redaction: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::MockCw;
+    use crate::CassetteMode;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn cassette_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "cwmanage-cassette-{}-{}.json",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let path = cassette_path("record-then-replay");
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_header("set-cookie", "session=secret")
+            .with_body(r#"{"isCloud": true}"#)
+            .create();
+
+        let recorder = mock.client().cassette(&path, CassetteMode::Record).build();
+        let recorded = recorder
+            .get_single("/system/info", &[("fields", "isCloud"), ("", "")])
+            .unwrap();
+        drop(mock);
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("secret"));
+        assert!(!raw.contains("Basic"));
+
+        // nothing is listening here, the replay must not need the network
+        let player = MockCw::new()
+            .client()
+            .api_url("http://127.0.0.1:9".to_string())
+            .cassette(&path, CassetteMode::Replay)
+            .build();
+        let replayed = player
+            .get_single("/system/info", &[("fields", "isCloud")])
+            .unwrap();
+
+        assert_eq!(replayed, recorded);
+        assert_eq!(replayed, json!({"isCloud": true}));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_mismatch() {
+        let path = cassette_path("mismatch");
+        std::fs::write(
+            &path,
+            json!([{
+                "method": "GET",
+                "path": "/v4_6_release/apis/3.0/system/info",
+                "query": [["fields", "isCloud"]],
+                "response": {"status": 200, "headers": {}, "body": "{}"}
+            }])
+            .to_string(),
+        )
+        .unwrap();
+
+        let player = MockCw::new()
+            .client()
+            .api_url("http://127.0.0.1:9".to_string())
+            .cassette(&path, CassetteMode::Replay)
+            .build();
+        let result = player.get_single("/system/info", &[("fields", "version")]);
+
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("+ GET /v4_6_release/apis/3.0/system/info?fields=version"));
+        assert!(message.contains("- GET /v4_6_release/apis/3.0/system/info?fields=isCloud"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
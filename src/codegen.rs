@@ -0,0 +1,452 @@
+//! Serde structs generated from the connectwise OpenAPI (swagger) definitions, for the
+//! entities the crate doesn't have a model for (the `codegen` feature)
+//!
+//! The definitions aren't fetched, download them once (from the developer site, or
+//! `/openapi` of your instance) and keep the file with your code.  Generate from a build
+//! script and include the result in a module:
+//!
+//! ```no_run
+//! // build.rs, with cwmanage (and the codegen feature) in [build-dependencies]
+//! use cwmanage::codegen::{generate_file, CodegenOptions};
+//!
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("cw_models.rs");
+//! let options = CodegenOptions {
+//!     entities: vec!["Ticket".to_string(), "Company".to_string()],
+//!     ..Default::default()
+//! };
+//! generate_file("openapi/manage.json", out, &options).unwrap();
+//! println!("cargo:rerun-if-changed=openapi/manage.json");
+//! ```
+//!
+//! ```ignore
+//! // src/models.rs
+//! include!(concat!(env!("OUT_DIR"), "/cw_models.rs"));
+//! ```
+//!
+//! The generated code uses `serde`, `serde_json` and `cwmanage` (and `chrono` with
+//! [CodegenOptions::dates]), so they have to be dependencies of the crate including it.
+//! References (the `*Reference` schemas) are [crate::CwRef], every field is an `Option`
+//! since connectwise leaves out what wasn't asked for in `fields`
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// What happens to fields the definitions don't have, see [CodegenOptions::unknown_fields]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownFields {
+    /// they are skipped, so a newer api doesn't break the structs
+    Ignore,
+    /// they are kept in an `extra` map on each struct
+    Keep,
+    /// they are an error (`#[serde(deny_unknown_fields)]`), to find out when the api changes
+    Deny,
+}
+
+/// How [generate] writes the structs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodegenOptions {
+    /// the schemas to generate (`Ticket`, `Company`), all of them when empty.  A name that
+    /// isn't in the definitions is an error
+    pub entities: Vec<String>,
+    /// [UnknownFields::Ignore] by default
+    pub unknown_fields: UnknownFields,
+    /// `date-time` fields as `chrono::DateTime<chrono::Utc>` read and written with
+    /// [crate::dates] (needs the `chrono` feature where the code is included), instead of
+    /// strings.  On by default
+    pub dates: bool,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        CodegenOptions {
+            entities: Vec::new(),
+            unknown_fields: UnknownFields::Ignore,
+            dates: true,
+        }
+    }
+}
+
+/// Rust keywords a field can't be named without `r#`
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro",
+    "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where",
+    "while", "yield",
+];
+
+/// Reads the definitions at `input` and writes the structs to `output`, see [generate]
+pub fn generate_file(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    options: &CodegenOptions,
+) -> Result<()> {
+    let (input, output) = (input.as_ref(), output.as_ref());
+    let raw = fs::read_to_string(input)
+        .map_err(|e| anyhow!("cannot read definitions {:?}: {}", input, e))?;
+    let spec: Value = serde_json::from_str(&raw)
+        .map_err(|e| anyhow!("cannot parse definitions {:?}: {}", input, e))?;
+    fs::write(output, generate(&spec, options)?)
+        .map_err(|e| anyhow!("cannot write {:?}: {}", output, e))
+}
+
+/// The structs for the schemas of an OpenAPI 3 (`components/schemas`) or swagger 2
+/// (`definitions`) document, as rust source
+///
+/// ```
+/// use cwmanage::codegen::{generate, CodegenOptions};
+/// use serde_json::json;
+///
+/// let spec = json!({"components": {"schemas": {"Priority": {
+///     "type": "object",
+///     "properties": {"id": {"type": "integer"}, "name": {"type": "string"}}
+/// }}}});
+/// let code = generate(&spec, &CodegenOptions::default()).unwrap();
+/// assert!(code.contains("pub struct Priority {"));
+/// assert!(code.contains("pub name: Option<String>,"));
+/// ```
+pub fn generate(spec: &Value, options: &CodegenOptions) -> Result<String> {
+    let schemas = spec
+        .pointer("/components/schemas")
+        .or_else(|| spec.get("definitions"))
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("no components/schemas or definitions in the document"))?;
+
+    let wanted: BTreeSet<&str> = match options.entities.is_empty() {
+        true => schemas
+            .keys()
+            .filter(|name| !is_reference(name))
+            .map(String::as_str)
+            .collect(),
+        false => options.entities.iter().map(String::as_str).collect(),
+    };
+    if let Some(missing) = wanted.iter().find(|name| !schemas.contains_key(**name)) {
+        return Err(anyhow!("{} is not in the definitions", missing));
+    }
+
+    let mut code = String::from(
+        "// Generated by cwmanage::codegen from the connectwise OpenAPI definitions, do not edit\n",
+    );
+    for name in &wanted {
+        code.push('\n');
+        write_struct(&mut code, name, &schemas[*name], &wanted, options);
+    }
+    Ok(code)
+}
+
+fn write_struct(
+    code: &mut String,
+    name: &str,
+    schema: &Value,
+    wanted: &BTreeSet<&str>,
+    options: &CodegenOptions,
+) {
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    write_doc(code, "", schema, &format!("The `{}` entity", name));
+    code.push_str(
+        "#[derive(Debug, Clone, PartialEq, Default, ::serde::Serialize, ::serde::Deserialize)]\n",
+    );
+    match options.unknown_fields {
+        UnknownFields::Deny => {
+            code.push_str("#[serde(rename_all = \"camelCase\", deny_unknown_fields)]\n")
+        }
+        _ => code.push_str("#[serde(rename_all = \"camelCase\")]\n"),
+    }
+    let _ = writeln!(code, "pub struct {} {{", type_name(name));
+
+    for (field, property) in properties {
+        let rust_name = field_name(field);
+        let (ty, date) = field_type(property, wanted, options);
+        let fallback = match property.get("enum").and_then(Value::as_array) {
+            Some(values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|v| format!("`{}`", v.as_str().unwrap_or_default()))
+                    .collect();
+                format!("`{}`, one of {}", field, values.join(", "))
+            }
+            None => format!("`{}`", field),
+        };
+        write_doc(code, "    ", property, &fallback);
+
+        let mut serde = vec!["default", "skip_serializing_if = \"Option::is_none\""];
+        let rename = format!("rename = \"{}\"", field);
+        if camel_case(rust_name.trim_start_matches("r#")) != *field {
+            serde.insert(0, &rename);
+        }
+        if date {
+            serde.push("with = \"cwmanage::dates::option\"");
+        }
+        let _ = writeln!(code, "    #[serde({})]", serde.join(", "));
+        let _ = writeln!(code, "    pub {}: Option<{}>,", rust_name, ty);
+    }
+
+    if options.unknown_fields == UnknownFields::Keep {
+        code.push_str("    /// the fields the definitions don't have\n");
+        code.push_str("    #[serde(flatten)]\n");
+        code.push_str("    pub extra: ::std::collections::HashMap<String, ::serde_json::Value>,\n");
+    }
+    code.push_str("}\n");
+}
+
+/// the type of a property, and whether it is a date
+fn field_type(
+    property: &Value,
+    wanted: &BTreeSet<&str>,
+    options: &CodegenOptions,
+) -> (String, bool) {
+    if let Some(target) = property.get("$ref").and_then(Value::as_str) {
+        let target = target.rsplit('/').next().unwrap_or_default();
+        let ty = match (is_reference(target), wanted.contains(target)) {
+            (true, _) => "cwmanage::CwRef".to_string(),
+            (false, true) => type_name(target),
+            (false, false) => "::serde_json::Value".to_string(),
+        };
+        return (ty, false);
+    }
+    let kind = property
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let format = property
+        .get("format")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let ty = match kind {
+        "integer" => "i64".to_string(),
+        "number" => "f64".to_string(),
+        "boolean" => "bool".to_string(),
+        "string" if format == "date-time" && options.dates => {
+            return ("chrono::DateTime<chrono::Utc>".to_string(), true)
+        }
+        "string" => "String".to_string(),
+        "array" => {
+            let items = property.get("items").unwrap_or(&Value::Null);
+            format!("Vec<{}>", field_type(items, wanted, options).0)
+        }
+        "object" => match property.get("additionalProperties") {
+            Some(values) if values.is_object() => format!(
+                "::std::collections::HashMap<String, {}>",
+                field_type(values, wanted, options).0
+            ),
+            _ => "::serde_json::Value".to_string(),
+        },
+        _ => "::serde_json::Value".to_string(),
+    };
+    (ty, false)
+}
+
+/// the `description` of a schema as a doc comment, `fallback` when it has none
+fn write_doc(code: &mut String, indent: &str, schema: &Value, fallback: &str) {
+    let description = schema
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .unwrap_or(fallback);
+    for line in description.lines() {
+        let _ = writeln!(code, "{}/// {}", indent, line.trim_end());
+    }
+}
+
+/// connectwise names the `{id, name, _info}` shape `BoardReference`, `CompanyReference`...
+fn is_reference(name: &str) -> bool {
+    name.ends_with("Reference")
+}
+
+fn type_name(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).collect()
+}
+
+/// `dateEntered` is `date_entered`, `_info` is `info` and `type` is `r#type`
+fn field_name(field: &str) -> String {
+    let chars: Vec<char> = field.trim_start_matches('_').chars().collect();
+    let mut name = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            name.push('_');
+            continue;
+        }
+        let prev = if i > 0 { chars.get(i - 1) } else { None };
+        let next_lower = matches!(chars.get(i + 1), Some(n) if n.is_ascii_lowercase());
+        let boundary = match prev {
+            Some(p) if c.is_ascii_uppercase() => {
+                p.is_ascii_lowercase()
+                    || p.is_ascii_digit()
+                    || (p.is_ascii_uppercase() && next_lower)
+            }
+            _ => false,
+        };
+        if boundary && !name.ends_with('_') {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    match KEYWORDS.contains(&name.as_str()) {
+        true => format!("r#{}", name),
+        false => name,
+    }
+}
+
+/// what `rename_all = "camelCase"` makes of a field name
+fn camel_case(name: &str) -> String {
+    let mut camel = String::new();
+    let mut upper = false;
+    for c in name.chars() {
+        match c {
+            '_' => upper = true,
+            c if upper => {
+                camel.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => camel.push(c),
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SPEC: &str = include_str!("../tests/fixtures/codegen/openapi.json");
+    const SNAPSHOT: &str = include_str!("../tests/fixtures/codegen/models.rs");
+
+    fn subset() -> CodegenOptions {
+        CodegenOptions {
+            entities: vec![
+                "Ticket".to_string(),
+                "Company".to_string(),
+                "Member".to_string(),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// the snapshot compiles and reads a record
+    #[cfg(feature = "chrono")]
+    #[allow(dead_code)]
+    mod generated {
+        use crate as cwmanage;
+        include!("../tests/fixtures/codegen/models.rs");
+    }
+
+    #[test]
+    fn test_generate_snapshot() {
+        let spec: Value = serde_json::from_str(SPEC).unwrap();
+        let code = generate(&spec, &subset()).unwrap();
+        // `UPDATE_SNAPSHOTS=1 cargo test --features codegen` rewrites it after a change
+        if std::env::var("UPDATE_SNAPSHOTS").is_ok() {
+            fs::write(
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/tests/fixtures/codegen/models.rs"
+                ),
+                &code,
+            )
+            .unwrap();
+            return;
+        }
+        assert_eq!(code, SNAPSHOT);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_generated_models() {
+        use chrono::TimeZone;
+        let ticket: generated::Ticket = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "summary": "printer on fire",
+            "board": {"id": 3, "name": "Help Desk"},
+            "company": {"id": 250, "identifier": "myco"},
+            "dateEntered": "2022-07-14T15:34:22Z",
+            "type": {"id": 4, "name": "Hardware"},
+            "_info": {"notes_href": "https://example.com"},
+            "newInThisVersion": true
+        }))
+        .unwrap();
+        assert_eq!(ticket.summary.as_deref(), Some("printer on fire"));
+        assert_eq!(ticket.board.unwrap().name.as_deref(), Some("Help Desk"));
+        assert_eq!(ticket.r#type.unwrap().id, 4);
+        assert_eq!(
+            ticket.date_entered,
+            Some(
+                chrono::Utc
+                    .with_ymd_and_hms(2022, 7, 14, 15, 34, 22)
+                    .unwrap()
+            )
+        );
+        assert_eq!(ticket.closed_flag, None);
+    }
+
+    #[test]
+    fn test_generate_options() {
+        let spec: Value = serde_json::from_str(SPEC).unwrap();
+        let options = CodegenOptions {
+            unknown_fields: UnknownFields::Keep,
+            dates: false,
+            ..subset()
+        };
+        let code = generate(&spec, &options).unwrap();
+        assert!(
+            code.contains("pub extra: ::std::collections::HashMap<String, ::serde_json::Value>,")
+        );
+        assert!(code.contains("pub date_entered: Option<String>,"));
+        assert!(!code.contains("pub struct Activity"));
+
+        let deny = CodegenOptions {
+            unknown_fields: UnknownFields::Deny,
+            ..subset()
+        };
+        assert!(generate(&spec, &deny)
+            .unwrap()
+            .contains("#[serde(rename_all = \"camelCase\", deny_unknown_fields)]"));
+
+        // everything but the references
+        let all = generate(&spec, &CodegenOptions::default()).unwrap();
+        assert!(all.contains("pub struct Activity {"));
+        assert!(!all.contains("pub struct BoardReference"));
+
+        let missing = CodegenOptions {
+            entities: vec!["Tiket".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            generate(&spec, &missing).unwrap_err().to_string(),
+            "Tiket is not in the definitions"
+        );
+    }
+
+    #[test]
+    fn test_field_name() {
+        for (field, name) in [
+            ("id", "id"),
+            ("dateEntered", "date_entered"),
+            ("_info", "info"),
+            ("type", "r#type"),
+            ("billTimeDaysCount", "bill_time_days_count"),
+            ("mobileGuid", "mobile_guid"),
+            ("HTMLText", "html_text"),
+            ("addressLine1", "address_line1"),
+        ]
+        .iter()
+        .copied()
+        {
+            assert_eq!(field_name(field), name);
+        }
+        assert_eq!(camel_case("address_line1"), "addressLine1");
+        assert_eq!(camel_case("date_entered"), "dateEntered");
+    }
+}
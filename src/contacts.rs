@@ -0,0 +1,321 @@
+//! Creating contacts and the lookups (communication types, relationships, departments) they
+//! need.  See [Client::create_contact]
+use crate::reference::find_by_name;
+use crate::{Client, IdOrName};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+const COMMUNICATION_TYPES_PATH: &str = "/company/communicationTypes";
+const RELATIONSHIPS_PATH: &str = "/company/contacts/relationships";
+const DEPARTMENTS_PATH: &str = "/company/contacts/departments";
+
+/// A communication type (`/company/communicationTypes`), the kind of an email address or
+/// phone number on a contact
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommunicationType {
+    /// the id of the type
+    pub id: i64,
+    /// the name of the type (`Email`, `Direct`, `Cell`)
+    #[serde(default)]
+    pub description: String,
+    /// true for an email type
+    #[serde(default)]
+    pub email_flag: bool,
+    /// true for a phone type
+    #[serde(default)]
+    pub phone_flag: bool,
+    /// true for a fax type
+    #[serde(default)]
+    pub fax_flag: bool,
+    /// true for the default type of its kind (email, phone or fax)
+    #[serde(default)]
+    pub default_flag: bool,
+}
+
+/// A contact relationship or department, both are an id and a name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactLookup {
+    /// the id
+    pub id: i64,
+    /// the name (`Decision Maker`, `Accounting`)
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A contact to create with [Client::create_contact].  The communication types of the email
+/// and phone are picked automatically (the instance's default of each kind) and the
+/// relationship and department can be given by name
+///
+/// ```
+/// use cwmanage::NewContact;
+///
+/// let contact = NewContact::new("Jane", "Doe")
+///     .company(250)
+///     .email("jane@example.com")
+///     .phone("555-0100")
+///     .relationship("Decision Maker");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NewContact {
+    first_name: String,
+    last_name: String,
+    company_id: Option<i64>,
+    email: Option<String>,
+    phone: Option<String>,
+    relationship: Option<IdOrName>,
+    department: Option<IdOrName>,
+}
+
+impl NewContact {
+    /// a contact with a name
+    pub fn new(first_name: &str, last_name: &str) -> NewContact {
+        NewContact {
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// the company the contact works for
+    pub fn company(mut self, company_id: i64) -> NewContact {
+        self.company_id = Some(company_id);
+        self
+    }
+
+    /// the email address, sent with the default email communication type
+    pub fn email(mut self, email: &str) -> NewContact {
+        self.email = Some(email.to_string());
+        self
+    }
+
+    /// the phone number, sent with the default phone communication type
+    pub fn phone(mut self, phone: &str) -> NewContact {
+        self.phone = Some(phone.to_string());
+        self
+    }
+
+    /// the relationship, by id or name
+    pub fn relationship(mut self, relationship: impl Into<IdOrName>) -> NewContact {
+        self.relationship = Some(relationship.into());
+        self
+    }
+
+    /// the department, by id or name
+    pub fn department(mut self, department: impl Into<IdOrName>) -> NewContact {
+        self.department = Some(department.into());
+        self
+    }
+}
+
+impl Client {
+    /// Gets every communication type
+    pub fn get_communication_types(&self) -> Result<Vec<CommunicationType>> {
+        self.get_decoded(COMMUNICATION_TYPES_PATH, &[("", "")])
+    }
+
+    /// Gets every contact relationship
+    pub fn get_contact_relationships(&self) -> Result<Vec<ContactLookup>> {
+        self.get_decoded(RELATIONSHIPS_PATH, &[("", "")])
+    }
+
+    /// Gets every contact department
+    pub fn get_contact_departments(&self) -> Result<Vec<ContactLookup>> {
+        self.get_decoded(DEPARTMENTS_PATH, &[("", "")])
+    }
+
+    /// Finds the communication type with this description, ignoring case.
+    /// [crate::Error::UnknownName] if there isn't one
+    pub fn find_communication_type(&self, description: &str) -> Result<CommunicationType> {
+        let types = self.get_communication_types()?;
+        find_by_name(&types, COMMUNICATION_TYPES_PATH, description, |t| {
+            &t.description
+        })
+        .cloned()
+    }
+
+    /// Finds the contact relationship with this name, ignoring case.
+    /// [crate::Error::UnknownName] if there isn't one
+    pub fn find_contact_relationship(&self, name: &str) -> Result<ContactLookup> {
+        let relationships = self.get_contact_relationships()?;
+        find_by_name(&relationships, RELATIONSHIPS_PATH, name, |r| &r.name).cloned()
+    }
+
+    /// Finds the contact department with this name, ignoring case.
+    /// [crate::Error::UnknownName] if there isn't one
+    pub fn find_contact_department(&self, name: &str) -> Result<ContactLookup> {
+        let departments = self.get_contact_departments()?;
+        find_by_name(&departments, DEPARTMENTS_PATH, name, |d| &d.name).cloned()
+    }
+
+    /// Creates a contact and returns it.  The lookups it needs are asked for first
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, NewContact};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let contact = NewContact::new("Jane", "Doe")
+    ///     .company(250)
+    ///     .email("jane@example.com");
+    /// let created = client.create_contact(&contact).unwrap();
+    /// println!("created contact {}", created["id"]);
+    /// ```
+    pub fn create_contact(&self, contact: &NewContact) -> Result<Value> {
+        let mut body = Map::new();
+        body.insert("firstName".to_string(), json!(contact.first_name));
+        body.insert("lastName".to_string(), json!(contact.last_name));
+        if let Some(company_id) = contact.company_id {
+            body.insert("company".to_string(), json!({ "id": company_id }));
+        }
+
+        let mut items = Vec::new();
+        if contact.email.is_some() || contact.phone.is_some() {
+            let types = self.get_communication_types()?;
+            if let Some(email) = &contact.email {
+                let email_type = default_type(&types, "email", |t| t.email_flag)?;
+                items.push(communication_item(email_type, email));
+            }
+            if let Some(phone) = &contact.phone {
+                let phone_type = default_type(&types, "phone", |t| t.phone_flag)?;
+                items.push(communication_item(phone_type, phone));
+            }
+        }
+        if !items.is_empty() {
+            body.insert("communicationItems".to_string(), Value::Array(items));
+        }
+
+        if let Some(relationship) = &contact.relationship {
+            let id = match relationship {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => self.find_contact_relationship(name)?.id,
+            };
+            body.insert("relationship".to_string(), json!({ "id": id }));
+        }
+        if let Some(department) = &contact.department {
+            let id = match department {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => self.find_contact_department(name)?.id,
+            };
+            body.insert("department".to_string(), json!({ "id": id }));
+        }
+
+        self.post("/company/contacts", Value::Object(body).to_string())
+    }
+}
+
+fn communication_item(type_id: i64, value: &str) -> Value {
+    json!({"type": {"id": type_id}, "value": value, "defaultFlag": true})
+}
+
+/// the id of the default communication type of a kind, or the first of the kind if none is
+/// marked default
+fn default_type(
+    types: &[CommunicationType],
+    kind: &str,
+    is_kind: impl Fn(&CommunicationType) -> bool,
+) -> Result<i64> {
+    types
+        .iter()
+        .filter(|t| is_kind(t))
+        .max_by_key(|t| (t.default_flag, std::cmp::Reverse(t.id)))
+        .map(|t| t.id)
+        .ok_or_else(|| anyhow!("there is no {} communication type", kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_create_contact() {
+        let mut mock = MockCw::new();
+        let _types = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/company/communicationTypes")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "description": "Direct", "phoneFlag": true},
+                    {"id": 2, "description": "Email", "emailFlag": true, "defaultFlag": true},
+                    {"id": 3, "description": "Cell", "phoneFlag": true, "defaultFlag": true},
+                    {"id": 4, "description": "Fax", "faxFlag": true, "defaultFlag": true}]"#,
+            )
+            .create();
+        let _relationships = mock
+            .server()
+            .mock(
+                "GET",
+                "/v4_6_release/apis/3.0/company/contacts/relationships",
+            )
+            .match_query(Matcher::Any)
+            .with_body(r#"[{"id": 5, "name": "Decision Maker"}]"#)
+            .create();
+        let create = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/company/contacts")
+            .match_body(Matcher::Json(json!({
+                "firstName": "Jane",
+                "lastName": "Doe",
+                "company": {"id": 250},
+                "communicationItems": [
+                    {"type": {"id": 2}, "value": "jane@example.com", "defaultFlag": true},
+                    {"type": {"id": 3}, "value": "555-0100", "defaultFlag": true}
+                ],
+                "relationship": {"id": 5},
+                "department": {"id": 9}
+            })))
+            .with_body(r#"{"id": 42}"#)
+            .create();
+        let contact = NewContact::new("Jane", "Doe")
+            .company(250)
+            .email("jane@example.com")
+            .phone("555-0100")
+            .relationship("decision maker")
+            .department(9);
+
+        let created = mock.client().create_contact(&contact).unwrap();
+
+        create.assert();
+        assert_eq!(created["id"], 42);
+    }
+
+    #[test]
+    fn test_default_type() {
+        let t = |id: i64, email_flag: bool, default_flag: bool| CommunicationType {
+            id,
+            description: String::new(),
+            email_flag,
+            phone_flag: false,
+            fax_flag: false,
+            default_flag,
+        };
+
+        assert_eq!(
+            default_type(&[t(1, true, false), t(2, true, false)], "email", |t| t
+                .email_flag)
+            .unwrap(),
+            1
+        );
+        assert_eq!(
+            default_type(&[t(1, true, false), t(2, true, true)], "email", |t| t
+                .email_flag)
+            .unwrap(),
+            2
+        );
+        assert!(default_type(&[t(1, false, true)], "email", |t| t.email_flag).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Types for working with the `customFields` array that comes back on most connectwise
+//! records.  See [crate::Client::get_custom_field] and friends
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How custom field captions are compared to the `field` passed to the custom field
+/// helpers.  See [Client::caption_match] for how to customize
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum CaptionMatch {
+    /// The caption must match exactly (the default)
+    #[default]
+    Exact,
+    /// The caption must match ignoring case
+    CaseInsensitive,
+    /// The caption must match ignoring case and leading/trailing whitespace
+    Normalized,
+}
+
+impl CaptionMatch {
+    fn matches(&self, caption: &str, field: &str) -> bool {
+        match self {
+            CaptionMatch::Exact => caption == field,
+            CaptionMatch::CaseInsensitive => caption.to_lowercase() == field.to_lowercase(),
+            CaptionMatch::Normalized => {
+                caption.trim().to_lowercase() == field.trim().to_lowercase()
+            }
+        }
+    }
+}
+
+/// The type of a custom field, as reported in the `type` key of each custom field
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum CustomFieldType {
+    /// A button, these do not hold a value
+    Button,
+    /// A checkbox (`true`/`false`)
+    Checkbox,
+    /// A currency amount
+    Currency,
+    /// A date
+    Date,
+    /// A url
+    Hyperlink,
+    /// An ip address
+    #[serde(rename = "IPAddress")]
+    IpAddress,
+    /// A number
+    Number,
+    /// A password
+    Password,
+    /// A percentage
+    Percent,
+    /// Single line of text
+    Text,
+    /// Multiple lines of text
+    TextArea,
+    /// Any type this crate doesn't know about yet, these are sent as text
+    #[serde(other)]
+    Unknown,
+}
+
+impl CustomFieldType {
+    /// Validates and converts a value (given as a string, see [crate::Client::patch_custom_field])
+    /// to the json connectwise expects for this type.  Clearly impossible values (`"abc"` for a
+    /// `Number`, anything for a `Button`) are an error
+    pub fn coerce(&self, value: &str) -> Result<Value> {
+        match self {
+            CustomFieldType::Button => Err(anyhow!("button fields cannot be written")),
+            CustomFieldType::Checkbox => match value.trim().to_lowercase().as_str() {
+                "true" => Ok(Value::Bool(true)),
+                "false" => Ok(Value::Bool(false)),
+                _ => Err(anyhow!("{:?} is not a valid checkbox value", value)),
+            },
+            CustomFieldType::Currency | CustomFieldType::Number | CustomFieldType::Percent => {
+                let trimmed = value.trim();
+                if let Ok(i) = trimmed.parse::<i64>() {
+                    return Ok(Value::from(i));
+                }
+                trimmed
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+                    .ok_or(anyhow!("{:?} is not a valid {:?} value", value, self))
+            }
+            CustomFieldType::Date => coerce_date(value),
+            _ => Ok(Value::String(value.to_string())),
+        }
+    }
+}
+
+#[cfg(not(feature = "chrono"))]
+fn coerce_date(value: &str) -> Result<Value> {
+    Ok(Value::String(value.to_string()))
+}
+
+#[cfg(feature = "chrono")]
+fn coerce_date(value: &str) -> Result<Value> {
+    let parsed =
+        crate::dates::parse(value).map_err(|_| anyhow!("{:?} is not a valid date value", value))?;
+    Ok(date_value(&parsed))
+}
+
+/// Formats a date the way connectwise expects it for a `Date` custom field
+#[cfg(feature = "chrono")]
+pub fn date_value(date: &chrono::DateTime<chrono::Utc>) -> Value {
+    Value::String(crate::dates::format(date))
+}
+
+/// A single custom field, as returned in the `customFields` array.
+/// See [crate::Client::get_custom_field_full]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct CustomField {
+    /// The id of the field, used when patching
+    pub id: i64,
+    /// The caption (name) of the field
+    pub caption: String,
+    /// The type of the field
+    #[serde(rename = "type")]
+    pub field_type: CustomFieldType,
+    /// The current value, `Null` when unset
+    #[serde(default)]
+    pub value: Value,
+}
+
+pub(crate) fn find_custom_field<'a>(
+    custom_fields: &'a [Value],
+    field: &str,
+    caption_match: &CaptionMatch,
+) -> Result<Option<&'a Value>> {
+    let mut found_field: Option<&Value> = None;
+    for f in custom_fields.iter() {
+        let caption = f["caption"]
+            .as_str()
+            .ok_or(anyhow!("cannot convert caption to string"))?;
+        if caption_match.matches(caption, field) {
+            if let (Some(found), CaptionMatch::CaseInsensitive | CaptionMatch::Normalized) =
+                (found_field, caption_match)
+            {
+                return Err(anyhow!(
+                    "field {:?} is ambiguous, matches captions {:?} and {:?}",
+                    field,
+                    found["caption"],
+                    f["caption"]
+                ));
+            }
+            found_field = Some(f);
+        }
+    }
+
+    Ok(found_field)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_custom_field_caption_match() {
+        let custom_fields = vec![
+            json!({"id": 1, "caption": "E-Rate ", "value": true}),
+            json!({"id": 2, "caption": "WaitReason", "value": "Something Else"}),
+        ];
+
+        let exact = find_custom_field(&custom_fields, "E-rate", &CaptionMatch::Exact);
+        assert_eq!(exact.unwrap(), None);
+
+        let insensitive =
+            find_custom_field(&custom_fields, "waitreason", &CaptionMatch::CaseInsensitive);
+        assert_eq!(insensitive.unwrap(), Some(&custom_fields[1]));
+
+        let normalized = find_custom_field(&custom_fields, "E-rate", &CaptionMatch::Normalized);
+        assert_eq!(normalized.unwrap(), Some(&custom_fields[0]));
+    }
+
+    #[test]
+    fn test_find_custom_field_ambiguous() {
+        let custom_fields = vec![
+            json!({"id": 1, "caption": "E-Rate ", "value": true}),
+            json!({"id": 2, "caption": "E-rate", "value": false}),
+        ];
+
+        let exact = find_custom_field(&custom_fields, "E-rate", &CaptionMatch::Exact);
+        assert_eq!(exact.unwrap(), Some(&custom_fields[1]));
+
+        let normalized = find_custom_field(&custom_fields, "E-rate", &CaptionMatch::Normalized);
+        assert!(normalized.is_err());
+    }
+
+    #[test]
+    fn test_custom_field_deserialize() {
+        let raw = json!({
+            "id": 67,
+            "caption": "WaitReason",
+            "type": "Text",
+            "entryMethod": "EntryField",
+            "numberOfDecimals": 0,
+            "value": "Something Else"
+        });
+        let expected = CustomField {
+            id: 67,
+            caption: "WaitReason".to_string(),
+            field_type: CustomFieldType::Text,
+            value: json!("Something Else"),
+        };
+
+        let result: CustomField = serde_json::from_value(raw).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_custom_field_deserialize_unset() {
+        let raw = json!({"id": 12, "caption": "Thing", "type": "SomethingNew"});
+
+        let result: CustomField = serde_json::from_value(raw).unwrap();
+        assert_eq!(result.field_type, CustomFieldType::Unknown);
+        assert_eq!(result.value, Value::Null);
+    }
+
+    #[test]
+    fn test_coerce() {
+        assert_eq!(
+            CustomFieldType::Checkbox.coerce("TRUE").unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            CustomFieldType::Checkbox.coerce("false").unwrap(),
+            json!(false)
+        );
+        assert_eq!(CustomFieldType::Number.coerce("1234").unwrap(), json!(1234));
+        assert_eq!(
+            CustomFieldType::Percent.coerce(" 12.5 ").unwrap(),
+            json!(12.5)
+        );
+        assert_eq!(CustomFieldType::Text.coerce("1234").unwrap(), json!("1234"));
+    }
+
+    #[test]
+    fn test_coerce_impossible() {
+        assert!(CustomFieldType::Checkbox.coerce("yes").is_err());
+        assert!(CustomFieldType::Number.coerce("abc").is_err());
+        assert!(CustomFieldType::Currency.coerce("").is_err());
+        assert!(CustomFieldType::Button.coerce("click").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_coerce_date() {
+        assert_eq!(
+            CustomFieldType::Date.coerce("2022-07-04").unwrap(),
+            json!("2022-07-04T00:00:00Z")
+        );
+        assert_eq!(
+            CustomFieldType::Date
+                .coerce("2022-07-04T10:30:00-04:00")
+                .unwrap(),
+            json!("2022-07-04T14:30:00Z")
+        );
+        assert!(CustomFieldType::Date.coerce("last tuesday").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Serde helpers for connectwise dates, use with `#[serde(with = "cwmanage::dates")]` on a
+//! `DateTime<Utc>` field (or `cwmanage::dates::option` on an `Option<DateTime<Utc>>`)
+//!
+//! Connectwise sends dates as `2022-07-14T15:34:22Z`, some endpoints with fractional seconds
+//! (`2022-07-14T15:34:22.187Z`) or an offset (`+00:00`), and date only fields as midnight.
+//! Any of those are read (as is a bare `2022-07-14`), and dates are always written as
+//! `2022-07-14T15:34:22Z`, the one form every write path accepts
+//!
+//! ```
+//! use chrono::{DateTime, TimeZone, Utc};
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Entry {
+//!     #[serde(with = "cwmanage::dates")]
+//!     time_start: DateTime<Utc>,
+//!     #[serde(default, with = "cwmanage::dates::option")]
+//!     time_end: Option<DateTime<Utc>>,
+//! }
+//!
+//! let entry: Entry =
+//!     serde_json::from_str(r#"{"timeStart": "2022-07-14T15:00:00.123Z", "timeEnd": null}"#)
+//!         .unwrap();
+//! assert_eq!(entry.time_start.timestamp(), 1657810800);
+//! assert_eq!(entry.time_end, None);
+//! assert_eq!(
+//!     serde_json::to_string(&entry).unwrap(),
+//!     r#"{"timeStart":"2022-07-14T15:00:00Z","timeEnd":null}"#
+//! );
+//! ```
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// How dates are written
+const WRITE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+/// Reads a connectwise date
+///
+/// ```
+/// let date = cwmanage::dates::parse("2022-07-14T15:34:22.1870000Z").unwrap();
+/// assert_eq!(cwmanage::dates::format(&date), "2022-07-14T15:34:22Z");
+/// ```
+pub fn parse(value: &str) -> Result<DateTime<Utc>> {
+    let trimmed = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(trimmed) {
+        return Ok(date.with_timezone(&Utc));
+    }
+    // no offset, connectwise dates are in utc
+    if let Ok(date) = NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Ok(date.and_utc());
+    }
+    NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+        .map(|d| d.and_time(NaiveTime::MIN).and_utc())
+        .map_err(|_| anyhow!("{:?} is not a valid date", value))
+}
+
+/// Writes a date the way connectwise accepts it (`2022-07-14T15:34:22Z`), fractional
+/// seconds are dropped
+pub fn format(date: &DateTime<Utc>) -> String {
+    date.format(WRITE_FORMAT).to_string()
+}
+
+/// Serializes a date, see [format]
+pub fn serialize<S: Serializer>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(date))
+}
+
+/// Deserializes a date, see [parse]
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse(&value).map_err(serde::de::Error::custom)
+}
+
+/// The same for an `Option<DateTime<Utc>>`.  `null` and an empty string are `None`, `None`
+/// is written as `null` (add `skip_serializing_if = "Option::is_none"` to leave it out)
+pub mod option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    /// Serializes a date or `null`, see [super::format]
+    pub fn serialize<S: Serializer>(
+        date: &Option<DateTime<Utc>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => super::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    /// Deserializes a date or `null`, see [super::parse]
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DateTime<Utc>>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) if !value.trim().is_empty() => super::parse(&value)
+                .map(Some)
+                .map_err(serde::de::Error::custom),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Reads an optional date field of a model, `None` when it is missing or isn't a date
+pub(crate) fn parse_field(value: Option<&str>) -> Option<DateTime<Utc>> {
+    parse(value?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use serde::Serialize;
+    use serde_json::Value;
+
+    const TIME_ENTRY: &str = include_str!("../tests/fixtures/dates/time_entry.json");
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry {
+        id: i64,
+        #[serde(with = "crate::dates")]
+        time_start: DateTime<Utc>,
+        #[serde(default, with = "crate::dates::option")]
+        time_end: Option<DateTime<Utc>>,
+        #[serde(with = "crate::dates")]
+        date_entered: DateTime<Utc>,
+        #[serde(default, with = "crate::dates::option")]
+        date_approved: Option<DateTime<Utc>>,
+        #[serde(default, with = "crate::dates::option")]
+        date_invoiced: Option<DateTime<Utc>>,
+    }
+
+    #[test]
+    fn test_parse() {
+        let expected = Utc.with_ymd_and_hms(2022, 7, 14, 15, 34, 22).unwrap();
+        for value in [
+            "2022-07-14T15:34:22Z",
+            "2022-07-14T15:34:22.187Z",
+            "2022-07-14T15:34:22.1870000Z",
+            "2022-07-14T15:34:22+00:00",
+            "2022-07-14T10:34:22-05:00",
+            "2022-07-14T15:34:22",
+            " 2022-07-14T15:34:22Z ",
+        ] {
+            assert_eq!(
+                parse(value).unwrap().timestamp(),
+                expected.timestamp(),
+                "{}",
+                value
+            );
+        }
+        assert_eq!(
+            parse("2022-07-14").unwrap(),
+            Utc.with_ymd_and_hms(2022, 7, 14, 0, 0, 0).unwrap()
+        );
+        assert!(parse("14/07/2022").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entry: Entry = serde_json::from_str(TIME_ENTRY).unwrap();
+        assert_eq!(
+            entry.time_start,
+            Utc.with_ymd_and_hms(2022, 7, 14, 15, 0, 0).unwrap()
+        );
+        assert_eq!(entry.date_approved, None);
+        assert_eq!(entry.date_invoiced, None);
+
+        let written = serde_json::to_value(&entry).unwrap();
+        assert_eq!(written["timeStart"], "2022-07-14T15:00:00Z");
+        assert_eq!(written["timeEnd"], "2022-07-14T15:30:00Z");
+        assert_eq!(written["dateEntered"], "2022-07-14T15:31:02Z");
+        assert_eq!(written["dateApproved"], Value::Null);
+
+        // what was written reads back the same, to the second
+        let again: Entry = serde_json::from_value(written).unwrap();
+        assert_eq!(again.time_start, entry.time_start);
+        assert_eq!(again.time_end, entry.time_end);
+        assert_eq!(
+            again.date_entered.timestamp(),
+            entry.date_entered.timestamp()
+        );
+    }
+
+    #[test]
+    fn test_model_dates() {
+        let entry: crate::TimeEntry = serde_json::from_str(TIME_ENTRY).unwrap();
+
+        assert_eq!(
+            entry.time_start_utc(),
+            Some(Utc.with_ymd_and_hms(2022, 7, 14, 15, 0, 0).unwrap())
+        );
+        assert_eq!(
+            entry.time_end_utc().map(|d| format(&d)),
+            entry.time_end.clone()
+        );
+    }
+}
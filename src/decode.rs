@@ -0,0 +1,487 @@
+//! Turning records into types, with errors that say where the record and the field are.
+//! See [Error::Decode], and [Client::collect_unknown_fields] for the fields the types don't have
+use crate::{Client, Error};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+/// Gets the [UnknownFields] of each call, see [Client::on_unknown_fields]
+pub type UnknownFieldsHandler = dyn Fn(&UnknownFields) + Send + Sync;
+
+/// Fields in the response that the type it was decoded into doesn't have, see
+/// [Client::collect_unknown_fields]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownFields {
+    /// the path the records came from
+    pub path: String,
+    /// the type the records were decoded into (`Ticket`)
+    pub model: String,
+    /// the fields, nested ones as `company.identifier` and in lists as `contacts[].email`
+    pub fields: BTreeSet<String>,
+}
+
+/// The name of `T` without its module
+fn model_name<T>() -> String {
+    let name = std::any::type_name::<T>();
+    let base = name.split('<').next().unwrap_or(name);
+    match base.rfind("::") {
+        Some(at) => name[at + 2..].to_string(),
+        None => name.to_string(),
+    }
+}
+
+/// `company.identifier` for the path of an ignored field, lists without the index
+fn field_path(path: &serde_ignored::Path<'_>) -> String {
+    use serde_ignored::Path;
+    match path {
+        Path::Root => String::new(),
+        Path::Seq { parent, .. } => format!("{}[]", field_path(parent)),
+        Path::Map { parent, key } => match field_path(parent) {
+            parent if parent.is_empty() => key.clone(),
+            parent => format!("{}.{}", parent, key),
+        },
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => field_path(parent),
+    }
+}
+
+impl Client {
+    /// Decodes one record from `path`
+    pub(crate) fn decode<T: DeserializeOwned>(&self, path: &str, record: Value) -> Result<T> {
+        let mut unknown = BTreeSet::new();
+        let decoded = self.decode_at(path, record, None, None, &mut unknown)?;
+        self.report_unknown_fields::<T>(path, unknown)?;
+        Ok(decoded)
+    }
+
+    /// Decodes a record, the error has its `index` in the list and the `page` it was on.  The
+    /// fields `T` doesn't have go in `unknown` when they are collected
+    fn decode_at<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        record: Value,
+        index: Option<usize>,
+        page: Option<usize>,
+        unknown: &mut BTreeSet<String>,
+    ) -> Result<T> {
+        let id = record.get("id").and_then(Value::as_i64);
+        let decoded = match self.collect_unknown_fields {
+            true => {
+                // the `_info` links are on every record and in no type
+                let mut ignored = |path: serde_ignored::Path<'_>| {
+                    let field = field_path(&path);
+                    if !(field == "_info" || field.ends_with("._info")) {
+                        unknown.insert(field);
+                    }
+                };
+                serde_path_to_error::deserialize(serde_ignored::Deserializer::new(
+                    record,
+                    &mut ignored,
+                ))
+            }
+            false => serde_path_to_error::deserialize(record),
+        };
+        decoded.map_err(|e| {
+            let inner = e.path().to_string();
+            let field = match (index, inner.as_str()) {
+                (None, inner) => inner.to_string(),
+                (Some(index), ".") => format!("[{}]", index),
+                (Some(index), inner) if inner.starts_with('[') => format!("[{}]{}", index, inner),
+                (Some(index), inner) => format!("[{}].{}", index, inner),
+            };
+            let mut message = e.into_inner().to_string();
+            // serde quotes the value that didn't fit
+            if matches!(&self.redaction, Some(redaction) if redaction.covers(&inner)) {
+                message = match message.find(", expected") {
+                    Some(at) => format!("invalid value{}", &message[at..]),
+                    None => "invalid value".to_string(),
+                };
+            }
+            Error::Decode {
+                path: path.to_string(),
+                field,
+                id,
+                page,
+                message,
+            }
+            .into()
+        })
+    }
+
+    /// Logs and hands the fields `T` doesn't have to [Client::on_unknown_fields], or fails
+    /// with [Error::UnknownFields] when they are denied
+    fn report_unknown_fields<T>(&self, path: &str, fields: BTreeSet<String>) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        let unknown = UnknownFields {
+            path: path.to_string(),
+            model: model_name::<T>(),
+            fields,
+        };
+        tracing::warn!(
+            path,
+            model = unknown.model.as_str(),
+            fields = ?unknown.fields,
+            "the response has fields the model doesn't"
+        );
+        if let Some(on_unknown_fields) = &self.on_unknown_fields {
+            (on_unknown_fields.0)(&unknown);
+        }
+        match self.deny_unknown_fields {
+            true => Err(Error::UnknownFields {
+                path: unknown.path,
+                model: unknown.model,
+                fields: unknown.fields.into_iter().collect(),
+            }
+            .into()),
+            false => Ok(()),
+        }
+    }
+
+    /// [Client::get] decoded into `T` a page at a time, instead of collecting every record as
+    /// a [Value] and converting the list.  A record that doesn't fit is [Error::Decode] with
+    /// its index in the whole list (`[52].status.name`), its id and the page it was on
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the list (example `/system/members`)
+    /// - `query` - the query, as [Client::get]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Member};
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let members: Vec<Member> = client
+    ///     .get_as("/system/members", &[("conditions", "inactiveFlag = false")])
+    ///     .unwrap();
+    /// for member in members {
+    ///     println!("{} {}", member.first_name, member.last_name);
+    /// }
+    /// ```
+    pub fn get_as<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        self.get_decoded(path, query)
+    }
+
+    /// [Client::get_single] decoded into `T`.  A field that is missing or the wrong type is
+    /// [Error::Decode] with where it is (`server_time_zone`), and a list (a list endpoint by
+    /// mistake) is [Error::ExpectedObject] instead of serde's "invalid type: sequence"
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the record (example `/system/info`)
+    /// - `query` - the query, as [Client::get_single]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// #[serde(rename_all = "camelCase")]
+    /// struct SystemInfo {
+    ///   version: String,
+    ///   is_cloud: bool,
+    ///   server_time_zone: String,
+    /// }
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let info: SystemInfo = client.get_single_as("/system/info", &[]).unwrap();
+    /// assert_eq!(info.server_time_zone, "Eastern Standard Time");
+    /// ```
+    pub fn get_single_as<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        self.decode(path, self.get_single(path, query)?)
+    }
+
+    /// [Client::get] decoded into `T` a page at a time, a record that doesn't fit is
+    /// [Error::Decode] with its place in the whole list and the page it was on
+    pub(crate) fn get_decoded<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<T>> {
+        self.get_decoded_limited(path, query, None)
+    }
+
+    /// [Client::get_decoded] that stops after `limit` records, no more pages are requested
+    /// once it has them
+    pub(crate) fn get_decoded_limited<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        limit: Option<usize>,
+    ) -> Result<Vec<T>> {
+        let mut collected: Vec<T> = Vec::new();
+        let mut unknown = BTreeSet::new();
+
+        let reached = |collected: &Vec<T>| limit.is_some_and(|limit| collected.len() >= limit);
+        for (page_index, page) in self.pages(path, query).enumerate() {
+            for record in page? {
+                if reached(&collected) {
+                    break;
+                }
+                let index = collected.len();
+                collected.push(self.decode_at(
+                    path,
+                    record,
+                    Some(index),
+                    Some(page_index + 1),
+                    &mut unknown,
+                )?);
+            }
+            if reached(&collected) {
+                break;
+            }
+        }
+
+        self.report_unknown_fields::<T>(path, unknown)?;
+        Ok(collected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw};
+    use crate::{Member, TicketTask};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_decode_error_path() {
+        let mock = MockCw::new().list(
+            "/service/tickets/1/tasks",
+            vec![
+                vec![
+                    json!({"id": 10, "notes": "one"}),
+                    json!({"id": 11, "notes": "two"}),
+                ],
+                vec![json!({"id": 12, "notes": {"text": "three"}})],
+            ],
+        );
+        let client = mock.client();
+
+        let err = client.get_ticket_tasks(1).unwrap_err();
+
+        assert!(err.to_string().contains("[2].notes"), "{}", err);
+        match err.downcast_ref::<Error>() {
+            Some(Error::Decode {
+                path,
+                field,
+                id,
+                page,
+                ..
+            }) => {
+                assert_eq!(path, "/service/tickets/1/tasks");
+                assert_eq!(field, "[2].notes");
+                assert_eq!(*id, Some(12));
+                assert_eq!(*page, Some(2));
+            }
+            other => panic!("{:?}", other),
+        }
+
+        let single = client.decode::<TicketTask>("/service/tickets/1/tasks/12", json!({"id": "x"}));
+        assert_eq!(
+            single.unwrap_err().to_string(),
+            "can't decode id of /service/tickets/1/tasks/12: invalid type: string \"x\", expected i64"
+        );
+    }
+
+    #[test]
+    fn test_get_as() {
+        let mock = MockCw::new()
+            .list(
+                "/system/members",
+                vec![
+                    vec![fixtures::member(1), fixtures::member(2)],
+                    vec![fixtures::member(3)],
+                ],
+            )
+            .list(
+                "/system/departments",
+                vec![
+                    vec![json!({"id": 1, "identifier": "service"})],
+                    vec![json!({"id": 2, "identifier": 7})],
+                ],
+            );
+        let client = mock.client();
+
+        let members: Vec<Member> = client.get_as("/system/members", &[]).unwrap();
+        assert_eq!(
+            members.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(members[2].identifier, "member3");
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Department {
+            #[allow(dead_code)]
+            identifier: String,
+        }
+        let err = client
+            .get_as::<Department>("/system/departments", &[])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't decode [1].identifier of /system/departments (id 2, page 2): \
+             invalid type: integer `7`, expected a string"
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_single_as() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SystemInfo {
+            version: String,
+            is_cloud: bool,
+            server_time_zone: String,
+        }
+
+        let mock = MockCw::new()
+            .single(
+                "/system/info",
+                json!({
+                    "version": "v2022.1.12345",
+                    "isCloud": true,
+                    "serverTimeZone": "Eastern Standard Time",
+                }),
+            )
+            .single(
+                "/system/info/bad",
+                json!({"version": "v2022.1", "isCloud": "yes"}),
+            )
+            .single(
+                "/system/members",
+                json!([fixtures::member(1), fixtures::member(2)]),
+            );
+        let client = mock.client();
+
+        let info: SystemInfo = client.get_single_as("/system/info", &[]).unwrap();
+        assert_eq!(info.version, "v2022.1.12345");
+        assert!(info.is_cloud);
+        assert_eq!(info.server_time_zone, "Eastern Standard Time");
+
+        let err = client
+            .get_single_as::<SystemInfo>("/system/info/bad", &[])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't decode isCloud of /system/info/bad: invalid type: string \"yes\", expected a boolean"
+        );
+        let err = client
+            .get_single_as::<SystemInfo>("/system/members", &[])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::ExpectedObject { .. })
+        ));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Company {
+            #[allow(dead_code)]
+            id: i64,
+        }
+        #[derive(Debug, serde::Deserialize)]
+        struct Record {
+            #[allow(dead_code)]
+            company: Company,
+            #[allow(dead_code)]
+            contacts: Vec<Company>,
+        }
+
+        let mut server = mockito::Server::new();
+        let _tasks = server
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/1/tasks")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 10, "notes": "one", "_info": {"updatedBy": "zach"}, "assignee": "zach"},
+                {"id": 11, "notes": "two", "estimatedHours": 1.5}]"#,
+            )
+            .expect(3)
+            .create();
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let on_unknown_fields = {
+            let reported = reported.clone();
+            Arc::new(move |unknown: &UnknownFields| reported.lock().unwrap().push(unknown.clone()))
+        };
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .on_unknown_fields(on_unknown_fields)
+        .build();
+
+        // not collected by default
+        assert_eq!(client.get_ticket_tasks(1).unwrap().len(), 2);
+        assert!(reported.lock().unwrap().is_empty());
+
+        // once a call, with the fields of every record
+        let client = client.collect_unknown_fields(true);
+        assert_eq!(client.get_ticket_tasks(1).unwrap().len(), 2);
+        let fields = |fields: &[&str]| fields.iter().map(|f| f.to_string()).collect();
+        assert_eq!(
+            *reported.lock().unwrap(),
+            vec![UnknownFields {
+                path: "/service/tickets/1/tasks".to_string(),
+                model: "TicketTask".to_string(),
+                fields: fields(&["assignee", "estimatedHours"]),
+            }]
+        );
+
+        // nested and in lists
+        let record = json!({
+            "company": {"id": 1, "_info": {}, "identifier": "myco"},
+            "contacts": [{"id": 2, "email": "a@example.com"}, {"id": 3, "email": "b@example.com"}],
+        });
+        reported.lock().unwrap().clear();
+        client.decode::<Record>("/record", record.clone()).unwrap();
+        assert_eq!(
+            reported.lock().unwrap()[0].fields,
+            fields(&["company.identifier", "contacts[].email"])
+        );
+
+        let err = client
+            .deny_unknown_fields(true)
+            .get_ticket_tasks(1)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "TicketTask from /service/tickets/1/tasks has fields TicketTask doesn't: \
+             assignee, estimatedHours"
+        );
+    }
+}
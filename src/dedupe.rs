@@ -0,0 +1,381 @@
+//! POSTs that are retried without creating the record twice.  See [Client::post_idempotent]
+use crate::{post_response, Client, Error};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Looks for the record a POST may have created, see [DedupeRule::custom]
+pub type DedupeFn = dyn Fn(&Client, &Value) -> Result<Option<Value>> + Send + Sync;
+
+/// How far before the POST `{sent_at}` is, for a server clock that is behind ours
+const SENT_AT_SLACK: Duration = Duration::from_secs(60);
+
+/// How [Client::post_idempotent] finds out whether a POST that failed part way created the
+/// record anyway
+#[derive(Clone)]
+pub enum DedupeRule {
+    /// conditions for the list the POST was to, with `{field}` (or `{company/id}` for a
+    /// nested field) replaced by the value in the body and `{sent_at}` by the time of the POST
+    /// (a minute early, as a `[date]`).  See [DedupeRule::conditions]
+    Conditions(String),
+    /// a lookup that gets the client and the body and returns the record when it exists.  See
+    /// [DedupeRule::custom]
+    Custom(Arc<DedupeFn>),
+}
+
+impl fmt::Debug for DedupeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DedupeRule::Conditions(template) => {
+                f.debug_tuple("Conditions").field(template).finish()
+            }
+            DedupeRule::Custom(_) => f.debug_tuple("Custom").finish(),
+        }
+    }
+}
+
+impl DedupeRule {
+    /// a [DedupeRule::Conditions]
+    ///
+    /// ```
+    /// use cwmanage::DedupeRule;
+    ///
+    /// let rule = DedupeRule::conditions(
+    ///     "summary = {summary} AND company/id = {company/id} AND dateEntered > {sent_at}",
+    /// );
+    /// ```
+    pub fn conditions(template: &str) -> DedupeRule {
+        DedupeRule::Conditions(template.to_string())
+    }
+
+    /// a [DedupeRule::Custom]
+    pub fn custom(
+        lookup: impl Fn(&Client, &Value) -> Result<Option<Value>> + Send + Sync + 'static,
+    ) -> DedupeRule {
+        DedupeRule::Custom(Arc::new(lookup))
+    }
+
+    /// the record the POST of `body` to `path` created, if it did
+    fn find(
+        &self,
+        client: &Client,
+        path: &str,
+        body: &Value,
+        sent_at: SystemTime,
+    ) -> Result<Option<Value>> {
+        match self {
+            DedupeRule::Conditions(template) => {
+                let conditions = fill_conditions(template, body, sent_at)?;
+                let query = [
+                    ("conditions", conditions.as_str()),
+                    ("orderby", "id desc"),
+                    ("pagesize", "1"),
+                ];
+                match client.pages(path, &query).next() {
+                    Some(page) => Ok(page?.into_iter().next()),
+                    None => Ok(None),
+                }
+            }
+            DedupeRule::Custom(lookup) => lookup(client, body),
+        }
+    }
+}
+
+/// the conditions of a template for `body`
+fn fill_conditions(template: &str, body: &Value, sent_at: SystemTime) -> Result<String> {
+    let mut conditions = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("unclosed {{ in the dedupe conditions {:?}", template))?;
+        conditions.push_str(&rest[..start]);
+        let field = &rest[start + 1..end];
+        let value = match field {
+            "sent_at" => format!("[{}]", crate::har::cw_timestamp(sent_at - SENT_AT_SLACK)),
+            _ => match body.pointer(&format!("/{}", field)) {
+                Some(Value::String(s)) => format!("'{}'", s.replace('\'', "\\'")),
+                Some(Value::Number(n)) => n.to_string(),
+                Some(Value::Bool(b)) => b.to_string(),
+                Some(Value::Null) => "null".to_string(),
+                _ => {
+                    return Err(anyhow!(
+                        "the body has no {} for the dedupe conditions",
+                        field
+                    ))
+                }
+            },
+        };
+        conditions.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    conditions.push_str(rest);
+    Ok(conditions)
+}
+
+/// How a POST attempt ended
+enum Attempt {
+    Created(Value),
+    /// it may or may not have been created
+    Unknown(anyhow::Error),
+    /// it certainly wasn't created, try again after the wait
+    NotCreated(anyhow::Error, Option<Duration>),
+    /// it failed in a way sending it again won't fix
+    Failed(anyhow::Error),
+}
+
+/// true when the request went out but the response didn't come back whole
+fn failed_after_sending(e: &anyhow::Error) -> bool {
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(e) => {
+            !e.is_connect() && (e.is_timeout() || e.is_body() || e.is_decode() || e.is_request())
+        }
+        None => false,
+    }
+}
+
+impl Client {
+    /// Creates a record like [Client::post], but when the POST fails in a way that leaves it
+    /// unknown whether the record was created (the connection dropped or timed out after the
+    /// request was sent, or a gateway answered 502, 503 or 504) it looks for the record with
+    /// `dedupe` first and returns it if it is there, and only POSTs again if it isn't.  A
+    /// POST that certainly didn't go through (the connection couldn't be made, or a 429) is
+    /// sent again straight away.  It tries [Client::max_retries] times (at least once more)
+    /// with the [Client::retry_backoff] between tries
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, DedupeRule};
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let body = json!({"summary": "printer on fire", "company": {"id": 250}});
+    /// let rule = DedupeRule::conditions(
+    ///     "summary = {summary} AND company/id = {company/id} AND dateEntered > {sent_at}",
+    /// );
+    /// let ticket = client
+    ///     .post_idempotent("/service/tickets", body.to_string(), rule)
+    ///     .unwrap();
+    /// ```
+    pub fn post_idempotent(&self, path: &str, body: String, dedupe: DedupeRule) -> Result<Value> {
+        let parsed: Value = serde_json::from_str(&body)
+            .map_err(|e| anyhow!("the body of a post_idempotent must be json: {}", e))?;
+        // the retries are done here, where they can look for the record first
+        let once = Client {
+            max_retries: 0,
+            ..self.clone()
+        };
+        let sent_at = SystemTime::now();
+        let retries = self.max_retries.max(1);
+        let mut attempt: u32 = 0;
+
+        loop {
+            attempt += 1;
+            let (err, wait) = match once.post_attempt(path, &body) {
+                Attempt::Created(record) => return Ok(record),
+                Attempt::Failed(err) => return Err(err),
+                Attempt::NotCreated(err, wait) => (err, wait),
+                Attempt::Unknown(err) => {
+                    let found = dedupe.find(self, path, &parsed, sent_at).map_err(|e| {
+                        e.context(format!(
+                            "POST {} failed ({}) and the lookup for the record failed, it may \
+                             or may not have been created",
+                            path, err
+                        ))
+                    })?;
+                    if let Some(record) = found {
+                        tracing::info!(path, "the failed post created the record, not retried");
+                        return Ok(record);
+                    }
+                    (err, None)
+                }
+            };
+            if attempt > retries {
+                return Err(err);
+            }
+            tracing::debug!(path, attempt, "post wasn't created, retrying: {}", err);
+            std::thread::sleep(
+                wait.unwrap_or(self.retry_backoff * 2u32.saturating_pow(attempt - 1)),
+            );
+        }
+    }
+
+    fn post_attempt(&self, path: &str, body: &str) -> Attempt {
+        let req = match self.write_request(reqwest::Method::POST, path) {
+            Ok(req) => req.body(body.to_string()),
+            Err(e) => return Attempt::Failed(e),
+        };
+        let result = self.call(req, |res| match res.status.as_u16() {
+            502..=504 => Ok(Err(anyhow!("{} from POST {}", res.status, path))),
+            _ => post_response(res, path).map(Ok),
+        });
+        match result {
+            Ok(Ok(record)) => Attempt::Created(record),
+            Ok(Err(gateway)) => Attempt::Unknown(gateway),
+            Err(e) if failed_after_sending(&e) => Attempt::Unknown(e),
+            Err(e) => match e.downcast_ref::<Error>() {
+                Some(Error::RateLimited { retry_after }) => {
+                    let wait = *retry_after;
+                    Attempt::NotCreated(e, wait)
+                }
+                _ => match e.downcast_ref::<reqwest::Error>() {
+                    Some(connect) if connect.is_connect() => Attempt::NotCreated(e, None),
+                    _ => Attempt::Failed(e),
+                },
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::UNIX_EPOCH;
+
+    fn ticket() -> Value {
+        json!({"summary": "printer on fire", "company": {"id": 250}})
+    }
+
+    #[test]
+    fn test_post_idempotent_was_created() {
+        let mut mock = MockCw::new();
+        // connectwise took the ticket but the connection dropped before the response
+        let post = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_chunked_body(|_| Err(std::io::Error::other("connection reset")))
+            .expect(1)
+            .create();
+        let lookup = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded(
+                    "conditions".to_string(),
+                    "summary = 'printer on fire' AND company/id = 250".to_string(),
+                ),
+                Matcher::UrlEncoded("orderby".to_string(), "id desc".to_string()),
+            ]))
+            .with_body(r#"[{"id": 7, "summary": "printer on fire"}]"#)
+            .expect(1)
+            .create();
+
+        let ticket = mock
+            .client()
+            .retry_backoff(Duration::from_millis(0))
+            .post_idempotent(
+                "/service/tickets",
+                ticket().to_string(),
+                DedupeRule::conditions("summary = {summary} AND company/id = {company/id}"),
+            )
+            .unwrap();
+
+        post.assert();
+        lookup.assert();
+        assert_eq!(ticket, json!({"id": 7, "summary": "printer on fire"}));
+    }
+
+    #[test]
+    fn test_post_idempotent_was_not_created() {
+        let mut mock = MockCw::new();
+        let timed_out = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(504)
+            .expect(1)
+            .create();
+        let created = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .match_body(Matcher::Json(ticket()))
+            .with_status(201)
+            .with_body(r#"{"id": 8, "summary": "printer on fire"}"#)
+            .expect(1)
+            .create();
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counted = lookups.clone();
+        let rule = DedupeRule::custom(move |_, body| {
+            assert_eq!(body["company"]["id"], 250);
+            counted.fetch_add(1, Ordering::SeqCst);
+            Ok(None)
+        });
+
+        let ticket = mock
+            .client()
+            .retry_backoff(Duration::from_millis(0))
+            .post_idempotent("/service/tickets", ticket().to_string(), rule)
+            .unwrap();
+
+        timed_out.assert();
+        created.assert();
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        assert_eq!(ticket["id"], 8);
+    }
+
+    #[test]
+    fn test_post_idempotent_refused() {
+        let mut mock = MockCw::new();
+        let refused = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "summary is required"}"#)
+            .expect(1)
+            .create();
+        let rule = DedupeRule::custom(|_, _| panic!("a refused post isn't looked for"));
+
+        let err = mock
+            .client()
+            .retry_backoff(Duration::from_millis(0))
+            .post_idempotent("/service/tickets", "{}".to_string(), rule)
+            .unwrap_err();
+
+        refused.assert();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Api { status: 400, .. })
+        ));
+    }
+
+    #[test]
+    fn test_fill_conditions() {
+        let body = json!({"summary": "Bob's printer", "company": {"id": 250}, "closedFlag": false});
+        let sent_at = UNIX_EPOCH + Duration::from_secs(1_657_812_862);
+
+        assert_eq!(
+            fill_conditions(
+                "summary = {summary} AND company/id = {company/id} AND closedFlag = {closedFlag} \
+                 AND dateEntered > {sent_at}",
+                &body,
+                sent_at
+            )
+            .unwrap(),
+            "summary = 'Bob\\'s printer' AND company/id = 250 AND closedFlag = false \
+             AND dateEntered > [2022-07-14T15:33:22Z]"
+        );
+        assert_eq!(
+            fill_conditions("contact/id = {contact/id}", &body, sent_at)
+                .unwrap_err()
+                .to_string(),
+            "the body has no contact/id for the dedupe conditions"
+        );
+        assert!(fill_conditions("id = {id", &body, sent_at).is_err());
+    }
+}
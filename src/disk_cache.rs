@@ -0,0 +1,236 @@
+//! A cache of GET responses on disk, so a client can keep reading while offline.  See
+//! [crate::Client::disk_cache] and [crate::Client::offline]
+use crate::RawResponse;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The header added to a response that came from the cache, the unix time (in seconds) it was
+/// cached at
+pub const CACHED_AT_HEADER: &str = "x-cwmanage-cached-at";
+
+/// Response headers that are never written to the cache
+const SKIPPED_HEADERS: &[&str] = &["authorization", "set-cookie"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    cached_at: u64,
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    pub(crate) fn new(dir: PathBuf, ttl: Duration) -> DiskCache {
+        DiskCache { dir, ttl }
+    }
+
+    /// what a request is cached under: the company (responses differ between companies on
+    /// the same host), the url without its query and the query sorted
+    pub(crate) fn key(company_id: &str, req: &reqwest::blocking::Request) -> String {
+        let mut url = req.url().clone();
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .into_owned()
+            .filter(|(k, _)| !k.is_empty())
+            .collect();
+        query.sort();
+        url.set_query(None);
+        let query: Vec<String> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        format!("{} {}?{}", company_id, url, query.join("&"))
+    }
+
+    /// the cached response for `key`, as long as it is younger than the ttl (or at any age
+    /// with `stale`).  An entry that can't be read is a miss
+    pub(crate) fn get(&self, key: &str, stale: bool) -> Option<RawResponse> {
+        let raw = fs::read_to_string(self.file(key)).ok()?;
+        let entry: Entry = match serde_json::from_str(&raw) {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::debug!(key, error = %e, "unreadable cache entry");
+                return None;
+            }
+        };
+        if entry.key != key {
+            return None;
+        }
+        let age = now().saturating_sub(entry.cached_at);
+        if !stale && Duration::from_secs(age) > self.ttl {
+            return None;
+        }
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in entry.headers.iter() {
+            if let (Ok(name), Ok(value)) = (
+                reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+                reqwest::header::HeaderValue::from_str(value),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        headers.insert(CACHED_AT_HEADER, entry.cached_at.into());
+        tracing::debug!(key, cached_at = entry.cached_at, "from the disk cache");
+        Some(RawResponse {
+            status: reqwest::StatusCode::from_u16(entry.status).ok()?,
+            headers,
+            body: entry.body,
+            redaction: None,
+        })
+    }
+
+    /// caches a successful response
+    pub(crate) fn put(&self, key: &str, res: &RawResponse) -> Result<()> {
+        if !res.status.is_success() {
+            return Ok(());
+        }
+        let entry = Entry {
+            key: key.to_string(),
+            cached_at: now(),
+            status: res.status.as_u16(),
+            headers: res
+                .headers
+                .iter()
+                .filter(|(k, _)| !SKIPPED_HEADERS.contains(&k.as_str()))
+                .map(|(k, v)| {
+                    (
+                        k.to_string(),
+                        String::from_utf8_lossy(v.as_bytes()).to_string(),
+                    )
+                })
+                .collect(),
+            body: res.body.clone(),
+        };
+
+        fs::create_dir_all(&self.dir)?;
+        let file = self.file(key);
+        let partial = file.with_extension("partial");
+        fs::write(&partial, serde_json::to_string(&entry)?)?;
+        fs::rename(&partial, &file)?;
+        Ok(())
+    }
+
+    fn file(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(key)))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// a hash that stays the same between builds, so the file names do
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_dir, MockCw};
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_disk_cache() {
+        let mut mock = MockCw::new();
+        let dir = temp_dir("cache-online");
+        let info = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .with_header("set-cookie", "session=secret")
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .expect(1)
+            .create();
+        let client = mock.client().disk_cache(&dir, Duration::from_secs(3600));
+
+        let first = client.get_single("/system/info", &[]).unwrap();
+        let second = client.get_single("/system/info", &[]).unwrap();
+        let offline = client.clone().offline(true);
+        let third = offline.get_single("/system/info", &[]).unwrap();
+
+        info.assert();
+        assert_eq!(first, json!({"version": "v2022.1"}));
+        assert_eq!(second, first);
+        assert_eq!(third, first);
+        for file in fs::read_dir(&dir).unwrap() {
+            let cached = fs::read_to_string(file.unwrap().path()).unwrap();
+            assert!(!cached.contains("Basic "));
+            assert!(!cached.to_lowercase().contains("authorization"));
+            assert!(!cached.contains("secret"));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_offline() {
+        let mut mock = MockCw::new();
+        let dir = temp_dir("cache-offline");
+        let nothing = mock.server().mock("GET", Matcher::Any).expect(0).create();
+        let no_writes = mock.server().mock("POST", Matcher::Any).expect(0).create();
+        let client = mock
+            .client()
+            .disk_cache(&dir, Duration::from_secs(3600))
+            .offline(true);
+        let cache = DiskCache::new(dir.clone(), Duration::from_secs(1));
+        let req = client
+            .http_request(reqwest::Method::GET, "/service/tickets/1")
+            .unwrap()
+            .build()
+            .unwrap();
+        let key = DiskCache::key("myco", &req);
+        let stale = Entry {
+            key: key.clone(),
+            cached_at: 1_600_000_000,
+            status: 200,
+            headers: BTreeMap::new(),
+            body: r#"{"id": 1}"#.to_string(),
+        };
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(cache.file(&key), serde_json::to_string(&stale).unwrap()).unwrap();
+
+        let ticket = client.get_single("/service/tickets/1", &[]).unwrap();
+        assert_eq!(ticket["id"], 1);
+        assert_eq!(
+            cache.get(&key, true).unwrap().headers[CACHED_AT_HEADER],
+            "1600000000"
+        );
+        assert!(cache.get(&key, false).is_none());
+
+        fs::write(cache.file(&key), "{not json").unwrap();
+        assert!(cache.get(&key, true).is_none());
+        let err = client.get_single("/service/tickets/1", &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Offline { .. })
+        ));
+        let err = client
+            .post("/service/tickets", "{}".to_string())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Offline { .. })
+        ));
+
+        nothing.assert();
+        no_writes.assert();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
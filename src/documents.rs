@@ -0,0 +1,496 @@
+//! Documents (attachments) on records.  See [Client::get_ticket_attachments]
+use crate::{api_error, Client, Error, RawResponse};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// the largest invoice pdf [Client::download_invoice_pdf] will download
+const MAX_PDF_SIZE: u64 = 50 * 1024 * 1024;
+
+/// what every pdf starts with
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+/// A document attached to a record (`/system/documents`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Document {
+    /// the id of the document
+    pub id: i64,
+    /// the title shown in manage
+    #[serde(default)]
+    pub title: String,
+    /// the name of the file that was uploaded
+    #[serde(default)]
+    pub file_name: String,
+    /// the kind of record it is attached to (`Ticket`, `Company`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_type: Option<String>,
+    /// the id of the record it is attached to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_id: Option<i64>,
+    /// the size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+/// What [Client::download_ticket_attachments] saved.  One failed download never stops the
+/// others
+#[derive(Debug, Default)]
+pub struct AttachmentReport {
+    /// the files that were written, in the order of the documents
+    pub saved: Vec<PathBuf>,
+    /// the documents that couldn't be downloaded
+    pub failed: Vec<AttachmentFailure>,
+}
+
+impl AttachmentReport {
+    /// true if every attachment was saved
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// A document that couldn't be downloaded
+#[derive(Debug)]
+pub struct AttachmentFailure {
+    /// the document
+    pub document: Document,
+    /// why it failed
+    pub error: anyhow::Error,
+}
+
+impl Client {
+    /// Gets the documents attached to a ticket, an empty list when there are none
+    pub fn get_ticket_attachments(&self, ticket_id: i64) -> Result<Vec<Document>> {
+        let record_id = ticket_id.to_string();
+        self.get_decoded(
+            "/system/documents",
+            &[("recordType", "Ticket"), ("recordId", &record_id)],
+        )
+    }
+
+    /// Writes the contents of a document to `writer` as it downloads, returns the number of
+    /// bytes written.  Downloads aren't retried or recorded by a cassette
+    pub fn download_document<W: Write>(&self, id: i64, mut writer: W) -> Result<u64> {
+        let path = format!("/system/documents/{}/download", id);
+        let mut res = self.open_download(&path)?;
+        let written = res.copy_to(&mut writer)?;
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Writes the PDF of an invoice to `writer` as it downloads, returns the number of bytes
+    /// written.  The PDF is the `.pdf` document attached to the invoice, or what the invoice's
+    /// print route returns on versions that have one.  When neither has a PDF (it hasn't been
+    /// generated yet) the error is [Error::NotFound].
+    ///
+    /// The download has to be a PDF, a page (an error or login page) with a success status is
+    /// an error and nothing is written.  PDFs over 50 MiB are refused, when the size isn't
+    /// known up front the writer may have the start of the file when that error is returned
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let mut file = std::fs::File::create("INV-1001.pdf").unwrap();
+    /// client.download_invoice_pdf(1001, &mut file).unwrap();
+    /// ```
+    pub fn download_invoice_pdf(&self, invoice_id: i64, writer: &mut impl Write) -> Result<u64> {
+        let record_id = invoice_id.to_string();
+        let documents: Vec<Document> = self.get_decoded(
+            "/system/documents",
+            &[("recordType", "Invoice"), ("recordId", &record_id)],
+        )?;
+        let pdf = documents
+            .iter()
+            .find(|d| d.file_name.to_lowercase().ends_with(".pdf"));
+
+        let path = match pdf {
+            Some(document) => format!("/system/documents/{}/download", document.id),
+            None => format!("/finance/invoices/{}/pdf", invoice_id),
+        };
+        let res = self.open_download(&path)?;
+        copy_pdf(res, &path, writer, MAX_PDF_SIZE)
+    }
+
+    /// starts a download, without following redirects (they lose the authorization).  A 404
+    /// is [Error::NotFound]
+    fn open_download(&self, path: &str) -> Result<reqwest::blocking::Response> {
+        let req = self.http_request(reqwest::Method::GET, path)?.build()?;
+        let _entered = tracing::debug_span!("cwmanage download", path = path).entered();
+
+        let res = self.http.get()?.execute(req)?;
+        if !res.status().is_success() {
+            let failed = RawResponse {
+                status: res.status(),
+                headers: res.headers().clone(),
+                body: res.text()?,
+                redaction: None,
+            };
+            return Err(match failed.status {
+                reqwest::StatusCode::NOT_FOUND => Error::NotFound {
+                    path: path.to_string(),
+                }
+                .into(),
+                _ => api_error(&failed, path),
+            });
+        }
+        Ok(res)
+    }
+
+    /// Downloads every document attached to a ticket into `dir`.  File names are cleaned up
+    /// so they are safe to write, and a name that is already taken gets `-1`, `-2`, ... before
+    /// the extension.  A download that fails is listed in the report (and its partial file
+    /// removed), the rest carry on
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let report = client
+    ///     .download_ticket_attachments(123, std::path::Path::new("attachments"))
+    ///     .unwrap();
+    /// for failure in report.failed.iter() {
+    ///     println!("{} failed: {}", failure.document.file_name, failure.error);
+    /// }
+    /// ```
+    pub fn download_ticket_attachments(
+        &self,
+        ticket_id: i64,
+        dir: &Path,
+    ) -> Result<AttachmentReport> {
+        let documents = self.get_ticket_attachments(ticket_id)?;
+        std::fs::create_dir_all(dir)?;
+
+        let mut report = AttachmentReport::default();
+        let mut taken = HashSet::new();
+        for document in documents {
+            let name = unique_name(dir, &safe_file_name(&document), &mut taken);
+            let path = dir.join(name);
+            let result = File::create(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| self.download_document(document.id, file));
+            match result {
+                Ok(_) => report.saved.push(path),
+                Err(error) => {
+                    let _ = std::fs::remove_file(&path);
+                    report.failed.push(AttachmentFailure { document, error });
+                }
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// copies a pdf download to `writer`, refusing anything that isn't a pdf or is over `max_size`
+fn copy_pdf(
+    mut res: reqwest::blocking::Response,
+    path: &str,
+    writer: &mut impl Write,
+    max_size: u64,
+) -> Result<u64> {
+    let content_type = res
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    if !["application/pdf", "application/octet-stream", ""].contains(&mime) {
+        return Err(anyhow!("{} is {}, not a pdf", path, content_type));
+    }
+    if let Some(size) = res.content_length().filter(|size| *size > max_size) {
+        return Err(anyhow!(
+            "{} is {} bytes, more than the {} allowed",
+            path,
+            size,
+            max_size
+        ));
+    }
+
+    let mut start = Vec::new();
+    (&mut res)
+        .take(PDF_MAGIC.len() as u64)
+        .read_to_end(&mut start)?;
+    if start != PDF_MAGIC {
+        return Err(anyhow!("{} is not a pdf", path));
+    }
+    writer.write_all(&start)?;
+    let limit = max_size - start.len() as u64;
+    let written = std::io::copy(&mut (&mut res).take(limit + 1), writer)?;
+    if written > limit {
+        return Err(anyhow!(
+            "{} is more than the {} bytes allowed",
+            path,
+            max_size
+        ));
+    }
+    writer.flush()?;
+    Ok(start.len() as u64 + written)
+}
+
+/// the file name of a document with anything that isn't safe in a file name replaced
+fn safe_file_name(document: &Document) -> String {
+    let name = match document.file_name.trim() {
+        "" => document.title.trim(),
+        name => name,
+    };
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c.is_whitespace());
+    match cleaned.is_empty() {
+        true => format!("document-{}", document.id),
+        false => cleaned.to_string(),
+    }
+}
+
+/// `name`, or `name-1`, `name-2`, ... (before the extension) if it is already in `dir` or was
+/// already used
+fn unique_name(dir: &Path, name: &str, taken: &mut HashSet<String>) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => (&name[..dot], &name[dot..]),
+        _ => (name, ""),
+    };
+    let is_free = |candidate: &str, taken: &HashSet<String>| {
+        !taken.contains(&candidate.to_lowercase()) && !dir.join(candidate).exists()
+    };
+
+    let mut candidate = name.to_string();
+    let mut n = 0;
+    while !is_free(&candidate, taken) {
+        n += 1;
+        candidate = format!("{}-{}{}", stem, n, ext);
+    }
+    taken.insert(candidate.to_lowercase());
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_dir, MockCw};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    fn document(id: i64, file_name: &str) -> Document {
+        Document {
+            id,
+            title: String::new(),
+            file_name: file_name.to_string(),
+            record_type: None,
+            record_id: None,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn test_download_ticket_attachments() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("recordType".to_string(), "Ticket".to_string()),
+                Matcher::UrlEncoded("recordId".to_string(), "123".to_string()),
+            ]))
+            .with_body(
+                r#"[{"id": 1, "fileName": "log.txt"},
+                    {"id": 2, "fileName": "../log.txt"},
+                    {"id": 3, "fileName": "broken.png"}]"#,
+            )
+            .create();
+        let _first = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents/1/download")
+            .with_body(vec![0u8, 159, 146, 150])
+            .create();
+        let _second = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents/2/download")
+            .with_body("second")
+            .create();
+        let _broken = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents/3/download")
+            .with_status(500)
+            .create();
+        let dir = temp_dir("documents-ticket");
+
+        let report = mock
+            .client()
+            .download_ticket_attachments(123, &dir)
+            .unwrap();
+
+        assert_eq!(
+            report.saved,
+            vec![dir.join("log.txt"), dir.join("_log.txt")]
+        );
+        assert_eq!(
+            std::fs::read(dir.join("log.txt")).unwrap(),
+            vec![0u8, 159, 146, 150]
+        );
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].document.id, 3);
+        assert!(!dir.join("broken.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_no_attachments() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create();
+        let dir = temp_dir("documents-none");
+
+        let report = mock.client().download_ticket_attachments(1, &dir).unwrap();
+
+        assert!(report.saved.is_empty());
+        assert!(report.is_success());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_names() {
+        let dir = temp_dir("documents-names");
+        let mut taken = HashSet::new();
+
+        assert_eq!(safe_file_name(&document(1, "a/b:c?.pdf")), "a_b_c_.pdf");
+        assert_eq!(safe_file_name(&document(2, " .. ")), "document-2");
+        assert_eq!(unique_name(&dir, "report.pdf", &mut taken), "report.pdf");
+        assert_eq!(unique_name(&dir, "Report.pdf", &mut taken), "Report-1.pdf");
+        assert_eq!(unique_name(&dir, "report.pdf", &mut taken), "report-2.pdf");
+        assert_eq!(unique_name(&dir, "README", &mut taken), "README");
+        assert_eq!(unique_name(&dir, "README", &mut taken), "README-1");
+    }
+
+    #[test]
+    fn test_download_invoice_pdf() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents")
+            .match_query(Matcher::UrlEncoded(
+                "recordType".to_string(),
+                "Invoice".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 4, "fileName": "notes.txt"}, {"id": 5, "fileName": "INV-1001.PDF"}]"#,
+            )
+            .create();
+        let _pdf = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents/5/download")
+            .with_header("content-type", "application/pdf")
+            .with_body("%PDF-1.4 invoice")
+            .create();
+        let mut pdf = Vec::new();
+
+        let written = mock.client().download_invoice_pdf(1001, &mut pdf).unwrap();
+
+        assert_eq!(written, 16);
+        assert_eq!(pdf, b"%PDF-1.4 invoice");
+    }
+
+    #[test]
+    fn test_invoice_pdf_not_generated() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create();
+        let print = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/finance/invoices/1001/pdf")
+            .with_status(404)
+            .create();
+
+        let err = mock
+            .client()
+            .download_invoice_pdf(1001, &mut Vec::new())
+            .unwrap_err();
+
+        print.assert();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::NotFound {
+                path: "/finance/invoices/1001/pdf".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_invoice_pdf_checked() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/documents")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create();
+        let _login = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/finance/invoices/1/pdf")
+            .with_header("content-type", "text/html; charset=utf-8")
+            .with_body("<html>please log in</html>")
+            .create();
+        let _unlabelled = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/finance/invoices/2/pdf")
+            .with_header("content-type", "application/octet-stream")
+            .with_body("<html>please log in</html>")
+            .create();
+        let _big = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/finance/invoices/3/pdf")
+            .with_header("content-type", "application/pdf")
+            .with_body(format!("%PDF-{}", "x".repeat(100)))
+            .create();
+        let client = mock.client();
+        let mut written = Vec::new();
+
+        assert!(client.download_invoice_pdf(1, &mut written).is_err());
+        assert!(client.download_invoice_pdf(2, &mut written).is_err());
+        assert!(written.is_empty());
+
+        let path = "/finance/invoices/3/pdf";
+        let res = client.open_download(path).unwrap();
+        assert!(copy_pdf(res, path, &mut written, 50).is_err());
+        let res = client.open_download(path).unwrap();
+        assert_eq!(copy_pdf(res, path, &mut Vec::new(), 105).unwrap(), 105);
+    }
+}
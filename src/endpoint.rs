@@ -0,0 +1,365 @@
+//! One fluent way to build and send a request to an endpoint, see [Client::endpoint]
+use crate::{Client, Pages, PatchOperation, MAX_PAGE_SIZE};
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+/// the query keys `/count` takes, the rest are dropped by [Endpoint::count]
+const COUNT_KEYS: [&str; 3] = ["conditions", "childConditions", "customFieldConditions"];
+
+/// A request to an endpoint being built, from [Client::endpoint].  The query methods set a
+/// key (setting it again replaces it) and the terminal methods send it with the lower level
+/// call they are named after, so the urls are the same as calling that with
+/// [Endpoint::query_pairs]
+///
+/// ```
+/// use cwmanage::Client;
+///
+/// let client = Client::new(
+///     "myco".to_string(),
+///     "public".to_string(),
+///     "private".to_string(),
+///     "clientid".to_string(),
+/// )
+/// .build();
+/// let tickets = client
+///     .endpoint("/service/tickets")
+///     .fields(["id", "summary"])
+///     .conditions("closedFlag = false")
+///     .order_by_desc("lastUpdated")
+///     .page_size(500);
+///
+/// assert_eq!(
+///     tickets.list_url().unwrap().as_str(),
+///     "https://api-na.myconnectwise.net/v4_6_release/apis/3.0/service/tickets?pageid=1&fields=id%2Csummary&conditions=closedFlag+%3D+false&orderby=lastUpdated+desc&pagesize=500"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Endpoint<'a> {
+    client: &'a Client,
+    path: String,
+    query: Vec<(String, String)>,
+    limit: Option<usize>,
+}
+
+impl Client {
+    /// Starts a request to `path` (`/service/tickets`), see [Endpoint]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Member};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let members: Vec<Member> = client
+    ///     .endpoint("/system/members")
+    ///     .conditions("inactiveFlag = false")
+    ///     .order_by("identifier")
+    ///     .limit(50)
+    ///     .get_into()
+    ///     .unwrap();
+    /// let open = client
+    ///     .endpoint("/service/tickets")
+    ///     .conditions("closedFlag = false")
+    ///     .count()
+    ///     .unwrap();
+    /// ```
+    pub fn endpoint(&self, path: &str) -> Endpoint<'_> {
+        Endpoint {
+            client: self,
+            path: path.to_string(),
+            query: Vec::new(),
+            limit: None,
+        }
+    }
+}
+
+impl<'a> Endpoint<'a> {
+    /// sets a query key, replacing it if it is already set
+    pub fn query(mut self, key: &str, value: &str) -> Endpoint<'a> {
+        match self.query.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.query.push((key.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// only these fields (`fields`), nested ones with a `/` (`company/name`)
+    pub fn fields<I, S>(self, fields: I) -> Endpoint<'a>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let fields: Vec<String> = fields.into_iter().map(|f| f.as_ref().to_string()).collect();
+        self.query("fields", &fields.join(","))
+    }
+
+    /// the records that match (`conditions`), `closedFlag = false`
+    pub fn conditions(self, conditions: &str) -> Endpoint<'a> {
+        self.query("conditions", conditions)
+    }
+
+    /// the records with a child that matches (`childConditions`)
+    pub fn child_conditions(self, conditions: &str) -> Endpoint<'a> {
+        self.query("childConditions", conditions)
+    }
+
+    /// the records with custom fields that match (`customFieldConditions`)
+    pub fn custom_field_conditions(self, conditions: &str) -> Endpoint<'a> {
+        self.query("customFieldConditions", conditions)
+    }
+
+    /// ordered by `field` ascending (`orderby`)
+    pub fn order_by(self, field: &str) -> Endpoint<'a> {
+        self.query("orderby", &format!("{} asc", field))
+    }
+
+    /// ordered by `field` descending (`orderby`)
+    pub fn order_by_desc(self, field: &str) -> Endpoint<'a> {
+        self.query("orderby", &format!("{} desc", field))
+    }
+
+    /// how many records each page has (`pagesize`), at most [MAX_PAGE_SIZE]
+    pub fn page_size(self, size: usize) -> Endpoint<'a> {
+        self.query("pagesize", &size.to_string())
+    }
+
+    /// stops a [Endpoint::get] or [Endpoint::get_into] after `limit` records, no more pages
+    /// are requested once it has them.  Without a [Endpoint::page_size] the pages are `limit`
+    /// records (up to [MAX_PAGE_SIZE]) so a small limit is one small request
+    pub fn limit(mut self, limit: usize) -> Endpoint<'a> {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// the query as it is sent, for the lower level calls
+    pub fn query_pairs(&self) -> Vec<(&str, &str)> {
+        self.query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect()
+    }
+
+    /// the query of a list, with the page size of the [Endpoint::limit]
+    fn list_query(&self) -> Endpoint<'a> {
+        match (self.limit, self.query.iter().any(|(k, _)| k == "pagesize")) {
+            (Some(limit), false) => self.clone().page_size(limit.clamp(1, MAX_PAGE_SIZE)),
+            _ => self.clone(),
+        }
+    }
+
+    /// the url of the request as given, see [Client::url_for]
+    pub fn url(&self) -> Result<Url> {
+        self.client.url_for(&self.path, &self.query_pairs())
+    }
+
+    /// the url of the first page of the list, see [Client::list_url_for]
+    pub fn list_url(&self) -> Result<Url> {
+        let list = self.list_query();
+        self.client.list_url_for(&self.path, &list.query_pairs())
+    }
+
+    /// the pages of the list, see [Client::pages].  The [Endpoint::limit] isn't applied
+    pub fn pages(&self) -> Pages<'a> {
+        let list = self.list_query();
+        self.client.pages(&self.path, &list.query_pairs())
+    }
+
+    /// every record of the list (up to the [Endpoint::limit]), see [Client::get]
+    pub fn get(&self) -> Result<Vec<Value>> {
+        self.get_into()
+    }
+
+    /// every record of the list (up to the [Endpoint::limit]) decoded into `T`, a record that
+    /// doesn't fit is [crate::Error::Decode]
+    pub fn get_into<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        if self.limit == Some(0) {
+            return Ok(Vec::new());
+        }
+        let list = self.list_query();
+        self.client
+            .get_decoded_limited(&self.path, &list.query_pairs(), self.limit)
+    }
+
+    /// the one record at the path, see [Client::get_single]
+    pub fn get_single(&self) -> Result<Value> {
+        self.client.get_single(&self.path, &self.query_pairs())
+    }
+
+    /// [Endpoint::get_single] decoded into `T`
+    pub fn get_single_into<T: DeserializeOwned>(&self) -> Result<T> {
+        self.client.decode(&self.path, self.get_single()?)
+    }
+
+    /// how many records match, [Client::get_count] with the conditions (the rest of the
+    /// query doesn't apply to a count)
+    pub fn count(&self) -> Result<u64> {
+        let query: Vec<(&str, &str)> = self
+            .query_pairs()
+            .into_iter()
+            .filter(|(k, _)| COUNT_KEYS.contains(k))
+            .collect();
+        self.client.get_count(&self.path, &query)
+    }
+
+    /// creates a record, see [Client::post_json]
+    pub fn post<B: Serialize + ?Sized>(&self, body: &B) -> Result<Value> {
+        self.client.post_json(&self.path, body)
+    }
+
+    /// changes the record with the operations, all in one request
+    pub fn patch(&self, ops: &[PatchOperation]) -> Result<Value> {
+        self.client.patch_ops(&self.path, ops)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw, API_PREFIX};
+    use crate::{Member, PatchOp};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_endpoint_urls() {
+        let client = MockCw::new().client();
+        let endpoint = client
+            .endpoint("/service/tickets")
+            .fields(["id", "summary"])
+            .conditions("board/id = 1")
+            .order_by_desc("lastUpdated")
+            .page_size(500)
+            .conditions("closedFlag = false");
+        let query = [
+            ("fields", "id,summary"),
+            ("conditions", "closedFlag = false"),
+            ("orderby", "lastUpdated desc"),
+            ("pagesize", "500"),
+        ];
+
+        assert_eq!(endpoint.query_pairs(), query.to_vec());
+        assert_eq!(
+            endpoint.list_url().unwrap(),
+            client.list_url_for("/service/tickets", &query).unwrap()
+        );
+        assert_eq!(
+            endpoint.url().unwrap(),
+            client.url_for("/service/tickets", &query).unwrap()
+        );
+        // a limit without a page size asks for pages of the limit
+        assert_eq!(
+            client
+                .endpoint("/system/members")
+                .limit(20)
+                .list_url()
+                .unwrap(),
+            client
+                .list_url_for("/system/members", &[("pagesize", "20")])
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_endpoint_get() {
+        let mut mock = MockCw::new();
+        let path = format!("{}/system/members", API_PREFIX);
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let page = |pageid: &str| {
+            Matcher::Exact(format!(
+                "pageid={}&conditions=inactiveFlag+%3D+false&pagesize=2&orderby=id+asc",
+                pageid
+            ))
+        };
+        let first = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(page("1"))
+            .with_header("link", &next)
+            .with_body(json!([fixtures::member(1), fixtures::member(2)]).to_string())
+            .expect(3)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(page("2"))
+            .with_body(json!([fixtures::member(3)]).to_string())
+            .expect(2)
+            .create();
+        let client = mock.client();
+        let members = client
+            .endpoint("/system/members")
+            .conditions("inactiveFlag = false")
+            .page_size(2);
+
+        let all: Vec<Member> = members.get_into().unwrap();
+        let limited = members.clone().limit(2).get().unwrap();
+        let lower = client
+            .get(
+                "/system/members",
+                &[("conditions", "inactiveFlag = false"), ("pagesize", "2")],
+            )
+            .unwrap();
+
+        // the limited get stops after the first page
+        first.assert();
+        second.assert();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].identifier, "member3");
+        assert_eq!(limited, lower[..2].to_vec());
+        assert!(members.limit(0).get().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_endpoint_single_count_and_writes() {
+        let mut mock = MockCw::new()
+            .single("/service/tickets/count", json!({"count": 7}))
+            .single("/service/tickets/5", fixtures::ticket(5));
+        let path = format!("{}/service/tickets", API_PREFIX);
+        let post = mock
+            .server()
+            .mock("POST", path.as_str())
+            .match_body(Matcher::Json(json!({"summary": "new"})))
+            .with_body(fixtures::ticket(6).to_string())
+            .create();
+        let patch = mock
+            .server()
+            .mock("PATCH", format!("{}/5", path).as_str())
+            .match_body(Matcher::Json(
+                json!([{"op": "replace", "path": "summary", "value": "changed"}]),
+            ))
+            .with_body(fixtures::ticket(5).to_string())
+            .create();
+        let client = mock.client();
+        let tickets = client
+            .endpoint("/service/tickets")
+            .conditions("closedFlag = false")
+            .fields(["id"]);
+        let ticket = client.endpoint("/service/tickets/5");
+
+        assert_eq!(tickets.count().unwrap(), 7);
+        assert_eq!(ticket.get_single().unwrap()["id"], 5);
+        assert_eq!(tickets.post(&json!({"summary": "new"})).unwrap()["id"], 6);
+        let ops = [PatchOperation::new(
+            PatchOp::Replace,
+            "summary",
+            json!("changed"),
+        )];
+        assert_eq!(ticket.patch(&ops).unwrap()["id"], 5);
+
+        mock.assert();
+        post.assert();
+        patch.assert();
+    }
+}
@@ -0,0 +1,479 @@
+//! Errors specific to the connectwise api.  These are returned inside of an
+//! [anyhow::Error] so the existing `Result` signatures don't change, use
+//! [anyhow::Error::downcast_ref] to match on them
+//!
+//! ```
+//! use cwmanage::Error;
+//!
+//! let err: anyhow::Error = Error::NotFound { path: "/service/tickets/1".to_string() }.into();
+//! match err.downcast_ref::<Error>() {
+//!     Some(Error::NotFound { path }) => assert_eq!(path, "/service/tickets/1"),
+//!     _ => panic!("expected not found"),
+//! }
+//! ```
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// The error body connectwise returns with a failed request
+///
+/// ```
+/// use cwmanage::ApiError;
+///
+/// let body = r#"{"code": "InvalidObject", "message": "ticket object is invalid",
+///     "errors": [{"code": "NullValue", "message": "summary may not be empty",
+///     "resource": "ticket", "field": "summary"}]}"#;
+/// let err: ApiError = serde_json::from_str(body).unwrap();
+/// assert_eq!(err.errors[0].field, "summary");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiError {
+    /// the kind of error (`InvalidObject`, `NotFound`, ...)
+    #[serde(deserialize_with = "null_default")]
+    pub code: String,
+    /// what went wrong
+    #[serde(deserialize_with = "null_default")]
+    pub message: String,
+    /// the details, usually one per invalid field
+    #[serde(deserialize_with = "null_default")]
+    pub errors: Vec<ApiErrorDetail>,
+}
+
+/// One detail of an [ApiError]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiErrorDetail {
+    /// the kind of error (`NullValue`, ...)
+    #[serde(deserialize_with = "null_default")]
+    pub code: String,
+    /// what went wrong
+    #[serde(deserialize_with = "null_default")]
+    pub message: String,
+    /// the kind of record
+    #[serde(deserialize_with = "null_default")]
+    pub resource: String,
+    /// the field that is invalid
+    #[serde(deserialize_with = "null_default")]
+    pub field: String,
+}
+
+/// connectwise sends `null` for empty fields
+fn null_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+impl ApiError {
+    /// parses a response body, `None` if it isn't an error body
+    pub(crate) fn from_body(body: &str) -> Option<ApiError> {
+        serde_json::from_str::<ApiError>(body)
+            .ok()
+            .filter(|e| !e.code.is_empty() || !e.message.is_empty())
+    }
+}
+
+/// Our possible api errors
+#[derive(Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// The record (or endpoint) does not exist, the api returned a 404
+    NotFound {
+        /// the path that was requested
+        path: String,
+    },
+    /// Nothing (active) in a lookup list has the name that was given, see [crate::IdOrName]
+    UnknownName {
+        /// the lookup list that was searched (`/sales/activities/statuses`)
+        path: String,
+        /// the name that was looked for
+        name: String,
+        /// the names that are in the list
+        valid: Vec<String>,
+    },
+    /// The record exists but the endpoint doesn't expose `customFields`
+    CustomFieldsUnsupported {
+        /// the path that was requested
+        path: String,
+    },
+    /// A link does not point at the configured connectwise instance so it wasn't followed
+    ForeignLink {
+        /// the link that was refused
+        href: String,
+    },
+    /// A field path (`status/name`) contains characters connectwise doesn't allow
+    InvalidFieldPath {
+        /// the field path that was rejected
+        field_path: String,
+    },
+    /// The api path given to a request isn't valid
+    InvalidPath {
+        /// the path that was rejected
+        path: String,
+        /// why it was rejected
+        reason: String,
+    },
+    /// A callback came from a different connectwise instance or company than the client is
+    /// set up for, see [crate::Client::verify_callback]
+    ForeignCallback {
+        /// the `FromUrl` of the callback
+        from_url: String,
+        /// the `CompanyId` of the callback
+        company_id: String,
+    },
+    /// A list was expected (by [crate::Client::get]) but the endpoint returned a single object
+    ExpectedList {
+        /// the path that was requested
+        path: String,
+        /// what to use instead
+        hint: &'static str,
+    },
+    /// A single object was expected (by [crate::Client::get_single]) but the endpoint returned
+    /// a list
+    ExpectedObject {
+        /// the path that was requested
+        path: String,
+        /// what to use instead
+        hint: &'static str,
+    },
+    /// More than one record matched where at most one should, see
+    /// [crate::Client::get_exactly_one]
+    NotUnique {
+        /// the path that was requested
+        path: String,
+    },
+    /// A query parameter can't be used the way it was given
+    InvalidQuery {
+        /// the query key
+        key: String,
+        /// why it was rejected
+        reason: String,
+    },
+    /// The forward only pagination cursor (`pageId`) stopped being valid part way through a
+    /// list, see [crate::Client::resume_on_cursor_expiry]
+    CursorExpired {
+        /// the path that was being listed
+        path: String,
+        /// the `pageId` that was refused
+        page: String,
+    },
+    /// The api answered with a redirect.  Redirects aren't followed because a followed `POST`
+    /// becomes a `GET` without its body, point [crate::Client::api_url] at the new location
+    Redirected {
+        /// the redirect status (301, 307, ...)
+        status: u16,
+        /// where it redirected to
+        location: String,
+    },
+    /// The instance time zone isn't one we know, see [crate::SystemInfo]
+    UnknownTimeZone {
+        /// the time zone name connectwise reported
+        name: String,
+    },
+    /// The authenticated member isn't allowed to do this, the api returned a 403
+    Forbidden {
+        /// the path that was requested
+        path: String,
+        /// what the api said
+        error: ApiError,
+    },
+    /// The api refused the request and said why
+    Api {
+        /// the path that was requested
+        path: String,
+        /// the http status (400, 409, ...)
+        status: u16,
+        /// what the api said
+        error: ApiError,
+    },
+    /// Receiving this many would be more than is still expected on the purchase order line,
+    /// nothing was sent.  See [crate::Client::receive_po_line]
+    OverReceived {
+        /// the purchase order line
+        line_id: i64,
+        /// how many are still expected
+        remaining: f64,
+        /// how many were to be received
+        quantity: f64,
+    },
+    /// The client is read only (see [crate::Client::read_only]) so the write wasn't sent
+    ReadOnly,
+    /// The record changed since it was read, so the patch wasn't sent.  See
+    /// [crate::Client::patch_if_unchanged]
+    Conflict {
+        /// the record's `_info/lastUpdated` now
+        current: String,
+    },
+    /// The ticket's board has no (active) closed status to close it with, see
+    /// [crate::Client::close_ticket]
+    NoClosedStatus {
+        /// the board of the ticket
+        board_id: i64,
+    },
+    /// The url of a list would be longer than [crate::Client::max_url_length] and the list
+    /// has no search route, use fewer conditions (or [crate::Client::search] if it does have
+    /// one)
+    QueryTooLong {
+        /// the path that was requested
+        path: String,
+        /// how long the url would be
+        length: usize,
+        /// the longest url allowed
+        max: usize,
+    },
+    /// A record doesn't fit the type it is read into
+    Decode {
+        /// the path the record came from
+        path: String,
+        /// where in the response the bad value is (`[8412].status.name`)
+        field: String,
+        /// the `id` of the record, if it has one
+        id: Option<i64>,
+        /// the page of the list the record was on, for lists
+        page: Option<usize>,
+        /// what serde said
+        message: String,
+    },
+    /// The body of a write can't be turned into json (a map with keys that aren't strings, or a
+    /// `Serialize` that fails), so nothing was sent
+    Encode {
+        /// the path the body was for
+        path: String,
+        /// what serde said
+        message: String,
+    },
+    /// The response has fields the type it was decoded into doesn't have, and the client
+    /// denies them (see [crate::Client::deny_unknown_fields])
+    UnknownFields {
+        /// the path the records came from
+        path: String,
+        /// the type the records were decoded into
+        model: String,
+        /// the fields the type doesn't have
+        fields: Vec<String>,
+    },
+    /// The credentials of the client can't be put in a Basic `Authorization` header, see
+    /// [crate::Client::new]
+    InvalidCredentials {
+        /// the credential (`company id`, `public key` or `private key`)
+        field: String,
+        /// what is wrong with it
+        reason: String,
+    },
+    /// There are no credentials in the os keychain for the service and account, see
+    /// `Client::from_keyring`
+    NoKeyringEntry {
+        /// the keychain service
+        service: String,
+        /// the keychain account
+        account: String,
+    },
+    /// The os keychain can't be used (it is locked, or there isn't one), see
+    /// `Client::from_keyring`
+    KeyringUnavailable {
+        /// the keychain service
+        service: String,
+        /// the keychain account
+        account: String,
+        /// what the keychain said
+        reason: String,
+    },
+    /// The [crate::CancelToken] of the client (see [crate::Client::cancel_on]) was cancelled,
+    /// so the request wasn't sent
+    Cancelled {
+        /// the method of the request
+        method: String,
+        /// the path that was requested
+        path: String,
+    },
+    /// The client is offline (see [crate::Client::offline]) and the request can't be answered
+    /// from the disk cache, so nothing was sent
+    Offline {
+        /// the method of the request
+        method: String,
+        /// the path that was requested
+        path: String,
+    },
+    /// The api is rate limiting us, it returned a 429
+    RateLimited {
+        /// how long the api asked us to wait (the `Retry-After` header), if it said
+        retry_after: Option<Duration>,
+    },
+    /// The deadline of a multi request operation passed before it finished, see
+    /// [crate::Client::get_with_deadline]
+    DeadlineExceeded {
+        /// how long the operation ran
+        elapsed: Duration,
+        /// how many records had been returned when it stopped
+        records_collected: usize,
+        /// the `pageid` of the page that wasn't fetched, pass it in the query to carry on
+        resume_cursor: Option<String>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound { path } => write!(f, "not found: {}", path),
+            Error::CustomFieldsUnsupported { path } => {
+                write!(f, "{} does not support customFields", path)
+            }
+            Error::ForeignLink { href } => {
+                write!(f, "{} is not a link to this connectwise instance", href)
+            }
+            Error::InvalidFieldPath { field_path } => {
+                write!(f, "{:?} is not a valid field path", field_path)
+            }
+            Error::InvalidPath { path, reason } => {
+                write!(f, "invalid path {:?}: {}", path, reason)
+            }
+            Error::ForeignCallback {
+                from_url,
+                company_id,
+            } => write!(
+                f,
+                "callback from {} ({}) is not for this connectwise instance",
+                from_url, company_id
+            ),
+            Error::ExpectedList { path, hint } => {
+                write!(f, "expected a list from {}: {}", path, hint)
+            }
+            Error::ExpectedObject { path, hint } => {
+                write!(f, "expected a single object from {}: {}", path, hint)
+            }
+            Error::NotUnique { path } => {
+                write!(f, "more than one record of {} matches", path)
+            }
+            Error::InvalidQuery { key, reason } => {
+                write!(f, "invalid query {:?}: {}", key, reason)
+            }
+            Error::CursorExpired { path, page } => {
+                write!(f, "page {} of {} is no longer valid", page, path)
+            }
+            Error::Redirected { status, location } => {
+                write!(
+                    f,
+                    "redirected ({}) to {}, check the api_url",
+                    status, location
+                )
+            }
+            Error::UnknownTimeZone { name } => write!(f, "unknown time zone {:?}", name),
+            Error::UnknownName { path, name, valid } => match valid.is_empty() {
+                true => write!(f, "no {:?} in {}", name, path),
+                false => write!(f, "no {:?} in {}, it has {}", name, path, valid.join(", ")),
+            },
+            Error::Forbidden { path, error } => {
+                write!(f, "forbidden {}: {}", path, error.message)
+            }
+            Error::Api {
+                path,
+                status,
+                error,
+            } => write!(
+                f,
+                "{} from {}: {} {}",
+                status, path, error.code, error.message
+            ),
+            Error::OverReceived {
+                line_id,
+                remaining,
+                quantity,
+            } => write!(
+                f,
+                "can't receive {} on line {}, only {} still expected",
+                quantity, line_id, remaining
+            ),
+            Error::ReadOnly => write!(f, "client is read only"),
+            Error::Conflict { current } => {
+                write!(f, "the record has changed, it was last updated {}", current)
+            }
+            Error::NoClosedStatus { board_id } => {
+                write!(f, "board {} has no closed status", board_id)
+            }
+            Error::QueryTooLong { path, length, max } => write!(
+                f,
+                "the url for {} would be {} long, more than {}, use search or fewer conditions",
+                path, length, max
+            ),
+            Error::Decode {
+                path,
+                field,
+                id,
+                page,
+                message,
+            } => {
+                write!(f, "can't decode {} of {}", field, path)?;
+                match (id, page) {
+                    (Some(id), Some(page)) => write!(f, " (id {}, page {})", id, page)?,
+                    (Some(id), None) => write!(f, " (id {})", id)?,
+                    (None, Some(page)) => write!(f, " (page {})", page)?,
+                    (None, None) => {}
+                }
+                write!(f, ": {}", message)
+            }
+            Error::Encode { path, message } => {
+                write!(f, "can't encode the body for {}: {}", path, message)
+            }
+            Error::UnknownFields {
+                path,
+                model,
+                fields,
+            } => write!(
+                f,
+                "{} from {} has fields {} doesn't: {}",
+                model,
+                path,
+                model,
+                fields.join(", ")
+            ),
+            Error::InvalidCredentials { field, reason } => {
+                write!(f, "invalid {}: {}", field, reason)
+            }
+            Error::NoKeyringEntry { service, account } => write!(
+                f,
+                "no credentials in the keychain for service {:?}, account {:?}",
+                service, account
+            ),
+            Error::KeyringUnavailable {
+                service,
+                account,
+                reason,
+            } => write!(
+                f,
+                "keychain unavailable for service {:?}, account {:?}: {}",
+                service, account, reason
+            ),
+            Error::Cancelled { method, path } => {
+                write!(f, "cancelled, didn't {} {}", method, path)
+            }
+            Error::Offline { method, path } => {
+                write!(f, "client is offline, can't {} {}", method, path)
+            }
+            Error::RateLimited { retry_after } => match retry_after {
+                Some(wait) => write!(f, "rate limited, retry after {}s", wait.as_secs()),
+                None => write!(f, "rate limited"),
+            },
+            Error::DeadlineExceeded {
+                elapsed,
+                records_collected,
+                resume_cursor,
+            } => {
+                write!(
+                    f,
+                    "deadline exceeded after {:.1}s with {} records",
+                    elapsed.as_secs_f64(),
+                    records_collected
+                )?;
+                match resume_cursor {
+                    Some(cursor) => write!(f, ", resume from page {}", cursor),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,415 @@
+//! Writing results out to other formats
+//!
+//! # NDJSON Example
+//! ```no_run
+//! use cwmanage::export::to_ndjson;
+//! use cwmanage::Client;
+//! use dotenv::dotenv;
+//! dotenv().ok();
+//! let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+//! let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+//! let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+//! let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+//! let client = Client::new(company_id, public_key, private_key, client_id).build();
+//!
+//! let file = std::fs::File::create("tickets.ndjson").unwrap();
+//! let stats = to_ndjson(&client, "/service/tickets", &[("", "")], file).unwrap();
+//! println!("wrote {} tickets", stats.records);
+//! ```
+//!
+//! # CSV Example
+//! ```no_run
+//! # #[cfg(feature = "csv")]
+//! # {
+//! use cwmanage::export::{to_csv, CsvOptions};
+//! use cwmanage::Client;
+//! use dotenv::dotenv;
+//! dotenv().ok();
+//! let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+//! let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+//! let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+//! let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+//! let client = Client::new(company_id, public_key, private_key, client_id).build();
+//!
+//! let tickets = client
+//!     .get("/service/tickets", &[("fields", "id,summary,status/name")])
+//!     .unwrap();
+//! let file = std::fs::File::create("tickets.csv").unwrap();
+//! to_csv(&tickets, file, CsvOptions::default()).unwrap();
+//! # }
+//! ```
+use crate::Client;
+use anyhow::Result;
+#[cfg(feature = "arrow")]
+pub use arrow::{to_arrow, to_arrow_pages, ArrowBatch, ArrowPages};
+pub use exporter::{ExportEstimate, ExportFormat, Exporter};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "csv")]
+use serde_json::Value;
+#[cfg(feature = "csv")]
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::Write;
+
+#[cfg(feature = "arrow")]
+mod arrow;
+mod exporter;
+
+/// What an export wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ExportStats {
+    /// pages fetched
+    pub pages: u64,
+    /// records (lines) written
+    pub records: u64,
+    /// bytes written
+    pub bytes: u64,
+}
+
+/// Added to the error when an export fails part way.  Everything counted in `stats` was
+/// written and flushed before the failure
+///
+/// ```
+/// use cwmanage::export::{ExportInterrupted, ExportStats};
+///
+/// fn lines_written(err: &anyhow::Error) -> u64 {
+///     match err.downcast_ref::<ExportInterrupted>() {
+///         Some(interrupted) => interrupted.stats.records,
+///         None => 0,
+///     }
+/// }
+/// let err = anyhow::anyhow!("connection reset").context(ExportInterrupted {
+///     stats: ExportStats { pages: 2, records: 2000, bytes: 1_048_576 },
+/// });
+/// assert_eq!(lines_written(&err), 2000);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportInterrupted {
+    /// what was written before the failure
+    pub stats: ExportStats,
+}
+
+impl fmt::Display for ExportInterrupted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "export interrupted after {} records ({} bytes) were written",
+            self.stats.records, self.stats.bytes
+        )
+    }
+}
+
+impl std::error::Error for ExportInterrupted {}
+
+/// Writes every record of a list endpoint as newline delimited json, one compact object per
+/// line.  The pages are written (and flushed) as they are fetched so nothing is held in memory
+/// besides the current page.  If a page fails to fetch or write the error has an
+/// [ExportInterrupted] with what was already written
+///
+/// # Arguments
+///
+/// - `client` - the client to fetch with
+/// - `path` - the api path you want to export (example `/service/tickets`)
+/// - `query` - additional query options *must be set*.  If non, use [("", "")]
+/// - `writer` - where to write, a file or stdout
+pub fn to_ndjson<W: Write>(
+    client: &Client,
+    path: &str,
+    query: &[(&str, &str)],
+    mut writer: W,
+) -> Result<ExportStats> {
+    let mut stats = ExportStats::default();
+
+    for page in client.pages(path, query) {
+        let interrupted = |e: anyhow::Error| e.context(ExportInterrupted { stats });
+        let records = page.map_err(interrupted)?;
+
+        let mut buf = Vec::new();
+        for record in records.iter() {
+            serde_json::to_writer(&mut buf, record)?;
+            buf.push(b'\n');
+        }
+        writer
+            .write_all(&buf)
+            .and_then(|_| writer.flush())
+            .map_err(|e| interrupted(e.into()))?;
+
+        stats.pages += 1;
+        stats.records += records.len() as u64;
+        stats.bytes += buf.len() as u64;
+    }
+
+    Ok(stats)
+}
+
+/// Options for [to_csv] and [to_csv_pages]
+#[cfg(feature = "csv")]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CsvOptions {
+    columns: Option<Vec<String>>,
+    null: String,
+    include_info: bool,
+}
+
+#[cfg(feature = "csv")]
+impl CsvOptions {
+    /// only write these (flattened) columns, in this order.  By default every column found
+    /// is written, sorted by name
+    pub fn columns(mut self, columns: Vec<String>) -> CsvOptions {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// what to write for a null or missing value, by default an empty string
+    pub fn null(mut self, null: &str) -> CsvOptions {
+        self.null = null.to_string();
+        self
+    }
+
+    /// include the `_info` maps (mostly links and update times), by default they are skipped
+    pub fn include_info(mut self, include_info: bool) -> CsvOptions {
+        self.include_info = include_info;
+        self
+    }
+}
+
+/// Writes records (from [crate::Client::get]) as csv with a header row.  Nested objects are
+/// flattened into dotted column names (`status.name`, `company.identifier`), arrays are written
+/// as json, and the columns are the union of the columns of every record
+#[cfg(feature = "csv")]
+pub fn to_csv<W: Write>(records: &[Value], writer: W, options: CsvOptions) -> Result<()> {
+    let rows: Vec<BTreeMap<String, Value>> = records
+        .iter()
+        .map(|r| flatten_record(r, options.include_info))
+        .collect();
+    let columns = csv_columns(&rows, &options);
+
+    let mut wtr = csv::Writer::from_writer(writer);
+    wtr.write_record(&columns)?;
+    for row in rows.iter() {
+        write_csv_row(&mut wtr, row, &columns, &options)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Same as [to_csv] but writes each page as it arrives (see [crate::Client::pages]) so the
+/// whole result never has to be in memory.  Because the header is written first the columns
+/// are either the ones set with [CsvOptions::columns] or the ones found on the first page,
+/// columns that only show up on later pages are not written.  Returns the number of records
+/// written
+#[cfg(feature = "csv")]
+pub fn to_csv_pages<W, I>(pages: I, writer: W, options: CsvOptions) -> Result<u64>
+where
+    W: Write,
+    I: IntoIterator<Item = Result<Vec<Value>>>,
+{
+    let mut wtr = csv::Writer::from_writer(writer);
+    let mut columns: Option<Vec<String>> = None;
+    let mut written: u64 = 0;
+
+    for page in pages {
+        let rows: Vec<BTreeMap<String, Value>> = page?
+            .iter()
+            .map(|r| flatten_record(r, options.include_info))
+            .collect();
+        let columns = match columns.as_ref() {
+            Some(c) => c,
+            None => {
+                let c = csv_columns(&rows, &options);
+                wtr.write_record(&c)?;
+                columns.get_or_insert(c)
+            }
+        };
+        for row in rows.iter() {
+            write_csv_row(&mut wtr, row, columns, &options)?;
+            written += 1;
+        }
+        wtr.flush()?;
+    }
+
+    if columns.is_none() {
+        wtr.write_record(options.columns.clone().unwrap_or_default())?;
+        wtr.flush()?;
+    }
+    Ok(written)
+}
+
+#[cfg(feature = "csv")]
+fn csv_columns(rows: &[BTreeMap<String, Value>], options: &CsvOptions) -> Vec<String> {
+    match &options.columns {
+        Some(columns) => columns.clone(),
+        None => rows
+            .iter()
+            .flat_map(|r| r.keys().cloned())
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect(),
+    }
+}
+
+#[cfg(feature = "csv")]
+fn write_csv_row<W: Write>(
+    wtr: &mut csv::Writer<W>,
+    row: &BTreeMap<String, Value>,
+    columns: &[String],
+    options: &CsvOptions,
+) -> Result<()> {
+    let fields = columns.iter().map(|c| match row.get(c) {
+        None | Some(Value::Null) => options.null.clone(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    });
+    wtr.write_record(fields)?;
+    Ok(())
+}
+
+#[cfg(feature = "csv")]
+fn flatten_record(record: &Value, include_info: bool) -> BTreeMap<String, Value> {
+    let options = crate::Flatten::default().include_info(include_info);
+    match crate::flatten(record, &options) {
+        Value::Object(map) => map.into_iter().collect(),
+        other => BTreeMap::from([(String::new(), other)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_ndjson() {
+        let mock = MockCw::new().list(
+            "/service/tickets",
+            vec![
+                vec![
+                    json!({"id": 1, "summary": "a"}),
+                    json!({"id": 2, "summary": "b"}),
+                ],
+                vec![json!({"id": 3, "summary": "c"})],
+            ],
+        );
+        let mut out = Vec::new();
+
+        let stats = to_ndjson(&mock.client(), "/service/tickets", &[], &mut out).unwrap();
+
+        let expected = "{\"id\":1,\"summary\":\"a\"}\n{\"id\":2,\"summary\":\"b\"}\n{\"id\":3,\"summary\":\"c\"}\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+        assert_eq!(
+            stats,
+            ExportStats {
+                pages: 2,
+                records: 3,
+                bytes: expected.len() as u64
+            }
+        );
+    }
+
+    #[test]
+    fn test_to_ndjson_interrupted() {
+        let mut mock = MockCw::new();
+        let next = format!(
+            "<{}/v4_6_release/apis/3.0/service/tickets?pageId=2>; rel=\"next\"",
+            mock.url()
+        );
+        let _first = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(mockito::Matcher::UrlEncoded("pageid".into(), "1".into()))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}, {"id": 2}]"#)
+            .create();
+        let _second = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(mockito::Matcher::UrlEncoded("pageid".into(), "2".into()))
+            .with_status(500)
+            .with_body("<html>oops</html>")
+            .create();
+        let mut out = Vec::new();
+
+        let err = to_ndjson(&mock.client(), "/service/tickets", &[], &mut out).unwrap_err();
+
+        let interrupted = err.downcast_ref::<ExportInterrupted>().unwrap();
+        assert_eq!(interrupted.stats.records, 2);
+        assert_eq!(interrupted.stats.bytes, out.len() as u64);
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"id\":1}\n{\"id\":2}\n");
+    }
+
+    #[cfg(feature = "csv")]
+    fn tickets() -> Vec<Value> {
+        vec![
+            json!({
+                "id": 1,
+                "summary": "printer, \"on fire\"",
+                "status": {"id": 16, "name": "Closed", "_info": {"status_href": "x"}},
+                "_info": {"lastUpdated": "2022-07-14T15:34:22Z"}
+            }),
+            json!({
+                "id": 2,
+                "summary": "new laptop",
+                "company": {"identifier": "ACME"},
+                "contact": null,
+                "tags": ["a", "b"]
+            }),
+        ]
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_to_csv() {
+        let expected = "\
+company.identifier,contact,id,status.id,status.name,summary,tags
+,,1,16,Closed,\"printer, \"\"on fire\"\"\",
+ACME,,2,,,new laptop,\"[\"\"a\"\",\"\"b\"\"]\"
+";
+        let mut out = Vec::new();
+
+        to_csv(&tickets(), &mut out, CsvOptions::default()).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_to_csv_options() {
+        let expected = "\
+id,_info.lastUpdated,status._info.status_href
+1,2022-07-14T15:34:22Z,x
+2,NULL,NULL
+";
+        let options = CsvOptions::default()
+            .columns(vec![
+                "id".to_string(),
+                "_info.lastUpdated".to_string(),
+                "status._info.status_href".to_string(),
+            ])
+            .null("NULL")
+            .include_info(true);
+        let mut out = Vec::new();
+
+        to_csv(&tickets(), &mut out, options).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_to_csv_pages() {
+        let expected = "\
+id,status.id,status.name,summary
+1,16,Closed,\"printer, \"\"on fire\"\"\"
+2,,,new laptop
+";
+        let pages = tickets().into_iter().map(|t| Ok(vec![t]));
+        let mut out = Vec::new();
+
+        let written = to_csv_pages(pages, &mut out, CsvOptions::default()).unwrap();
+
+        assert_eq!(written, 2);
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
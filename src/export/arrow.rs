@@ -0,0 +1,417 @@
+//! Converting records to arrow record batches (the `arrow` feature), for polars or parquet
+use crate::{flatten, ArrayMode, Flatten};
+use anyhow::{anyhow, Result};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+/// The records converted by [to_arrow] or [to_arrow_pages]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrowBatch {
+    /// the records, one row each
+    pub batch: RecordBatch,
+    /// what didn't convert cleanly, a column with mixed types (written as text) or a value
+    /// that didn't fit the column's type (left null)
+    pub warnings: Vec<String>,
+}
+
+/// Converts records (from [crate::Client::get]) to an arrow record batch.  One level of nested
+/// objects is flattened into dotted columns (`status.name`), anything deeper and arrays are
+/// written as json text.  The `_info` maps are skipped
+///
+/// Without `schema_hint` the type of each column is worked out from its values: `Boolean`,
+/// `Int64`, `Float64` (a mix of whole and decimal numbers), `Timestamp(Millisecond, UTC)` (rfc
+/// 3339 dates, the way connectwise sends them) or `Utf8`.  A column with more than one kind of
+/// value is `Utf8` and gets a warning rather than failing.  With `schema_hint` exactly its
+/// columns are written, as its types (only the ones above are supported)
+///
+/// ```
+/// use cwmanage::export::to_arrow;
+/// use serde_json::json;
+///
+/// let tickets = [
+///     json!({"id": 1, "status": {"name": "New"}, "dateEntered": "2023-03-01T10:00:00Z"}),
+///     json!({"id": 2, "status": {"name": "Closed"}, "dateEntered": "2023-03-02T10:00:00Z"}),
+/// ];
+/// let converted = to_arrow(&tickets, None).unwrap();
+/// assert_eq!(converted.batch.num_rows(), 2);
+/// assert!(converted.batch.column_by_name("status.name").is_some());
+/// ```
+pub fn to_arrow(records: &[Value], schema_hint: Option<&Schema>) -> Result<ArrowBatch> {
+    let rows = flatten_rows(records);
+    let mut warnings = Vec::new();
+    let schema = match schema_hint {
+        Some(schema) => Arc::new(schema.clone()),
+        None => infer_schema(&rows, &mut warnings),
+    };
+    let batch = build_batch(&schema, &rows, &mut warnings)?;
+    Ok(ArrowBatch { batch, warnings })
+}
+
+/// Same as [to_arrow] but converts each page as it arrives (see [crate::Client::pages]), so
+/// an export can be larger than memory.  Every batch has the same schema, `schema_hint` or
+/// the one worked out from the first page.  Columns that only show up on later pages are
+/// dropped with a warning
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::export::to_arrow_pages;
+/// use cwmanage::Client;
+/// use dotenv::dotenv;
+/// dotenv().ok();
+/// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+/// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+/// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+/// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+/// let client = Client::new(company_id, public_key, private_key, client_id).build();
+///
+/// for converted in to_arrow_pages(client.pages("/service/tickets", &[("", "")]), None) {
+///     let converted = converted.unwrap();
+///     println!("{} rows, {:?}", converted.batch.num_rows(), converted.warnings);
+/// }
+/// ```
+pub fn to_arrow_pages<I>(pages: I, schema_hint: Option<&Schema>) -> ArrowPages<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<Vec<Value>>>,
+{
+    ArrowPages {
+        pages: pages.into_iter(),
+        schema: schema_hint.map(|s| Arc::new(s.clone())),
+    }
+}
+
+/// The batches of [to_arrow_pages], one per page
+#[derive(Debug)]
+pub struct ArrowPages<I> {
+    pages: I,
+    schema: Option<SchemaRef>,
+}
+
+impl<I> Iterator for ArrowPages<I>
+where
+    I: Iterator<Item = Result<Vec<Value>>>,
+{
+    type Item = Result<ArrowBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let records = match self.pages.next()? {
+            Ok(records) => records,
+            Err(e) => return Some(Err(e)),
+        };
+        let rows = flatten_rows(&records);
+        let mut warnings = Vec::new();
+        let schema = self
+            .schema
+            .get_or_insert_with(|| infer_schema(&rows, &mut warnings))
+            .clone();
+
+        let dropped: BTreeSet<&String> = rows
+            .iter()
+            .flat_map(|r| r.keys())
+            .filter(|k| schema.field_with_name(k).is_err())
+            .collect();
+        for column in dropped {
+            warnings.push(format!("column {} isn't in the schema, dropped", column));
+        }
+        Some(build_batch(&schema, &rows, &mut warnings).map(|batch| ArrowBatch { batch, warnings }))
+    }
+}
+
+fn flatten_rows(records: &[Value]) -> Vec<Map<String, Value>> {
+    let options = Flatten::default()
+        .depth(1)
+        .arrays(ArrayMode::Json)
+        .include_info(false);
+    records
+        .iter()
+        .map(|r| match flatten(r, &options) {
+            Value::Object(map) => map,
+            other => std::iter::once((String::new(), other)).collect(),
+        })
+        .collect()
+}
+
+/// the kinds of value seen in a column
+#[derive(Debug, Default)]
+struct Seen {
+    bools: bool,
+    ints: bool,
+    floats: bool,
+    timestamps: bool,
+    strings: bool,
+    other: bool,
+}
+
+fn infer_schema(rows: &[Map<String, Value>], warnings: &mut Vec<String>) -> SchemaRef {
+    let mut columns: BTreeMap<&str, Seen> = BTreeMap::new();
+    for row in rows.iter() {
+        for (key, value) in row.iter() {
+            let seen = columns.entry(key).or_default();
+            match value {
+                Value::Null => {}
+                Value::Bool(_) => seen.bools = true,
+                Value::Number(n) if n.is_i64() => seen.ints = true,
+                Value::Number(_) => seen.floats = true,
+                Value::String(s) if timestamp_millis(s).is_some() => seen.timestamps = true,
+                Value::String(_) => seen.strings = true,
+                _ => seen.other = true,
+            }
+        }
+    }
+
+    let fields: Vec<Field> = columns
+        .into_iter()
+        .map(|(name, seen)| {
+            let data_type = match seen {
+                Seen {
+                    bools: true,
+                    ints: false,
+                    floats: false,
+                    timestamps: false,
+                    strings: false,
+                    other: false,
+                } => DataType::Boolean,
+                Seen {
+                    bools: false,
+                    ints: true,
+                    floats: false,
+                    timestamps: false,
+                    strings: false,
+                    other: false,
+                } => DataType::Int64,
+                Seen {
+                    bools: false,
+                    floats: true,
+                    timestamps: false,
+                    strings: false,
+                    other: false,
+                    ..
+                } => DataType::Float64,
+                Seen {
+                    bools: false,
+                    ints: false,
+                    floats: false,
+                    timestamps: true,
+                    strings: false,
+                    other: false,
+                } => timestamp_type(),
+                // text, and the nested objects and arrays written as json
+                Seen {
+                    bools: false,
+                    ints: false,
+                    floats: false,
+                    ..
+                } => DataType::Utf8,
+                _ => {
+                    warnings.push(format!("column {} has mixed types, written as text", name));
+                    DataType::Utf8
+                }
+            };
+            Field::new(name, data_type, true)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn build_batch(
+    schema: &SchemaRef,
+    rows: &[Map<String, Value>],
+    warnings: &mut Vec<String>,
+) -> Result<RecordBatch> {
+    let mut arrays: Vec<ArrayRef> = Vec::new();
+    for field in schema.fields().iter() {
+        let values = rows
+            .iter()
+            .map(|r| r.get(field.name()).unwrap_or(&Value::Null));
+        let mut misfits = 0;
+        let mut fits = |fit: bool| {
+            if !fit {
+                misfits += 1;
+            }
+        };
+
+        let array: ArrayRef = match field.data_type() {
+            DataType::Boolean => Arc::new(BooleanArray::from(
+                values
+                    .map(|v| convert(v, Value::as_bool, &mut fits))
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Int64 => Arc::new(Int64Array::from(
+                values
+                    .map(|v| convert(v, Value::as_i64, &mut fits))
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Float64 => Arc::new(Float64Array::from(
+                values
+                    .map(|v| convert(v, Value::as_f64, &mut fits))
+                    .collect::<Vec<_>>(),
+            )),
+            DataType::Timestamp(TimeUnit::Millisecond, tz) => Arc::new(
+                TimestampMillisecondArray::from(
+                    values
+                        .map(|v| convert(v, |v| timestamp_millis(v.as_str()?), &mut fits))
+                        .collect::<Vec<_>>(),
+                )
+                .with_timezone_opt(tz.clone()),
+            ),
+            DataType::Utf8 => Arc::new(StringArray::from(
+                values
+                    .map(|v| match v {
+                        Value::Null => None,
+                        Value::String(s) => Some(s.clone()),
+                        other => Some(other.to_string()),
+                    })
+                    .collect::<Vec<_>>(),
+            )),
+            other => {
+                return Err(anyhow!(
+                    "column {} is {}, only Boolean, Int64, Float64, Timestamp(Millisecond) and \
+                     Utf8 are supported",
+                    field.name(),
+                    other
+                ))
+            }
+        };
+        if misfits > 0 {
+            warnings.push(format!(
+                "{} values in column {} aren't {}, left null",
+                misfits,
+                field.name(),
+                field.data_type()
+            ));
+        }
+        arrays.push(array);
+    }
+
+    let options = arrow_array::RecordBatchOptions::new().with_row_count(Some(rows.len()));
+    Ok(RecordBatch::try_new_with_options(
+        schema.clone(),
+        arrays,
+        &options,
+    )?)
+}
+
+/// converts a value that isn't null, counting the ones that don't fit
+fn convert<T>(
+    value: &Value,
+    to: impl Fn(&Value) -> Option<T>,
+    fits: &mut impl FnMut(bool),
+) -> Option<T> {
+    if value.is_null() {
+        return None;
+    }
+    let converted = to(value);
+    fits(converted.is_some());
+    converted
+}
+
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+}
+
+fn timestamp_millis(s: &str) -> Option<i64> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|d| d.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_arrow() {
+        let records = [
+            json!({"id": 1, "summary": "a", "closedFlag": false, "budget": 1,
+                   "status": {"id": 16, "name": "New", "_info": {"href": "x"}},
+                   "dateEntered": "2023-03-01T10:00:00Z", "code": 5, "site": {"id": 1},
+                   "_info": {"lastUpdated": "2023-03-01T10:00:00Z"}}),
+            json!({"id": 2, "summary": null, "closedFlag": true, "budget": 1.5,
+                   "status": {"id": 17, "name": "Closed"},
+                   "dateEntered": "2023-03-02T10:00:00Z", "code": "A"}),
+        ];
+
+        let converted = to_arrow(&records, None).unwrap();
+
+        let schema = converted.batch.schema();
+        let types: Vec<(&str, &DataType)> = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("budget", &DataType::Float64),
+                ("closedFlag", &DataType::Boolean),
+                ("code", &DataType::Utf8),
+                ("dateEntered", &timestamp_type()),
+                ("id", &DataType::Int64),
+                ("site.id", &DataType::Int64),
+                ("status.id", &DataType::Int64),
+                ("status.name", &DataType::Utf8),
+                ("summary", &DataType::Utf8),
+            ]
+        );
+        assert_eq!(
+            converted.warnings,
+            vec!["column code has mixed types, written as text"]
+        );
+        let dates = converted
+            .batch
+            .column_by_name("dateEntered")
+            .unwrap()
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(dates.value(1), 1_677_751_200_000);
+        let summary = converted.batch.column_by_name("summary").unwrap();
+        assert!(summary.is_null(1));
+    }
+
+    #[test]
+    fn test_to_arrow_pages() {
+        let pages = vec![
+            Ok(vec![json!({"id": 1, "summary": "a"})]),
+            Ok(vec![json!({"id": "two", "summary": "b", "extra": true})]),
+        ];
+
+        let converted: Vec<ArrowBatch> =
+            to_arrow_pages(pages, None).collect::<Result<_>>().unwrap();
+
+        assert_eq!(converted.len(), 2);
+        assert_eq!(converted[0].batch.schema(), converted[1].batch.schema());
+        assert!(converted[0].warnings.is_empty());
+        assert_eq!(
+            converted[1].warnings,
+            vec![
+                "column extra isn't in the schema, dropped",
+                "1 values in column id aren't Int64, left null",
+            ]
+        );
+        assert!(converted[1].batch.column_by_name("id").unwrap().is_null(0));
+    }
+
+    #[test]
+    fn test_to_arrow_schema_hint() {
+        let hint = Schema::new(vec![
+            Field::new("id", DataType::Int64, true),
+            Field::new("status.name", DataType::Utf8, true),
+        ]);
+        let records = [json!({"id": 1, "summary": "a", "status": {"name": "New"}})];
+
+        let converted = to_arrow(&records, Some(&hint)).unwrap();
+        let unsupported = Schema::new(vec![Field::new("id", DataType::Int8, true)]);
+
+        assert_eq!(converted.batch.schema().as_ref(), &hint);
+        assert!(to_arrow(&records, Some(&unsupported)).is_err());
+    }
+}
@@ -0,0 +1,407 @@
+//! Exporting a whole endpoint to a file, with a checkpoint so a failed export carries on
+//! where it stopped.  See [Exporter]
+use super::{ExportInterrupted, ExportStats};
+use crate::Client;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The page size connectwise uses when the query doesn't have one
+const DEFAULT_PAGE_SIZE: u64 = 25;
+
+/// How an [Exporter] writes the records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    /// newline delimited json, one compact object per line (see [super::to_ndjson])
+    #[default]
+    Ndjson,
+}
+
+/// How big an export will be, from [Exporter::estimate]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportEstimate {
+    /// the records the endpoint has for the conditions
+    pub records: u64,
+    /// the pages that takes at the page size of the query
+    pub pages: u64,
+}
+
+/// What is saved after each page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    path: String,
+    query: Vec<(String, String)>,
+    /// the pagination cursor of the next page, `None` once the export is done
+    next_page: Option<String>,
+    /// the highest id written, used to carry on when the export is in id order
+    last_id: Option<i64>,
+    stats: ExportStats,
+    done: bool,
+}
+
+/// Exports every record of a list endpoint to a file, page by page.  With a
+/// [Exporter::checkpoint_file] the progress is saved after every page and running the same
+/// export again carries on after the last page saved instead of starting over
+///
+/// The export is in id order (`orderby=id asc` is added when the query doesn't have an
+/// `orderby`) so it carries on with `id >` the last id written, which works however long ago
+/// the export stopped.  With another `orderby` it carries on from the saved pagination
+/// cursor, which connectwise only keeps for a while
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::export::{ExportFormat, Exporter};
+/// use cwmanage::Client;
+/// use dotenv::dotenv;
+/// dotenv().ok();
+/// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+/// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+/// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+/// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+/// let client = Client::new(company_id, public_key, private_key, client_id).build();
+///
+/// let exporter = Exporter::new(&client, "/service/tickets", &[("pagesize", "1000")])
+///     .output("tickets.ndjson")
+///     .checkpoint_file("tickets.checkpoint.json")
+///     .format(ExportFormat::Ndjson);
+/// println!("about {} tickets", exporter.estimate().unwrap().records);
+/// let stats = exporter.run().unwrap();
+/// println!("wrote {} tickets", stats.records);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Exporter<'a> {
+    client: &'a Client,
+    path: String,
+    query: Vec<(String, String)>,
+    output: Option<PathBuf>,
+    checkpoint_file: Option<PathBuf>,
+    format: ExportFormat,
+}
+
+impl<'a> Exporter<'a> {
+    /// Creates an export of `path` with `query` (conditions, fields, pagesize, ...)
+    pub fn new(client: &'a Client, path: &str, query: &[(&str, &str)]) -> Exporter<'a> {
+        Exporter {
+            client,
+            path: path.to_string(),
+            query: query
+                .iter()
+                .filter(|(k, _)| !k.is_empty())
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            output: None,
+            checkpoint_file: None,
+            format: ExportFormat::default(),
+        }
+    }
+
+    /// the file the records are written to, it must be set
+    pub fn output(mut self, path: impl Into<PathBuf>) -> Exporter<'a> {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// where the progress is saved, without one a failed export starts over
+    pub fn checkpoint_file(mut self, path: impl Into<PathBuf>) -> Exporter<'a> {
+        self.checkpoint_file = Some(path.into());
+        self
+    }
+
+    /// how the records are written, by default [ExportFormat::Ndjson]
+    pub fn format(mut self, format: ExportFormat) -> Exporter<'a> {
+        self.format = format;
+        self
+    }
+
+    /// How many records (and pages) the export will have, from the endpoint's `/count`.
+    /// Nothing is written
+    pub fn estimate(&self) -> Result<ExportEstimate> {
+        let conditions: Vec<(&str, &str)> = self
+            .query
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("conditions"))
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let path = format!("{}/count", self.path.trim_end_matches('/'));
+        let count = self.client.get_single(&path, &conditions)?;
+        let records = count["count"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("no count from {}: {}", path, count))?;
+        let page_size = self
+            .query
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("pagesize"))
+            .and_then(|(_, v)| v.trim().parse::<u64>().ok())
+            .filter(|size| *size > 0)
+            .unwrap_or(DEFAULT_PAGE_SIZE);
+        Ok(ExportEstimate {
+            records,
+            pages: records.div_ceil(page_size),
+        })
+    }
+
+    /// Runs the export, or carries on with it from the checkpoint.  Returns what the whole
+    /// export wrote, including before it was resumed.  A checkpoint for another path or query
+    /// is refused.  If a page fails the error has an [ExportInterrupted] with the totals saved
+    /// so far
+    pub fn run(&self) -> Result<ExportStats> {
+        let output = self
+            .output
+            .as_ref()
+            .ok_or_else(|| anyhow!("set the file to export to with Exporter::output"))?;
+        let mut checkpoint = match self.load_checkpoint()? {
+            Some(checkpoint) if checkpoint.done => return Ok(checkpoint.stats),
+            Some(checkpoint) => checkpoint,
+            None => Checkpoint {
+                path: self.path.clone(),
+                query: self.query.clone(),
+                next_page: None,
+                last_id: None,
+                stats: ExportStats::default(),
+                done: false,
+            },
+        };
+
+        // anything written after the last checkpoint is written again
+        OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(output)?
+            .set_len(checkpoint.stats.bytes)?;
+        let mut file = OpenOptions::new().append(true).open(output)?;
+
+        let query = self.resume_query(&checkpoint);
+        let query: Vec<(&str, &str)> = query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let mut pages = self.client.pages(&self.path, &query);
+        while let Some(page) = pages.next() {
+            let stats = checkpoint.stats;
+            let interrupted = |e: anyhow::Error| e.context(ExportInterrupted { stats });
+            let records = page.map_err(interrupted)?;
+
+            let mut buf = Vec::new();
+            match self.format {
+                ExportFormat::Ndjson => {
+                    for record in records.iter() {
+                        serde_json::to_writer(&mut buf, record)?;
+                        buf.push(b'\n');
+                    }
+                }
+            }
+            file.write_all(&buf)
+                .and_then(|_| file.sync_data())
+                .map_err(|e| interrupted(e.into()))?;
+
+            checkpoint.stats.pages += 1;
+            checkpoint.stats.records += records.len() as u64;
+            checkpoint.stats.bytes += buf.len() as u64;
+            checkpoint.last_id = records
+                .iter()
+                .filter_map(|r| r["id"].as_i64())
+                .chain(checkpoint.last_id)
+                .max();
+            checkpoint.next_page = pages.next_page_id().map(|p| p.to_string());
+            checkpoint.done = checkpoint.next_page.is_none();
+            self.save_checkpoint(&checkpoint)
+                .map_err(|e| e.context(ExportInterrupted { stats }))?;
+        }
+
+        checkpoint.done = true;
+        self.save_checkpoint(&checkpoint)?;
+        tracing::info!(
+            path = self.path.as_str(),
+            pages = checkpoint.stats.pages,
+            records = checkpoint.stats.records,
+            bytes = checkpoint.stats.bytes,
+            "export finished"
+        );
+        Ok(checkpoint.stats)
+    }
+
+    /// the query for the pages: in id order unless there is another order, and after where
+    /// the checkpoint stopped
+    fn resume_query(&self, checkpoint: &Checkpoint) -> Vec<(String, String)> {
+        let mut query = self.query.clone();
+        let ordered = query.iter().any(|(k, _)| k.eq_ignore_ascii_case("orderby"));
+        if !ordered {
+            query.push(("orderby".to_string(), "id asc".to_string()));
+        }
+        if checkpoint.stats.pages == 0 {
+            return query;
+        }
+
+        match (ordered, checkpoint.last_id, &checkpoint.next_page) {
+            (false, Some(last_id), _) => {
+                let after = format!("id > {}", last_id);
+                match query
+                    .iter_mut()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("conditions"))
+                {
+                    Some((_, c)) if !c.trim().is_empty() => *c = format!("({}) AND {}", c, after),
+                    Some((_, c)) => *c = after,
+                    None => query.push(("conditions".to_string(), after)),
+                }
+            }
+            (_, _, Some(next_page)) => query.push(("pageid".to_string(), next_page.clone())),
+            _ => {}
+        }
+        query
+    }
+
+    fn load_checkpoint(&self) -> Result<Option<Checkpoint>> {
+        let file = match &self.checkpoint_file {
+            Some(file) if file.exists() => file,
+            _ => return Ok(None),
+        };
+        let checkpoint: Checkpoint = serde_json::from_str(&fs::read_to_string(file)?)
+            .map_err(|e| anyhow!("can't read the checkpoint {}: {}", file.display(), e))?;
+        if checkpoint.path != self.path || checkpoint.query != self.query {
+            return Err(anyhow!(
+                "the checkpoint {} is for {} {:?}, not this export.  Remove it to start over",
+                file.display(),
+                checkpoint.path,
+                checkpoint.query
+            ));
+        }
+        Ok(Some(checkpoint))
+    }
+
+    fn save_checkpoint(&self, checkpoint: &Checkpoint) -> Result<()> {
+        let file = match &self.checkpoint_file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let partial = partial_path(file);
+        fs::write(&partial, serde_json::to_string_pretty(checkpoint)?)?;
+        File::open(&partial)?.sync_all()?;
+        fs::rename(&partial, file)?;
+        Ok(())
+    }
+}
+
+fn partial_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".partial");
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{temp_dir, MockCw};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_exporter_resume() {
+        let mut mock = MockCw::new();
+        let dir = temp_dir("export-resume");
+        let next = format!(
+            "<{}/v4_6_release/apis/3.0/service/tickets?pageId=2>; rel=\"next\"",
+            mock.url()
+        );
+        let first = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("pageid".into(), "1".into()),
+                Matcher::UrlEncoded("conditions".into(), "closedFlag = false".into()),
+                Matcher::UrlEncoded("orderby".into(), "id asc".into()),
+            ]))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}, {"id": 2}]"#)
+            .expect(1)
+            .create();
+        let broken = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(Matcher::UrlEncoded("pageid".into(), "2".into()))
+            .with_status(500)
+            .expect(1)
+            .create();
+        let client = mock.client();
+        let exporter = Exporter::new(
+            &client,
+            "/service/tickets",
+            &[("conditions", "closedFlag = false")],
+        )
+        .output(dir.join("tickets.ndjson"))
+        .checkpoint_file(dir.join("checkpoint.json"));
+
+        let err = exporter.run().unwrap_err();
+        let saved = err.downcast_ref::<ExportInterrupted>().unwrap().stats;
+        first.assert();
+        broken.assert();
+        assert_eq!(saved.records, 2);
+
+        // the second run starts after the last id written, not from the first page
+        let rest = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("pageid".into(), "1".into()),
+                Matcher::UrlEncoded(
+                    "conditions".into(),
+                    "(closedFlag = false) AND id > 2".into(),
+                ),
+            ]))
+            .with_body(r#"[{"id": 3}]"#)
+            .expect(1)
+            .create();
+        let stats = exporter.run().unwrap();
+        let again = exporter.run().unwrap();
+
+        rest.assert();
+        assert_eq!(
+            fs::read_to_string(dir.join("tickets.ndjson")).unwrap(),
+            "{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n"
+        );
+        assert_eq!(stats.records, 3);
+        assert_eq!(stats.pages, 2);
+        assert_eq!(again, stats);
+
+        let changed = Exporter::new(&client, "/service/tickets", &[])
+            .output(dir.join("tickets.ndjson"))
+            .checkpoint_file(dir.join("checkpoint.json"));
+        assert!(changed.run().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_exporter_estimate() {
+        let mut mock = MockCw::new();
+        let _count = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/count")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".into(),
+                "closedFlag = false".into(),
+            ))
+            .with_body(r#"{"count": 2501}"#)
+            .create();
+        let client = mock.client();
+
+        let estimate = Exporter::new(
+            &client,
+            "/service/tickets",
+            &[("conditions", "closedFlag = false"), ("pagesize", "1000")],
+        )
+        .estimate()
+        .unwrap();
+
+        assert_eq!(
+            estimate,
+            ExportEstimate {
+                records: 2501,
+                pages: 3
+            }
+        );
+    }
+}
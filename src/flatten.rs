@@ -0,0 +1,231 @@
+//! Flattening nested records into dotted keys for quick analysis.  See [Client::get_flattened]
+use crate::Client;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// What to do with the arrays in a record being flattened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMode {
+    /// leave the array as it is
+    #[default]
+    Keep,
+    /// replace the array with its json (`["a","b"]`) as a string
+    Json,
+}
+
+/// Options for [flatten] and [Client::get_flattened]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flatten {
+    depth: Option<usize>,
+    arrays: ArrayMode,
+    include_info: bool,
+}
+
+impl Default for Flatten {
+    fn default() -> Self {
+        Flatten {
+            depth: None,
+            arrays: ArrayMode::Keep,
+            include_info: true,
+        }
+    }
+}
+
+impl Flatten {
+    /// how many levels of nested objects are merged into the record, by default all of them.
+    /// With a depth of 1 `status.name` is flattened but `status._info` is left as an object
+    pub fn depth(mut self, depth: usize) -> Flatten {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// what to do with arrays, by default they are left as they are
+    pub fn arrays(mut self, arrays: ArrayMode) -> Flatten {
+        self.arrays = arrays;
+        self
+    }
+
+    /// include the `_info` maps (mostly links and update times), by default they are
+    pub fn include_info(mut self, include_info: bool) -> Flatten {
+        self.include_info = include_info;
+        self
+    }
+}
+
+/// Flattens the nested objects of a record into dotted keys (`status.name`,
+/// `_info.lastUpdated`).  A value that isn't an object is returned as it is
+///
+/// When two values end up with the same key (a record with both a `status.name` key and a
+/// `status` object with a `name`) the one nested the least keeps the key and the others get
+/// `#2`, `#3`, ... in the order of their paths, so the result is the same every time
+///
+/// ```
+/// use cwmanage::{flatten, Flatten};
+/// use serde_json::json;
+///
+/// let ticket = json!({
+///     "id": 1,
+///     "status": {"id": 16, "name": "Closed"},
+///     "_info": {"lastUpdated": "2022-07-14T15:34:22Z"}
+/// });
+///
+/// assert_eq!(
+///     flatten(&ticket, &Flatten::default()),
+///     json!({
+///         "id": 1,
+///         "status.id": 16,
+///         "status.name": "Closed",
+///         "_info.lastUpdated": "2022-07-14T15:34:22Z"
+///     })
+/// );
+/// ```
+pub fn flatten(record: &Value, options: &Flatten) -> Value {
+    let map = match record {
+        Value::Object(map) => map,
+        other => return other.clone(),
+    };
+    let mut found: Vec<(Vec<&str>, Value)> = Vec::new();
+    flatten_into(&mut Vec::new(), map, options, &mut found);
+
+    // the least nested first, then by path, so collisions always resolve the same way
+    found.sort_by(|(a, _), (b, _)| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+
+    let mut out: BTreeMap<String, Value> = BTreeMap::new();
+    for (path, value) in found {
+        let key = path.join(".");
+        let key = match out.contains_key(&key) {
+            false => key,
+            true => (2..)
+                .map(|n| format!("{}#{}", key, n))
+                .find(|k| !out.contains_key(k))
+                .unwrap(),
+        };
+        out.insert(key, value);
+    }
+    Value::Object(out.into_iter().collect())
+}
+
+fn flatten_into<'a>(
+    path: &mut Vec<&'a str>,
+    map: &'a Map<String, Value>,
+    options: &Flatten,
+    out: &mut Vec<(Vec<&'a str>, Value)>,
+) {
+    for (k, v) in map.iter() {
+        if !options.include_info && k == "_info" {
+            continue;
+        }
+        path.push(k);
+        match v {
+            Value::Object(inner) if options.depth.is_none_or(|d| path.len() <= d) => {
+                flatten_into(path, inner, options, out)
+            }
+            Value::Array(_) if options.arrays == ArrayMode::Json => {
+                out.push((path.clone(), Value::String(v.to_string())))
+            }
+            other => out.push((path.clone(), other.clone())),
+        }
+        path.pop();
+    }
+}
+
+impl Client {
+    /// Same as [Client::get] with every record flattened (see [flatten])
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Flatten};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let tickets = client
+    ///     .get_flattened("/service/tickets", &[("", "")], &Flatten::default().depth(1))
+    ///     .unwrap();
+    /// println!("{}", tickets[0]["company.identifier"]);
+    /// ```
+    pub fn get_flattened(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        options: &Flatten,
+    ) -> Result<Vec<Value>> {
+        Ok(self
+            .get(path, query)?
+            .iter()
+            .map(|r| flatten(r, options))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn ticket() -> Value {
+        json!({
+            "id": 1,
+            "status": {"id": 16, "name": "Closed", "_info": {"status_href": "x"}},
+            "tags": ["a", "b"],
+            "_info": {"lastUpdated": "2022-07-14T15:34:22Z"}
+        })
+    }
+
+    #[test]
+    fn test_flatten_options() {
+        let options = Flatten::default()
+            .depth(1)
+            .arrays(ArrayMode::Json)
+            .include_info(false);
+
+        assert_eq!(
+            flatten(&ticket(), &options),
+            json!({
+                "id": 1,
+                "status.id": 16,
+                "status.name": "Closed",
+                "tags": "[\"a\",\"b\"]"
+            })
+        );
+        assert_eq!(
+            flatten(&ticket(), &Flatten::default().depth(1))["status._info"],
+            json!({"status_href": "x"})
+        );
+        assert_eq!(
+            flatten(&ticket(), &Flatten::default())["status._info.status_href"],
+            "x"
+        );
+        assert_eq!(flatten(&json!([1]), &Flatten::default()), json!([1]));
+    }
+
+    #[test]
+    fn test_flatten_collisions() {
+        let record = json!({
+            "status": {"name": "nested", "a": {"b": "deeper"}},
+            "status.a": {"b": "middle"},
+            "status.a.b": "literal",
+            "status.name": "literal"
+        });
+
+        assert_eq!(
+            flatten(&record, &Flatten::default()),
+            json!({
+                "status.name": "literal",
+                "status.name#2": "nested",
+                "status.a.b": "literal",
+                "status.a.b#2": "middle",
+                "status.a.b#3": "deeper"
+            })
+        );
+    }
+}
@@ -0,0 +1,434 @@
+//! Capture of api traffic to a HAR 1.2 file, to attach to a support case or open in the
+//! network tab of browser devtools.  See [crate::Client::har_capture]
+use crate::redact::{self, Redaction, MASK};
+use crate::RawResponse;
+use anyhow::{anyhow, Result};
+use reqwest::header::HeaderMap;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The largest a capture file gets by default, older entries are dropped to stay under it
+pub const DEFAULT_HAR_MAX_FILE_BYTES: usize = 10 * 1024 * 1024;
+
+/// The most of a request or response body kept by default, the rest is cut off
+pub const DEFAULT_HAR_MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Headers whose value is never written
+const REDACTED_HEADERS: &[&str] = &["authorization", "cookie", "set-cookie", "clientid"];
+
+/// A request as it was sent, taken before it goes out
+pub(crate) struct CapturedRequest {
+    method: String,
+    url: reqwest::Url,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+    started: SystemTime,
+}
+
+impl CapturedRequest {
+    pub(crate) fn new(req: &reqwest::blocking::Request) -> CapturedRequest {
+        CapturedRequest {
+            method: req.method().to_string(),
+            url: req.url().clone(),
+            headers: req.headers().clone(),
+            body: req.body().and_then(|b| b.as_bytes()).map(|b| b.to_vec()),
+            started: SystemTime::now(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// the entries in the file and the size of each once written
+    entries: VecDeque<(Value, usize)>,
+    size: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct HarCapture {
+    path: PathBuf,
+    max_file_bytes: usize,
+    max_body_bytes: usize,
+    state: Mutex<State>,
+}
+
+impl PartialEq for HarCapture {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+            && self.max_file_bytes == other.max_file_bytes
+            && self.max_body_bytes == other.max_body_bytes
+    }
+}
+
+impl HarCapture {
+    pub(crate) fn new(path: PathBuf, max_file_bytes: usize, max_body_bytes: usize) -> HarCapture {
+        HarCapture {
+            path,
+            max_file_bytes,
+            max_body_bytes,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// adds the entry for `req` and what came back (a response or the error it failed with)
+    /// and rewrites the file.  The oldest entries are dropped to keep it under the size limit.
+    /// The bodies are masked by `redaction`
+    pub(crate) fn record(
+        &self,
+        req: CapturedRequest,
+        res: &Result<RawResponse>,
+        elapsed: Duration,
+        redaction: Option<&Redaction>,
+    ) -> Result<()> {
+        let entry = self.entry(req, res, elapsed, redaction);
+        let size = serde_json::to_string(&entry)?.len() + 1;
+
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| anyhow!("har capture lock poisoned"))?;
+        state.entries.push_back((entry, size));
+        state.size += size;
+        while state.size + HEADER_BYTES > self.max_file_bytes {
+            match state.entries.pop_front() {
+                Some((_, dropped)) => state.size -= dropped,
+                None => break,
+            }
+        }
+        self.save(&state)
+    }
+
+    fn entry(
+        &self,
+        req: CapturedRequest,
+        res: &Result<RawResponse>,
+        elapsed: Duration,
+        redaction: Option<&Redaction>,
+    ) -> Value {
+        let query: Vec<Value> = req
+            .url
+            .query_pairs()
+            .map(|(k, v)| json!({"name": k, "value": v}))
+            .collect();
+        let mut request = json!({
+            "method": req.method,
+            "url": req.url.as_str(),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(&req.headers),
+            "queryString": query,
+            "headersSize": -1,
+            "bodySize": req.body.as_ref().map_or(0, |b| b.len() as i64),
+        });
+        if let Some(body) = &req.body {
+            let body = redact::text(redaction, &String::from_utf8_lossy(body));
+            let (text, comment) = self.truncate(&body);
+            request["postData"] = json!({
+                "mimeType": header(&req.headers, "content-type"),
+                "text": text,
+            });
+            if let Some(comment) = comment {
+                request["postData"]["comment"] = json!(comment);
+            }
+        }
+
+        let response = match res {
+            Ok(res) => {
+                let (text, comment) = self.truncate(&redact::text(redaction, &res.body));
+                let mut content = json!({
+                    "size": res.body.len(),
+                    "mimeType": header(&res.headers, "content-type"),
+                    "text": text,
+                });
+                if let Some(comment) = comment {
+                    content["comment"] = json!(comment);
+                }
+                json!({
+                    "status": res.status.as_u16(),
+                    "statusText": res.status.canonical_reason().unwrap_or_default(),
+                    "httpVersion": "HTTP/1.1",
+                    "cookies": [],
+                    "headers": headers(&res.headers),
+                    "content": content,
+                    "redirectURL": header(&res.headers, "location"),
+                    "headersSize": -1,
+                    "bodySize": res.body.len(),
+                })
+            }
+            // no response, the way browsers show a request that failed
+            Err(e) => json!({
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "",
+                "cookies": [],
+                "headers": [],
+                "content": {"size": 0, "mimeType": ""},
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+                "comment": e.to_string(),
+            }),
+        };
+
+        let millis = elapsed.as_secs_f64() * 1000.0;
+        json!({
+            "startedDateTime": iso8601(req.started),
+            "time": millis,
+            "request": request,
+            "response": response,
+            "cache": {},
+            // only the whole time is known, it is all waiting
+            "timings": {"send": 0, "wait": millis, "receive": 0},
+        })
+    }
+
+    /// the body cut to the size limit, and a comment saying so when it was
+    fn truncate(&self, body: &str) -> (String, Option<String>) {
+        if body.len() <= self.max_body_bytes {
+            return (body.to_string(), None);
+        }
+        let mut end = self.max_body_bytes;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        (
+            body[..end].to_string(),
+            Some(format!(
+                "truncated to {} of {} bytes",
+                self.max_body_bytes,
+                body.len()
+            )),
+        )
+    }
+
+    fn save(&self, state: &State) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entries: Vec<&Value> = state.entries.iter().map(|(e, _)| e).collect();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {"name": "cwmanage", "version": env!("CARGO_PKG_VERSION")},
+                "entries": entries,
+            }
+        });
+        fs::write(&self.path, serde_json::to_string(&har)?)
+            .map_err(|e| anyhow!("cannot write har capture {:?}: {}", self.path, e))
+    }
+}
+
+/// Room for the `log` and `creator` around the entries
+const HEADER_BYTES: usize = 128;
+
+fn headers(headers: &HeaderMap) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(k, v)| {
+            let value = match REDACTED_HEADERS.contains(&k.as_str()) {
+                true => MASK.to_string(),
+                false => String::from_utf8_lossy(v.as_bytes()).to_string(),
+            };
+            json!({"name": k.as_str(), "value": value})
+        })
+        .collect()
+}
+
+fn header(headers: &HeaderMap, name: &str) -> String {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// `time` as connectwise writes it, to the second (`2022-07-14T15:34:22Z`)
+pub(crate) fn cw_timestamp(time: SystemTime) -> String {
+    format!("{}Z", &iso8601(time)[..19])
+}
+
+/// `time` as `2022-07-14T15:34:22.187Z`
+pub(crate) fn iso8601(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (days, rest) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // days to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60,
+        since.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Client;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    fn capture_client(server: &mockito::Server, path: &std::path::Path) -> Client {
+        Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .har_capture_with_limits(path, 4096, 16)
+        .build()
+    }
+
+    /// checks the parts of the HAR 1.2 schema that are required, and their types
+    fn validate(har: &Value) {
+        let log = &har["log"];
+        assert_eq!(log["version"], "1.2");
+        assert!(log["creator"]["name"].is_string());
+        assert!(log["creator"]["version"].is_string());
+        for entry in log["entries"].as_array().unwrap() {
+            let started = entry["startedDateTime"].as_str().unwrap();
+            assert_eq!(started.len(), 24, "{}", started);
+            assert!(started.ends_with('Z'));
+            assert!(entry["time"].as_f64().unwrap() >= 0.0);
+            assert!(entry["cache"].is_object());
+            for timing in ["send", "wait", "receive"].iter().copied() {
+                assert!(entry["timings"][timing].as_f64().unwrap() >= 0.0);
+            }
+
+            let request = &entry["request"];
+            assert!(request["method"].is_string());
+            reqwest::Url::parse(request["url"].as_str().unwrap()).unwrap();
+            assert!(request["httpVersion"].is_string());
+            assert!(request["headersSize"].is_i64());
+            assert!(request["bodySize"].is_i64());
+            for list in ["cookies", "headers", "queryString"].iter().copied() {
+                for pair in request[list].as_array().unwrap() {
+                    assert!(pair["name"].is_string() && pair["value"].is_string());
+                }
+            }
+            if let Some(post) = request.get("postData") {
+                assert!(post["mimeType"].is_string() && post["text"].is_string());
+            }
+
+            let response = &entry["response"];
+            assert!(response["status"].is_u64());
+            assert!(response["statusText"].is_string());
+            assert!(response["httpVersion"].is_string());
+            assert!(response["cookies"].is_array());
+            assert!(response["headers"].is_array());
+            assert!(response["content"]["size"].is_i64());
+            assert!(response["content"]["mimeType"].is_string());
+            assert!(response["redirectURL"].is_string());
+            assert!(response["headersSize"].is_i64());
+            assert!(response["bodySize"].is_i64());
+        }
+    }
+
+    #[test]
+    fn test_har_capture() {
+        let dir = std::env::temp_dir().join(format!("cwmanage-har-{}", std::process::id()));
+        let path = dir.join("capture.har");
+        let mut server = mockito::Server::new();
+        let _info = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_header("set-cookie", "session=secret")
+            .with_body(r#"{"version": "v2022.1", "isCloud": true}"#)
+            .create();
+        let _note = server
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/1/notes")
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "bad"}"#)
+            .create();
+        let client = capture_client(&server, &path);
+
+        client
+            .get_single("/system/info", &[("fields", "version")])
+            .unwrap();
+        client
+            .post(
+                "/service/tickets/1/notes",
+                r#"{"text": "hello"}"#.to_string(),
+            )
+            .unwrap_err();
+
+        let raw = fs::read_to_string(&path).unwrap();
+        let har: Value = serde_json::from_str(&raw).unwrap();
+        validate(&har);
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["request"]["method"], "GET");
+        assert_eq!(
+            entries[0]["request"]["queryString"][0],
+            json!({"name": "fields", "value": "version"})
+        );
+        assert_eq!(entries[0]["response"]["status"], 200);
+        assert_eq!(
+            entries[0]["response"]["content"]["comment"],
+            "truncated to 16 of 39 bytes"
+        );
+        assert_eq!(entries[1]["response"]["status"], 400);
+        assert_eq!(
+            entries[1]["request"]["postData"]["text"],
+            r#"{"text": "hello""#
+        );
+
+        // never the credentials
+        assert!(!raw.contains(&client.gen_basic_auth().unwrap()));
+        assert!(!raw.contains("secret"));
+        assert!(!raw.contains(r#""value":"clientid""#));
+        assert!(raw.contains(MASK));
+
+        // stays under the limit by dropping the oldest
+        for _ in 0..20 {
+            client.get_single("/system/info", &[]).unwrap();
+        }
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(raw.len() <= 4096, "{}", raw.len());
+        let har: Value = serde_json::from_str(&raw).unwrap();
+        validate(&har);
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert!(entries.len() < 22);
+        assert_eq!(entries.last().unwrap()["request"]["method"], "GET");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_millis(1_657_812_862_187)),
+            "2022-07-14T15:34:22.187Z"
+        );
+        assert_eq!(
+            cw_timestamp(UNIX_EPOCH + Duration::from_millis(1_657_812_862_187)),
+            "2022-07-14T15:34:22Z"
+        );
+        assert_eq!(
+            iso8601(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00.000Z"
+        );
+    }
+}
@@ -0,0 +1,374 @@
+//! Checking that a client can talk to its instance, see [Client::health_report]
+use crate::{Client, Error, SystemInfo};
+use anyhow::Result;
+use serde::Deserialize;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How long [Client::health_report] has for all of its checks
+pub const HEALTH_REPORT_DEADLINE: Duration = Duration::from_secs(15);
+
+/// a rate limit with less than this share (in percent) left is a warning
+const RATE_LIMIT_LOW_PERCENT: u64 = 10;
+
+/// The outcome of a check, worst last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HealthStatus {
+    /// it works
+    Pass,
+    /// it works, but something should be looked at
+    Warn,
+    /// it doesn't work (or wasn't run before the deadline)
+    Fail,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HealthStatus::Pass => "PASS",
+            HealthStatus::Warn => "WARN",
+            HealthStatus::Fail => "FAIL",
+        })
+    }
+}
+
+/// One check of a [HealthReport]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheck {
+    /// what was checked (`credentials`, `members`)
+    pub name: &'static str,
+    /// how it went
+    pub status: HealthStatus,
+    /// what was found, for people
+    pub message: String,
+    /// how long it took
+    pub elapsed: Duration,
+}
+
+/// What [Client::health_report] found.  It prints a line per check:
+/// `PASS credentials (182ms): v2022.1.12345, cloud`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    /// the checks, in the order they were run
+    pub checks: Vec<HealthCheck>,
+    /// how long the whole report took
+    pub elapsed: Duration,
+}
+
+impl HealthReport {
+    /// the worst status of the checks
+    pub fn status(&self) -> HealthStatus {
+        self.checks
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(HealthStatus::Pass)
+    }
+
+    /// true if no check failed, warnings are fine
+    pub fn is_healthy(&self) -> bool {
+        self.status() != HealthStatus::Fail
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(
+                f,
+                "{} {} ({}ms): {}",
+                check.status,
+                check.name,
+                check.elapsed.as_millis(),
+                check.message
+            )?;
+        }
+        write!(f, "{} in {}ms", self.status(), self.elapsed.as_millis())
+    }
+}
+
+/// what `/login/companyinfo/{company}` says about the company, `null` when there isn't one
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CompanyInfo {
+    /// the codebase with a trailing `/` (`v2022_1/`)
+    codebase: String,
+}
+
+impl Client {
+    /// Runs a few cheap checks of the client against its instance and reports each as
+    /// pass, warn or fail with a message:
+    ///
+    /// * `codebase`: the company is on the host and the codebase (`/login/companyinfo`) is the
+    ///   one the client uses
+    /// * `credentials`: `/system/info` accepts the keys and client id
+    /// * `members`: the api member can read `/system/members`
+    /// * `tickets`: the api member can count `/service/tickets`
+    /// * `rate limit`: what is left of the rate limit the responses reported
+    ///
+    /// A check that fails doesn't stop the others.  All of them share
+    /// [HEALTH_REPORT_DEADLINE], a check that can't start before it passes fails
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let report = client.health_report();
+    /// println!("{}", report);
+    /// assert!(report.is_healthy());
+    /// ```
+    pub fn health_report(&self) -> HealthReport {
+        self.health_report_within(HEALTH_REPORT_DEADLINE)
+    }
+
+    fn health_report_within(&self, limit: Duration) -> HealthReport {
+        let started = Instant::now();
+        let deadline = started + limit;
+        let run = |name: &'static str, check: &dyn Fn() -> Result<(HealthStatus, String)>| {
+            let check_started = Instant::now();
+            let (status, message) = match check_started >= deadline {
+                true => (
+                    HealthStatus::Fail,
+                    "not run, the health report ran out of time".to_string(),
+                ),
+                false => check().unwrap_or_else(|e| failed(name, &e)),
+            };
+            HealthCheck {
+                name,
+                status,
+                message,
+                elapsed: check_started.elapsed(),
+            }
+        };
+
+        let mut checks = vec![
+            run("codebase", &|| self.check_codebase(deadline)),
+            run("credentials", &|| {
+                let info: SystemInfo = self.decode(
+                    "/system/info",
+                    self.get_value_in("/system/info", &[], Some(deadline))?,
+                )?;
+                let hosting = match info.is_cloud {
+                    true => "cloud",
+                    false => "on premise",
+                };
+                Ok((HealthStatus::Pass, format!("{}, {}", info.version, hosting)))
+            }),
+            run("members", &|| {
+                let query = [("fields", "id"), ("pagesize", "1")];
+                self.fetch_page("/system/members", &query, "1", None, Some(deadline))?;
+                Ok((HealthStatus::Pass, "can read /system/members".to_string()))
+            }),
+            run("tickets", &|| {
+                let count = self.get_value_in("/service/tickets/count", &[], Some(deadline))?;
+                Ok((
+                    HealthStatus::Pass,
+                    format!("{} service tickets", count["count"]),
+                ))
+            }),
+        ];
+        checks.push(run("rate limit", &|| Ok(self.check_rate_limit())));
+
+        HealthReport {
+            checks,
+            elapsed: started.elapsed(),
+        }
+    }
+
+    /// the company is on the host and on the codebase the client uses
+    fn check_codebase(&self, deadline: Instant) -> Result<(HealthStatus, String)> {
+        let url = format!(
+            "{}/login/companyinfo/{}",
+            self.gen_base_url(),
+            self.company_id
+        );
+        let req = self.http.get()?.get(url);
+        let info: Option<CompanyInfo> = self.call_in(None, Some(deadline), req, |res| match res
+            .status
+            .is_success()
+        {
+            true => Ok(serde_json::from_str(&res.body)?),
+            false => Err(anyhow::anyhow!("{} from /login/companyinfo", res.status)),
+        })?;
+        let codebase = match info {
+            Some(info) => info.codebase.trim_matches('/').to_string(),
+            None => {
+                return Ok((
+                    HealthStatus::Fail,
+                    format!(
+                        "no company {:?} on this host, check the company id and api_url",
+                        self.company_id
+                    ),
+                ))
+            }
+        };
+        Ok(match codebase == self.codebase {
+            true => (HealthStatus::Pass, format!("on {}", codebase)),
+            false => (
+                HealthStatus::Warn,
+                format!(
+                    "the instance is on {} but the client uses {}, see Client::codebase",
+                    codebase, self.codebase
+                ),
+            ),
+        })
+    }
+
+    /// what is left of the rate limit the last response reported
+    fn check_rate_limit(&self) -> (HealthStatus, String) {
+        match self.last_rate_limit() {
+            None => (HealthStatus::Pass, "no rate limit reported".to_string()),
+            Some(rate) => {
+                let message = format!("{} of {} requests left", rate.remaining, rate.limit);
+                match rate.remaining * 100 < rate.limit * RATE_LIMIT_LOW_PERCENT {
+                    true => (HealthStatus::Warn, message),
+                    false => (HealthStatus::Pass, message),
+                }
+            }
+        }
+    }
+}
+
+/// the status and message of a check that got an error
+fn failed(name: &str, e: &anyhow::Error) -> (HealthStatus, String) {
+    match e.downcast_ref::<Error>() {
+        Some(Error::RateLimited { .. }) => (HealthStatus::Warn, format!("rate limited: {}", e)),
+        Some(Error::Api { status: 401, .. }) => (
+            HealthStatus::Fail,
+            format!("the keys or client id were refused: {}", e),
+        ),
+        Some(Error::Forbidden { .. }) => (
+            HealthStatus::Fail,
+            format!(
+                "the security role of the api member doesn't allow {}: {}",
+                name, e
+            ),
+        ),
+        _ => (HealthStatus::Fail, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn statuses(report: &HealthReport) -> Vec<(&'static str, HealthStatus)> {
+        report.checks.iter().map(|c| (c.name, c.status)).collect()
+    }
+
+    #[test]
+    fn test_health_report() {
+        let mut mock = MockCw::new()
+            .single(
+                "/system/info",
+                json!({"version": "v2022.1.12345", "isCloud": true, "serverTimeZone": "Eastern Standard Time"}),
+            )
+            .list("/system/members", vec![vec![json!({"id": 1})]]);
+        let _info = mock
+            .server()
+            .mock("GET", "/login/companyinfo/myco")
+            .with_body(r#"{"CompanyName": "My Co", "Codebase": "v2022_1/", "IsCloud": true}"#)
+            .create();
+        let _count = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/count")
+            .with_header("x-ratelimit-limit", "1000")
+            .with_header("x-ratelimit-remaining", "40")
+            .with_body(r#"{"count": 1234}"#)
+            .create();
+
+        let report = mock.client().health_report();
+
+        assert_eq!(
+            statuses(&report),
+            vec![
+                ("codebase", HealthStatus::Warn),
+                ("credentials", HealthStatus::Pass),
+                ("members", HealthStatus::Pass),
+                ("tickets", HealthStatus::Pass),
+                ("rate limit", HealthStatus::Warn),
+            ]
+        );
+        assert!(report.is_healthy());
+        let printed = report.to_string();
+        assert!(printed.contains(
+            "the instance is on v2022_1 but the client uses v4_6_release, see Client::codebase"
+        ));
+        assert!(printed.contains("): v2022.1.12345, cloud\n"));
+        assert!(printed.contains("): 1234 service tickets\n"));
+        assert!(printed.contains("): 40 of 1000 requests left\n"));
+        assert!(printed.starts_with("WARN codebase ("));
+    }
+
+    #[test]
+    fn test_health_report_failures() {
+        let mut mock = MockCw::new()
+            .error(
+                "GET",
+                "/system/info",
+                401,
+                fixtures::api_error("Unauthorized", "bad keys"),
+            )
+            .error(
+                "GET",
+                "/system/members",
+                403,
+                fixtures::api_error("Forbidden", "no access"),
+            )
+            .single("/service/tickets/count", json!({"count": 3}));
+        let _info = mock
+            .server()
+            .mock("GET", "/login/companyinfo/myco")
+            .with_body("null")
+            .create();
+
+        let report = mock.client().health_report();
+
+        assert_eq!(
+            statuses(&report),
+            vec![
+                ("codebase", HealthStatus::Fail),
+                ("credentials", HealthStatus::Fail),
+                ("members", HealthStatus::Fail),
+                ("tickets", HealthStatus::Pass),
+                ("rate limit", HealthStatus::Pass),
+            ]
+        );
+        assert!(!report.is_healthy());
+        assert!(report.checks[1]
+            .message
+            .starts_with("the keys or client id were refused"));
+        assert!(report.checks[2]
+            .message
+            .starts_with("the security role of the api member doesn't allow members"));
+    }
+
+    #[test]
+    fn test_health_report_deadline() {
+        let mut mock = MockCw::new();
+        let nothing = mock.server().mock("GET", Matcher::Any).expect(0).create();
+
+        let report = mock.client().health_report_within(Duration::ZERO);
+
+        nothing.assert();
+        assert_eq!(report.checks.len(), 5);
+        assert!(report
+            .checks
+            .iter()
+            .all(|c| c.status == HealthStatus::Fail && c.message.starts_with("not run")));
+    }
+}
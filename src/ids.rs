@@ -0,0 +1,347 @@
+//! Listing only the ids of a list endpoint, without parsing the records, and getting records
+//! by id.  See [Client::get_all_ids] and [Client::get_by_ids]
+use crate::{check_page, expected_list, Client, Error, MAX_PAGE_SIZE};
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// A record of an id only page, the id is kept as it was sent
+#[derive(Deserialize)]
+struct IdRecord<'a> {
+    #[serde(borrow, default)]
+    id: Option<&'a RawValue>,
+}
+
+impl Client {
+    /// The id of every record of `path` matching `conditions` (all of them when it is empty).
+    /// Only `fields=id` is requested, [MAX_PAGE_SIZE] at a time in id order, and the ids are
+    /// read from the responses without building a [serde_json::Value] of each record, much
+    /// faster than [Client::get] for reconciling large lists
+    ///
+    /// A record without an `id`, or with one that isn't an integer, is [Error::Decode] with
+    /// its place in the list and the page it was on
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let open = client
+    ///     .get_all_ids("/service/tickets", "closedFlag = false")
+    ///     .unwrap();
+    /// println!("{} open tickets", open.len());
+    /// ```
+    pub fn get_all_ids(&self, path: &str, conditions: &str) -> Result<Vec<i64>> {
+        let page_size = MAX_PAGE_SIZE.to_string();
+        let mut query = vec![
+            ("fields", "id"),
+            ("orderby", "id asc"),
+            ("pagesize", page_size.as_str()),
+        ];
+        if !conditions.trim().is_empty() {
+            query.push(("conditions", conditions));
+        }
+
+        let mut ids = Vec::new();
+        let mut next = Some("1".to_string());
+        let mut page_number = 0;
+        while let Some(page) = next.take() {
+            page_number += 1;
+            let req = self
+                .http_request(reqwest::Method::GET, path)?
+                .query(&[("pageid", page.as_str())])
+                .query(&query);
+            next = self.call(req, |res| {
+                let next = check_page(&res, path, &page)?;
+                parse_ids(&res.body, path, page_number, &mut ids)?;
+                Ok(next)
+            })?;
+        }
+
+        Ok(ids)
+    }
+}
+
+impl Client {
+    /// The records of `path` with the `ids`, in the order of `ids`.  The ids are asked for with
+    /// `conditions=id in (...)`, as many at a time as fit in [Client::max_url_length] (and at
+    /// most [MAX_PAGE_SIZE]), so thousands of ids are a few requests.  An id given twice is
+    /// returned once and an id without a record (it was deleted) is left out, not an error
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the list (example `/service/tickets`)
+    /// - `ids` - the ids of the records
+    /// - `fields` - the fields to get, all of them when it is empty (`id` is always added)
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ids: Vec<i64> = (1..=5000).collect();
+    /// let tickets = client
+    ///     .get_by_ids("/service/tickets", &ids, &["id", "summary"])
+    ///     .unwrap();
+    /// ```
+    pub fn get_by_ids(&self, path: &str, ids: &[i64], fields: &[&str]) -> Result<Vec<Value>> {
+        let mut seen = HashSet::new();
+        let ids: Vec<i64> = ids.iter().copied().filter(|id| seen.insert(*id)).collect();
+        let fields = match fields.is_empty() || fields.contains(&"id") {
+            true => fields.join(","),
+            false => format!("id,{}", fields.join(",")),
+        };
+
+        let mut found: HashMap<i64, Value> = HashMap::new();
+        for chunk in self.id_chunks(path, &ids, &fields)? {
+            let conditions = in_conditions(chunk);
+            let page_size = chunk.len().to_string();
+            let mut query = vec![
+                ("conditions", conditions.as_str()),
+                ("pagesize", &page_size),
+            ];
+            if !fields.is_empty() {
+                query.push(("fields", &fields));
+            }
+            for record in self.get(path, &query)? {
+                if let Some(id) = record["id"].as_i64() {
+                    found.insert(id, record);
+                }
+            }
+        }
+
+        Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+    }
+
+    /// splits `ids` into chunks whose `id in (...)` fits in the url
+    fn id_chunks<'a>(&self, path: &str, ids: &'a [i64], fields: &str) -> Result<Vec<&'a [i64]>> {
+        let page_size = MAX_PAGE_SIZE.to_string();
+        let mut query = vec![("conditions", "id in ()"), ("pagesize", page_size.as_str())];
+        if !fields.is_empty() {
+            query.push(("fields", fields));
+        }
+        let base = self.list_url_for(path, &query)?.as_str().len();
+        let room = self.max_url_length.saturating_sub(base);
+
+        let mut chunks = Vec::new();
+        let (mut start, mut used) = (0, 0);
+        for (i, id) in ids.iter().enumerate() {
+            // the id and an encoded comma (`%2C`) before it
+            let length = id.to_string().len() + if i == start { 0 } else { 3 };
+            if i > start && (used + length > room || i - start == MAX_PAGE_SIZE) {
+                chunks.push(&ids[start..i]);
+                start = i;
+                used = id.to_string().len();
+            } else {
+                used += length;
+            }
+        }
+        if start < ids.len() {
+            chunks.push(&ids[start..]);
+        }
+        Ok(chunks)
+    }
+}
+
+/// `id in (1,2,3)`
+fn in_conditions(ids: &[i64]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+    format!("id in ({})", ids.join(","))
+}
+
+/// adds the ids of one page (the `page_number`th) to `ids`
+fn parse_ids(body: &str, path: &str, page_number: usize, ids: &mut Vec<i64>) -> Result<()> {
+    let records: Vec<IdRecord<'_>> = match serde_json::from_str(body) {
+        Ok(records) => records,
+        Err(_) if body.trim_start().starts_with('{') => return Err(expected_list(path)),
+        Err(e) => return Err(e.into()),
+    };
+    ids.reserve(records.len());
+    for record in records {
+        let invalid = |message: String| Error::Decode {
+            path: path.to_string(),
+            field: format!("[{}].id", ids.len()),
+            id: None,
+            page: Some(page_number),
+            message,
+        };
+        let id = match record.id {
+            Some(id) => id
+                .get()
+                .parse::<i64>()
+                .map_err(|_| invalid(format!("the id {} isn't an integer", id.get())))?,
+            None => return Err(invalid("the record has no id".to_string()).into()),
+        };
+        ids.push(id);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockCw, API_PREFIX};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_all_ids() {
+        let mut mock = MockCw::new();
+        let next = format!(
+            "<{}{}/service/tickets?pageId=2>; rel=\"next\"",
+            mock.url(),
+            API_PREFIX
+        );
+        let query = |page: &str| {
+            Matcher::AllOf(vec![
+                Matcher::UrlEncoded("pageid".to_string(), page.to_string()),
+                Matcher::UrlEncoded("fields".to_string(), "id".to_string()),
+                Matcher::UrlEncoded("orderby".to_string(), "id asc".to_string()),
+                Matcher::UrlEncoded("pagesize".to_string(), "1000".to_string()),
+                Matcher::UrlEncoded("conditions".to_string(), "closedFlag = false".to_string()),
+            ])
+        };
+        let path = format!("{}/service/tickets", API_PREFIX);
+        let first = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(query("1"))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}, {"id": 5}]"#)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(query("2"))
+            .with_body(r#"[{"id": 9, "_info": {"lastUpdated": "2022-07-14T15:00:00Z"}}]"#)
+            .create();
+        let client = mock.client();
+
+        let ids = client
+            .get_all_ids("/service/tickets", "closedFlag = false")
+            .unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(ids, vec![1, 5, 9]);
+    }
+
+    #[test]
+    fn test_get_by_ids() {
+        let mut mock = MockCw::new();
+        let path = format!("{}/service/tickets", API_PREFIX);
+        let first = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("conditions".to_string(), "id in (30,10)".to_string()),
+                Matcher::UrlEncoded("pagesize".to_string(), "2".to_string()),
+                Matcher::UrlEncoded("fields".to_string(), "id,summary".to_string()),
+            ]))
+            .with_body(r#"[{"id": 10, "summary": "b"}, {"id": 30, "summary": "a"}]"#)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", path.as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("conditions".to_string(), "id in (20,40)".to_string()),
+                Matcher::UrlEncoded("pagesize".to_string(), "2".to_string()),
+            ]))
+            .with_body(r#"[{"id": 20, "summary": "c"}]"#)
+            .create();
+        // room for two ids in each url
+        let client = mock.client();
+        let base = client
+            .list_url_for(
+                "/service/tickets",
+                &[
+                    ("conditions", "id in ()"),
+                    ("pagesize", "1000"),
+                    ("fields", "id,summary"),
+                ],
+            )
+            .unwrap()
+            .as_str()
+            .len();
+        let client = client.max_url_length(base + 7);
+
+        let tickets = client
+            .get_by_ids("/service/tickets", &[30, 10, 30, 20, 40], &["summary"])
+            .unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(
+            tickets,
+            vec![
+                json!({"id": 30, "summary": "a"}),
+                json!({"id": 10, "summary": "b"}),
+                json!({"id": 20, "summary": "c"}),
+            ]
+        );
+        assert!(client
+            .get_by_ids("/service/tickets", &[], &[])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_all_ids_invalid() {
+        let mock = MockCw::new()
+            .list(
+                "/company/contacts",
+                vec![
+                    vec![json!({"id": 1})],
+                    vec![json!({"id": 2}), json!({"id": "3"})],
+                ],
+            )
+            .list("/company/sites", vec![vec![json!({"name": "Main"})]])
+            .single("/system/info", json!({"version": "v2022.1"}));
+        let client = mock.client();
+
+        let err = client.get_all_ids("/company/contacts", "").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::Decode {
+                path: "/company/contacts".to_string(),
+                field: "[2].id".to_string(),
+                id: None,
+                page: Some(2),
+                message: "the id \"3\" isn't an integer".to_string(),
+            })
+        );
+
+        let err = client.get_all_ids("/company/sites", "").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't decode [0].id of /company/sites (page 1): the record has no id"
+        );
+
+        let err = client.get_all_ids("/system/info", "").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ExpectedList { .. })
+        ));
+    }
+}
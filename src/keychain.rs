@@ -0,0 +1,194 @@
+//! Credentials kept in the os keychain (the `keyring` feature), see [Client::from_keyring]
+//!
+//! The four credentials are one keychain entry, a json blob:
+//! `{"companyId": "...", "publicKey": "...", "privateKey": "...", "clientId": "..."}` and
+//! optionally `"apiUrl"`, so they are provisioned (and rotated) together
+use crate::{Client, Error};
+use anyhow::{anyhow, Result};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+/// The credentials kept in the keychain, see [store_credentials]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyringCredentials {
+    /// the company id
+    pub company_id: String,
+    /// the public key of the api member
+    pub public_key: String,
+    /// the private key of the api member
+    pub private_key: String,
+    /// the client id of the integration
+    pub client_id: String,
+    /// overrides the api_url, see [Client::api_url]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+/// Stores `credentials` in the os keychain for [Client::from_keyring], replacing what was there
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::{store_credentials, KeyringCredentials};
+///
+/// store_credentials(
+///     "cwmanage",
+///     "production",
+///     &KeyringCredentials {
+///         company_id: "myco".to_string(),
+///         public_key: "public".to_string(),
+///         private_key: "private".to_string(),
+///         client_id: "clientid".to_string(),
+///         api_url: None,
+///     },
+/// )
+/// .unwrap();
+/// ```
+pub fn store_credentials(
+    service: &str,
+    account: &str,
+    credentials: &KeyringCredentials,
+) -> Result<()> {
+    store(&entry(service, account)?, service, account, credentials)
+}
+
+impl Client {
+    /// Creates a new client from the credentials in the os keychain (the `keyring` feature),
+    /// see [store_credentials].  [Error::NoKeyringEntry] when there are none for the service
+    /// and account, and [Error::KeyringUnavailable] when the keychain is locked or there isn't one
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Error};
+    ///
+    /// let client = match Client::from_keyring("cwmanage", "production") {
+    ///     Ok(client) => client,
+    ///     Err(e) if matches!(e.downcast_ref::<Error>(), Some(Error::NoKeyringEntry { .. })) => {
+    ///         panic!("run the provisioning first")
+    ///     }
+    ///     Err(e) => panic!("{}", e),
+    /// };
+    /// ```
+    pub fn from_keyring(service: &str, account: &str) -> Result<Client> {
+        load(&entry(service, account)?, service, account)
+    }
+}
+
+fn entry(service: &str, account: &str) -> Result<Entry> {
+    Entry::new(service, account).map_err(|e| keyring_error(e, service, account))
+}
+
+fn store(
+    entry: &Entry,
+    service: &str,
+    account: &str,
+    credentials: &KeyringCredentials,
+) -> Result<()> {
+    entry
+        .set_password(&serde_json::to_string(credentials)?)
+        .map_err(|e| keyring_error(e, service, account))
+}
+
+fn load(entry: &Entry, service: &str, account: &str) -> Result<Client> {
+    let stored = entry
+        .get_password()
+        .map_err(|e| keyring_error(e, service, account))?;
+    let credentials: KeyringCredentials = serde_json::from_str(&stored).map_err(|e| {
+        anyhow!(
+            "the keychain entry for service {:?}, account {:?} isn't cwmanage credentials: {}",
+            service,
+            account,
+            e
+        )
+    })?;
+    let client = Client::try_new(
+        credentials.company_id,
+        credentials.public_key,
+        credentials.private_key,
+        credentials.client_id,
+    )?;
+    Ok(match credentials.api_url {
+        Some(api_url) => client.api_url(api_url),
+        None => client,
+    })
+}
+
+fn keyring_error(e: keyring::Error, service: &str, account: &str) -> anyhow::Error {
+    match e {
+        keyring::Error::NoEntry => Error::NoKeyringEntry {
+            service: service.to_string(),
+            account: account.to_string(),
+        }
+        .into(),
+        e => Error::KeyringUnavailable {
+            service: service.to_string(),
+            account: account.to_string(),
+            reason: e.to_string(),
+        }
+        .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyring::mock::MockCredential;
+    use pretty_assertions::assert_eq;
+
+    fn mock_entry() -> Entry {
+        Entry::new_with_credential(Box::<MockCredential>::default())
+    }
+
+    fn mock(entry: &Entry) -> &MockCredential {
+        entry.get_credential().downcast_ref().unwrap()
+    }
+
+    #[test]
+    fn test_keyring_round_trip() {
+        let entry = mock_entry();
+        let credentials = KeyringCredentials {
+            company_id: "myco".to_string(),
+            public_key: "public".to_string(),
+            private_key: "private".to_string(),
+            client_id: "clientid".to_string(),
+            api_url: Some("staging.example.com".to_string()),
+        };
+        store(&entry, "cwmanage", "staging", &credentials).unwrap();
+
+        let client = load(&entry, "cwmanage", "staging").unwrap();
+        let expected = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url("staging.example.com".to_string());
+        assert_eq!(client, expected);
+    }
+
+    #[test]
+    fn test_keyring_errors() {
+        let entry = mock_entry();
+        let err = load(&entry, "cwmanage", "production").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::NoKeyringEntry {
+                service: "cwmanage".to_string(),
+                account: "production".to_string(),
+            })
+        );
+
+        mock(&entry).set_error(keyring::Error::NoStorageAccess("locked".into()));
+        let err = load(&entry, "cwmanage", "production").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "keychain unavailable for service \"cwmanage\", account \"production\": \
+             Couldn't access platform secure storage: locked"
+        );
+
+        entry.set_password("hunter2").unwrap();
+        let err = load(&entry, "cwmanage", "production").unwrap_err();
+        assert!(err.to_string().contains("isn't cwmanage credentials"));
+        assert!(err.downcast_ref::<Error>().is_none());
+    }
+}
@@ -0,0 +1,222 @@
+//! Knowledge base articles (`/service/knowledgeBaseArticles`).  See [Client::list_kb_articles]
+use crate::{Client, PatchOperation};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const KB_PATH: &str = "/service/knowledgeBaseArticles";
+
+/// What [Client::list_kb_articles] asks for when the query has no `fields`, everything but the
+/// (large) html of `issue` and `resolution`
+pub const KB_LIST_FIELDS: &str =
+    "id,title,categoryId,subCategoryId,dateCreated,createdBy,_info/lastUpdated,_info/updatedBy";
+
+/// A knowledge base article
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KbArticle {
+    /// the id of the article
+    pub id: i64,
+    /// the title of the article
+    #[serde(default)]
+    pub title: String,
+    /// the problem, as html.  `None` when it wasn't asked for (see [KB_LIST_FIELDS])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+    /// the fix, as html.  `None` when it wasn't asked for (see [KB_LIST_FIELDS])
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+    /// the category of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<i64>,
+    /// the sub category of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_category_id: Option<i64>,
+    /// when the article was created (`2022-07-14T15:34:22Z`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_created: Option<String>,
+    /// the `_info` map, `lastUpdated` is when the article last changed
+    #[serde(rename = "_info", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<HashMap<String, String>>,
+}
+
+#[cfg(feature = "chrono")]
+impl KbArticle {
+    /// [KbArticle::date_created] as a date, see [crate::dates].  `None` when it isn't set (or isn't a date)
+    pub fn date_created_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.date_created.as_deref())
+    }
+}
+
+/// A knowledge base article to create with [Client::create_kb_article]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewKbArticle {
+    /// the title of the article
+    pub title: String,
+    /// the problem, as html
+    pub issue: String,
+    /// the fix, as html
+    pub resolution: String,
+    /// the category of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<i64>,
+    /// the sub category of the article
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sub_category_id: Option<i64>,
+}
+
+impl Client {
+    /// Gets the knowledge base articles, `query` is passed on as with [Client::get].  Unless
+    /// the query has its own `fields` only [KB_LIST_FIELDS] are asked for, so the html of
+    /// each article isn't downloaded.  Use [Client::get_kb_article] for the whole article
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for article in client.list_kb_articles(&[("conditions", "categoryId = 3")]).unwrap() {
+    ///     let full = client.get_kb_article(article.id).unwrap();
+    ///     println!("{}: {}", full.title, full.resolution.unwrap_or_default());
+    /// }
+    /// ```
+    pub fn list_kb_articles(&self, query: &[(&str, &str)]) -> Result<Vec<KbArticle>> {
+        let mut query = query.to_vec();
+        if !query.iter().any(|(k, _)| k.eq_ignore_ascii_case("fields")) {
+            query.push(("fields", KB_LIST_FIELDS));
+        }
+        self.get_decoded(KB_PATH, &query)
+    }
+
+    /// Gets a whole knowledge base article
+    pub fn get_kb_article(&self, id: i64) -> Result<KbArticle> {
+        let path = format!("{}/{}", KB_PATH, id);
+        self.decode(&path, self.get_single(&path, &[])?)
+    }
+
+    /// Creates a knowledge base article and returns it
+    pub fn create_kb_article(&self, article: &NewKbArticle) -> Result<KbArticle> {
+        let created = self.post(KB_PATH, serde_json::to_string(article)?)?;
+        self.decode(KB_PATH, created)
+    }
+
+    /// Applies patch operations to a knowledge base article and returns it
+    pub fn update_kb_article(&self, id: i64, ops: &[PatchOperation]) -> Result<KbArticle> {
+        let path = format!("{}/{}", KB_PATH, id);
+        self.decode(&path, self.patch_ops(&path, ops)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use crate::PatchOp;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_list_kb_articles_fields() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/knowledgeBaseArticles";
+        let default = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded(
+                "fields".to_string(),
+                KB_LIST_FIELDS.to_string(),
+            ))
+            .with_body(r#"[{"id": 1, "title": "Reset a password", "categoryId": 3}]"#)
+            .expect(1)
+            .create();
+        let asked = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded(
+                "fields".to_string(),
+                "id,issue".to_string(),
+            ))
+            .with_body(r#"[{"id": 1, "issue": "<p>locked out</p>"}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let listed = client.list_kb_articles(&[("", "")]).unwrap();
+        let with_issue = client.list_kb_articles(&[("fields", "id,issue")]).unwrap();
+
+        default.assert();
+        asked.assert();
+        assert_eq!(listed[0].title, "Reset a password");
+        assert_eq!(listed[0].issue, None);
+        assert_eq!(with_issue[0].issue.as_deref(), Some("<p>locked out</p>"));
+    }
+
+    #[test]
+    fn test_kb_article_round_trip() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/knowledgeBaseArticles";
+        let html = "<p class=\"x\">caf\u{e9} \\ \"quoted\" &amp; \u{1f5a8}</p>\r\n<br/>\t";
+        let article = NewKbArticle {
+            title: "Printers".to_string(),
+            issue: html.to_string(),
+            resolution: "<ol><li>turn it off</li></ol>".to_string(),
+            category_id: Some(3),
+            ..Default::default()
+        };
+        let stored = json!({"id": 9, "title": "Printers", "issue": html,
+            "resolution": "<ol><li>turn it off</li></ol>", "categoryId": 3});
+        let create = mock
+            .server()
+            .mock("POST", path)
+            .match_body(Matcher::Json(json!({"title": "Printers", "issue": html,
+                "resolution": "<ol><li>turn it off</li></ol>", "categoryId": 3})))
+            .with_body(stored.to_string())
+            .create();
+        let get = mock
+            .server()
+            .mock("GET", format!("{}/9", path).as_str())
+            .match_query(Matcher::Any)
+            .with_body(stored.to_string())
+            .create();
+        let update = mock
+            .server()
+            .mock("PATCH", format!("{}/9", path).as_str())
+            .match_body(Matcher::Json(
+                json!([{"op": "replace", "path": "title", "value": "Printers!"}]),
+            ))
+            .with_body(json!({"id": 9, "title": "Printers!"}).to_string())
+            .create();
+        let client = mock.client();
+
+        let created = client.create_kb_article(&article).unwrap();
+        let fetched = client.get_kb_article(created.id).unwrap();
+        let updated = client
+            .update_kb_article(
+                9,
+                &[PatchOperation::new(
+                    PatchOp::Replace,
+                    "title",
+                    json!("Printers!"),
+                )],
+            )
+            .unwrap();
+
+        create.assert();
+        get.assert();
+        update.assert();
+        assert_eq!(fetched.issue.as_deref(), Some(html));
+        assert_eq!(fetched.category_id, Some(3));
+        assert_eq!(updated.title, "Printers!");
+    }
+}
@@ -90,14 +90,145 @@
 //! - Only get the id field `[("fields", "id")]`
 //! - Also apply some conditions `[("fields", "id"), ("conditions", "name LIKE '%foo%'")]`
 use anyhow::{anyhow, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
-use std::string::ToString;
-use strum_macros;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 use url::Url;
 
-/// Default api url.  NA for north america.  Adjust to your cloud instance or local instance. See [Client] for how to customize
-pub const DEFAULT_API_URL: &str = "na.myconnectwise.net";
+mod accounting;
+mod activities;
+mod auth;
+#[cfg(feature = "chrono-tz")]
+mod availability;
+mod bulk;
+pub mod callbacks;
+mod cancel;
+#[cfg(feature = "record-replay")]
+mod cassette;
+#[cfg(feature = "codegen")]
+pub mod codegen;
+mod contacts;
+mod custom_fields;
+#[cfg(feature = "chrono")]
+pub mod dates;
+mod decode;
+mod dedupe;
+mod disk_cache;
+mod documents;
+mod endpoint;
+mod error;
+pub mod export;
+mod flatten;
+mod har;
+mod health;
+mod ids;
+#[cfg(feature = "keyring")]
+mod keychain;
+mod knowledge_base;
+mod members;
+mod my_company;
+mod notes;
+#[cfg(feature = "otel")]
+mod otel;
+mod pages;
+mod pool;
+mod priorities;
+mod procurement;
+mod projects;
+mod queue;
+mod rate_limit;
+mod redact;
+mod reference;
+mod region;
+mod request;
+mod request_id;
+mod retry;
+mod search;
+mod shared;
+mod sla;
+mod stats;
+mod surveys;
+#[cfg(feature = "chrono")]
+mod sync;
+mod system;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+mod ticket_tasks;
+mod tickets;
+mod time_entries;
+mod time_sheets;
+#[cfg(feature = "chrono-tz")]
+mod timezone;
+mod value_ext;
+mod warnings;
+pub use accounting::{AccountingBatch, BatchResult, ExportSettings, UnpostedInvoice};
+pub use activities::{ActivityStatus, ActivityType, NewActivity};
+#[cfg(feature = "chrono-tz")]
+pub use availability::{Availability, Interval};
+pub use bulk::{
+    BatchEntry, BatchOp, BatchOptions, BatchOutcome, BatchReport, BulkDeleteOptions, BulkFailure,
+    BulkOptions, BulkReport,
+};
+pub use cancel::CancelToken;
+#[cfg(feature = "record-replay")]
+pub use cassette::CassetteMode;
+pub use contacts::{CommunicationType, ContactLookup, NewContact};
+#[cfg(feature = "chrono")]
+pub use custom_fields::date_value;
+use custom_fields::find_custom_field;
+pub use custom_fields::{CaptionMatch, CustomField, CustomFieldType};
+pub use decode::{UnknownFields, UnknownFieldsHandler};
+pub use dedupe::{DedupeFn, DedupeRule};
+pub use disk_cache::CACHED_AT_HEADER;
+pub use documents::{AttachmentFailure, AttachmentReport, Document};
+pub use endpoint::Endpoint;
+pub use error::{ApiError, ApiErrorDetail, Error};
+pub use flatten::{flatten, ArrayMode, Flatten};
+pub use har::{DEFAULT_HAR_MAX_BODY_BYTES, DEFAULT_HAR_MAX_FILE_BYTES};
+pub use health::{HealthCheck, HealthReport, HealthStatus, HEALTH_REPORT_DEADLINE};
+#[cfg(feature = "keyring")]
+pub use keychain::{store_credentials, KeyringCredentials};
+pub use knowledge_base::{KbArticle, NewKbArticle, KB_LIST_FIELDS};
+pub use members::{Access, AccessReport, Member, ModuleAccess, SecurityRole, ACCESS_MODULES};
+pub use my_company::{CorporateStructure, Department, Location, MyCompanyOther, StructureLevel};
+pub use notes::{CompanyNote, ContactNote};
+pub use pages::{Pages, LATEST_ORDER_FIELD, MAX_PAGE_SIZE};
+pub use pool::{ClientPool, TenantConfig};
+pub use priorities::{Priority, PriorityMatrixEntry, TicketLevel};
+pub use procurement::PoLineItem;
+pub use projects::{Phase, PhaseHours, ProjectHoursRollup, ProjectPhase, ProjectTicket, WorkPlan};
+pub use queue::{FlushOutcome, FlushedEntry, JournalEntry, Queued, QueuedClient};
+pub use rate_limit::{RateLimitHandler, RateLimitInfo};
+pub use reference::{CwRef, IdOrName};
+pub use region::Region;
+pub use request::{CwRequestBuilder, CwResponse};
+pub use request_id::RequestId;
+pub use retry::{RetryContext, RetryPredicate};
+pub use sla::{SlaStage, TicketSla};
+pub use stats::{StatBucket, StatsGroup};
+pub use surveys::{Survey, SurveyAnswer, SurveyResult};
+#[cfg(feature = "chrono")]
+pub use sync::UpdatedSince;
+pub use system::SystemInfo;
+pub use ticket_tasks::TicketTask;
+pub use tickets::{
+    Board, BoardStatus, CloseOptions, NewTicket, TicketBundle, TicketConfiguration, TicketNote,
+    TimeEntry,
+};
+pub use time_entries::{
+    BillableOption, Clock, NewTimeEntry, Rounding, SystemClock, Timer, TimerOptions,
+};
+pub use time_sheets::TimeSheet;
+pub use value_ext::CwValueExt;
+pub use warnings::{ApiWarning, WarningHandler};
+
+/// Default api url.  The api host of the north america cloud, see [Region] for the others.
+/// Adjust to your cloud instance or local instance. See [Client] for how to customize
+pub const DEFAULT_API_URL: &str = "api-na.myconnectwise.net";
 
 /// This is the release version specified in the documentation.  
 /// There is a way to dynamically look up your api version.  This
@@ -108,22 +239,104 @@ pub const DEFAULT_API_CODEBASE: &str = "v4_6_release";
 /// it is customizable. See [Client] for how to customize
 pub const DEFAULT_API_VERSION: &str = "3.0";
 
-/// Our possible patch operations
-#[derive(Debug, strum_macros::ToString)]
+/// Default longest url [Client::get] sends, see [Client::max_url_length]
+pub const DEFAULT_MAX_URL_LENGTH: usize = 2048;
+
+/// Default wait before the first retry, see [Client::retry_backoff]
+pub const DEFAULT_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Our possible patch operations.  These display (and serialize) as the lowercase value
+/// connectwise expects, `PatchOp::Replace` is `replace`, and can be parsed from a string
+/// ignoring case
+///
+/// ```
+/// use cwmanage::PatchOp;
+///
+/// let op: PatchOp = "Replace".parse().unwrap();
+/// assert_eq!(op, PatchOp::Replace);
+/// assert_eq!(op.to_string(), "replace");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PatchOp {
     /// Add to a non-existing field
-    #[strum(serialize = "add")]
     Add,
     /// Replace existing value with the provided one
-    #[strum(serialize = "replace")]
     Replace,
     /// Remove the specified viewed
-    #[strum(serialize = "remove")]
     Remove,
+    /// Check the field has the value, if it doesn't the whole patch fails
+    Test,
+}
+
+impl PatchOp {
+    /// The value connectwise expects for this operation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PatchOp::Add => "add",
+            PatchOp::Replace => "replace",
+            PatchOp::Remove => "remove",
+            PatchOp::Test => "test",
+        }
+    }
+}
+
+impl fmt::Display for PatchOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PatchOp {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "add" => Ok(PatchOp::Add),
+            "replace" => Ok(PatchOp::Replace),
+            "remove" => Ok(PatchOp::Remove),
+            "test" => Ok(PatchOp::Test),
+            _ => Err(anyhow!("unknown patch operation {:?}", s)),
+        }
+    }
+}
+
+/// One operation of a patch request, connectwise takes a list of these
+///
+/// ```
+/// use cwmanage::{PatchOp, PatchOperation};
+/// use serde_json::json;
+///
+/// let op = PatchOperation::new(PatchOp::Replace, "status/id", json!(42));
+/// assert_eq!(
+///     serde_json::to_value(&op).unwrap(),
+///     json!({"op": "replace", "path": "status/id", "value": 42})
+/// );
+/// ```
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PatchOperation {
+    /// the operation
+    pub op: PatchOp,
+    /// the field to change (example `summary`, `status/id`)
+    pub path: String,
+    /// the new value
+    pub value: Value,
+}
+
+impl PatchOperation {
+    /// Creates a patch operation
+    pub fn new(op: PatchOp, path: &str, value: Value) -> PatchOperation {
+        PatchOperation {
+            op,
+            path: path.to_string(),
+            value,
+        }
+    }
 }
 
 /// Connectwise client.  Initinitialize with [Client::new].  Use [Client::api_url],
-/// [Client::api_version] and [Client::codebase] to customize.  The finalize with [Client::build]
+/// [Client::api_version], [Client::codebase], [Client::caption_match] and [Client::read_only]
+/// to customize.  The finalize with [Client::build]
 /// * `company_id` is your _short name_ (ie the one you use to login to CW)
 /// * `public_key` is obtained by creating an api member with keys
 /// * `private_key` is obtained by creating an api member with keys
@@ -135,11 +348,45 @@ pub struct Client {
     private_key: String,
     client_id: String,
     api_url: String,
+    cloud_api_prefix: bool,
     codebase: String,
     api_version: String,
+    caption_match: CaptionMatch,
+    read_only: bool,
+    resume_on_cursor_expiry: bool,
+    stable_pagination: bool,
+    max_url_length: usize,
+    search_routes: Vec<String>,
+    unwrap_single_array: bool,
+    max_retries: u32,
+    retry_backoff: std::time::Duration,
+    retry_non_idempotent: bool,
+    request_id_header: Option<String>,
+    retry_if: Option<shared::Hook<RetryPredicate>>,
+    on_warning: Option<shared::Hook<WarningHandler>>,
+    collect_unknown_fields: bool,
+    deny_unknown_fields: bool,
+    on_unknown_fields: Option<shared::Hook<UnknownFieldsHandler>>,
+    on_rate_limit_low: Option<rate_limit::RateLimitLow>,
+    disk_cache: Option<std::sync::Arc<disk_cache::DiskCache>>,
+    har: Option<std::sync::Arc<har::HarCapture>>,
+    redaction: Option<std::sync::Arc<redact::Redaction>>,
+    cancel: Option<CancelToken>,
+    offline: bool,
+    request_interval: Option<Duration>,
+    http: shared::Http,
+    shared: shared::Shared,
+    #[cfg(feature = "record-replay")]
+    cassette: Option<std::sync::Arc<cassette::Cassette>>,
 }
 impl Client {
     /// Creates a new client using the default values
+    ///
+    /// The credentials are sent as RFC 7617 says for UTF-8: normalized to NFC and encoded as
+    /// UTF-8, so accented company ids work however they were typed.  A company id or public
+    /// key with a `:` or whitespace, or any of them with a control character, can't be sent
+    /// and every request fails with [Error::InvalidCredentials] before anything is sent.  Use
+    /// [Client::try_new] to find out when the client is made
     pub fn new(
         company_id: String,
         public_key: String,
@@ -152,21 +399,138 @@ impl Client {
             private_key,
             client_id,
             api_url: DEFAULT_API_URL.to_string(),
+            cloud_api_prefix: true,
             codebase: DEFAULT_API_CODEBASE.to_string(),
             api_version: DEFAULT_API_VERSION.to_string(),
+            caption_match: CaptionMatch::Exact,
+            read_only: false,
+            resume_on_cursor_expiry: false,
+            stable_pagination: true,
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            search_routes: Vec::new(),
+            unwrap_single_array: false,
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            retry_non_idempotent: false,
+            request_id_header: None,
+            retry_if: None,
+            on_warning: None,
+            collect_unknown_fields: false,
+            deny_unknown_fields: false,
+            on_unknown_fields: None,
+            on_rate_limit_low: None,
+            disk_cache: None,
+            har: None,
+            redaction: None,
+            cancel: None,
+            offline: false,
+            request_interval: None,
+            http: Default::default(),
+            shared: Default::default(),
+            #[cfg(feature = "record-replay")]
+            cassette: None,
         }
     }
-    /// Builds (finalizes the client)
+    /// [Client::new], but [Error::InvalidCredentials] if the credentials can't be sent
+    ///
+    /// ```
+    /// use cwmanage::{Client, Error};
+    ///
+    /// let client = Client::try_new(
+    ///     "my co".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// );
+    /// assert!(matches!(
+    ///     client.unwrap_err().downcast_ref::<Error>(),
+    ///     Some(Error::InvalidCredentials { .. })
+    /// ));
+    /// ```
+    pub fn try_new(
+        company_id: String,
+        public_key: String,
+        private_key: String,
+        client_id: String,
+    ) -> Result<Client> {
+        auth::basic_auth(&company_id, &public_key, &private_key)?;
+        Ok(Client::new(company_id, public_key, private_key, client_id))
+    }
+
+    /// Creates a new client from the `CWMANAGE_COMPANY_ID`, `CWMANAGE_PUBLIC_KEY`,
+    /// `CWMANAGE_PRIVATE_KEY` and `CWMANAGE_CLIENT_ID` environment variables (the ones the
+    /// examples load with dotenv).  When `CWMANAGE_API_URL` is set it overrides the api_url.
+    /// When `CWMANAGE_PROFILE` is set it is [Client::from_env_profile] with that profile
+    pub fn from_env() -> Result<Client> {
+        let profile = std::env::var("CWMANAGE_PROFILE").unwrap_or_default();
+        Client::from_env_profile(&profile)
+    }
+
+    /// [Client::from_env] for a named profile: `staging` reads `CWMANAGE_STAGING_COMPANY_ID`,
+    /// `CWMANAGE_STAGING_PUBLIC_KEY` and so on, and the unprefixed variable for any the profile
+    /// doesn't set.  The profile is upper cased with anything but letters and digits as `_`.
+    /// An empty profile is the unprefixed variables
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // CWMANAGE_STAGING_COMPANY_ID=mycostaging
+    /// // CWMANAGE_STAGING_API_URL=https://staging.example.com
+    /// let staging = Client::from_env_profile("staging").unwrap();
+    /// ```
+    pub fn from_env_profile(profile: &str) -> Result<Client> {
+        Client::from_vars(profile, |name| std::env::var(name).ok())
+    }
+
+    /// [Client::from_env_profile] with the variables from `lookup`
+    fn from_vars(profile: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Client> {
+        let prefix = match profile {
+            "" => "CWMANAGE_".to_string(),
+            profile => {
+                let profile: String = profile
+                    .chars()
+                    .map(|c| match c {
+                        c if c.is_ascii_alphanumeric() => c.to_ascii_uppercase(),
+                        _ => '_',
+                    })
+                    .collect();
+                format!("CWMANAGE_{}_", profile)
+            }
+        };
+        let optional = |name: &str| {
+            lookup(&format!("{}{}", prefix, name)).or_else(|| lookup(&format!("CWMANAGE_{}", name)))
+        };
+        let var = |name: &str| {
+            optional(name).ok_or_else(|| match profile {
+                "" => anyhow!("{}{} isn't set", prefix, name),
+                _ => anyhow!(
+                    "{}{} isn't set, and neither is CWMANAGE_{}",
+                    prefix,
+                    name,
+                    name
+                ),
+            })
+        };
+        let client = Client::try_new(
+            var("COMPANY_ID")?,
+            var("PUBLIC_KEY")?,
+            var("PRIVATE_KEY")?,
+            var("CLIENT_ID")?,
+        )?;
+        Ok(match optional("API_URL") {
+            Some(api_url) => client.api_url(api_url),
+            None => client,
+        })
+    }
+
+    /// Builds (finalizes the client).  This makes the http client the requests are sent with,
+    /// shared by the clones of the client; if it can't be made the first request says why
     pub fn build(&self) -> Client {
-        Client {
-            company_id: self.company_id.to_owned(),
-            public_key: self.public_key.to_owned(),
-            private_key: self.private_key.to_owned(),
-            client_id: self.client_id.to_owned(),
-            api_url: self.api_url.to_owned(),
-            codebase: self.codebase.to_owned(),
-            api_version: self.api_version.to_owned(),
+        if let Err(e) = self.http.get() {
+            tracing::warn!("the http client couldn't be made: {}", e);
         }
+        self.clone()
     }
 
     /// overrides the default api_version
@@ -175,48 +539,122 @@ impl Client {
         self
     }
 
-    /// overrides the default api_url
+    /// overrides the default api_url.  This is normally just the host, `https` is used.  To use
+    /// another scheme (a local instance or a test server) include it, `http://localhost:8080`.
+    /// The host people log in to for a cloud region (`na.myconnectwise.net`) is replaced by
+    /// its api host (`api-na.myconnectwise.net`), see [Client::cloud_api_prefix]
     pub fn api_url(mut self, api_url: String) -> Client {
         self.api_url = api_url;
         self
     }
 
+    /// sends the requests to the api host of a cloud region, same as [Client::api_url] with
+    /// [Region::host]
+    pub fn region(mut self, region: Region) -> Client {
+        self.api_url = region.host().to_string();
+        self
+    }
+
+    /// whether a cloud login host given to [Client::api_url] (`na.myconnectwise.net`) is
+    /// replaced by the api host connectwise wants the api traffic on
+    /// (`api-na.myconnectwise.net`).  On by default, turn it off to send the requests to
+    /// exactly the host given
+    pub fn cloud_api_prefix(mut self, prefix: bool) -> Client {
+        self.cloud_api_prefix = prefix;
+        self
+    }
+
     /// overrides the default codebase
     pub fn codebase(mut self, codebase: String) -> Client {
         self.codebase = codebase;
         self
     }
-    fn gen_basic_auth(&self) -> String {
-        let encoded = base64::encode(format!(
-            "{}+{}:{}",
-            self.company_id, self.public_key, self.private_key
-        ));
-        format!("Basic {}", encoded)
+
+    /// overrides the default caption matching ([CaptionMatch::Exact]) used by the custom field
+    /// helpers.  With the looser modes more than one caption matching the same field is an error
+    pub fn caption_match(mut self, caption_match: CaptionMatch) -> Client {
+        self.caption_match = caption_match;
+        self
     }
-    fn gen_api_url(&self, path: &str) -> String {
-        format!(
-            "https://{}/{}/apis/{}{}",
-            self.api_url, self.codebase, self.api_version, path
-        )
+
+    /// makes the client read only.  Every write (`post`, `patch` and the helpers built on them)
+    /// returns [Error::ReadOnly] without sending anything, reads work as normal
+    pub fn read_only(mut self, read_only: bool) -> Client {
+        self.read_only = read_only;
+        self
     }
-    /// GETs a path from the connectwise api.  `get_single` is only used on certain api endpoints.
-    /// It is expecting the response from the connectwise api to be a single "object" and not a list
-    /// like it normally returns
-    ///
-    /// # Arguments
-    ///
-    /// - `path` - the api path you want to retrieve (example `/service/info`)
-    /// - `query` - additional query options *must be set*.  If non, use [("", "")]
-    ///
-    /// # Known Endpoints
-    ///
-    /// - /system/info
+
+    /// when the forward only pagination cursor expires part way through [Client::get] (or
+    /// [Client::pages]) start a fresh list after the highest `id` seen so far instead of
+    /// failing with [Error::CursorExpired].  This needs the results in id order so `orderby=id
+    /// asc` is added when there is no `orderby`, with another `orderby` it doesn't resume.  A
+    /// fresh list that expires again before getting past the last `id` isn't resumed, and
+    /// neither is one that has been resumed ten times, the error is returned instead
+    pub fn resume_on_cursor_expiry(mut self, resume: bool) -> Client {
+        self.resume_on_cursor_expiry = resume;
+        self
+    }
+
+    /// adds `orderby=id asc` to the pages of [Client::get] (and [Client::pages]) when the
+    /// query has no `orderby`, so records created while the pages are read don't shift the
+    /// results.  [Client::list_url_for] shows the url with it.  On by default, turn it off for
+    /// endpoints without an `id`
+    pub fn stable_pagination(mut self, stable: bool) -> Client {
+        self.stable_pagination = stable;
+        self
+    }
+
+    /// the longest url (query included) [Client::get] sends.  When a list's url would be
+    /// longer (a long `id in (...)`) it is fetched with [Client::search] if the list has a
+    /// search route (see [Client::search_route]), otherwise the get fails with
+    /// [Error::QueryTooLong].  By default [DEFAULT_MAX_URL_LENGTH]
+    pub fn max_url_length(mut self, length: usize) -> Client {
+        self.max_url_length = length;
+        self
+    }
+
+    /// adds a list with a POST `{path}/search` route (`/company/companies`) to the ones
+    /// [Client::get] falls back to when the url would be longer than
+    /// [Client::max_url_length].  `/service/tickets` and `/project/tickets` always have one
+    pub fn search_route(mut self, path: &str) -> Client {
+        self.search_routes
+            .push(format!("/{}", path.trim_matches('/')));
+        self
+    }
+
+    /// when [Client::get_single] gets a list with exactly one object return that object instead
+    /// of [Error::ExpectedObject].  Off by default
+    pub fn unwrap_single_array(mut self, unwrap: bool) -> Client {
+        self.unwrap_single_array = unwrap;
+        self
+    }
+
+    /// how many times a request that failed for a reason that may go away (a dropped
+    /// connection, a timeout, a 502, 503 or 504, rate limiting) is tried again.  By default 0,
+    /// nothing is retried.  Only requests that are safe to send twice are retried, see
+    /// [Client::retry_non_idempotent]
+    pub fn max_retries(mut self, max_retries: u32) -> Client {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// how long to wait before the first retry, it doubles for each retry after that.  When
+    /// rate limited the `Retry-After` the api sends is used instead.  By default
+    /// [DEFAULT_RETRY_BACKOFF]
+    pub fn retry_backoff(mut self, retry_backoff: std::time::Duration) -> Client {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// fails every request after `token` is cancelled with [Error::Cancelled], without sending
+    /// it.  Set it on a clone of the client for one operation: the pages of a
+    /// [Client::get] or [Client::pages] stop at the next page, a retry stops waiting for its
+    /// backoff and the bulk helpers don't start the ids that are left.  A request already on
+    /// the wire isn't interrupted, it finishes (or times out) first
     ///
     /// # Example
-    ///
-    /// ## Basic get, returning parsed json
-    /// ```
-    /// use cwmanage::Client;
+    /// ```no_run
+    /// use cwmanage::{CancelToken, Client};
     ///
     /// // this example is using dotenv to load our settings from
     /// // the environment, you could also specify this manually
@@ -226,159 +664,304 @@ impl Client {
     /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
     /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
     /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
-    ///
     /// let client = Client::new(company_id, public_key, private_key, client_id).build();
     ///
-    /// let query = [("", "")];
-    /// let path = "/system/info";
-    /// let result = client.get_single(&path, &query).unwrap();
+    /// let token = CancelToken::new();
+    /// let cancellable = client.clone().cancel_on(token.clone());
+    /// let walk = std::thread::spawn(move || cancellable.get("/service/tickets", &[]));
+    /// // the user navigated away
+    /// token.cancel();
+    /// assert!(walk.join().unwrap().is_err());
+    /// ```
+    pub fn cancel_on(mut self, token: CancelToken) -> Client {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// retry every request, not only the ones that are safe to send twice.  By default GET,
+    /// PUT, DELETE and a PATCH with a `test` operation are retried and a POST (or a PATCH
+    /// without a `test`) isn't, because if the first one did reach connectwise the retry
+    /// creates a duplicate.  Turn this on only for endpoints where that can't happen
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Client {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// decides which failed requests are retried (up to [Client::max_retries] times) instead
+    /// of the default, [RetryContext::default_policy].  It is asked before each retry
     ///
-    /// assert_eq!(&result["isCloud"], true);
     /// ```
-    /// ## Basic get, take parsed json and convert to a struct
+    /// use cwmanage::{Client, RetryContext};
+    /// use std::sync::Arc;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .max_retries(3)
+    /// // our load balancer sends a 408 that is safe to retry
+    /// .retry_if(Arc::new(|ctx: &RetryContext| {
+    ///     ctx.default_policy() || (ctx.idempotent && ctx.status == Some(408))
+    /// }))
+    /// .build();
     /// ```
-    /// use cwmanage::Client;
-    /// use serde::{Deserialize};
+    pub fn retry_if(mut self, retry_if: std::sync::Arc<RetryPredicate>) -> Client {
+        self.retry_if = Some(shared::Hook(retry_if));
+        self
+    }
+
+    /// called with the [ApiWarning] of every response that says its endpoint is deprecated
+    /// or going away (the `Deprecation`, `Sunset` and `Warning` headers).  The warnings are
+    /// also logged at warn level with or without this.  They don't change the result of the
+    /// request
     ///
-    /// #[derive(Debug, Deserialize)]
-    /// #[serde(rename_all = "camelCase")]
-    /// struct SystemInfo {
-    ///   version: String,
-    ///   is_cloud: bool,
-    ///   server_time_zone: String,
-    /// }
+    /// ```
+    /// use cwmanage::{ApiWarning, Client};
+    /// use std::sync::Arc;
     ///
-    /// // this example is using dotenv to load our settings from
-    /// // the environment, you could also specify this manually
-    /// use dotenv::dotenv;
-    /// dotenv().ok();
-    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
-    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
-    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
-    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .on_warning(Arc::new(|warning: &ApiWarning| {
+    ///     eprintln!("{} {} ends {:?}", warning.method, warning.path, warning.sunset)
+    /// }))
+    /// .build();
+    /// ```
+    pub fn on_warning(mut self, on_warning: std::sync::Arc<WarningHandler>) -> Client {
+        self.on_warning = Some(shared::Hook(on_warning));
+        self
+    }
+
+    /// masks the values of `fields` wherever the crate writes request and response data for
+    /// someone to read: the bodies in a [Client::har_capture] file, the body of a failed
+    /// response in its error and the value in an [Error::Decode].  What is sent and returned
+    /// is never changed.  The values become `"[redacted]"`, objects and arrays keep their shape
     ///
-    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    /// A field is `summary`, `contact/email` (or `contact.email`), `customFields[*].value` for
+    /// every element of an array, `customFields[0].value` for one and `site/*` for all the
+    /// fields of an object.  A field name also applies to every element of an array, so
+    /// `summary` covers each record of a list
     ///
-    /// let query = [("", "")];
-    /// let path = "/system/info";
-    /// let result = client.get_single(&path, &query).unwrap();
+    /// ```
+    /// use cwmanage::Client;
     ///
-    /// // got our result, just like before.
-    /// // now convert it into our struct
-    /// let info: SystemInfo = serde_json::from_value(result).unwrap();
-    /// assert_eq!(info.is_cloud, true);
-    /// assert_eq!(info.server_time_zone, "Eastern Standard Time");
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .redact_fields(&["summary", "contact/email", "customFields[*].value"])
+    /// .har_capture("support-case.har")
+    /// .build();
     /// ```
-    pub fn get_single(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
-        let res = reqwest::blocking::Client::new()
-            .get(&self.gen_api_url(path))
-            .header("Authorization", &self.gen_basic_auth())
-            .header("Content-Type", "application/json")
-            .header("clientid", self.client_id.to_owned())
-            .header("pagination-type", "forward-only")
-            .query(&query)
-            .send()?
-            .text()?;
+    pub fn redact_fields(mut self, fields: &[&str]) -> Client {
+        self.redaction = Some(std::sync::Arc::new(redact::Redaction::new(fields)));
+        self
+    }
 
-        let v: Value = serde_json::from_str(&res)?;
-        Ok(v)
+    /// finds the fields of the responses that the types of the crate ([Ticket], [Member],
+    /// ...) don't have, the ones a connectwise upgrade adds or renames.  They are logged at
+    /// warn level and handed to [Client::on_unknown_fields], once a call with the fields of
+    /// all of its records (`company.identifier`, `contacts[].email`).  The `_info` links aren't
+    /// reported.  Off by default: every field decoded goes through one more layer, a little
+    /// slower on large lists.  The untyped calls ([Client::get] and the like) aren't checked
+    pub fn collect_unknown_fields(mut self, collect: bool) -> Client {
+        self.collect_unknown_fields = collect;
+        self
     }
 
-    /// This will get a custom field Value, it helps with some of the juggleing of all of the
-    /// custom fields that get returned
+    /// [Client::collect_unknown_fields] that fails the call with [Error::UnknownFields] when
+    /// a response has fields the type doesn't, for a canary job that should notice an upgrade
+    pub fn deny_unknown_fields(mut self, deny: bool) -> Client {
+        self.deny_unknown_fields = deny;
+        self.collect_unknown_fields |= deny;
+        self
+    }
+
+    /// called with the [UnknownFields] of every typed call, see [Client::collect_unknown_fields]
     ///
-    /// # Arguments
+    /// ```
+    /// use cwmanage::{Client, UnknownFields};
+    /// use std::sync::Arc;
     ///
-    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
-    /// - `field` - The field we want to update (also known as the "Caption")
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .collect_unknown_fields(true)
+    /// .on_unknown_fields(Arc::new(|unknown: &UnknownFields| {
+    ///     eprintln!("{} from {} has new fields {:?}", unknown.model, unknown.path, unknown.fields)
+    /// }))
+    /// .build();
+    /// ```
+    pub fn on_unknown_fields(
+        mut self,
+        on_unknown_fields: std::sync::Arc<UnknownFieldsHandler>,
+    ) -> Client {
+        self.on_unknown_fields = Some(shared::Hook(on_unknown_fields));
+        self
+    }
+
+    /// sends at most `per_second` requests a second (each retry is a request), waiting before
+    /// a request until it is its turn.  The clones of the client share the limit.  When the
+    /// responses report a rate limit ([Client::last_rate_limit]) the requests slow down to make
+    /// the requests left last until it resets, and wait for the reset once none are left.  No
+    /// limit by default
+    pub fn max_requests_per_second(mut self, per_second: u32) -> Client {
+        self.request_interval = match per_second {
+            0 => None,
+            n => Some(Duration::from_secs(1) / n),
+        };
+        self
+    }
+
+    /// sends the requests with `http` (sharing its connections) instead of a client of its own
+    pub(crate) fn http(mut self, http: shared::Http) -> Client {
+        self.http = http;
+        self
+    }
+
+    /// sends a new random id (a uuid) with every request in this header (`X-Request-Id`).  The
+    /// id is in the tracing span of the request and is attached to every error the request
+    /// produces as a [RequestId].  The pages of a [Client::get] each have their own id and
+    /// share a parent id.  Off by default
+    pub fn request_id_header(mut self, header: &str) -> Client {
+        self.request_id_header = Some(header.to_string());
+        self
+    }
+
+    /// records every request and response to (or replays them from) the cassette file at
+    /// `path`.  See [CassetteMode] for the details
+    #[cfg(feature = "record-replay")]
+    pub fn cassette(mut self, path: impl Into<std::path::PathBuf>, mode: CassetteMode) -> Client {
+        self.cassette = Some(std::sync::Arc::new(cassette::Cassette::new(
+            path.into(),
+            mode,
+        )));
+        self
+    }
+
+    /// writes every request and its response (or the error it failed with) to a HAR 1.2 file
+    /// at `path`, which opens in the network tab of browser devtools or can be attached to a
+    /// support case.  The `Authorization`, `clientId` and cookie headers are written as
+    /// `[redacted]`, bodies are cut to [DEFAULT_HAR_MAX_BODY_BYTES] and the oldest entries are
+    /// dropped to keep the file under [DEFAULT_HAR_MAX_FILE_BYTES].  The file is rewritten after
+    /// each request, and a capture that can't be written is logged without failing the request
     ///
-    /// # Example
-    /// ## getting a field
     /// ```
     /// use cwmanage::Client;
-    /// use serde_json::json;
-    ///
-    /// // this example is using dotenv to load our settings from
-    /// // the environment, you could also specify this manually
-    /// use dotenv::dotenv;
-    /// dotenv().ok();
-    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
-    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
-    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
-    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
-    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
-    ///
-    /// let path = "/project/projects/1799";
-    /// let field_name = "EPL";
-    /// let expected = Some(json!(false));
-    ///
-    /// let result = client.get_custom_field(path, field_name);
     ///
-    /// assert_eq!(result.unwrap(), expected);
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .har_capture("support-case.har")
+    /// .build();
     /// ```
-    pub fn get_custom_field(&self, path: &str, field: &str) -> Result<Option<Value>> {
-        let query = &[("fields", "customFields")];
-        let res = &self.get_single(path, query)?;
-
-        let custom_fields = res
-            .get("customFields")
-            .ok_or(anyhow!("cannot get customFields"))?
-            .as_array()
-            .ok_or(anyhow!("cannot parse as array"))?;
-
-        let mut found_field: Option<Value> = None;
-        for f in custom_fields.iter() {
-            if &f["caption"].as_str().unwrap() == &field {
-                found_field = Some(f["value"].clone());
-            }
-        }
+    pub fn har_capture(self, path: impl Into<std::path::PathBuf>) -> Client {
+        self.har_capture_with_limits(path, DEFAULT_HAR_MAX_FILE_BYTES, DEFAULT_HAR_MAX_BODY_BYTES)
+    }
 
-        Ok(found_field)
+    /// [Client::har_capture] keeping the file under `max_file_bytes` and at most
+    /// `max_body_bytes` of each body
+    pub fn har_capture_with_limits(
+        mut self,
+        path: impl Into<std::path::PathBuf>,
+        max_file_bytes: usize,
+        max_body_bytes: usize,
+    ) -> Client {
+        self.har = Some(std::sync::Arc::new(har::HarCapture::new(
+            path.into(),
+            max_file_bytes,
+            max_body_bytes,
+        )));
+        self
     }
 
-    fn get_custom_field_id(&self, path: &str, field: &str) -> Result<i64> {
-        let query = &[("fields", "customFields")];
-        let res = &self.get_single(path, query)?;
-
-        let custom_fields = res
-            .get("customFields")
-            .ok_or(anyhow!("cannot get customFields"))?
-            .as_array()
-            .ok_or(anyhow!("cannot convert custom fires from to array"))?;
-
-        let mut id: i64 = 0;
-        for f in custom_fields.iter() {
-            if &f["caption"]
-                .as_str()
-                .ok_or(anyhow!("cannot convert caption to string"))?
-                == &field
-            {
-                id = f["id"]
-                    .as_i64()
-                    .ok_or(anyhow!("cannot convert id to i64"))?;
-            }
-        }
+    /// keeps every successful GET in the directory `path` for `ttl`, and answers the same GET
+    /// (the same path and query, in any order) from there until it expires.  The
+    /// `Authorization` header is never stored.  A cached response has a [CACHED_AT_HEADER]
+    /// header with the time it was cached
+    pub fn disk_cache(mut self, path: impl Into<std::path::PathBuf>, ttl: Duration) -> Client {
+        self.disk_cache = Some(std::sync::Arc::new(disk_cache::DiskCache::new(
+            path.into(),
+            ttl,
+        )));
+        self
+    }
 
-        match id {
-            0 => Err(anyhow!("couldn't get id")),
-            _any => Ok(id),
+    /// answers GETs only from the [Client::disk_cache], however old the entry is, and fails
+    /// anything else with [Error::Offline] without sending it.  For working from a snapshot
+    /// without a connection
+    pub fn offline(mut self, offline: bool) -> Client {
+        self.offline = offline;
+        self
+    }
+
+    /// true if the client was made read only with [Client::read_only]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        match self.read_only {
+            true => Err(Error::ReadOnly.into()),
+            false => Ok(()),
         }
     }
+    fn gen_basic_auth(&self) -> Result<String, Error> {
+        auth::basic_auth(&self.company_id, &self.public_key, &self.private_key)
+    }
+    fn gen_api_url(&self, path: &str) -> String {
+        format!(
+            "{}/{}/apis/{}{}",
+            self.gen_base_url(),
+            self.codebase,
+            self.api_version,
+            path
+        )
+    }
 
-    /// This will Patch a custom field, this abstracts out some of the operations.
+    /// the scheme and host requests go to, the api host of the region for a cloud instance
+    pub(crate) fn gen_base_url(&self) -> String {
+        let mut base =
+            if self.api_url.starts_with("http://") || self.api_url.starts_with("https://") {
+                self.api_url.to_owned()
+            } else {
+                format!("https://{}", self.api_url)
+            };
+        if self.cloud_api_prefix {
+            if let Ok(mut url) = Url::parse(&base) {
+                let api_host = url.host_str().and_then(region::api_host);
+                if api_host.is_some() && url.set_host(api_host).is_ok() {
+                    base = url.as_str().trim_end_matches('/').to_string();
+                }
+            }
+        }
+        base
+    }
+    /// GETs an href from an `_info` map (`"board_href": "https://.../service/boards/1"`).  The
+    /// href must point at this client's instance (same scheme, host and codebase) otherwise it
+    /// is refused with [Error::ForeignLink] and no request (with our credentials) is sent.
+    /// See [CwValueExt::info_href] for pulling the href out of a result
     ///
     /// # Arguments
     ///
-    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
-    /// - `field` - The field we want to update (also known as the "Caption")
-    /// - `value` - The value we want to update it to.  This is sent as a string and then
-    ///             parsed to the appropriate datatype (ie it is sent as json). Example
-    ///              "1234" for `1234`, "true" for `true`, etc
+    /// - `href` - the absolute url to follow
     ///
     /// # Example
-    /// ## updating a field
-    /// ```
-    /// use cwmanage::Client;
+    /// ```no_run
+    /// use cwmanage::{Client, CwValueExt};
     ///
     /// // this example is using dotenv to load our settings from
     /// // the environment, you could also specify this manually
@@ -390,37 +973,323 @@ impl Client {
     /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
     /// let client = Client::new(company_id, public_key, private_key, client_id).build();
     ///
-    /// let path = "/project/projects/1799";
-    /// let field_name = "EPL";
-    /// let field_value = "false";
-    /// let expected = ();
+    /// let ticket = client.get_single("/service/tickets/123", &[("", "")]).unwrap();
+    /// let board = client
+    ///     .follow_link(ticket["board"].info_href("board").unwrap())
+    ///     .unwrap();
+    /// ```
+    pub fn follow_link(&self, href: &str) -> Result<Value> {
+        let foreign = || Error::ForeignLink {
+            href: href.to_string(),
+        };
+        let link = Url::parse(href).map_err(|_| foreign())?;
+        let base = Url::parse(&self.gen_api_url(""))?;
+
+        // the hrefs may name the login host of the region rather than its api host
+        let link_host = match self.cloud_api_prefix {
+            true => link.host_str().map(|h| region::api_host(h).unwrap_or(h)),
+            false => link.host_str(),
+        };
+        if link.scheme() != base.scheme()
+            || link_host != base.host_str()
+            || link.port_or_known_default() != base.port_or_known_default()
+        {
+            return Err(foreign().into());
+        }
+        let path = link
+            .path()
+            .strip_prefix(base.path())
+            .filter(|p| p.starts_with('/'))
+            .ok_or_else(foreign)?;
+
+        let query: Vec<(String, String)> = link.query_pairs().into_owned().collect();
+        let query: Vec<(&str, &str)> = query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        self.get_value(path, &query)
+    }
+
+    /// The full url (including the encoded query) a request for `path` would be sent to.  This
+    /// is built the same way as the requests themselves so it is useful for logging and for
+    /// linking to the exact request.  Credentials are sent as headers and are never part of it.
+    /// For the pages of a list see [Client::list_url_for]
     ///
-    /// let result = client.patch_custom_field(path, field_name, field_value);
+    /// # Arguments
     ///
-    /// assert_eq!(result.unwrap(), expected);
+    /// - `path` - the api path (example `/service/tickets`)
+    /// - `query` - additional query options *must be set*.  If non, use [("", "")]
+    ///
+    /// # Example
     /// ```
-    pub fn patch_custom_field(&self, path: &str, field: &str, value: &str) -> Result<()> {
-        let field_id = &self.get_custom_field_id(path, field)?;
-        let value = json!([{ "id": field_id, "value": value}]);
-        match &self.patch(path, PatchOp::Replace, "customFields", value) {
-            Ok(_) => Ok(()),
-            Err(e) => Err(anyhow!("could not patch field: {:?}", e)),
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let url = client
+    ///     .url_for("/service/tickets", &[("conditions", "id = 1")])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     url.as_str(),
+    ///     "https://api-na.myconnectwise.net/v4_6_release/apis/3.0/service/tickets?conditions=id+%3D+1"
+    /// );
+    /// ```
+    pub fn url_for(&self, path: &str, query: &[(&str, &str)]) -> Result<Url> {
+        let req = self
+            .http_request(reqwest::Method::GET, path)?
+            .query(&query)
+            .build()?;
+        Ok(req.url().clone())
+    }
+
+    fn write_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        self.check_writable()?;
+        self.http_request(method, path)
+    }
+
+    /// sends a write and parses the response, a failure is [Error::Forbidden] or [Error::Api]
+    /// (or a plain error when the body isn't an [ApiError]) instead of a string of the body
+    fn write_checked(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<String>,
+    ) -> Result<Value> {
+        let req = self.write_request(method, path)?;
+        let req = match body {
+            Some(body) => req.body(body),
+            None => req,
+        };
+
+        self.call(req, |res| match res.status.is_success() {
+            true => Ok(serde_json::from_str(&res.body)?),
+            false => Err(api_error(&res, path)),
+        })
+    }
+
+    /// sends a request and handles the response with `handle`.  With request ids on (see
+    /// [Client::request_id_header]) every error gets the [RequestId] of the request
+    fn call<T>(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+        handle: impl FnOnce(RawResponse) -> Result<T>,
+    ) -> Result<T> {
+        self.call_in(None, None, req, handle)
+    }
+
+    /// [Client::call] as part of a larger operation (the pages of a `get`) with its own id,
+    /// which has to finish (retries included) by `deadline`
+    fn call_in<T>(
+        &self,
+        parent: Option<&str>,
+        deadline: Option<Instant>,
+        req: reqwest::blocking::RequestBuilder,
+        handle: impl FnOnce(RawResponse) -> Result<T>,
+    ) -> Result<T> {
+        let request_id = self
+            .request_id_header
+            .as_ref()
+            .map(|_| RequestId::new(parent));
+
+        let result = self
+            .send(req, request_id.as_ref(), deadline)
+            .and_then(handle);
+
+        match request_id {
+            Some(request_id) => result.map_err(|e| e.context(request_id)),
+            None => result,
         }
     }
 
-    /// GETs a path from the connectwise api.  `get` will return *all* results so make sure you
-    /// set your `query` with the appropriate conditions. This follows the api pagination so, again,
-    /// *all* results will be returned  For example `/service/tickets` will
-    /// return **every** ticket in the system.  The result is a vec of
-    /// [serde_json::value::Value](https://docs.serde.rs/serde_json/value/enum.Value.html)
+    fn send(
+        &self,
+        req: reqwest::blocking::RequestBuilder,
+        request_id: Option<&RequestId>,
+        deadline: Option<Instant>,
+    ) -> Result<RawResponse> {
+        let req = match (&self.request_id_header, request_id) {
+            (Some(header), Some(request_id)) => req.header(header.as_str(), &request_id.id),
+            _ => req,
+        };
+        let req = req.build()?;
+
+        let span = tracing::debug_span!(
+            "cwmanage request",
+            method = %req.method(),
+            path = req.url().path(),
+            request_id = tracing::field::Empty,
+            parent_id = tracing::field::Empty,
+            http.method = %req.method(),
+            url.path = req.url().path(),
+            http.status_code = tracing::field::Empty,
+        );
+        if let Some(request_id) = request_id {
+            span.record("request_id", request_id.id.as_str());
+            if let Some(parent) = &request_id.parent {
+                span.record("parent_id", parent.as_str());
+            }
+        }
+        #[cfg(feature = "otel")]
+        let req = otel::inject(&span, req);
+        let _entered = span.enter();
+
+        let http = self.http.get()?;
+
+        let method = req.method().to_string();
+        let path = req.url().path().to_string();
+        let result = self.send_cached(&http, req, deadline);
+        match &result {
+            Ok(res) => {
+                span.record("http.status_code", res.status.as_u16());
+                self.check_warnings(&method, &path, res);
+                tracing::debug!(status = res.status.as_u16(), "response")
+            }
+            Err(e) => tracing::debug!(error = %e, "request failed"),
+        }
+        result
+    }
+
+    /// waits until the next request is allowed by [Client::max_requests_per_second]
+    fn wait_for_turn(&self) {
+        let interval = match self.request_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let interval = self.tuned_interval(interval);
+        let now = Instant::now();
+        let turn = {
+            let mut next = self.shared.next_request.lock().unwrap();
+            let turn = next.map_or(now, |next| next.max(now));
+            let turn = self
+                .rate_limit_turn(now)
+                .map_or(turn, |reset| reset.max(turn));
+            *next = Some(turn + interval);
+            turn
+        };
+        if turn > now {
+            std::thread::sleep(turn - now);
+        }
+    }
+
+    /// answers a GET from the disk cache when it can, and caches what it gets
+    fn send_cached(
+        &self,
+        http: &reqwest::blocking::Client,
+        req: reqwest::blocking::Request,
+        deadline: Option<Instant>,
+    ) -> Result<RawResponse> {
+        let cache = match (&self.disk_cache, req.method()) {
+            (Some(cache), &reqwest::Method::GET) => Some(cache),
+            _ => None,
+        };
+        let key = cache.map(|_| disk_cache::DiskCache::key(&self.company_id, &req));
+        if let (Some(cache), Some(key)) = (cache, &key) {
+            if let Some(res) = cache.get(key, self.offline) {
+                return Ok(res);
+            }
+        }
+        if self.offline {
+            return Err(Error::Offline {
+                method: req.method().to_string(),
+                path: req.url().path().to_string(),
+            }
+            .into());
+        }
+
+        let res = self.send_retrying(http, req, deadline)?;
+        if let (Some(cache), Some(key)) = (cache, key) {
+            if let Err(e) = cache.put(&key, &res) {
+                tracing::warn!(error = %e, "couldn't write to the disk cache");
+            }
+        }
+        Ok(res)
+    }
+
+    fn send_once(
+        &self,
+        http: &reqwest::blocking::Client,
+        req: reqwest::blocking::Request,
+    ) -> Result<RawResponse> {
+        self.wait_for_turn();
+        #[cfg(feature = "record-replay")]
+        if let Some(cassette) = &self.cassette {
+            let mut res = cassette.execute(http, req)?;
+            res.redaction = self.redaction.clone();
+            self.record_rate_limit(&res.headers);
+            return check_response(res);
+        }
+
+        let mut res = match &self.har {
+            Some(har) => {
+                let captured = har::CapturedRequest::new(&req);
+                let started = Instant::now();
+                let res = execute(http, req);
+                let redaction = self.redaction.as_deref();
+                if let Err(e) = har.record(captured, &res, started.elapsed(), redaction) {
+                    tracing::warn!("har capture failed: {}", e);
+                }
+                res?
+            }
+            None => execute(http, req)?,
+        };
+        res.redaction = self.redaction.clone();
+        self.record_rate_limit(&res.headers);
+        check_response(res)
+    }
+
+    fn http_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        Ok(self
+            .numbered_request(method, path)?
+            .header("pagination-type", "forward-only"))
+    }
+
+    /// a request without the forward only pagination header, so a list is paged by number
+    /// (`page`) instead of by cursor, see [Client::get_page]
+    fn numbered_request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        let path = normalize_path(path)?;
+        Ok(self
+            .http
+            .get()?
+            .request(method, self.gen_api_url(&path))
+            .header("Authorization", self.gen_basic_auth()?)
+            .header("Content-Type", "application/json")
+            .header("clientid", self.client_id.to_owned()))
+    }
+
+    /// GETs a path from the connectwise api.  `get_single` is only used on certain api endpoints.
+    /// It is expecting the response from the connectwise api to be a single "object" and not a list
+    /// like it normally returns
     ///
     /// # Arguments
     ///
-    /// - `path` - the api path you want to retrieve (example `/service/tickets`)
+    /// - `path` - the api path you want to retrieve (example `/service/info`)
     /// - `query` - additional query options *must be set*.  If non, use [("", "")]
+    ///
+    /// # Known Endpoints
+    ///
+    /// - /system/info
+    ///
     /// # Example
     ///
-    /// ## Getting all results, returning parsed json
+    /// ## Basic get, returning parsed json
     /// ```
     /// use cwmanage::Client;
     ///
@@ -432,25 +1301,26 @@ impl Client {
     /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
     /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
     /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    ///
     /// let client = Client::new(company_id, public_key, private_key, client_id).build();
     ///
-    /// let query = [("fields", "id")];
-    /// let path = "/system/members";
-    /// let result = client.get(&path, &query).unwrap();
+    /// let query = [("", "")];
+    /// let path = "/system/info";
+    /// let result = client.get_single(&path, &query).unwrap();
     ///
-    /// assert!(result.len() > 30);
+    /// assert_eq!(&result["isCloud"], true);
     /// ```
-    /// ## Getting all results, take parsed json and convert to a struct
+    /// ## Basic get, take parsed json and convert to a struct
     /// ```
     /// use cwmanage::Client;
     /// use serde::{Deserialize};
-    /// use serde_json::Value::Array;
     ///
     /// #[derive(Debug, Deserialize)]
     /// #[serde(rename_all = "camelCase")]
-    /// struct Member {
-    ///   id: i32,
-    ///   identifier: String,
+    /// struct SystemInfo {
+    ///   version: String,
+    ///   is_cloud: bool,
+    ///   server_time_zone: String,
     /// }
     ///
     /// // this example is using dotenv to load our settings from
@@ -461,481 +1331,2940 @@ impl Client {
     /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
     /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
     /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    ///
     /// let client = Client::new(company_id, public_key, private_key, client_id).build();
     ///
     /// let query = [("", "")];
-    /// let path = "/system/members";
-    /// let result = client.get(&path, &query).unwrap();
+    /// let path = "/system/info";
+    /// let result = client.get_single(&path, &query).unwrap();
     ///
     /// // got our result, just like before.
     /// // now convert it into our struct
-    /// let members: Vec<Member>= serde_json::from_value(Array(result)).unwrap();
-    /// assert_eq!(members.len(), 134);
+    /// let info: SystemInfo = serde_json::from_value(result).unwrap();
+    /// assert_eq!(info.is_cloud, true);
+    /// assert_eq!(info.server_time_zone, "Eastern Standard Time");
     /// ```
-
-    // pub fn get_single(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
-    //     let res = reqwest::blocking::Client::new()
-    pub fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Vec<Value>> {
-        let mut collected_res: Vec<Value> = Vec::new();
-        let mut page: String = "1".to_string();
-        let mut next: bool = true;
-
-        while next {
-            let res = reqwest::blocking::Client::new()
-                .get(&self.gen_api_url(path))
-                .header("Authorization", self.gen_basic_auth())
-                .header("Content-Type", "application/json")
-                .header("clientid", self.client_id.to_owned())
-                .header("pagination-type", "forward-only")
-                .query(&[("pageid", &page)])
-                .query(&query)
-                .send()?;
-
-            let hdrs = res.headers();
-
-            next = match hdrs.get("link") {
-                Some(link) => {
-                    if link.is_empty() {
-                        false
-                    } else {
-                        match get_page_id(hdrs) {
-                            Some(p) => {
-                                page = p;
-                                true
-                            }
-                            None => false,
-                        }
-                    }
-                }
-                None => false,
-            };
-
-            let body = res.text()?;
-            let mut v: Vec<Value> = serde_json::from_str(&body)?;
-            collected_res.append(&mut v);
+    pub fn get_single(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        match self.get_value(path, query)? {
+            Value::Array(mut list) if self.unwrap_single_array && list.len() == 1 => {
+                Ok(list.remove(0))
+            }
+            Value::Array(_) => Err(Error::ExpectedObject {
+                path: path.to_string(),
+                hint: "this endpoint returns a list, use get",
+            }
+            .into()),
+            v => Ok(v),
         }
-
-        Ok(collected_res)
     }
 
-    /// POSTS a body to an api endpoint
-    /// The expected return is the object was created
-    /// If an error occurs (api level, not http level) it will return an error message
+    /// How many records a list endpoint has, from its `/count` sibling
+    /// (`/service/tickets/count` returns `{"count": 1234}`), without getting them
     ///
     /// # Arguments
     ///
-    /// - `path` - the api path you want to retrieve (example `/service/info`)
-    /// - `body` - the body of the post (see api docs for details). formated as json
+    /// - `path` - the api path of the list (example `/service/tickets`), `/count` is added
+    ///   unless it ends in it already
+    /// - `query` - the conditions to count by, `conditions`, `childConditions` and
+    ///   `customFieldConditions` (the others are ignored by connectwise)
     ///
     /// # Example
-    /// see main docs
+    /// ```no_run
+    /// use cwmanage::Client;
     ///
-    pub fn post(&self, path: &str, body: String) -> Result<Value> {
-        let res = reqwest::blocking::Client::new()
-            .post(&self.gen_api_url(path))
-            .header("Authorization", &self.gen_basic_auth())
-            .header("Content-Type", "application/json")
-            .header("clientid", self.client_id.to_owned())
-            .header("pagination-type", "forward-only")
-            .body(body)
-            .send()?
-            .text()?;
-
-        let v: Value = serde_json::from_str(&res)?;
-
-        match &v["errors"].as_array() {
-            Some(_e) => Err(anyhow!("we got some errors: {:?}", &v["errors"].as_array())),
-            None => {
-                // Sometimes 'errors' is null but there is a message
-                match &v["message"].as_str() {
-                    Some(_e) => Err(anyhow!("we got some errors: {:?}", &v["message"].as_str())),
-                    None => Ok(v),
-                }
-            }
-        }
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let open = client
+    ///     .get_count("/service/tickets", &[("conditions", "closedFlag = false")])
+    ///     .unwrap();
+    /// ```
+    pub fn get_count(&self, path: &str, query: &[(&str, &str)]) -> Result<u64> {
+        let path = path.trim_end_matches('/');
+        let path = match path.ends_with("/count") {
+            true => path.to_string(),
+            false => format!("{}/count", path),
+        };
+        let count = self.get_single(&path, query)?;
+        count["count"]
+            .as_u64()
+            .ok_or_else(|| anyhow!("{} can't be counted, it returned {}", path, count))
     }
 
-    /// Patch (aka updated) to provided `patch_path` (field) on the object specified by path
-    /// The expected return is the new version of the object that was modified
-    /// If an error occurs (api level, not http level) it will return an error message
+    /// GETs a path and parses whatever it returns
+    fn get_value(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        self.get_value_in(path, query, None)
+    }
+
+    /// GETs a path and returns the response as it came, whatever the status or the body (an
+    /// html maintenance page, a truncated body, an endpoint that doesn't return json).  Only a
+    /// redirect or a 429 (after the retries) is an error, as for every request
     ///
     /// # Arguments
     ///
-    /// - `path` - the api path you want to retrieve (example `/service/info`)
-    /// - `op` - one fo the allowed `PatchOp` values (Add | Replace | Remove)
-    /// - `path_path` - field you want to modify (example `summmary`, `member/id`)
-    /// - `value` - the value you want to update (example `New Name`)
+    /// - `path` - the api path you want to retrieve (example `/system/info`)
+    /// - `query` - additional query options, as [Client::get_single]
     ///
     /// # Example
-    /// see main docs
-    pub fn patch(
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let res = client.get_raw("/system/info", &[]).unwrap();
+    /// if !res.status.is_success() {
+    ///     eprintln!("{} {:?}: {}", res.status, res.headers.get("content-type"), res.body);
+    /// }
+    /// ```
+    pub fn get_raw(&self, path: &str, query: &[(&str, &str)]) -> Result<RawResponse> {
+        let req = self.get_request(path, query)?;
+
+        self.call(req, Ok)
+    }
+
+    /// the GET of `path` with `query`, what [Client::get_raw] and [Client::get_single] send
+    fn get_request(
         &self,
         path: &str,
-        op: PatchOp,
-        patch_path: &str,
-        value: serde_json::Value,
-    ) -> Result<Value> {
-        // create the body - please note the [] square brackets
-        let body = json!([{
-            "op": op.to_string(),
-            "path": patch_path,
-            "value": value,
-        }])
-        .to_string();
+        query: &[(&str, &str)],
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        Ok(self.http_request(reqwest::Method::GET, path)?.query(&query))
+    }
 
-        let res = reqwest::blocking::Client::new()
-            .patch(&self.gen_api_url(path))
-            .header("Authorization", &self.gen_basic_auth())
-            .header("Content-Type", "application/json")
-            .header("clientid", self.client_id.to_owned())
-            .header("pagination-type", "forward-only")
-            .body(body)
-            .send()?
-            .text()?;
+    /// [Client::get_value] that has to finish (retries included) by `deadline`
+    pub(crate) fn get_value_in(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        deadline: Option<Instant>,
+    ) -> Result<Value> {
+        let req = self.get_request(path, query)?;
 
-        let v: Value = serde_json::from_str(&res)?;
+        self.call_in(None, deadline, req, |res| {
+            if res.status == reqwest::StatusCode::NOT_FOUND {
+                return Err(Error::NotFound {
+                    path: path.to_string(),
+                }
+                .into());
+            }
+            if !res.status.is_success() {
+                return Err(api_error(&res, path));
+            }
 
-        match &v["message"].as_str() {
-            Some(_e) => Err(anyhow!("we got some errors: {:?}", &v)),
-            None => Ok(v),
-        }
+            let v: Value = serde_json::from_str(&res.body)?;
+            Ok(v)
+        })
     }
-}
-
-// *** Private Functions ***
-fn get_page_id(hdrs: &reqwest::header::HeaderMap) -> Option<String> {
-    let url = hdrs
-        .get("link")
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .split("link =")
-        .collect::<Vec<&str>>()[0]
-        .split('<')
-        .collect::<Vec<&str>>()[1]
-        .split('>')
-        .collect::<Vec<&str>>()[0];
-
-    let parsed_url = Url::parse(url).ok()?;
-    let hash_query: HashMap<_, _> = parsed_url.query_pairs().into_owned().collect();
 
-    match hash_query.contains_key("pageId") {
-        false => None,
-        true => Some(hash_query["pageId"].to_string()),
+    /// Checks if the record at `path` (`/service/tickets/123456`) exists, only its `id` is
+    /// asked for.  A 404 (or an empty response) is `false`, any other failure is an error, so a
+    /// missing record isn't mistaken for credentials that were refused ([Error::Api] with a
+    /// 401) or a path that isn't valid
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// if !client.exists("/service/tickets/123456").unwrap() {
+    ///     println!("no ticket 123456");
+    /// }
+    /// ```
+    pub fn exists(&self, path: &str) -> Result<bool> {
+        match self.get_value(path, &[("fields", "id")]) {
+            Ok(Value::Null) => Ok(false),
+            Ok(Value::Array(records)) => Ok(!records.is_empty()),
+            Ok(Value::Object(record)) => Ok(!record.is_empty()),
+            Ok(_) => Ok(true),
+            Err(e) => match e.downcast_ref::<Error>() {
+                Some(Error::NotFound { .. }) => Ok(false),
+                _ => Err(e),
+            },
+        }
     }
-}
-
-// *** Tests ***
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use dotenv::dotenv;
-    use pretty_assertions::assert_eq;
-    use serde_json::json;
 
-    fn testing_client() -> Client {
-        dotenv().ok();
-        let company_id: String =
-            dotenv::var("CWMANAGE_COMPANY_ID").expect("CWMANAGE_COMPANY_ID needs to be set");
-        let public_key: String =
-            dotenv::var("CWMANAGE_PUBLIC_KEY").expect("CWMANAGE_PUBLIC_KEY needs to be set");
-        let private_key: String =
-            dotenv::var("CWMANAGE_PRIVATE_KEY").expect("CWMANAGE_PRIVATE_KEY needs to be set");
-        let client_id: String =
-            dotenv::var("CWMANAGE_CLIENT_ID").expect("CWMANAGE_CLIENT_ID needs to be set");
-        Client::new(company_id, public_key, private_key, client_id).build()
-    }
+    /// This will get a single (standard) field from an object.  Only the requested field is
+    /// asked for (using the `fields` query) and then pulled out of the result.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The 'path" is the exact url to the object (`/service/tickets/123`, etc).
+    /// - `field_path` - The field we want, nested fields are separated with a `/` using the same
+    ///   syntax as the api (`summary`, `status/name`, `company/identifier`)
+    ///
+    /// Returns `None` when the field (or any object on the way to it) isn't set.  A `field_path`
+    /// with characters other than letters, numbers, `_` and `/` is an [Error::InvalidFieldPath]
+    /// and is rejected before anything is sent
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let result = client.get_single_field("/service/tickets/123", "status/name").unwrap();
+    ///
+    /// assert_eq!(result, Some(json!("Closed")));
+    /// ```
+    pub fn get_single_field(&self, path: &str, field_path: &str) -> Result<Option<Value>> {
+        let valid = !field_path.is_empty()
+            && field_path.split('/').all(|segment| {
+                !segment.is_empty()
+                    && segment
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !valid {
+            return Err(Error::InvalidFieldPath {
+                field_path: field_path.to_string(),
+            }
+            .into());
+        }
 
-    #[test]
-    fn test_basic_auth() {
-        let expected: String = "Basic bXljbytwdWI6cHJpdg==".to_string();
-        let client = Client::new(
-            String::from("myco"),
-            String::from("pub"),
-            String::from("priv"),
-            String::from("something"),
-        )
-        .build();
-        let result = client.gen_basic_auth();
-        assert_eq!(result, expected);
+        let res = self.get_single(path, &[("fields", field_path)])?;
+        Ok(walk_field_path(&res, field_path).cloned())
     }
 
-    #[test]
-    fn test_gen_url() {
-        let expected = "https://na.myconnectwise.net/v4_6_release/apis/3.0/system/info";
-        let client = Client::new(
-            String::from("myco"),
-            String::from("pub"),
-            String::from("priv"),
-            String::from("something"),
+    /// This will get a custom field Value, it helps with some of the juggleing of all of the
+    /// custom fields that get returned
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
+    /// - `field` - The field we want to update (also known as the "Caption")
+    ///
+    /// # Errors
+    ///
+    /// - [Error::NotFound] if the record doesn't exist
+    /// - [Error::CustomFieldsUnsupported] if the endpoint doesn't have custom fields
+    ///
+    /// # Example
+    /// ## getting a field
+    /// ```
+    /// use cwmanage::Client;
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let path = "/project/projects/1799";
+    /// let field_name = "EPL";
+    /// let expected = Some(json!(false));
+    ///
+    /// let result = client.get_custom_field(path, field_name);
+    ///
+    /// assert_eq!(result.unwrap(), expected);
+    /// ```
+    pub fn get_custom_field(&self, path: &str, field: &str) -> Result<Option<Value>> {
+        let custom_fields = &self.get_custom_fields(path)?;
+
+        let found_field = find_custom_field(custom_fields, field, &self.caption_match)?
+            .map(|f| f["value"].clone());
+
+        Ok(found_field)
+    }
+
+    /// This will get a custom field along with its id and type (see [CustomField]).  Use this
+    /// over [Client::get_custom_field] when you need to know what kind of value the field holds
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
+    /// - `field` - The field we want to get (also known as the "Caption")
+    ///
+    /// # Example
+    /// ## getting a field
+    /// ```no_run
+    /// use cwmanage::{Client, CustomFieldType};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let path = "/project/projects/1799";
+    /// let field_name = "EPL";
+    ///
+    /// let result = client.get_custom_field_full(path, field_name).unwrap().unwrap();
+    ///
+    /// assert_eq!(result.field_type, CustomFieldType::Checkbox);
+    /// ```
+    pub fn get_custom_field_full(&self, path: &str, field: &str) -> Result<Option<CustomField>> {
+        let custom_fields = &self.get_custom_fields(path)?;
+
+        match find_custom_field(custom_fields, field, &self.caption_match)? {
+            Some(f) => Ok(Some(serde_json::from_value(f.clone())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn get_custom_fields(&self, path: &str) -> Result<Vec<Value>> {
+        let query = &[("fields", "customFields")];
+        let res = self.get_single(path, query)?;
+
+        match res.get("customFields") {
+            // the endpoint doesn't have custom fields at all
+            None => Err(Error::CustomFieldsUnsupported {
+                path: path.to_string(),
+            }
+            .into()),
+            // the endpoint has custom fields but none are set
+            Some(Value::Null) => Ok(Vec::new()),
+            Some(custom_fields) => Ok(custom_fields
+                .as_array()
+                .ok_or(anyhow!("cannot parse as array"))?
+                .to_vec()),
+        }
+    }
+
+    fn get_custom_field_id(&self, path: &str, field: &str) -> Result<i64> {
+        let custom_fields = &self.get_custom_fields(path)?;
+
+        let id = match find_custom_field(custom_fields, field, &self.caption_match)? {
+            Some(f) => f["id"]
+                .as_i64()
+                .ok_or(anyhow!("cannot convert id to i64"))?,
+            None => 0,
+        };
+
+        match id {
+            0 => Err(anyhow!("couldn't get id")),
+            _any => Ok(id),
+        }
+    }
+
+    /// This will Patch a custom field, this abstracts out some of the operations.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
+    /// - `field` - The field we want to update (also known as the "Caption")
+    /// - `value` - The value we want to update it to.  This is given as a string and then
+    ///   converted to the field's datatype (see [CustomFieldType::coerce]) before it is sent.
+    ///   Example "1234" for `1234`, "true" for `true`, etc.  Values that can't be converted
+    ///   ("abc" for a number field) are an error and nothing is sent
+    ///
+    /// # Example
+    /// ## updating a field
+    /// ```
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let path = "/project/projects/1799";
+    /// let field_name = "EPL";
+    /// let field_value = "false";
+    /// let expected = ();
+    ///
+    /// let result = client.patch_custom_field(path, field_name, field_value);
+    ///
+    /// assert_eq!(result.unwrap(), expected);
+    /// ```
+    pub fn patch_custom_field(&self, path: &str, field: &str, value: &str) -> Result<()> {
+        self.check_writable()?;
+        let custom_field = self
+            .get_custom_field_full(path, field)?
+            .ok_or(anyhow!("couldn't get id"))?;
+        let value = custom_field.field_type.coerce(value)?;
+        self.replace_custom_field(path, custom_field.id, value)
+    }
+
+    /// Same as [Client::patch_custom_field] but for `Date` fields, the date is sent in the
+    /// format connectwise expects
+    #[cfg(feature = "chrono")]
+    pub fn patch_custom_field_date(
+        &self,
+        path: &str,
+        field: &str,
+        value: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        self.check_writable()?;
+        let custom_field = self
+            .get_custom_field_full(path, field)?
+            .ok_or(anyhow!("couldn't get id"))?;
+        if custom_field.field_type != CustomFieldType::Date {
+            return Err(anyhow!(
+                "field {:?} is a {:?} field, not a date",
+                field,
+                custom_field.field_type
+            ));
+        }
+        self.replace_custom_field(path, custom_field.id, date_value(&value))
+    }
+
+    fn replace_custom_field(&self, path: &str, field_id: i64, value: Value) -> Result<()> {
+        let value = json!([{ "id": field_id, "value": value}]);
+        match &self.patch(path, PatchOp::Replace, "customFields", value) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(anyhow!("could not patch field: {:?}", e)),
+        }
+    }
+
+    /// This will clear (blank) a custom field.  Passing an empty string to
+    /// [Client::patch_custom_field] writes an empty string, this sends a `null` value
+    /// for the field in the `customFields` replace array which is what connectwise
+    /// treats as "unset".
+    ///
+    /// Checkbox fields cannot be unset, connectwise stores a cleared checkbox as `false`
+    /// so reading it back with [Client::get_custom_field] returns `Some(false)`.
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - The 'path" is the exact url to the object (`/projects/project/123`, etc).
+    /// - `field` - The field we want to clear (also known as the "Caption")
+    ///
+    /// # Example
+    /// ## clearing a field
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let path = "/project/projects/1799";
+    /// let field_name = "EPL";
+    ///
+    /// client.clear_custom_field(path, field_name).unwrap();
+    /// ```
+    pub fn clear_custom_field(&self, path: &str, field: &str) -> Result<()> {
+        self.check_writable()?;
+        let field_id = self.get_custom_field_id(path, field)?;
+        self.replace_custom_field(path, field_id, Value::Null)
+    }
+
+    /// GETs a path from the connectwise api.  `get` will return *all* results so make sure you
+    /// set your `query` with the appropriate conditions. This follows the api pagination so, again,
+    /// *all* results will be returned  For example `/service/tickets` will
+    /// return **every** ticket in the system.  The result is a vec of
+    /// [serde_json::value::Value](https://docs.serde.rs/serde_json/value/enum.Value.html)
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path you want to retrieve (example `/service/tickets`)
+    /// - `query` - additional query options *must be set*.  If non, use [("", "")]
+    /// # Example
+    ///
+    /// ## Getting all results, returning parsed json
+    /// ```
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let query = [("fields", "id")];
+    /// let path = "/system/members";
+    /// let result = client.get(&path, &query).unwrap();
+    ///
+    /// assert!(result.len() > 30);
+    /// ```
+    /// ## Getting all results, take parsed json and convert to a struct
+    /// ```
+    /// use cwmanage::Client;
+    /// use serde::{Deserialize};
+    /// use serde_json::Value::Array;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// #[serde(rename_all = "camelCase")]
+    /// struct Member {
+    ///   id: i32,
+    ///   identifier: String,
+    /// }
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let query = [("", "")];
+    /// let path = "/system/members";
+    /// let result = client.get(&path, &query).unwrap();
+    ///
+    /// // got our result, just like before.
+    /// // now convert it into our struct
+    /// let members: Vec<Member>= serde_json::from_value(Array(result)).unwrap();
+    /// assert_eq!(members.len(), 134);
+    /// ```
+    pub fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Vec<Value>> {
+        let mut collected_res: Vec<Value> = Vec::new();
+
+        for page in self.pages(path, query) {
+            collected_res.append(&mut page?);
+        }
+
+        Ok(collected_res)
+    }
+
+    /// Same as [Client::get] but gives up once `limit` has passed, with
+    /// [Error::DeadlineExceeded].  The deadline is checked before each page and bounds the
+    /// timeout (and retries) of the page in flight, so it isn't overshot by a whole request.
+    /// The records collected so far are dropped, use [Client::pages] with [Pages::deadline]
+    /// to keep them
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Error};
+    /// use std::time::Duration;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let limit = Duration::from_secs(15 * 60);
+    /// match client.get_with_deadline("/service/tickets", &[("", "")], limit) {
+    ///     Ok(tickets) => println!("got {} tickets", tickets.len()),
+    ///     Err(e) => match e.downcast_ref::<Error>() {
+    ///         Some(Error::DeadlineExceeded { resume_cursor, .. }) => {
+    ///             println!("out of time, carry on from {:?}", resume_cursor)
+    ///         }
+    ///         _ => panic!("{}", e),
+    ///     },
+    /// }
+    /// ```
+    pub fn get_with_deadline(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        limit: Duration,
+    ) -> Result<Vec<Value>> {
+        let mut collected_res: Vec<Value> = Vec::new();
+
+        for page in self.pages(path, query).deadline(limit) {
+            collected_res.append(&mut page?);
+        }
+
+        Ok(collected_res)
+    }
+
+    /// gets one page (`page` is the `pageid` of the forward only pagination) and the
+    /// `pageid` of the next page if there is one
+    pub(crate) fn fetch_page(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        page: &str,
+        parent: Option<&str>,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<Value>, Option<String>)> {
+        let req = self
+            .http_request(reqwest::Method::GET, path)?
+            .query(&[("pageid", page)])
+            .query(&query);
+
+        self.call_in(parent, deadline, req, |res| parse_page(res, path, page))
+    }
+
+    /// POSTS a body to an api endpoint
+    /// The expected return is the object was created
+    /// If an error occurs (api level, not http level) it will return an error message
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path you want to retrieve (example `/service/info`)
+    /// - `body` - the body of the post (see api docs for details). formated as json
+    ///
+    /// # Example
+    /// see main docs
+    ///
+    pub fn post(&self, path: &str, body: String) -> Result<Value> {
+        let req = self.write_request(reqwest::Method::POST, path)?.body(body);
+
+        self.call(req, |res| post_response(res, path))
+    }
+
+    /// POSTS any `Serialize` body to an api endpoint and decodes the object that was created
+    /// into `T` (`Value` to keep it as json).  The response is checked as [Client::post] does.
+    /// A body that can't be serialized is [Error::Encode] and nothing is sent, a created
+    /// object that doesn't fit `T` is [Error::Decode]
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path you want to post to (example `/sales/activities`)
+    /// - `body` - the body of the post (see api docs for details)
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use serde_json::{json, Value};
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let body = json!({"name": "test from rust cwmanage", "assignTo": {"id": 149}});
+    /// let activity: Value = client.post_json("/sales/activities", &body).unwrap();
+    /// ```
+    pub fn post_json<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T> {
+        let body = serde_json::to_vec(body).map_err(|e| Error::Encode {
+            path: path.to_string(),
+            message: e.to_string(),
+        })?;
+        let req = self.write_request(reqwest::Method::POST, path)?.body(body);
+
+        let created = self.call(req, |res| post_response(res, path))?;
+        self.decode(path, created)
+    }
+
+    /// POSTS a body to an api endpoint, as [Client::post], and returns only the `id` of the
+    /// object that was created.  A rejected post is the error [Client::post] gives, a created
+    /// object without an integer `id` (some endpoints have composite keys) is [Error::Decode]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use serde_json::json;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let body = json!({"name": "test from rust cwmanage", "assignTo": {"id": 149}});
+    /// let id = client.create("/sales/activities", body.to_string()).unwrap();
+    /// ```
+    pub fn create(&self, path: &str, body: String) -> Result<i64> {
+        let created = self.post(path, body)?;
+        let invalid = |message: String| Error::Decode {
+            path: path.to_string(),
+            field: "id".to_string(),
+            id: None,
+            page: None,
+            message,
+        };
+        match &created["id"] {
+            Value::Null => Err(invalid("the created object has no id".to_string()).into()),
+            id => id
+                .as_i64()
+                .ok_or_else(|| invalid(format!("the id {} isn't an integer", id)).into()),
+        }
+    }
+
+    /// PUTS a body to an api endpoint, replacing the whole object (fields left out of `body`
+    /// are cleared, use [Client::patch] to change only some)
+    /// The expected return is the object as it was replaced, or `Value::Null` when the api
+    /// returns an empty body
+    /// If an error occurs (api level, not http level) it will return an error message, as
+    /// [Client::post] does
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the object you want to replace (example `/sales/activities/1`)
+    /// - `body` - the full object (see api docs for details). formated as json
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use serde_json::json;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let body = json!({"name": "replaced from rust cwmanage", "assignTo": {"id": 149}});
+    /// let activity = client.put("/sales/activities/1", body.to_string()).unwrap();
+    /// ```
+    pub fn put(&self, path: &str, body: String) -> Result<Value> {
+        let req = self.write_request(reqwest::Method::PUT, path)?.body(body);
+
+        self.call(req, |res| put_response(res, path))
+    }
+
+    /// DELETES the object at an api endpoint
+    /// Connectwise answers a delete with an empty body, so there is nothing to return
+    /// A record that doesn't exist is [Error::NotFound], a rejected delete (deleting a closed
+    /// ticket) is [Error::Api] with the `code` and `message` connectwise gave
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the object you want to delete (example `/sales/activities/1`)
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// client.delete("/sales/activities/1").unwrap();
+    /// ```
+    pub fn delete(&self, path: &str) -> Result<()> {
+        let req = self.write_request(reqwest::Method::DELETE, path)?;
+
+        self.call(req, |res| match res.status {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND => Err(Error::NotFound {
+                path: path.to_string(),
+            }
+            .into()),
+            _ => Err(api_error(&res, path)),
+        })
+    }
+
+    /// Patch (aka updated) to provided `patch_path` (field) on the object specified by path
+    /// The expected return is the new version of the object that was modified
+    /// If an error occurs (api level, not http level) it will return an error message
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path you want to retrieve (example `/service/info`)
+    /// - `op` - one fo the allowed `PatchOp` values (Add | Replace | Remove)
+    /// - `path_path` - field you want to modify (example `summmary`, `member/id`)
+    /// - `value` - the value you want to update (example `New Name`)
+    ///
+    /// # Example
+    /// see main docs
+    pub fn patch(
+        &self,
+        path: &str,
+        op: PatchOp,
+        patch_path: &str,
+        value: serde_json::Value,
+    ) -> Result<Value> {
+        self.patch_ops(path, &[PatchOperation::new(op, patch_path, value)])
+    }
+
+    /// [Client::patch] with the updated object decoded into `T`.  The response is checked as
+    /// [Client::patch] does first, so a rejected patch is still [Error::Api] (or the `message`
+    /// error).  An object that doesn't fit `T` is [Error::Decode], and then the patch *was*
+    /// made
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, PatchOp};
+    /// use serde::Deserialize;
+    /// use serde_json::json;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Activity {
+    ///     id: i64,
+    ///     name: String,
+    /// }
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let activity: Activity = client
+    ///     .patch_as("/sales/activities/1", PatchOp::Replace, "name", json!("renamed"))
+    ///     .unwrap();
+    /// ```
+    pub fn patch_as<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        op: PatchOp,
+        patch_path: &str,
+        value: Value,
+    ) -> Result<T> {
+        let updated = self.patch(path, op, patch_path, value)?;
+        self.decode(path, updated)
+    }
+
+    /// Patches the record at `path` only if it hasn't changed since it was read, so two
+    /// writers don't silently overwrite each other.  `expected_last_updated` is the
+    /// `_info/lastUpdated` of the record as the caller read it
+    ///
+    /// Connectwise ignores a `test` operation on `_info` fields, so the record's
+    /// `_info/lastUpdated` is read again just before the patch and [Error::Conflict] is
+    /// returned without patching if it isn't `expected_last_updated`.  A write by someone else
+    /// in between that read and the patch still isn't caught
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Error, PatchOp, PatchOperation};
+    /// use serde_json::json;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ticket = client.get_single("/service/tickets/1", &[]).unwrap();
+    /// let last_updated = ticket["_info"]["lastUpdated"].as_str().unwrap();
+    /// let ops = [PatchOperation::new(PatchOp::Replace, "summary", json!("new"))];
+    /// match client.patch_if_unchanged("/service/tickets/1", &ops, last_updated) {
+    ///     Err(e) if matches!(e.downcast_ref::<Error>(), Some(Error::Conflict { .. })) => {
+    ///         println!("someone else changed it, read it again")
+    ///     }
+    ///     result => println!("{:?}", result),
+    /// }
+    /// ```
+    pub fn patch_if_unchanged(
+        &self,
+        path: &str,
+        ops: &[PatchOperation],
+        expected_last_updated: &str,
+    ) -> Result<Value> {
+        self.check_writable()?;
+        let record = self.get_single(path, &[("fields", "_info")])?;
+        let current = record["_info"]["lastUpdated"]
+            .as_str()
+            .ok_or_else(|| anyhow!("{} has no _info/lastUpdated", path))?;
+        if current != expected_last_updated {
+            return Err(Error::Conflict {
+                current: current.to_string(),
+            }
+            .into());
+        }
+        self.patch_ops(path, ops)
+    }
+
+    /// Clears `field` (a patch path, example `contact` or `requiredDate`) of the record at
+    /// `path` and returns the updated record
+    ///
+    /// Some fields clear with a `replace` to `null` and others only with a `remove`, so this
+    /// tries in a fixed order: first a `replace` with `null`, then a `remove` when connectwise
+    /// refuses the replace (a 400 [Error::Api]) or accepts it but the field still has a value
+    /// in the returned record.  Other errors are returned as they are.  Use
+    /// [Client::set_field_null] to only ever send the `replace`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ticket = client.clear_field("/service/tickets/1", "contact").unwrap();
+    /// assert!(ticket.get("contact").is_none());
+    /// ```
+    pub fn clear_field(&self, path: &str, field: &str) -> Result<Value> {
+        let replaced = match self.set_field_null(path, field) {
+            Ok(record) if is_cleared(&record, field) => return Ok(record),
+            Ok(_) => "the field still has a value",
+            Err(e) => match e.downcast_ref::<Error>() {
+                Some(Error::Api { status: 400, .. }) => "connectwise refused the replace",
+                _ => return Err(e),
+            },
+        };
+        tracing::debug!(
+            path,
+            field,
+            replaced,
+            "clearing with remove instead of null"
+        );
+        let remove = PatchOperation::new(PatchOp::Remove, field, Value::Null);
+        self.patch_ops(path, &[remove])
+    }
+
+    /// Sets `field` of the record at `path` to `null` with a `replace` and returns the updated
+    /// record.  Not every field can be cleared this way, see [Client::clear_field]
+    pub fn set_field_null(&self, path: &str, field: &str) -> Result<Value> {
+        let replace = PatchOperation::new(PatchOp::Replace, field, Value::Null);
+        self.patch_ops(path, &[replace])
+    }
+
+    /// Points the reference `field` (`status`, `board`, `owner`) of the record at `path` at the
+    /// record `id` and returns the updated record.  It is sent the way connectwise takes a
+    /// reference, a `replace` of `{field}/id` with the id as a number (see
+    /// [CwRef::patch_path]), not a `{"id": ...}` object.  A field given as `status/id` is
+    /// the same as `status`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ticket = client.patch_ref("/service/tickets/1", "status", 16).unwrap();
+    /// assert_eq!(ticket["status"]["id"], 16);
+    /// ```
+    pub fn patch_ref(&self, path: &str, field: &str, id: i64) -> Result<Value> {
+        self.patch_refs(path, &[(field, id)])
+    }
+
+    /// [Client::patch_ref] for several references of the record at once, in one request
+    pub fn patch_refs(&self, path: &str, refs: &[(&str, i64)]) -> Result<Value> {
+        let ops: Vec<PatchOperation> = refs
+            .iter()
+            .map(|(field, id)| ref_operation(field, *id))
+            .collect();
+        self.patch_ops(path, &ops)
+    }
+
+    /// Patches several fields of the object at `path` in a single request, connectwise applies
+    /// the operations in order.  The expected return is the new version of the object, the
+    /// response is checked as [Client::patch] does.  No operations is an error and nothing is
+    /// sent
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path of the object (example `/service/tickets/1`)
+    /// - `ops` - the operations, see [PatchOperation]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, PatchOp, PatchOperation};
+    /// use serde_json::json;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let ops = [
+    ///     PatchOperation::new(PatchOp::Replace, "summary", json!("printer is on fire")),
+    ///     PatchOperation::new(PatchOp::Replace, "status/id", json!(16)),
+    /// ];
+    /// let ticket = client.patch_ops("/service/tickets/1", &ops).unwrap();
+    /// ```
+    pub fn patch_ops(&self, path: &str, ops: &[PatchOperation]) -> Result<Value> {
+        if ops.is_empty() {
+            return Err(anyhow!("no patch operations given"));
+        }
+        let body = serde_json::to_string(ops)?;
+
+        let req = self.write_request(reqwest::Method::PATCH, path)?.body(body);
+
+        self.call(req, |res| {
+            if !res.status.is_success() && ApiError::from_body(&res.body).is_some() {
+                return Err(api_error(&res, path));
+            }
+            let v: Value = serde_json::from_str(&res.body)?;
+
+            match &v["message"].as_str() {
+                Some(_e) => Err(anyhow!("we got some errors: {:?}", &v)),
+                None => Ok(v),
+            }
+        })
+    }
+}
+
+// *** Private Functions ***
+
+/// true when the patch path `field` is missing or `null` in `record`
+fn is_cleared(record: &Value, field: &str) -> bool {
+    let pointer = format!("/{}", field.trim_start_matches('/'));
+    record.pointer(&pointer).unwrap_or(&Value::Null).is_null()
+}
+
+/// the http client requests are sent with, made once for a client and its clones (see
+/// [shared::Http]).  A followed redirect turns a post into a get and
+/// drops the body (and reqwest drops the auth header going to another host), so redirects are
+/// returned as errors instead
+fn http_client() -> Result<reqwest::blocking::Client> {
+    Ok(reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?)
+}
+
+/// checks the `path` given to every request and adds the leading `/` if it is missing
+fn normalize_path(path: &str) -> Result<String> {
+    let invalid = |reason: &str| {
+        Err(Error::InvalidPath {
+            path: path.to_string(),
+            reason: reason.to_string(),
+        }
+        .into())
+    };
+
+    if path.is_empty() {
+        return invalid("the path is empty");
+    }
+    if path.contains("://") {
+        return invalid("this is a full url, use the api path or follow_link");
+    }
+    if path.contains('?') || path.contains('#') {
+        return invalid("the path contains a query, pass it as the query argument");
+    }
+    if path.contains('\\') {
+        return invalid("the path contains a backslash, use '/'");
+    }
+    if path.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return invalid("the path contains whitespace or control characters");
+    }
+
+    match path.starts_with('/') {
+        true => Ok(path.to_string()),
+        false => Ok(format!("/{}", path)),
+    }
+}
+
+/// A response before it is parsed, see [Client::get_raw]
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    /// the http status
+    pub status: reqwest::StatusCode,
+    /// the headers of the response
+    pub headers: reqwest::header::HeaderMap,
+    /// the body, as it was sent
+    pub body: String,
+    /// how the body is shown in an error, see [Client::redact_fields]
+    pub(crate) redaction: Option<std::sync::Arc<redact::Redaction>>,
+}
+
+fn execute(
+    http: &reqwest::blocking::Client,
+    req: reqwest::blocking::Request,
+) -> Result<RawResponse> {
+    let res = http.execute(req)?;
+    Ok(RawResponse {
+        status: res.status(),
+        headers: res.headers().clone(),
+        body: res.text()?,
+        redaction: None,
+    })
+}
+
+/// turns a redirect into [Error::Redirected] and a 429 into [Error::RateLimited]
+fn check_response(res: RawResponse) -> Result<RawResponse> {
+    if res.status.is_redirection() {
+        let location = res
+            .headers
+            .get(reqwest::header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        return Err(Error::Redirected {
+            status: res.status.as_u16(),
+            location: location.to_string(),
+        }
+        .into());
+    }
+    if res.status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Ok(res);
+    }
+    let retry_after = res
+        .headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(std::time::Duration::from_secs);
+    Err(Error::RateLimited { retry_after }.into())
+}
+
+/// the error for a response that failed, [Error::Forbidden] or [Error::Api] when the body is
+/// an [ApiError]
+fn api_error(res: &RawResponse, path: &str) -> anyhow::Error {
+    match (res.status, ApiError::from_body(&res.body)) {
+        (reqwest::StatusCode::FORBIDDEN, Some(error)) => Error::Forbidden {
+            path: path.to_string(),
+            error,
+        }
+        .into(),
+        (status, Some(error)) => Error::Api {
+            path: path.to_string(),
+            status: status.as_u16(),
+            error,
+        }
+        .into(),
+        (status, None) => anyhow!(
+            "{} from {}: {}",
+            status,
+            path,
+            redact::text(res.redaction.as_deref(), &res.body)
+        ),
+    }
+}
+
+/// checks and parses one page of a list
+fn parse_page(res: RawResponse, path: &str, page: &str) -> Result<(Vec<Value>, Option<String>)> {
+    let next = check_page(&res, path, page)?;
+    match serde_json::from_str(&res.body)? {
+        Value::Array(v) => Ok((v, next)),
+        _ => Err(expected_list(path)),
+    }
+}
+
+/// the error for a list endpoint that returned a single object
+pub(crate) fn expected_list(path: &str) -> anyhow::Error {
+    Error::ExpectedList {
+        path: path.to_string(),
+        hint: "this endpoint returns a single object, use get_single",
+    }
+    .into()
+}
+
+/// true when the error of `res` is about the `pageId` (an invalid or expired cursor), not the
+/// query or the record
+fn refuses_page_id(res: &RawResponse) -> bool {
+    let about_page_id = |text: &str| text.to_ascii_lowercase().contains("pageid");
+    match ApiError::from_body(&res.body) {
+        Some(error) => {
+            about_page_id(&error.code)
+                || about_page_id(&error.message)
+                || error
+                    .errors
+                    .iter()
+                    .any(|e| about_page_id(&e.field) || about_page_id(&e.message))
+        }
+        None => false,
+    }
+}
+
+/// checks one page of a list and returns the `pageid` of the next page
+pub(crate) fn check_page(res: &RawResponse, path: &str, page: &str) -> Result<Option<String>> {
+    let expired = [
+        reqwest::StatusCode::BAD_REQUEST,
+        reqwest::StatusCode::NOT_FOUND,
+        reqwest::StatusCode::GONE,
+    ];
+    if page != "1" && expired.contains(&res.status) && refuses_page_id(res) {
+        return Err(Error::CursorExpired {
+            path: path.to_string(),
+            page: page.to_string(),
+        }
+        .into());
+    }
+
+    let hdrs = &res.headers;
+
+    let next = match hdrs.get("link") {
+        Some(link) => {
+            if link.is_empty() {
+                None
+            } else {
+                get_page_id(hdrs)
+            }
+        }
+        None => None,
+    };
+
+    if !res.status.is_success() {
+        return Err(api_error(res, path));
+    }
+    Ok(next)
+}
+
+fn walk_field_path<'a>(value: &'a Value, field_path: &str) -> Option<&'a Value> {
+    let found = field_path
+        .split('/')
+        .try_fold(value, |current, segment| current.get(segment))?;
+    match found {
+        Value::Null => None,
+        _ => Some(found),
+    }
+}
+
+/// the record a POST created, or the error it failed with
+fn post_response(res: RawResponse, path: &str) -> Result<Value> {
+    if !res.status.is_success() && ApiError::from_body(&res.body).is_some() {
+        return Err(api_error(&res, path));
+    }
+    let v: Value = serde_json::from_str(&res.body)?;
+
+    match &v["errors"].as_array() {
+        Some(_e) => Err(anyhow!("we got some errors: {:?}", &v["errors"].as_array())),
+        None => {
+            // Sometimes 'errors' is null but there is a message
+            match &v["message"].as_str() {
+                Some(_e) => Err(anyhow!("we got some errors: {:?}", &v["message"].as_str())),
+                None => Ok(v),
+            }
+        }
+    }
+}
+
+/// the response of a put is the response of a post, except an empty body is `Value::Null` and
+/// a body that isn't json says so instead of failing to parse
+fn put_response(res: RawResponse, path: &str) -> Result<Value> {
+    if res.status.is_success() && res.body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    if serde_json::from_str::<Value>(&res.body).is_err() && ApiError::from_body(&res.body).is_none()
+    {
+        let body: String = res.body.chars().take(200).collect();
+        return Err(anyhow!(
+            "PUT {} returned {} with a body that isn't json: {:?}",
+            path,
+            res.status,
+            body
+        ));
+    }
+    post_response(res, path)
+}
+
+/// the operation that points the reference `field` at `id`, `status` and `status/id` are both
+/// `status/id`
+fn ref_operation(field: &str, id: i64) -> PatchOperation {
+    let field = field.trim().trim_matches('/');
+    let field = field.strip_suffix("/id").unwrap_or(field);
+    PatchOperation::new(PatchOp::Replace, &CwRef::patch_path(field), json!(id))
+}
+
+fn get_page_id(hdrs: &reqwest::header::HeaderMap) -> Option<String> {
+    next_link_param(hdrs, "pageId")
+}
+
+/// the `key` query parameter of the next page's url in the `link` header: the
+/// `rel="next"` link, or the first link when they have no `rel`.  `None` when there is no
+/// such link or the header can't be read
+fn next_link_param(hdrs: &reqwest::header::HeaderMap, key: &str) -> Option<String> {
+    let mut rest = hdrs.get("link")?.to_str().ok()?;
+    let mut first = None;
+    let mut next = None;
+    while let Some(start) = rest.find('<') {
+        let end = start + rest[start..].find('>')?;
+        let url = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        let params = &rest[..rest.find('<').unwrap_or(rest.len())];
+        if params.contains("rel=\"next\"") {
+            next = Some(url);
+            break;
+        }
+        if first.is_none() && !params.contains("rel=") {
+            first = Some(url);
+        }
+    }
+
+    let parsed_url = Url::parse(next.or(first)?).ok()?;
+    let hash_query: HashMap<_, _> = parsed_url.query_pairs().into_owned().collect();
+    hash_query.get(key).cloned()
+}
+
+// *** Tests ***
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use dotenv::dotenv;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn testing_client() -> Client {
+        dotenv().ok();
+        #[cfg(feature = "record-replay")]
+        if let Some(client) = cassette_client() {
+            return client;
+        }
+        let company_id: String =
+            dotenv::var("CWMANAGE_COMPANY_ID").expect("CWMANAGE_COMPANY_ID needs to be set");
+        let public_key: String =
+            dotenv::var("CWMANAGE_PUBLIC_KEY").expect("CWMANAGE_PUBLIC_KEY needs to be set");
+        let private_key: String =
+            dotenv::var("CWMANAGE_PRIVATE_KEY").expect("CWMANAGE_PRIVATE_KEY needs to be set");
+        let client_id: String =
+            dotenv::var("CWMANAGE_CLIENT_ID").expect("CWMANAGE_CLIENT_ID needs to be set");
+        Client::new(company_id, public_key, private_key, client_id).build()
+    }
+
+    /// With `CWMANAGE_CASSETTE` set to `record` or `replay` the test uses the cassette named
+    /// after it in `tests/cassettes`.  Replaying doesn't need credentials
+    #[cfg(feature = "record-replay")]
+    fn cassette_client() -> Option<Client> {
+        let mode = match dotenv::var("CWMANAGE_CASSETTE").ok()?.as_str() {
+            "record" => CassetteMode::Record,
+            "replay" => CassetteMode::Replay,
+            other => panic!("CWMANAGE_CASSETTE must be record or replay, not {}", other),
+        };
+        let name = std::thread::current().name()?.replace("::", "-");
+        let path = format!(
+            "{}/tests/cassettes/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let var = |name: &str| match mode {
+            CassetteMode::Record => {
+                dotenv::var(name).unwrap_or_else(|_| panic!("{} needs to be set", name))
+            }
+            CassetteMode::Replay => dotenv::var(name).unwrap_or_else(|_| "replay".to_string()),
+        };
+        let client = Client::new(
+            var("CWMANAGE_COMPANY_ID"),
+            var("CWMANAGE_PUBLIC_KEY"),
+            var("CWMANAGE_PRIVATE_KEY"),
+            var("CWMANAGE_CLIENT_ID"),
+        )
+        .cassette(path, mode)
+        .build();
+        Some(client)
+    }
+
+    #[test]
+    fn test_basic_auth() {
+        let expected: String = "Basic bXljbytwdWI6cHJpdg==".to_string();
+        let client = Client::new(
+            String::from("myco"),
+            String::from("pub"),
+            String::from("priv"),
+            String::from("something"),
+        )
+        .build();
+        let result = client.gen_basic_auth().unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_from_env_profile() {
+        let vars: std::collections::HashMap<&str, &str> = [
+            ("CWMANAGE_COMPANY_ID", "myco"),
+            ("CWMANAGE_PUBLIC_KEY", "public"),
+            ("CWMANAGE_PRIVATE_KEY", "private"),
+            ("CWMANAGE_CLIENT_ID", "clientid"),
+            ("CWMANAGE_STAGING_COMPANY_ID", "mycostaging"),
+            ("CWMANAGE_STAGING_PRIVATE_KEY", "stagingprivate"),
+            ("CWMANAGE_STAGING_API_URL", "staging.example.com"),
+            ("CWMANAGE_SAND_BOX_PUBLIC_KEY", "sandboxpublic"),
+        ]
+        .iter()
+        .copied()
+        .collect();
+        let lookup = |name: &str| vars.get(name).map(|v| v.to_string());
+
+        let default = Client::from_vars("", lookup).unwrap();
+        assert_eq!(default.company_id, "myco");
+        assert_eq!(default.api_url, "api-na.myconnectwise.net");
+
+        // the profile overrides, the rest falls back to the unprefixed variables
+        let staging = Client::from_vars("staging", lookup).unwrap();
+        assert_eq!(staging.company_id, "mycostaging");
+        assert_eq!(staging.public_key, "public");
+        assert_eq!(staging.private_key, "stagingprivate");
+        assert_eq!(staging.client_id, "clientid");
+        assert_eq!(staging.api_url, "staging.example.com");
+
+        let sandbox = Client::from_vars("sand-box", lookup).unwrap();
+        assert_eq!(sandbox.public_key, "sandboxpublic");
+        assert_eq!(sandbox.company_id, "myco");
+
+        // the error names the variable the profile reads
+        let lookup = |name: &str| match name {
+            "CWMANAGE_CLIENT_ID" => None,
+            name => vars.get(name).map(|v| v.to_string()),
+        };
+        assert_eq!(
+            Client::from_vars("staging", lookup)
+                .unwrap_err()
+                .to_string(),
+            "CWMANAGE_STAGING_CLIENT_ID isn't set, and neither is CWMANAGE_CLIENT_ID"
+        );
+        assert_eq!(
+            Client::from_vars("", lookup).unwrap_err().to_string(),
+            "CWMANAGE_CLIENT_ID isn't set"
+        );
+    }
+
+    #[test]
+    fn test_from_env_profile_process() {
+        // a profile no other test reads, so the unprefixed variables are left alone
+        let vars = [
+            ("CWMANAGE_ENVTEST_COMPANY_ID", "myco"),
+            ("CWMANAGE_ENVTEST_PUBLIC_KEY", "public"),
+            ("CWMANAGE_ENVTEST_PRIVATE_KEY", "private"),
+            ("CWMANAGE_ENVTEST_CLIENT_ID", "clientid"),
+        ];
+        for (name, value) in vars.iter() {
+            std::env::set_var(name, value);
+        }
+        let client = Client::from_env_profile("envtest");
+        for (name, _) in vars.iter() {
+            std::env::remove_var(name);
+        }
+
+        let client = client.unwrap();
+        assert_eq!(client.company_id, "myco");
+        assert_eq!(client.client_id, "clientid");
+    }
+
+    #[test]
+    fn test_gen_url() {
+        let expected = "https://api-na.myconnectwise.net/v4_6_release/apis/3.0/system/info";
+        let client = Client::new(
+            String::from("myco"),
+            String::from("pub"),
+            String::from("priv"),
+            String::from("something"),
+        )
+        .build();
+        let result = client.gen_api_url("/system/info");
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_basic_get_panic() {
+        let query = [];
+        let _result = testing_client()
+            .get_single("/this/is/a/bad/path", &query)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_basic_get_single() {
+        let query = [];
+
+        let result = testing_client().get_single("/system/info", &query).unwrap();
+        assert_eq!(&result["cloudRegion"], "NA");
+        assert_eq!(&result["isCloud"], true);
+        assert_eq!(&result["serverTimeZone"], "Eastern Standard Time");
+    }
+
+    #[test]
+    fn test_basic_get() {
+        let query = [];
+
+        let result = testing_client().get("/system/members", &query).unwrap();
+
+        assert!(result.len() > 40);
+
+        let zach = &result[0];
+        assert_eq!(&zach["adminFlag"], true);
+        assert_eq!(&zach["dailyCapacity"], 8.0);
+        assert_eq!(&zach["identifier"], "ZPeters");
+    }
+
+    #[test]
+    fn test_basic_post() {
+        let body = json!({
+            "name": "test from rust cwmanage",
+            "assignTo": {
+                "id": 149,
+            }
+        })
+        .to_string();
+
+        let result = testing_client().post("/sales/activities", body);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_project_post_error() {
+        let body = json!({}).to_string();
+
+        let result = testing_client().post("/project/projects/1/notes", body);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_basic_post_error() {
+        let body = json!({"name": "test from rust cwmanage"}).to_string();
+
+        let result = testing_client().post("/sales/activities", body);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_new_client_default() {
+        let input_company_id = "myco".to_string();
+        let input_public_key = "public".to_string();
+        let input_private_key = "private".to_string();
+        let input_client_id = "clientid".to_string();
+
+        let expected = Client {
+            company_id: "myco".to_string(),
+            public_key: "public".to_string(),
+            private_key: "private".to_string(),
+            client_id: "clientid".to_string(),
+            api_version: "3.0".to_string(),
+            api_url: "api-na.myconnectwise.net".to_string(),
+            cloud_api_prefix: true,
+            codebase: "v4_6_release".to_string(),
+            caption_match: CaptionMatch::Exact,
+            read_only: false,
+            resume_on_cursor_expiry: false,
+            stable_pagination: true,
+            max_url_length: DEFAULT_MAX_URL_LENGTH,
+            search_routes: Vec::new(),
+            unwrap_single_array: false,
+            max_retries: 0,
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            retry_non_idempotent: false,
+            request_id_header: None,
+            retry_if: None,
+            on_warning: None,
+            collect_unknown_fields: false,
+            deny_unknown_fields: false,
+            on_unknown_fields: None,
+            on_rate_limit_low: None,
+            disk_cache: None,
+            har: None,
+            redaction: None,
+            cancel: None,
+            offline: false,
+            request_interval: None,
+            http: Default::default(),
+            shared: Default::default(),
+            #[cfg(feature = "record-replay")]
+            cassette: None,
+        };
+
+        let result = Client::new(
+            input_company_id,
+            input_public_key,
+            input_private_key,
+            input_client_id,
+        )
+        .build();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_build_makes_one_http_client() {
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        );
+        assert!(client.http.0.get().is_none());
+
+        let built = client.build();
+        let read_only = built.clone().read_only(true);
+        assert!(built.http.0.get().is_some());
+        assert!(std::sync::Arc::ptr_eq(&built.http.0, &read_only.http.0));
+    }
+
+    #[test]
+    fn test_new_client_api_version() {
+        let input_company_id = "myco".to_string();
+        let input_public_key = "public".to_string();
+        let input_private_key = "private".to_string();
+        let input_client_id = "clientid".to_string();
+        let input_api_version = "version".to_string();
+
+        let expected_api_version = "version";
+
+        let result = Client::new(
+            input_company_id,
+            input_public_key,
+            input_private_key,
+            input_client_id,
+        )
+        .api_version(input_api_version)
+        .build();
+
+        assert_eq!(result.api_version, expected_api_version);
+    }
+
+    #[test]
+    fn test_new_client_codebase() {
+        let input_company_id = "myco".to_string();
+        let input_public_key = "public".to_string();
+        let input_private_key = "private".to_string();
+        let input_client_id = "clientid".to_string();
+        let input_codebase = "codebase".to_string();
+
+        let expected_codebase = "codebase";
+
+        let result = Client::new(
+            input_company_id,
+            input_public_key,
+            input_private_key,
+            input_client_id,
+        )
+        .codebase(input_codebase)
+        .build();
+
+        assert_eq!(result.codebase, expected_codebase);
+    }
+
+    #[test]
+    fn test_new_client_chained_options() {
+        let result = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .codebase("codebase".to_string())
+        .api_url("api".to_string())
+        .build();
+
+        assert_eq!(result.api_url, "api".to_string());
+        assert_eq!(result.codebase, "codebase".to_string());
+    }
+
+    #[test]
+    /// This activity/name already exists so an add should fail
+    fn test_basic_patch_add_should_fail() {
+        let op = PatchOp::Add;
+        let path = "name";
+        let value = json!("test_basic_patch_add");
+
+        let result = testing_client().patch("/sales/activities/99", op, path, value);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_basic_patch_replace() {
+        let op = PatchOp::Replace;
+        let path = "name";
+        let value = json!("test_basic_patch_replace");
+
+        let result = testing_client().patch("/sales/activities/100", op, path, value);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_basic_patch_error() {
+        let op = PatchOp::Add;
+        let path = "summary";
+        let value = json!("test_basic_patch_error_test");
+
+        let result = testing_client().patch("/sales/activities/123", op, path, value);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_get_custom_field_bad_field_name() {
+        let path = "/project/projects/4";
+        let field_name = "A Fake Field";
+        let expected = None;
+
+        let result = testing_client().get_custom_field(path, field_name);
+
+        assert_eq!(result.unwrap(), expected);
+    }
+    #[test]
+    fn test_get_custom_field_something_set() {
+        let path = "/project/projects/1799";
+        let field_name = "E-rate";
+        let expected = Some(json!(false));
+
+        let result = testing_client().get_custom_field(path, field_name);
+
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_get_custom_field_id() {
+        let path = "/project/projects/1799";
+        let field_name = "WaitReason";
+        let expected: i64 = 67;
+
+        let result = testing_client().get_custom_field_id(path, field_name);
+
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_get_custom_field_id_missing() {
+        let path = "/project/projects/1799";
+        let field_name = "A Fake Thing";
+
+        let result = testing_client().get_custom_field_id(path, field_name);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_get_custom_field_something_else_set() {
+        let path = "/project/projects/1799";
+        let field_name = "WaitReason";
+        let expected = Some(json!("Something Else"));
+
+        let result = testing_client().get_custom_field(path, field_name);
+
+        assert_eq!(result.unwrap(), expected);
+    }
+    #[test]
+    fn test_update_custom_field_string() {
+        let path = "/project/projects/1799";
+        let field_name = "WaitReason";
+        let field_value = "Something Else";
+        let expected = ();
+
+        let result = testing_client().patch_custom_field(path, field_name, field_value);
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_update_custom_field_bool() {
+        let path = "/project/projects/1799";
+        let field_name = "EPL";
+        let field_value = "false";
+        let expected = ();
+
+        let result = testing_client().patch_custom_field(path, field_name, field_value);
+        assert_eq!(result.unwrap(), expected);
+    }
+    #[test]
+    fn test_update_custom_field_doesnt_exist() {
+        let path = "/project/projects/1799";
+        let field_name = "A Fake Field";
+        let field_value = "false";
+
+        let result = testing_client().patch_custom_field(path, field_name, field_value);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_clear_custom_field_checkbox() {
+        let path = "/project/projects/1799";
+        let field_name = "EPL";
+        let expected = Some(json!(false));
+
+        let client = testing_client();
+        client.clear_custom_field(path, field_name).unwrap();
+
+        let result = client.get_custom_field(path, field_name);
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_clear_custom_field_doesnt_exist() {
+        let path = "/project/projects/1799";
+        let field_name = "A Fake Field";
+
+        let result = testing_client().clear_custom_field(path, field_name);
+        assert!(result.is_err());
+    }
+
+    /// mounts a mock for every method that fails `assert` if anything was requested
+    fn expect_no_requests(server: &mut mockito::Server) -> Vec<mockito::Mock> {
+        ["GET", "POST", "PUT", "PATCH", "DELETE"]
+            .iter()
+            .map(|method| {
+                server
+                    .mock(method, mockito::Matcher::Any)
+                    .expect(0)
+                    .create()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gen_url_with_scheme() {
+        let expected = "http://localhost:8080/v4_6_release/apis/3.0/system/info";
+        let client = Client::new(
+            String::from("myco"),
+            String::from("pub"),
+            String::from("priv"),
+            String::from("something"),
         )
+        .api_url("http://localhost:8080".to_string())
         .build();
         let result = client.gen_api_url("/system/info");
         assert_eq!(result, expected);
     }
 
     #[test]
-    #[should_panic]
-    fn test_basic_get_panic() {
-        let query = [];
-        let _result = testing_client()
-            .get_single("/this/is/a/bad/path", &query)
+    fn test_get_custom_field_not_found() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/projects/404")
+            .match_query(mockito::Matcher::Any)
+            .with_status(404)
+            .with_body(r#"{"code": "NotFound", "message": "Project ID 404 not found"}"#)
+            .create();
+
+        let result = mock
+            .client()
+            .get_custom_field("/project/projects/404", "EPL");
+
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::NotFound {
+                path: "/project/projects/404".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_custom_field_unsupported() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/members/1")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "customFields".to_string(),
+            ))
+            .with_body("{}")
+            .create();
+
+        let result = mock.client().get_custom_field("/system/members/1", "EPL");
+
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::CustomFieldsUnsupported {
+                path: "/system/members/1".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_get_custom_field_null() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/projects/1")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"customFields": null}"#)
+            .create();
+
+        let result = mock.client().get_custom_field("/project/projects/1", "EPL");
+
+        assert_eq!(result.unwrap(), None);
+    }
+
+    #[test]
+    fn test_patch_op_display() {
+        assert_eq!(PatchOp::Add.to_string(), "add");
+        assert_eq!(PatchOp::Replace.to_string(), "replace");
+        assert_eq!(PatchOp::Remove.to_string(), "remove");
+    }
+
+    #[test]
+    fn test_patch_op_from_str() {
+        assert_eq!("add".parse::<PatchOp>().unwrap(), PatchOp::Add);
+        assert_eq!("REPLACE".parse::<PatchOp>().unwrap(), PatchOp::Replace);
+        assert_eq!("Remove".parse::<PatchOp>().unwrap(), PatchOp::Remove);
+        assert_eq!("test".parse::<PatchOp>().unwrap(), PatchOp::Test);
+        assert!("update".parse::<PatchOp>().is_err());
+    }
+
+    #[test]
+    fn test_patch_body() {
+        let expected = r#"[{"op":"replace","path":"name","value":"test_basic_patch_replace"}]"#;
+
+        let op = PatchOperation::new(PatchOp::Replace, "name", json!("test_basic_patch_replace"));
+        let result = serde_json::to_string(&[op]).unwrap();
+
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_url_for() {
+        let expected = "https://api-na.myconnectwise.net/v4_6_release/apis/3.0/service/tickets?fields=id%2Csummary&conditions=summary+LIKE+%27%25foo%25%27";
+        let client = Client::new(
+            String::from("myco"),
+            String::from("pub"),
+            String::from("priv"),
+            String::from("something"),
+        )
+        .build();
+
+        let result = client
+            .url_for(
+                "/service/tickets",
+                &[
+                    ("fields", "id,summary"),
+                    ("conditions", "summary LIKE '%foo%'"),
+                ],
+            )
             .unwrap();
+
+        assert_eq!(result.as_str(), expected);
+        assert_eq!(result.username(), "");
+        assert_eq!(result.password(), None);
     }
 
     #[test]
-    fn test_basic_get_single() {
-        let query = [];
+    fn test_get_page_id() {
+        let link = |value: &[u8]| {
+            let mut hdrs = reqwest::header::HeaderMap::new();
+            hdrs.insert(
+                "link",
+                reqwest::header::HeaderValue::from_bytes(value).unwrap(),
+            );
+            hdrs
+        };
+        let url = "https://na.myconnectwise.net/v4_6_release/apis/3.0/service/tickets";
 
-        let result = testing_client().get_single("/system/info", &query).unwrap();
-        assert_eq!(&result["cloudRegion"], "NA");
-        assert_eq!(&result["isCloud"], true);
-        assert_eq!(&result["serverTimeZone"], "Eastern Standard Time");
+        assert_eq!(
+            get_page_id(&link(
+                format!("<{}?pageId=7>; rel=\"next\"", url).as_bytes()
+            )),
+            Some("7".to_string())
+        );
+        let numbered = format!(
+            "<{}?page=1>; rel=\"first\", <{}?page=3&fields=id,summary>; rel=\"next\"",
+            url, url
+        );
+        assert_eq!(
+            next_link_param(&link(numbered.as_bytes()), "page"),
+            Some("3".to_string())
+        );
+        // a last page only links back
+        let last = format!("<{}?page=1>; rel=\"first\"", url);
+        assert_eq!(next_link_param(&link(last.as_bytes()), "page"), None);
+
+        // a missing or malformed header is no next page, not a panic
+        assert_eq!(get_page_id(&reqwest::header::HeaderMap::new()), None);
+        assert_eq!(get_page_id(&link(b"")), None);
+        assert_eq!(get_page_id(&link(b"rel=\"next\"")), None);
+        assert_eq!(get_page_id(&link(b"<https://host/list?pageId=2")), None);
+        assert_eq!(get_page_id(&link(b"<not a url>; rel=\"next\"")), None);
+        assert_eq!(get_page_id(&link(b"<https://host/list?pageId=\xff>")), None);
     }
 
     #[test]
-    fn test_basic_get() {
-        let query = [];
+    fn test_follow_link() {
+        let mut mock = MockCw::new();
+        let m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/boards/1")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "id,name".to_string(),
+            ))
+            .with_body(r#"{"id": 1, "name": "Help Desk"}"#)
+            .create();
+        let href = format!(
+            "{}/v4_6_release/apis/3.0/service/boards/1?fields=id,name",
+            mock.url()
+        );
 
-        let result = testing_client().get("/system/members", &query).unwrap();
+        let result = mock.client().follow_link(&href).unwrap();
 
-        assert!(result.len() > 40);
+        m.assert();
+        assert_eq!(result, json!({"id": 1, "name": "Help Desk"}));
+    }
 
-        let zach = &result[0];
-        assert_eq!(&zach["adminFlag"], true);
-        assert_eq!(&zach["dailyCapacity"], 8.0);
-        assert_eq!(&zach["identifier"], "ZPeters");
+    #[test]
+    fn test_follow_link_foreign() {
+        let mut mock = MockCw::new();
+        let mocks = expect_no_requests(mock.server());
+        let client = mock.client();
+        let hrefs = [
+            "https://na.myconnectwise.net/v4_6_release/apis/3.0/service/boards/1".to_string(),
+            format!("{}/v2022_1/apis/3.0/service/boards/1", mock.url()),
+            format!("{}/v4_6_release/apis/3.0", mock.url()),
+            "/v4_6_release/apis/3.0/service/boards/1".to_string(),
+        ];
+
+        for href in hrefs.iter() {
+            let result = client.follow_link(href);
+            assert_eq!(
+                result.unwrap_err().downcast_ref::<Error>(),
+                Some(&Error::ForeignLink { href: href.clone() })
+            );
+        }
+        mocks.iter().for_each(|m| m.assert());
     }
 
     #[test]
-    fn test_basic_post() {
-        let body = json!({
-            "name": "test from rust cwmanage",
-            "assignTo": {
-                "id": 149,
-            }
-        })
-        .to_string();
+    fn test_walk_field_path() {
+        let ticket = json!({
+            "id": 123,
+            "status": {"id": 16, "name": "Closed"},
+            "contact": null
+        });
+
+        assert_eq!(walk_field_path(&ticket, "id"), Some(&json!(123)));
+        assert_eq!(
+            walk_field_path(&ticket, "status/name"),
+            Some(&json!("Closed"))
+        );
+        assert_eq!(walk_field_path(&ticket, "status/_info/name"), None);
+        assert_eq!(walk_field_path(&ticket, "contact"), None);
+        assert_eq!(walk_field_path(&ticket, "contact/name"), None);
+        assert_eq!(walk_field_path(&ticket, "company/identifier"), None);
+    }
+
+    #[test]
+    fn test_get_single_field() {
+        let mut mock = MockCw::new();
+        let m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "status/name".to_string(),
+            ))
+            .with_body(r#"{"status": {"name": "Closed"}}"#)
+            .create();
+
+        let result = mock
+            .client()
+            .get_single_field("/service/tickets/123", "status/name")
+            .unwrap();
+
+        m.assert();
+        assert_eq!(result, Some(json!("Closed")));
+    }
+
+    #[test]
+    fn test_get_single_field_invalid() {
+        let mut mock = MockCw::new();
+        let mocks = expect_no_requests(mock.server());
+        let client = mock.client();
+
+        for field_path in [
+            "",
+            "status/",
+            "/name",
+            "status//name",
+            "summary,id",
+            "na me",
+        ] {
+            let result = client.get_single_field("/service/tickets/123", field_path);
+            assert_eq!(
+                result.unwrap_err().downcast_ref::<Error>(),
+                Some(&Error::InvalidFieldPath {
+                    field_path: field_path.to_string()
+                })
+            );
+        }
+        mocks.iter().for_each(|m| m.assert());
+    }
+
+    #[test]
+    fn test_read_only() {
+        let mut mock = MockCw::new();
+        let mocks = expect_no_requests(mock.server());
+        let client = mock.client().read_only(true).build();
+
+        assert!(client.clone().is_read_only());
 
-        let result = testing_client().post("/sales/activities", body);
-        assert!(!result.is_err());
+        let results = [
+            client.post("/sales/activities", json!({}).to_string()),
+            client.patch(
+                "/sales/activities/100",
+                PatchOp::Replace,
+                "name",
+                json!("a"),
+            ),
+        ];
+        for result in results {
+            assert_eq!(
+                result.unwrap_err().downcast_ref::<Error>(),
+                Some(&Error::ReadOnly)
+            );
+        }
+        let result = client.patch_custom_field("/project/projects/1799", "EPL", "false");
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::ReadOnly)
+        );
+        let result = client.clear_custom_field("/project/projects/1799", "EPL");
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::ReadOnly)
+        );
+
+        mocks.iter().for_each(|m| m.assert());
     }
 
     #[test]
-    fn test_project_post_error() {
-        let body = json!({}).to_string();
+    fn test_read_only_allows_get() {
+        let mut mock = MockCw::new();
+        let m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"isCloud": true}"#)
+            .create();
 
-        let result = testing_client().post("/project/projects/1/notes", body);
-        assert!(result.is_err());
+        let result = mock
+            .client()
+            .read_only(true)
+            .build()
+            .get_single("/system/info", &[]);
+
+        m.assert();
+        assert_eq!(result.unwrap(), json!({"isCloud": true}));
     }
 
     #[test]
-    fn test_basic_post_error() {
-        let body = json!({"name": "test from rust cwmanage"}).to_string();
+    fn test_patch_if_unchanged() {
+        let mut mock = MockCw::new();
+        // another service updated the ticket after we read it at 10:00
+        let _info = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/1")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "_info".to_string(),
+            ))
+            .with_body(r#"{"_info": {"lastUpdated": "2023-03-01T10:05:00Z"}}"#)
+            .create();
+        let patched = mock
+            .server()
+            .mock("PATCH", "/v4_6_release/apis/3.0/service/tickets/1")
+            .with_body(r#"{"id": 1, "summary": "new"}"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+        let ops = [PatchOperation::new(
+            PatchOp::Replace,
+            "summary",
+            json!("new"),
+        )];
 
-        let result = testing_client().post("/sales/activities", body);
-        assert!(result.is_err());
+        let stale = client.patch_if_unchanged("/service/tickets/1", &ops, "2023-03-01T10:00:00Z");
+        let fresh = client.patch_if_unchanged("/service/tickets/1", &ops, "2023-03-01T10:05:00Z");
+
+        assert_eq!(
+            stale.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::Conflict {
+                current: "2023-03-01T10:05:00Z".to_string()
+            })
+        );
+        assert_eq!(fresh.unwrap()["summary"], "new");
+        patched.assert();
     }
 
     #[test]
-    fn test_new_client_default() {
-        let input_company_id = "myco".to_string();
-        let input_public_key = "public".to_string();
-        let input_private_key = "private".to_string();
-        let input_client_id = "clientid".to_string();
+    fn test_get_count() {
+        let mut server = mockito::Server::new();
+        let open = server
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/count")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "closedFlag = false".to_string(),
+            ))
+            .with_body(r#"{"count": 1234}"#)
+            .expect(2)
+            .create();
+        let uncountable = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info/count")
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let expected = Client {
-            company_id: "myco".to_string(),
-            public_key: "public".to_string(),
-            private_key: "private".to_string(),
-            client_id: "clientid".to_string(),
-            api_version: "3.0".to_string(),
-            api_url: "na.myconnectwise.net".to_string(),
-            codebase: "v4_6_release".to_string(),
-        };
+        let query = [("conditions", "closedFlag = false")];
+        assert_eq!(client.get_count("/service/tickets", &query).unwrap(), 1234);
+        assert_eq!(
+            client.get_count("/service/tickets/count/", &query).unwrap(),
+            1234
+        );
+        let err = client.get_count("/system/info", &[]).unwrap_err();
+        assert!(err.to_string().contains("can't be counted"), "{}", err);
 
-        let result = Client::new(
-            input_company_id,
-            input_public_key,
-            input_private_key,
-            input_client_id,
+        open.assert();
+        uncountable.assert();
+    }
+
+    #[test]
+    fn test_post_json() {
+        #[derive(Debug, Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct NewActivity {
+            name: String,
+            assign_to: Reference,
+        }
+        #[derive(Debug, Serialize)]
+        struct Reference {
+            id: i64,
+        }
+        #[derive(Debug, Deserialize)]
+        struct Activity {
+            id: i64,
+            name: String,
+        }
+
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/sales/activities";
+        let created = server
+            .mock("POST", path)
+            .match_header("content-type", "application/json")
+            // the client's own header, not a second one
+            .match_request(|req| req.header("content-type").len() == 1)
+            .match_body(r#"{"name":"test from rust cwmanage","assignTo":{"id":149}}"#)
+            .with_body(r#"{"id": 12, "name": "test from rust cwmanage"}"#)
+            .create();
+        let rejected = server
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(400)
+            .with_body(
+                crate::test_util::fixtures::validation_error(
+                    "ticket",
+                    "summary",
+                    "Summary is required",
+                )
+                .to_string(),
+            )
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
         )
+        .api_url(server.url())
         .build();
 
-        assert_eq!(result, expected);
+        let body = NewActivity {
+            name: "test from rust cwmanage".to_string(),
+            assign_to: Reference { id: 149 },
+        };
+        let activity: Activity = client.post_json("/sales/activities", &body).unwrap();
+        assert_eq!(activity.id, 12);
+        assert_eq!(activity.name, "test from rust cwmanage");
+
+        let err = client
+            .post_json::<_, Value>("/service/tickets", &json!({}))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 400, .. })
+        ));
+
+        // a map with keys that aren't strings can't be json, and isn't sent
+        let unencodable: HashMap<(i64, i64), i64> = HashMap::from([((1, 2), 3)]);
+        let err = client
+            .post_json::<_, Value>("/sales/activities", &unencodable)
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref(),
+            Some(&Error::Encode {
+                path: "/sales/activities".to_string(),
+                message: "key must be a string".to_string(),
+            })
+        );
+
+        created.assert();
+        rejected.assert();
     }
 
     #[test]
-    fn test_new_client_api_version() {
-        let input_company_id = "myco".to_string();
-        let input_public_key = "public".to_string();
-        let input_private_key = "private".to_string();
-        let input_client_id = "clientid".to_string();
-        let input_api_version = "version".to_string();
-
-        let expected_api_version = "version";
+    fn test_patch_as() {
+        #[derive(Debug, Deserialize)]
+        struct Activity {
+            id: i64,
+            name: String,
+        }
 
-        let result = Client::new(
-            input_company_id,
-            input_public_key,
-            input_private_key,
-            input_client_id,
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/sales/activities";
+        let renamed = server
+            .mock("PATCH", format!("{}/1", path).as_str())
+            .match_body(r#"[{"op":"replace","path":"name","value":"renamed"}]"#)
+            .with_body(r#"{"id": 1, "name": "renamed"}"#)
+            .create();
+        let unnamed = server
+            .mock("PATCH", format!("{}/2", path).as_str())
+            .with_body(r#"{"id": 2, "name": null}"#)
+            .create();
+        let rejected = server
+            .mock("PATCH", format!("{}/3", path).as_str())
+            .with_status(400)
+            .with_body(
+                crate::test_util::fixtures::validation_error(
+                    "activity",
+                    "name",
+                    "Name is required",
+                )
+                .to_string(),
+            )
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
         )
-        .api_version(input_api_version)
+        .api_url(server.url())
         .build();
 
-        assert_eq!(result.api_version, expected_api_version);
+        let activity: Activity = client
+            .patch_as(
+                "/sales/activities/1",
+                PatchOp::Replace,
+                "name",
+                json!("renamed"),
+            )
+            .unwrap();
+        assert_eq!(activity.id, 1);
+        assert_eq!(activity.name, "renamed");
+
+        // patched, but the object doesn't fit
+        let err = client
+            .patch_as::<Activity>("/sales/activities/2", PatchOp::Replace, "name", json!(""))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Decode { id: Some(2), .. })
+        ));
+        let err = client
+            .patch_as::<Activity>("/sales/activities/3", PatchOp::Replace, "name", json!(""))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 400, .. })
+        ));
+
+        renamed.assert();
+        unnamed.assert();
+        rejected.assert();
     }
 
     #[test]
-    fn test_new_client_codebase() {
-        let input_company_id = "myco".to_string();
-        let input_public_key = "public".to_string();
-        let input_private_key = "private".to_string();
-        let input_client_id = "clientid".to_string();
-        let input_codebase = "codebase".to_string();
+    fn test_create() {
+        let mut server = mockito::Server::new();
+        let created = server
+            .mock("POST", "/v4_6_release/apis/3.0/sales/activities")
+            .with_body(r#"{"id": 12, "name": "test from rust cwmanage"}"#)
+            .create();
+        let rejected = server
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(400)
+            .with_body(
+                crate::test_util::fixtures::validation_error(
+                    "ticket",
+                    "summary",
+                    "Summary is required",
+                )
+                .to_string(),
+            )
+            .create();
+        let composite = server
+            .mock("POST", "/v4_6_release/apis/3.0/company/companies/1/teams")
+            .with_body(r#"{"id": "1-7", "teamRole": {"id": 2}}"#)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let expected_codebase = "codebase";
+        let body = json!({"name": "test from rust cwmanage"}).to_string();
+        assert_eq!(client.create("/sales/activities", body).unwrap(), 12);
+        let err = client
+            .create("/service/tickets", "{}".to_string())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 400, .. })
+        ));
+        let err = client
+            .create("/company/companies/1/teams", "{}".to_string())
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't decode id of /company/companies/1/teams: the id \"1-7\" isn't an integer"
+        );
 
-        let result = Client::new(
-            input_company_id,
-            input_public_key,
-            input_private_key,
-            input_client_id,
+        created.assert();
+        rejected.assert();
+        composite.assert();
+    }
+
+    #[test]
+    fn test_get_raw() {
+        let mut server = mockito::Server::new();
+        let maintenance = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "fields".to_string(),
+                "version".to_string(),
+            ))
+            .with_status(503)
+            .with_header("content-type", "text/html")
+            .with_body("<html>down for maintenance</html>")
+            .expect(2)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
         )
-        .codebase(input_codebase)
+        .api_url(server.url())
         .build();
 
-        assert_eq!(result.codebase, expected_codebase);
+        let res = client
+            .get_raw("/system/info", &[("fields", "version")])
+            .unwrap();
+        assert_eq!(res.status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(res.headers["content-type"], "text/html");
+        assert_eq!(res.body, "<html>down for maintenance</html>");
+        assert!(client
+            .get_single("/system/info", &[("fields", "version")])
+            .is_err());
+
+        maintenance.assert();
     }
 
     #[test]
-    fn test_new_client_chained_options() {
-        let result = Client::new(
+    fn test_exists() {
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let fields = || mockito::Matcher::UrlEncoded("fields".to_string(), "id".to_string());
+        let found = server
+            .mock("GET", format!("{}/1", path).as_str())
+            .match_query(fields())
+            .with_body(r#"{"id": 1}"#)
+            .create();
+        let missing = server
+            .mock("GET", format!("{}/123456", path).as_str())
+            .match_query(fields())
+            .with_status(404)
+            .with_body(crate::test_util::fixtures::not_found("Ticket", 123456).to_string())
+            .create();
+        let refused = server
+            .mock("GET", format!("{}/2", path).as_str())
+            .match_query(fields())
+            .with_status(401)
+            .with_body(
+                crate::test_util::fixtures::api_error("Unauthorized", "Invalid credentials")
+                    .to_string(),
+            )
+            .create();
+        let client = Client::new(
             "myco".to_string(),
             "public".to_string(),
             "private".to_string(),
             "clientid".to_string(),
         )
-        .codebase("codebase".to_string())
-        .api_url("api".to_string())
+        .api_url(server.url())
         .build();
 
-        assert_eq!(result.api_url, "api".to_string());
-        assert_eq!(result.codebase, "codebase".to_string());
+        assert!(client.exists("/service/tickets/1").unwrap());
+        assert!(!client.exists("/service/tickets/123456").unwrap());
+        let err = client.exists("/service/tickets/2").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 401, .. })
+        ));
+        let err = client.exists("/service/tickets/1?fields=id").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::InvalidPath { .. })
+        ));
+
+        found.assert();
+        missing.assert();
+        refused.assert();
     }
 
     #[test]
-    /// This activity/name already exists so an add should fail
-    fn test_basic_patch_add_should_fail() {
-        let op = PatchOp::Add;
-        let path = "name";
-        let value = json!("test_basic_patch_add");
+    fn test_put() {
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/sales/activities";
+        let replaced = server
+            .mock("PUT", format!("{}/1", path).as_str())
+            .match_body(r#"{"assignTo":{"id":149},"name":"replaced"}"#)
+            .with_body(r#"{"id": 1, "name": "replaced", "assignTo": {"id": 149}}"#)
+            .create();
+        let empty = server
+            .mock("PUT", format!("{}/2", path).as_str())
+            .with_status(204)
+            .create();
+        let invalid = server
+            .mock("PUT", format!("{}/3", path).as_str())
+            .with_status(400)
+            .with_body(
+                crate::test_util::fixtures::validation_error(
+                    "activity",
+                    "name",
+                    "Name is required",
+                )
+                .to_string(),
+            )
+            .create();
+        let html = server
+            .mock("PUT", format!("{}/4", path).as_str())
+            .with_status(502)
+            .with_body("<html>bad gateway</html>")
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let result = testing_client().patch("/sales/activities/99", op, path, value);
-        assert!(result.is_err());
+        let body = json!({"name": "replaced", "assignTo": {"id": 149}}).to_string();
+        let activity = client.put("/sales/activities/1", body).unwrap();
+        assert_eq!(activity["name"], "replaced");
+        assert_eq!(
+            client.put("/sales/activities/2", "{}".to_string()).unwrap(),
+            Value::Null
+        );
+        let err = client
+            .put("/sales/activities/3", "{}".to_string())
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 400, .. })
+        ));
+        let err = client
+            .put("/sales/activities/4", "{}".to_string())
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't json"), "{}", err);
+
+        replaced.assert();
+        empty.assert();
+        invalid.assert();
+        html.assert();
     }
 
     #[test]
-    fn test_basic_patch_replace() {
-        let op = PatchOp::Replace;
-        let path = "name";
-        let value = json!("test_basic_patch_replace");
+    fn test_delete() {
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/sales/activities";
+        let created = server
+            .mock("POST", path)
+            .with_body(r#"{"id": 12, "name": "test from rust cwmanage"}"#)
+            .create();
+        let deleted = server
+            .mock("DELETE", format!("{}/12", path).as_str())
+            .with_status(204)
+            .create();
+        let missing = server
+            .mock("DELETE", format!("{}/13", path).as_str())
+            .with_status(404)
+            .with_body(crate::test_util::fixtures::not_found("Activity", 13).to_string())
+            .create();
+        let closed = server
+            .mock("DELETE", "/v4_6_release/apis/3.0/service/tickets/5")
+            .with_status(400)
+            .with_body(
+                crate::test_util::fixtures::api_error(
+                    "InvalidObject",
+                    "Closed tickets cannot be deleted",
+                )
+                .to_string(),
+            )
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let result = testing_client().patch("/sales/activities/100", op, path, value);
-        assert!(!result.is_err());
+        let body = json!({"name": "test from rust cwmanage", "assignTo": {"id": 149}});
+        let activity = client.post("/sales/activities", body.to_string()).unwrap();
+        let id = activity["id"].as_i64().unwrap();
+        client.delete(&format!("/sales/activities/{}", id)).unwrap();
+        assert!(matches!(
+            client
+                .delete("/sales/activities/13")
+                .unwrap_err()
+                .downcast_ref(),
+            Some(Error::NotFound { .. })
+        ));
+        match client
+            .delete("/service/tickets/5")
+            .unwrap_err()
+            .downcast_ref()
+        {
+            Some(Error::Api {
+                status: 400, error, ..
+            }) => {
+                assert_eq!(error.code, "InvalidObject");
+                assert_eq!(error.message, "Closed tickets cannot be deleted");
+            }
+            other => panic!("{:?}", other),
+        }
+
+        created.assert();
+        deleted.assert();
+        missing.assert();
+        closed.assert();
     }
 
     #[test]
-    fn test_basic_patch_error() {
-        let op = PatchOp::Add;
-        let path = "summary";
-        let value = json!("test_basic_patch_error_test");
+    fn test_put_read_only() {
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .read_only(true)
+        .build();
 
-        let result = testing_client().patch("/sales/activities/123", op, path, value);
-        assert!(result.is_err());
+        assert!(client.put("/sales/activities/1", "{}".to_string()).is_err());
     }
 
     #[test]
-    fn test_get_custom_field_bad_field_name() {
-        let path = "/project/projects/4";
-        let field_name = "A Fake Field";
-        let expected = None;
+    fn test_patch_ops() {
+        let mut server = mockito::Server::new();
+        let ticket = server
+            .mock("PATCH", "/v4_6_release/apis/3.0/service/tickets/1")
+            .match_body(
+                r#"[{"op":"replace","path":"summary","value":"printer is on fire"},{"op":"remove","path":"contact","value":null}]"#,
+            )
+            .with_body(r#"{"id": 1, "summary": "printer is on fire"}"#)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let result = testing_client().get_custom_field(path, field_name);
+        let ops = [
+            PatchOperation::new(PatchOp::Replace, "summary", json!("printer is on fire")),
+            PatchOperation::new(PatchOp::Remove, "contact", Value::Null),
+        ];
+        let updated = client.patch_ops("/service/tickets/1", &ops).unwrap();
+        assert_eq!(updated["summary"], "printer is on fire");
+        let err = client.patch_ops("/service/tickets/1", &[]).unwrap_err();
+        assert_eq!(err.to_string(), "no patch operations given");
 
-        assert_eq!(result.unwrap(), expected);
+        ticket.assert();
     }
+
     #[test]
-    fn test_get_custom_field_something_set() {
-        let path = "/project/projects/1799";
-        let field_name = "E-rate";
-        let expected = Some(json!(false));
+    fn test_patch_ref() {
+        let mut server = mockito::Server::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets/1";
+        let status = server
+            .mock("PATCH", path)
+            .match_body(r#"[{"op":"replace","path":"status/id","value":16}]"#)
+            .with_body(r#"{"id": 1, "status": {"id": 16}}"#)
+            .expect(2)
+            .create();
+        let several = server
+            .mock("PATCH", path)
+            .match_body(
+                r#"[{"op":"replace","path":"board/id","value":3},{"op":"replace","path":"owner/id","value":7}]"#,
+            )
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
 
-        let result = testing_client().get_custom_field(path, field_name);
+        let ticket = client
+            .patch_ref("/service/tickets/1", "status", 16)
+            .unwrap();
+        assert_eq!(ticket["status"]["id"], 16);
+        // a field that already ends in /id isn't doubled
+        client
+            .patch_ref("/service/tickets/1", "status/id", 16)
+            .unwrap();
+        client
+            .patch_refs("/service/tickets/1", &[("board", 3), ("/owner/id", 7)])
+            .unwrap();
+        assert!(client.patch_refs("/service/tickets/1", &[]).is_err());
 
-        assert_eq!(result.unwrap(), expected);
+        status.assert();
+        several.assert();
     }
 
     #[test]
-    fn test_get_custom_field_id() {
-        let path = "/project/projects/1799";
-        let field_name = "WaitReason";
-        let expected: i64 = 67;
+    fn test_clear_field() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets/1";
+        let patch = |field: &str, op: &str| {
+            mockito::Matcher::Json(json!([{"op": op, "path": field, "value": null}]))
+        };
+        let refused = r#"{"code": "InvalidObject", "message": "ticket object is invalid",
+            "errors": [{"code": "NullValue", "field": "contact"}]}"#;
+        // a reference refuses null and needs a remove
+        let contact_null = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(patch("contact", "replace"))
+            .with_status(400)
+            .with_body(refused)
+            .expect(2)
+            .create();
+        let contact_remove = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(patch("contact", "remove"))
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+        // a text field takes null
+        let po_null = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(patch("poNumber", "replace"))
+            .with_body(r#"{"id": 1, "poNumber": null}"#)
+            .expect(1)
+            .create();
+        // a date field accepts null but keeps its value
+        let _date_null = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(patch("requiredDate", "replace"))
+            .with_body(r#"{"id": 1, "requiredDate": "2023-03-01T00:00:00Z"}"#)
+            .create();
+        let date_remove = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(patch("requiredDate", "remove"))
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
 
-        let result = testing_client().get_custom_field_id(path, field_name);
+        let contact = client.clear_field("/service/tickets/1", "contact").unwrap();
+        let po = client
+            .clear_field("/service/tickets/1", "poNumber")
+            .unwrap();
+        let date = client
+            .clear_field("/service/tickets/1", "requiredDate")
+            .unwrap();
+        let null = client
+            .set_field_null("/service/tickets/1", "contact")
+            .unwrap_err();
 
-        assert_eq!(result.unwrap(), expected);
+        assert_eq!(contact, json!({"id": 1}));
+        assert_eq!(po, json!({"id": 1, "poNumber": null}));
+        assert_eq!(date, json!({"id": 1}));
+        assert!(matches!(
+            null.downcast_ref::<Error>(),
+            Some(Error::Api { status: 400, .. })
+        ));
+        contact_null.assert();
+        contact_remove.assert();
+        po_null.assert();
+        date_remove.assert();
     }
 
     #[test]
-    fn test_get_custom_field_id_missing() {
-        let path = "/project/projects/1799";
-        let field_name = "A Fake Thing";
-
-        let result = testing_client().get_custom_field_id(path, field_name);
+    fn test_normalize_path() {
+        let valid = [
+            ("/system/info", "/system/info"),
+            ("system/info", "/system/info"),
+            ("/service/tickets/123/notes", "/service/tickets/123/notes"),
+            ("/company/companies/", "/company/companies/"),
+        ];
+        for (path, expected) in valid {
+            assert_eq!(normalize_path(path).unwrap(), expected);
+        }
 
-        assert!(result.is_err());
+        let invalid = [
+            "",
+            "https://na.myconnectwise.net/v4_6_release/apis/3.0/system/info",
+            "http://localhost/system/info",
+            "/system/members?fields=id",
+            "/system/members#top",
+            "\\system\\info",
+            "/system/ info",
+            "/system/info ",
+            "/system/info\n",
+            "/system/\tinfo",
+            "/system/\u{7}info",
+        ];
+        for path in invalid {
+            let result = normalize_path(path);
+            match result.unwrap_err().downcast_ref::<Error>() {
+                Some(Error::InvalidPath { path: p, .. }) => assert_eq!(p, path),
+                other => panic!("expected invalid path for {:?}, got {:?}", path, other),
+            }
+        }
     }
 
     #[test]
-    fn test_get_custom_field_something_else_set() {
-        let path = "/project/projects/1799";
-        let field_name = "WaitReason";
-        let expected = Some(json!("Something Else"));
+    fn test_invalid_path_not_sent() {
+        let mut mock = MockCw::new();
+        let mocks = expect_no_requests(mock.server());
+        let client = mock.client();
 
-        let result = testing_client().get_custom_field(path, field_name);
+        assert!(client.get("/system/members?fields=id", &[]).is_err());
+        assert!(client.get_single("/system/ info", &[]).is_err());
+        assert!(client
+            .post("https://example.com/sales/activities", "{}".to_string())
+            .is_err());
+        assert!(client.url_for("\\system\\info", &[]).is_err());
 
-        assert_eq!(result.unwrap(), expected);
+        mocks.iter().for_each(|m| m.assert());
     }
+
     #[test]
-    fn test_update_custom_field_string() {
-        let path = "/project/projects/1799";
-        let field_name = "WaitReason";
-        let field_value = "Something Else";
-        let expected = ();
+    fn test_get_on_single_object_endpoint() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"isCloud": true}"#)
+            .create();
 
-        let result = testing_client().patch_custom_field(path, field_name, field_value);
-        assert_eq!(result.unwrap(), expected);
+        let err = mock.client().get("/system/info", &[]).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::ExpectedList {
+                path: "/system/info".to_string(),
+                hint: "this endpoint returns a single object, use get_single",
+            })
+        );
     }
 
     #[test]
-    fn test_update_custom_field_bool() {
-        let path = "/project/projects/1799";
-        let field_name = "EPL";
-        let field_value = "false";
-        let expected = ();
+    fn test_get_single_on_list_endpoint() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/members")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"[{"id": 1}]"#)
+            .create();
+        let client = mock.client();
 
-        let result = testing_client().patch_custom_field(path, field_name, field_value);
-        assert_eq!(result.unwrap(), expected);
+        let err = client.get_single("/system/members", &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ExpectedObject { .. })
+        ));
+
+        let unwrapped = client
+            .unwrap_single_array(true)
+            .get_single("/system/members", &[])
+            .unwrap();
+        assert_eq!(unwrapped, json!({"id": 1}));
     }
+
     #[test]
-    fn test_update_custom_field_doesnt_exist() {
-        let path = "/project/projects/1799";
-        let field_name = "A Fake Field";
-        let field_value = "false";
+    fn test_redirect_not_followed() {
+        let mut mock = MockCw::new();
+        let moved = format!("{}/v4_6_release/apis/3.0/moved", mock.url());
+        let target = ["GET", "POST", "PATCH"].map(|method| {
+            mock.server()
+                .mock(method, "/v4_6_release/apis/3.0/moved")
+                .expect(0)
+                .create()
+        });
+        let client = mock.client();
 
-        let result = testing_client().patch_custom_field(path, field_name, field_value);
-        assert!(result.is_err());
+        for status in [301, 307, 308] {
+            let redirects = ["GET", "POST", "PATCH"].map(|method| {
+                mock.server()
+                    .mock(method, "/v4_6_release/apis/3.0/service/tickets/1")
+                    .match_query(mockito::Matcher::Any)
+                    .with_status(status)
+                    .with_header("location", &moved)
+                    .create()
+            });
+            let expected = Error::Redirected {
+                status: status as u16,
+                location: moved.clone(),
+            };
+
+            let results = [
+                client.get_single("/service/tickets/1", &[]).unwrap_err(),
+                client
+                    .post("/service/tickets/1", "{}".to_string())
+                    .unwrap_err(),
+                client
+                    .patch(
+                        "/service/tickets/1",
+                        PatchOp::Replace,
+                        "summary",
+                        json!("x"),
+                    )
+                    .unwrap_err(),
+            ];
+
+            for err in results.iter() {
+                assert_eq!(err.downcast_ref::<Error>(), Some(&expected));
+            }
+            redirects.iter().for_each(|m| m.assert());
+            redirects.iter().for_each(|m| m.remove());
+        }
+        target.iter().for_each(|m| m.assert());
     }
 }
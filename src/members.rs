@@ -0,0 +1,281 @@
+//! Members, security roles and what the client is allowed to see.  See [Client::check_access]
+use crate::{api_error, Client, CwRef};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// The modules [Client::check_access] knows, with the list each one is checked with
+pub const ACCESS_MODULES: &[(&str, &str)] = &[
+    ("company", "/company/companies"),
+    ("service", "/service/tickets"),
+    ("time", "/time/entries"),
+    ("project", "/project/projects"),
+    ("sales", "/sales/opportunities"),
+    ("finance", "/finance/agreements"),
+    ("procurement", "/procurement/products"),
+    ("schedule", "/schedule/entries"),
+    ("expense", "/expense/entries"),
+    ("marketing", "/marketing/groups"),
+    ("system", "/system/members"),
+];
+
+/// A member (`/system/members`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Member {
+    /// the id of the member
+    pub id: i64,
+    /// the login of the member (`zpeters`)
+    #[serde(default)]
+    pub identifier: String,
+    /// first name
+    #[serde(default)]
+    pub first_name: String,
+    /// last name
+    #[serde(default)]
+    pub last_name: String,
+    /// the security role, what the member (and an api key made for it) is allowed to do
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_role: Option<CwRef>,
+    /// the member's location
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_location: Option<CwRef>,
+    /// the member's department
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_department: Option<CwRef>,
+    /// true for a member that can't log in any more
+    #[serde(default)]
+    pub inactive_flag: bool,
+}
+
+/// A security role (`/system/securityRoles`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityRole {
+    /// the id of the role
+    pub id: i64,
+    /// the name of the role
+    #[serde(default)]
+    pub name: String,
+    /// who the role is for (`Internal`, `Customer`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role_type: Option<String>,
+}
+
+/// What [Client::check_access] found for one module
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    /// the list could be read
+    Allowed,
+    /// connectwise refused (401 or 403)
+    Forbidden,
+    /// something else went wrong, so it isn't known
+    Failed(String),
+}
+
+/// One module in an [AccessReport]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleAccess {
+    /// the module (`service`, `finance`, ...)
+    pub module: String,
+    /// the list it was checked with
+    pub path: String,
+    /// what happened
+    pub access: Access,
+}
+
+/// What the client can read, from [Client::check_access]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AccessReport {
+    /// each module checked, in the order of [ACCESS_MODULES]
+    pub modules: Vec<ModuleAccess>,
+}
+
+impl AccessReport {
+    /// true if the module was checked and could be read
+    pub fn is_allowed(&self, module: &str) -> bool {
+        self.modules
+            .iter()
+            .any(|m| m.module == module && m.access == Access::Allowed)
+    }
+
+    /// the modules that were refused
+    pub fn forbidden(&self) -> Vec<&str> {
+        self.modules
+            .iter()
+            .filter(|m| m.access == Access::Forbidden)
+            .map(|m| m.module.as_str())
+            .collect()
+    }
+}
+
+impl Client {
+    /// Gets the security roles
+    pub fn get_security_roles(&self) -> Result<Vec<SecurityRole>> {
+        self.get_decoded("/system/securityRoles", &[("", "")])
+    }
+
+    /// Gets the member with the login `identifier`.  For the member an api key was made for,
+    /// [Member::security_role] is what the key is allowed to do
+    pub fn get_member(&self, identifier: &str) -> Result<Member> {
+        let conditions = format!("identifier = '{}'", identifier.replace('\'', "\\'"));
+        let members = self.get("/system/members", &[("conditions", &conditions)])?;
+        let member = members
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("there is no member {:?}", identifier))?;
+        self.decode("/system/members", member)
+    }
+
+    /// Checks which modules the client can read, by asking each for one record.  `module` is
+    /// one of the names in [ACCESS_MODULES], or `all` for every one of them.  A module that
+    /// can't be read isn't an error, it is in the report
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let report = client.check_access("all").unwrap();
+    /// for module in report.modules.iter() {
+    ///     println!("{}: {:?}", module.module, module.access);
+    /// }
+    /// ```
+    pub fn check_access(&self, module: &str) -> Result<AccessReport> {
+        let wanted = module.trim().to_lowercase();
+        let modules: Vec<&(&str, &str)> = ACCESS_MODULES
+            .iter()
+            .filter(|(name, _)| wanted == "all" || *name == wanted)
+            .collect();
+        if modules.is_empty() {
+            let names: Vec<&str> = ACCESS_MODULES.iter().map(|(name, _)| *name).collect();
+            return Err(anyhow!(
+                "unknown module {:?}, use one of {} or all",
+                module,
+                names.join(", ")
+            ));
+        }
+
+        let mut report = AccessReport::default();
+        for (name, path) in modules {
+            report.modules.push(ModuleAccess {
+                module: name.to_string(),
+                path: path.to_string(),
+                access: self.probe(path),
+            });
+        }
+        Ok(report)
+    }
+
+    /// asks `path` for one record
+    fn probe(&self, path: &str) -> Access {
+        let req = match self.http_request(reqwest::Method::GET, path) {
+            Ok(req) => req.query(&[("pageSize", "1"), ("fields", "id")]),
+            Err(e) => return Access::Failed(e.to_string()),
+        };
+        let result = self.call(req, |res| match res.status.as_u16() {
+            200..=299 => Ok(Access::Allowed),
+            401 | 403 => Ok(Access::Forbidden),
+            _ => Err(api_error(&res, path)),
+        });
+        result.unwrap_or_else(|e| Access::Failed(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_check_access() {
+        let mut mock = MockCw::new();
+        let _allowed = mock
+            .server()
+            .mock("GET", Matcher::Any)
+            .match_query(Matcher::UrlEncoded("pageSize".to_string(), "1".to_string()))
+            .with_body("[]")
+            .create();
+        let _finance = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/finance/agreements")
+            .match_query(Matcher::Any)
+            .with_status(403)
+            .with_body(r#"{"code": "Forbidden", "message": "no finance"}"#)
+            .create();
+        let _sales = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/sales/opportunities")
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .create();
+        let client = mock.client();
+
+        let report = client.check_access("all").unwrap();
+        let one = client.check_access("Finance").unwrap();
+
+        assert_eq!(report.modules.len(), ACCESS_MODULES.len());
+        assert!(report.is_allowed("service"));
+        assert!(!report.is_allowed("finance"));
+        assert_eq!(report.forbidden(), vec!["finance"]);
+        assert!(matches!(
+            report
+                .modules
+                .iter()
+                .find(|m| m.module == "sales")
+                .unwrap()
+                .access,
+            Access::Failed(_)
+        ));
+        assert_eq!(one.modules.len(), 1);
+        assert_eq!(one.modules[0].access, Access::Forbidden);
+        assert!(client.check_access("payroll").is_err());
+    }
+
+    #[test]
+    fn test_member_role() {
+        let mut mock = MockCw::new();
+        let _member = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/members")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "identifier = 'apiuser'".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 150, "identifier": "apiuser",
+                     "securityRole": {"id": 63, "name": "API Integration"}}]"#,
+            )
+            .create();
+        let _roles = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/securityRoles")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 63, "name": "API Integration", "roleType": "Internal"},
+                    {"id": 1, "name": "Admin", "roleType": "Internal"}]"#,
+            )
+            .create();
+        let client = mock.client();
+
+        let member = client.get_member("apiuser").unwrap();
+        let roles = client.get_security_roles().unwrap();
+
+        let role = member.security_role.unwrap();
+        assert_eq!(role.name.as_deref(), Some("API Integration"));
+        assert_eq!(
+            roles.iter().find(|r| r.id == role.id).unwrap().name,
+            "API Integration"
+        );
+    }
+}
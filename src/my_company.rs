@@ -0,0 +1,280 @@
+//! The owner side of the instance: its corporate structure (locations and departments) and
+//! company settings.  See [Client::get_corporate_structure]
+use crate::reference::find_by_name;
+use crate::{Client, CwRef};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const LEVELS_PATH: &str = "/system/mycompany/corporateStructureLevels";
+const LOCATIONS_PATH: &str = "/system/locations";
+const DEPARTMENTS_PATH: &str = "/system/departments";
+
+/// A level of the corporate structure (`Company`, `Region`, `Location`, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructureLevel {
+    /// the id of the level
+    pub id: i64,
+    /// the name of the level
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A location (`/system/locations`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    /// the id of the location
+    pub id: i64,
+    /// the name of the location
+    #[serde(default)]
+    pub name: String,
+    /// the structure level the location is at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structure_level: Option<CwRef>,
+    /// the id of the level above it that owns it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_level_id: Option<i64>,
+}
+
+/// A department (`/system/departments`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Department {
+    /// the id of the department
+    pub id: i64,
+    /// the short name of the department
+    #[serde(default)]
+    pub identifier: String,
+    /// the name of the department
+    #[serde(default)]
+    pub name: String,
+    /// the locations the department is at, empty when it is at all of them
+    #[serde(default)]
+    pub location_ids: Vec<i64>,
+}
+
+/// The corporate structure, from [Client::get_corporate_structure]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorporateStructure {
+    /// the levels of the structure
+    pub levels: Vec<StructureLevel>,
+    /// every location
+    pub locations: Vec<Location>,
+    /// every department
+    pub departments: Vec<Department>,
+}
+
+impl CorporateStructure {
+    /// Finds the structure level called `name` (ignoring case)
+    pub fn level(&self, name: &str) -> Result<&StructureLevel> {
+        find_by_name(&self.levels, LEVELS_PATH, name, |l| &l.name)
+    }
+
+    /// Finds the location called `name` (ignoring case)
+    pub fn location(&self, name: &str) -> Result<&Location> {
+        find_by_name(&self.locations, LOCATIONS_PATH, name, |l| &l.name)
+    }
+
+    /// Finds the department with the identifier or name `name` (ignoring case)
+    pub fn department(&self, name: &str) -> Result<&Department> {
+        find_by_name(&self.departments, DEPARTMENTS_PATH, name, |d| &d.identifier)
+            .or_else(|_| find_by_name(&self.departments, DEPARTMENTS_PATH, name, |d| &d.name))
+    }
+
+    /// The departments at a location
+    pub fn departments_at(&self, location_id: i64) -> Vec<&Department> {
+        self.departments
+            .iter()
+            .filter(|d| d.location_ids.is_empty() || d.location_ids.contains(&location_id))
+            .collect()
+    }
+
+    /// Checks a location and department can go together on a record (a ticket, a time
+    /// entry), before it is sent
+    ///
+    /// ```
+    /// use cwmanage::{CorporateStructure, Department, Location};
+    ///
+    /// let structure = CorporateStructure {
+    ///     levels: vec![],
+    ///     locations: vec![Location {
+    ///         id: 2,
+    ///         name: "Milwaukee".to_string(),
+    ///         structure_level: None,
+    ///         owner_level_id: None,
+    ///     }],
+    ///     departments: vec![Department {
+    ///         id: 10,
+    ///         identifier: "Services".to_string(),
+    ///         name: "Services".to_string(),
+    ///         location_ids: vec![1],
+    ///     }],
+    /// };
+    /// assert!(structure.check_combination(2, 10).is_err());
+    /// ```
+    pub fn check_combination(&self, location_id: i64, department_id: i64) -> Result<()> {
+        let location = self
+            .locations
+            .iter()
+            .find(|l| l.id == location_id)
+            .ok_or_else(|| anyhow!("there is no location {}", location_id))?;
+        let department = self
+            .departments
+            .iter()
+            .find(|d| d.id == department_id)
+            .ok_or_else(|| anyhow!("there is no department {}", department_id))?;
+        match department.location_ids.is_empty() || department.location_ids.contains(&location.id) {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "department {:?} isn't at location {:?}",
+                department.name,
+                location.name
+            )),
+        }
+    }
+}
+
+/// The company settings under `/system/mycompany/other`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MyCompanyOther {
+    /// the id of the settings
+    pub id: i64,
+    /// the calendar used when a record doesn't have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_calendar: Option<CwRef>,
+    /// every other setting, by its connectwise name
+    #[serde(flatten)]
+    pub settings: Map<String, Value>,
+}
+
+impl Client {
+    /// Gets the corporate structure: its levels, locations and departments
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let structure = client.get_corporate_structure().unwrap();
+    /// let location = structure.location("Milwaukee").unwrap();
+    /// let department = structure.department("Services").unwrap();
+    /// structure
+    ///     .check_combination(location.id, department.id)
+    ///     .unwrap();
+    /// ```
+    pub fn get_corporate_structure(&self) -> Result<CorporateStructure> {
+        Ok(CorporateStructure {
+            levels: self.get_decoded(LEVELS_PATH, &[("", "")])?,
+            locations: self.get_decoded(LOCATIONS_PATH, &[("", "")])?,
+            departments: self.get_decoded(DEPARTMENTS_PATH, &[("", "")])?,
+        })
+    }
+
+    /// Gets the company settings under `/system/mycompany/other`
+    pub fn get_my_company_other(&self) -> Result<MyCompanyOther> {
+        let other = self.get("/system/mycompany/other", &[("", "")])?;
+        let first = other
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("no settings in /system/mycompany/other"))?;
+        self.decode("/system/mycompany/other", first)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::MockCw;
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_get_corporate_structure() {
+        let mut mock = MockCw::new();
+        let _levels = mock
+            .server()
+            .mock(
+                "GET",
+                "/v4_6_release/apis/3.0/system/mycompany/corporateStructureLevels",
+            )
+            .match_query(Matcher::Any)
+            .with_body(r#"[{"id": 1, "name": "Company"}, {"id": 3, "name": "Location"}]"#)
+            .create();
+        let _locations = mock.server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/locations")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "name": "Milwaukee", "structureLevel": {"id": 3, "name": "Location"}},
+                    {"id": 2, "name": "Madison", "structureLevel": {"id": 3, "name": "Location"}}]"#,
+            )
+            .create();
+        let _departments = mock.server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/departments")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 10, "identifier": "PS", "name": "Professional Services", "locationIds": [1]},
+                    {"id": 11, "identifier": "Admin", "name": "Administration"}]"#,
+            )
+            .create();
+
+        let structure = mock.client().get_corporate_structure().unwrap();
+
+        assert_eq!(structure.level("location").unwrap().id, 3);
+        let madison = structure.location("MADISON").unwrap();
+        assert_eq!(structure.department("ps").unwrap().id, 10);
+        assert_eq!(
+            structure.department("Professional Services").unwrap().id,
+            10
+        );
+        let at_madison: Vec<i64> = structure
+            .departments_at(madison.id)
+            .iter()
+            .map(|d| d.id)
+            .collect();
+        assert_eq!(at_madison, vec![11]);
+        assert!(structure.check_combination(1, 10).is_ok());
+        assert!(structure.check_combination(2, 10).is_err());
+        assert!(structure.check_combination(2, 11).is_ok());
+        assert!(matches!(
+            structure
+                .level("Region")
+                .unwrap_err()
+                .downcast_ref::<Error>(),
+            Some(Error::UnknownName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_get_my_company_other() {
+        let mut mock = MockCw::new();
+        let _other = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/mycompany/other")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "defaultCalendar": {"id": 2, "name": "Standard"},
+                     "logoPath": "logo.png"}]"#,
+            )
+            .create();
+
+        let other = mock.client().get_my_company_other().unwrap();
+
+        assert_eq!(
+            other.default_calendar.and_then(|c| c.name).as_deref(),
+            Some("Standard")
+        );
+        assert_eq!(other.settings["logoPath"], "logo.png");
+    }
+}
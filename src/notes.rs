@@ -0,0 +1,214 @@
+//! Notes on companies and contacts.  See [Client::add_company_note]
+use crate::reference::find_by_name;
+use crate::{Client, CwRef, IdOrName};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+const NOTE_TYPES_PATH: &str = "/company/noteTypes";
+
+/// A note on a company (`/company/companies/{id}/notes`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompanyNote {
+    /// the id of the note
+    pub id: i64,
+    /// the note itself
+    #[serde(default)]
+    pub text: String,
+    /// the note type
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub note_type: Option<CwRef>,
+    /// true if the note is flagged for attention
+    #[serde(default)]
+    pub flagged: bool,
+    /// the member who wrote the note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entered_by: Option<String>,
+}
+
+/// A note on a contact (`/company/contacts/{id}/notes`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactNote {
+    /// the id of the note
+    pub id: i64,
+    /// the contact the note is on
+    #[serde(default)]
+    pub contact_id: i64,
+    /// the note itself
+    #[serde(default)]
+    pub text: String,
+    /// the note type
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub note_type: Option<CwRef>,
+    /// true if the note is flagged for attention
+    #[serde(default)]
+    pub flagged: bool,
+}
+
+impl Client {
+    /// Gets the notes of a company, `query` is passed on as with [Client::get]
+    pub fn list_company_notes(
+        &self,
+        company_id: i64,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<CompanyNote>> {
+        let path = format!("/company/companies/{}/notes", company_id);
+        self.get_decoded(&path, query)
+    }
+
+    /// Adds a note to a company and returns it.  The note type can be given by name, it is
+    /// looked up in `/company/noteTypes`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let note = client
+    ///     .add_company_note(250, "Called about renewal", Some("Sales".into()), false)
+    ///     .unwrap();
+    /// println!("added note {}", note.id);
+    /// ```
+    pub fn add_company_note(
+        &self,
+        company_id: i64,
+        text: &str,
+        note_type: Option<IdOrName>,
+        flagged: bool,
+    ) -> Result<CompanyNote> {
+        let path = format!("/company/companies/{}/notes", company_id);
+        let note = self.post(&path, self.note_body(text, note_type, flagged)?)?;
+        self.decode(&path, note)
+    }
+
+    /// Gets the notes of a contact, `query` is passed on as with [Client::get]
+    pub fn list_contact_notes(
+        &self,
+        contact_id: i64,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<ContactNote>> {
+        let path = format!("/company/contacts/{}/notes", contact_id);
+        self.get_decoded(&path, query)
+    }
+
+    /// Adds a note to a contact and returns it, the same as [Client::add_company_note]
+    pub fn add_contact_note(
+        &self,
+        contact_id: i64,
+        text: &str,
+        note_type: Option<IdOrName>,
+        flagged: bool,
+    ) -> Result<ContactNote> {
+        let path = format!("/company/contacts/{}/notes", contact_id);
+        let note = self.post(&path, self.note_body(text, note_type, flagged)?)?;
+        self.decode(&path, note)
+    }
+
+    /// Gets the note types of company and contact notes
+    pub fn get_note_types(&self) -> Result<Vec<CwRef>> {
+        self.get_decoded(NOTE_TYPES_PATH, &[("", "")])
+    }
+
+    fn note_body(&self, text: &str, note_type: Option<IdOrName>, flagged: bool) -> Result<String> {
+        let mut body = Map::new();
+        body.insert("text".to_string(), json!(text));
+        body.insert("flagged".to_string(), json!(flagged));
+        if let Some(note_type) = note_type {
+            let id = match note_type {
+                IdOrName::Id(id) => id,
+                IdOrName::Name(name) => {
+                    let types = self.get_note_types()?;
+                    find_by_name(&types, NOTE_TYPES_PATH, &name, |t| {
+                        t.name.as_deref().unwrap_or_default()
+                    })?
+                    .id
+                }
+            };
+            body.insert("type".to_string(), json!({ "id": id }));
+        }
+        Ok(Value::Object(body).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_add_company_note() {
+        let mut mock = MockCw::new();
+        let text = format!(
+            "# Renewal\n\n* called **twice**\r\n\t- \"quoted\" \\ caf\u{e9}\n{}",
+            "x".repeat(8 * 1024)
+        );
+        let _types = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/company/noteTypes")
+            .match_query(Matcher::Any)
+            .with_body(r#"[{"id": 1, "name": "General"}, {"id": 2, "name": "Sales"}]"#)
+            .create();
+        let add = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/company/companies/250/notes")
+            .match_body(Matcher::Json(
+                json!({"text": text, "flagged": true, "type": {"id": 2}}),
+            ))
+            .with_body(
+                json!({"id": 5, "text": text, "flagged": true, "type": {"id": 2, "name": "Sales"}})
+                    .to_string(),
+            )
+            .create();
+
+        let note = mock
+            .client()
+            .add_company_note(250, &text, Some("sales".into()), true)
+            .unwrap();
+
+        add.assert();
+        assert_eq!(note.text, text);
+        assert!(note.flagged);
+        assert_eq!(note.note_type.map(|t| t.id), Some(2));
+    }
+
+    #[test]
+    fn test_contact_notes() {
+        let mut mock = MockCw::new();
+        let add = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/company/contacts/7/notes")
+            .match_body(Matcher::Json(
+                json!({"text": "met at expo", "flagged": false}),
+            ))
+            .with_body(r#"{"id": 1, "contactId": 7, "text": "met at expo"}"#)
+            .create();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/company/contacts/7/notes")
+            .match_query(Matcher::Any)
+            .with_body(r#"[{"id": 1, "contactId": 7, "text": "met at expo"}]"#)
+            .create();
+        let client = mock.client();
+
+        let added = client
+            .add_contact_note(7, "met at expo", None, false)
+            .unwrap();
+        let notes = client.list_contact_notes(7, &[("", "")]).unwrap();
+
+        add.assert();
+        assert_eq!(notes, vec![added]);
+    }
+}
@@ -0,0 +1,86 @@
+//! Sending the w3c trace context of the current span with each request (the `otel` feature),
+//! so the calls show up in the caller's trace and a proxy can match them up
+use opentelemetry::trace::TraceContextExt;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// adds `traceparent` (and `tracestate` when there is one) for `span`, or the span it is in
+/// when `span` itself isn't traced
+pub(crate) fn inject(
+    span: &tracing::Span,
+    mut req: reqwest::blocking::Request,
+) -> reqwest::blocking::Request {
+    let mut context = span.context();
+    if !context.span().span_context().is_valid() {
+        context = tracing::Span::current().context();
+    }
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return req;
+    }
+
+    let traceparent = format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    );
+    let headers = req.headers_mut();
+    if let Ok(value) = traceparent.parse() {
+        headers.insert("traceparent", value);
+    }
+    let tracestate = span_context.trace_state().header();
+    if let Ok(value) = tracestate.parse() {
+        if !tracestate.is_empty() {
+            headers.insert("tracestate", value);
+        }
+    }
+    req
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Client;
+    use mockito::Matcher;
+    use opentelemetry::trace::TracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_traceparent() {
+        let mut server = mockito::Server::new();
+        let traced = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_header(
+                "traceparent",
+                Matcher::Regex("^00-[0-9a-f]{32}-[0-9a-f]{16}-01$".to_string()),
+            )
+            .with_body(r#"{"version": "v2022.1", "isCloud": true, "serverTimeZone": "UTC"}"#)
+            .expect(1)
+            .create();
+        let untraced = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_header("traceparent", Matcher::Missing)
+            .with_body(r#"{"version": "v2022.1", "isCloud": true, "serverTimeZone": "UTC"}"#)
+            .expect(1)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url());
+
+        let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, || {
+            let _inbound = tracing::info_span!("inbound request").entered();
+            client.system_info().unwrap();
+        });
+        client.system_info().unwrap();
+
+        traced.assert();
+        untraced.assert();
+    }
+}
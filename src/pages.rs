@@ -0,0 +1,1242 @@
+//! Walking the pages of a list endpoint one at a time.  See [Client::pages]
+use crate::{api_error, expected_list, next_link_param, Client, Error};
+use anyhow::Result;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// The largest page connectwise returns
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// How many times the pages start a fresh list after an expired cursor before the error is
+/// returned, see [Client::resume_on_cursor_expiry]
+const MAX_CURSOR_RESUMES: u32 = 10;
+
+/// What [Client::get_latest] orders by when it isn't given a field
+pub const LATEST_ORDER_FIELD: &str = "_info/lastUpdated";
+
+/// An iterator over the pages of a list endpoint, created with [Client::pages].  Each page is
+/// only requested when the iterator gets to it.  After an error the iterator ends
+///
+/// Nothing is requested in the background, so dropping the iterator cancels it: the pages it
+/// didn't get to are never asked for.  To stop it from another thread use
+/// [Client::cancel_on], the next page is then [Error::Cancelled]
+#[derive(Debug)]
+pub struct Pages<'a> {
+    client: &'a Client,
+    path: String,
+    query: Vec<(String, String)>,
+    next: Option<String>,
+    last_id: Option<i64>,
+    resumed_after: Option<i64>,
+    resumes: u32,
+    invalid: Option<Error>,
+    parent: Option<String>,
+    started: Instant,
+    deadline: Option<Instant>,
+    records: usize,
+    search: bool,
+}
+
+impl Iterator for Pages<'_> {
+    type Item = Result<Vec<Value>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(invalid) = self.invalid.take() {
+            self.next = None;
+            return Some(Err(invalid.into()));
+        }
+        loop {
+            let page = self.next.take()?;
+            if self.is_past_deadline() {
+                return Some(Err(self.deadline_exceeded(page)));
+            }
+
+            match self.fetch(&page) {
+                Ok((records, next)) => {
+                    self.next = next;
+                    self.records += records.len();
+                    self.last_id = records
+                        .iter()
+                        .filter_map(|r| r["id"].as_i64())
+                        .chain(self.last_id)
+                        .max();
+                    return Some(Ok(records));
+                }
+                Err(e) => match self.resume_query(&e) {
+                    Some(query) => {
+                        self.query = query;
+                        self.resumed_after = self.last_id;
+                        self.resumes += 1;
+                        self.next = Some("1".to_string());
+                    }
+                    None if self.is_past_deadline() => {
+                        return Some(Err(self.deadline_exceeded(page)))
+                    }
+                    None => return Some(Err(e)),
+                },
+            }
+        }
+    }
+}
+
+impl Pages<'_> {
+    /// Stops the pages once `limit` (from now) has passed, with [Error::DeadlineExceeded].
+    /// The deadline is checked before each page and bounds the timeout (and retries) of the
+    /// page in flight.  See [Client::get_with_deadline]
+    pub fn deadline(mut self, limit: Duration) -> Self {
+        self.started = Instant::now();
+        self.deadline = Some(self.started + limit);
+        self
+    }
+
+    /// The `pageid` of the next page, `None` once the last page has been returned.  Pass it
+    /// in the query of [Client::pages] to carry on from there later
+    pub fn next_page_id(&self) -> Option<&str> {
+        self.next.as_deref()
+    }
+
+    fn is_past_deadline(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    fn deadline_exceeded(&self, page: String) -> anyhow::Error {
+        Error::DeadlineExceeded {
+            elapsed: self.started.elapsed(),
+            records_collected: self.records,
+            resume_cursor: Some(page),
+        }
+        .into()
+    }
+
+    fn fetch(&self, page: &str) -> Result<(Vec<Value>, Option<String>)> {
+        let query: Vec<(&str, &str)> = self
+            .query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let fetch = match self.search {
+            true => Client::fetch_search_page,
+            false => Client::fetch_page,
+        };
+        fetch(
+            self.client,
+            &self.path,
+            &query,
+            page,
+            self.parent.as_deref(),
+            self.deadline,
+        )
+    }
+
+    /// the query for a fresh pagination session starting after the last id seen, if the
+    /// error is an expired cursor and we can resume: the last fresh session got further than
+    /// the one before it and there have been fewer than [MAX_CURSOR_RESUMES]
+    fn resume_query(&self, err: &anyhow::Error) -> Option<Vec<(String, String)>> {
+        if !self.client.resume_on_cursor_expiry {
+            return None;
+        }
+        if !matches!(err.downcast_ref(), Some(Error::CursorExpired { .. })) {
+            return None;
+        }
+        let by_id = self.query.iter().any(|(k, v)| {
+            k.eq_ignore_ascii_case("orderby")
+                && matches!(v.trim().to_lowercase().as_str(), "id" | "id asc")
+        });
+        if !by_id {
+            return None;
+        }
+        let last_id = self.last_id?;
+        if self.resumes >= MAX_CURSOR_RESUMES || self.resumed_after >= Some(last_id) {
+            tracing::debug!(path = %self.path, last_id, "the cursor keeps expiring, not resuming");
+            return None;
+        }
+
+        let mut query: Vec<(String, String)> = self
+            .query
+            .iter()
+            .filter(|(k, _)| !k.eq_ignore_ascii_case("conditions"))
+            .cloned()
+            .collect();
+        let after = format!("id > {}", last_id);
+        let conditions = match self
+            .query
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("conditions"))
+        {
+            Some((_, c)) if !c.trim().is_empty() => format!("({}) AND {}", c, after),
+            _ => after,
+        };
+        query.push(("conditions".to_string(), conditions));
+        Some(query)
+    }
+}
+
+impl Client {
+    /// Like [Client::get] but instead of collecting every result up front, the pages are
+    /// returned one at a time as they are requested.  Use this for large endpoints that you
+    /// want to process (or write out) without holding everything in memory
+    ///
+    /// # Arguments
+    ///
+    /// - `path` - the api path you want to retrieve (example `/service/tickets`)
+    /// - `query` - additional query options *must be set*.  If non, use [("", "")]
+    ///
+    /// # Pagination in the query
+    ///
+    /// A `pageid` in the query is where the pages start (instead of the first page) and a
+    /// `pagesize` is passed on as is.  Each can only be given once.  `page` is for the other
+    /// kind of pagination and is refused with [Error::InvalidQuery]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for page in client.pages("/service/tickets", &[("fields", "id,summary")]) {
+    ///     let page = page.unwrap();
+    ///     println!("got {} tickets", page.len());
+    /// }
+    /// ```
+    pub fn pages(&self, path: &str, query: &[(&str, &str)]) -> Pages<'_> {
+        self.list_pages(path, query, false)
+    }
+
+    /// The newest `n` records of a list, by `order_field` descending (`_info/lastUpdated` when
+    /// it is empty, see [LATEST_ORDER_FIELD]).  Only one page is requested, of `n` records
+    /// (at most [MAX_PAGE_SIZE]), so this returns at most `n` records
+    ///
+    /// The `orderby` and `pagesize` are set here, giving either in `query` (or a `pageid`) is
+    /// [Error::InvalidQuery] rather than a request with both
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// // the 20 tickets on board 3 that changed last
+    /// let tickets = client
+    ///     .get_latest("/service/tickets", &[("conditions", "board/id = 3")], 20, "")
+    ///     .unwrap();
+    /// // the newest time entries, which are ordered by when they were entered
+    /// let entries = client
+    ///     .get_latest("/time/entries", &[], 20, "dateEntered")
+    ///     .unwrap();
+    /// ```
+    pub fn get_latest(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        n: usize,
+        order_field: &str,
+    ) -> Result<Vec<Value>> {
+        if let Some((key, _)) = query.iter().find(|(k, _)| {
+            ["orderby", "pagesize", "pageid", "page"]
+                .iter()
+                .any(|own| k.eq_ignore_ascii_case(own))
+        }) {
+            return Err(Error::InvalidQuery {
+                key: key.to_string(),
+                reason: "get_latest sets the order and the page itself".to_string(),
+            }
+            .into());
+        }
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        let order_field = match order_field.trim() {
+            "" => LATEST_ORDER_FIELD,
+            field => field,
+        };
+        let orderby = format!("{} desc", order_field);
+        let page_size = n.min(MAX_PAGE_SIZE).to_string();
+        let mut query = query.to_vec();
+        query.push(("orderby", &orderby));
+        query.push(("pagesize", &page_size));
+
+        match self.pages(path, &query).next() {
+            Some(page) => {
+                let mut page = page?;
+                page.truncate(n);
+                Ok(page)
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Page `page` of a list (`1` is the first page) with `page_size` records a page, and the
+    /// number of the page after it from the `link` header, `None` on the last.  For a paging
+    /// UI, or to get part of a big list without getting all of it.  Records are ordered as
+    /// [Client::get] orders them.  A page past the last one is empty
+    ///
+    /// Only the one page is requested: it is asked for by number (`page` and `pageSize`)
+    /// without the forward only pagination the rest of the client uses, so it is a page of
+    /// the list as it is now and records created or deleted between two calls can shift the
+    /// pages.  A `page_size` over [MAX_PAGE_SIZE] is logged as a warning and [MAX_PAGE_SIZE]
+    /// is asked for (connectwise would return that many anyway).  A `page` or `page_size` of
+    /// 0, or a `page`, `pageid` or `pagesize` in the query, is [Error::InvalidQuery]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let query = [("conditions", "closedFlag = false")];
+    /// let (tickets, next) = client.get_page("/service/tickets", &query, 3, 50).unwrap();
+    /// println!("showing {} tickets", tickets.len());
+    /// if let Some(next) = next {
+    ///     let next: u32 = next.parse().unwrap();
+    ///     let (more, _) = client.get_page("/service/tickets", &query, next, 50).unwrap();
+    /// }
+    /// ```
+    pub fn get_page(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        page: u32,
+        page_size: u32,
+    ) -> Result<(Vec<Value>, Option<String>)> {
+        let invalid = |key: &str, reason: &str| Error::InvalidQuery {
+            key: key.to_string(),
+            reason: reason.to_string(),
+        };
+        if let Some((key, _)) = query.iter().find(|(k, _)| {
+            ["pageid", "pagesize"]
+                .iter()
+                .any(|own| k.eq_ignore_ascii_case(own))
+        }) {
+            return Err(invalid(key, "get_page sets the page itself").into());
+        }
+        if page == 0 {
+            return Err(invalid("page", "pages are numbered from 1").into());
+        }
+        if page_size == 0 {
+            return Err(invalid("pagesize", "the page size is 0").into());
+        }
+        let page_size = match page_size as usize > MAX_PAGE_SIZE {
+            true => {
+                tracing::warn!(
+                    path,
+                    page_size,
+                    max = MAX_PAGE_SIZE,
+                    "the page size is more than connectwise returns, asking for the most it does"
+                );
+                MAX_PAGE_SIZE
+            }
+            false => page_size as usize,
+        };
+
+        let (query, _) = self.list_query(path, query)?;
+        let req = self
+            .numbered_request(reqwest::Method::GET, path)?
+            .query(&[
+                ("page", page.to_string()),
+                ("pageSize", page_size.to_string()),
+            ])
+            .query(&query);
+
+        self.call(req, |res| {
+            if !res.status.is_success() {
+                return Err(api_error(&res, path));
+            }
+            let next = next_link_param(&res.headers, "page");
+            match serde_json::from_str(&res.body)? {
+                Value::Array(records) => Ok((records, next)),
+                _ => Err(expected_list(path)),
+            }
+        })
+    }
+
+    /// The first record of a list matching `query` (`conditions=identifier = 'myco'`), or
+    /// `None` when nothing matches.  Only one record is asked for and no more pages are
+    /// requested, so a typo in the conditions doesn't download the whole list.  Records are
+    /// ordered as [Client::get] orders them, give an `orderby` to choose which is first.  A
+    /// `pageid`, `page` or `pagesize` in the query is [Error::InvalidQuery]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let newest = client
+    ///     .get_one("/service/tickets", &[("orderby", "id desc")])
+    ///     .unwrap();
+    /// ```
+    pub fn get_one(&self, path: &str, query: &[(&str, &str)]) -> Result<Option<Value>> {
+        Ok(self.first_records(path, query, 1)?.into_iter().next())
+    }
+
+    /// [Client::get_one] for lookups that have to be unique (a company by its identifier), more
+    /// than one record matching is [Error::NotUnique].  Two records are asked for, to tell one
+    /// match from several
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let company = client
+    ///     .get_exactly_one("/company/companies", &[("conditions", "identifier = 'myco'")])
+    ///     .unwrap();
+    /// ```
+    pub fn get_exactly_one(&self, path: &str, query: &[(&str, &str)]) -> Result<Option<Value>> {
+        let mut records = self.first_records(path, query, 2)?;
+        if records.len() > 1 {
+            return Err(Error::NotUnique {
+                path: path.to_string(),
+            }
+            .into());
+        }
+        Ok(records.pop())
+    }
+
+    /// the first `n` records of the first page, the page is `n` long
+    fn first_records(&self, path: &str, query: &[(&str, &str)], n: usize) -> Result<Vec<Value>> {
+        if let Some((key, _)) = query.iter().find(|(k, _)| {
+            ["pageid", "page", "pagesize"]
+                .iter()
+                .any(|own| k.eq_ignore_ascii_case(own))
+        }) {
+            return Err(Error::InvalidQuery {
+                key: key.to_string(),
+                reason: "only the first page is asked for, its size is set here".to_string(),
+            }
+            .into());
+        }
+        let (mut query, first) = self.list_query(path, query)?;
+        query.push(("pagesize".to_string(), n.to_string()));
+        let query: Vec<(&str, &str)> = query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let (mut records, _) = self.fetch_page(path, &query, &first, None, None)?;
+        records.truncate(n);
+        Ok(records)
+    }
+
+    /// the pages of a list from the url, or the search route (see [Client::search]) when
+    /// `search` or the url would be longer than [Client::max_url_length]
+    pub(crate) fn list_pages(&self, path: &str, query: &[(&str, &str)], search: bool) -> Pages<'_> {
+        let (query, first, mut invalid) = match self.list_query(path, query) {
+            Ok((query, first)) => (query, first, None),
+            Err(e) => (Vec::new(), "1".to_string(), Some(e)),
+        };
+        let mut search = search;
+        if !search && invalid.is_none() {
+            let length = self
+                .list_url(path, &query, &first)
+                .map_or(0, |u| u.as_str().len());
+            if length > self.max_url_length && self.has_search_route(path) {
+                tracing::debug!(path, length, "url too long, using the search route");
+                search = true;
+            } else if length > self.max_url_length {
+                invalid = Some(Error::QueryTooLong {
+                    path: path.to_string(),
+                    length,
+                    max: self.max_url_length,
+                });
+            }
+        }
+
+        Pages {
+            client: self,
+            path: path.to_string(),
+            query,
+            next: Some(first),
+            last_id: None,
+            resumed_after: None,
+            resumes: 0,
+            invalid,
+            parent: self
+                .request_id_header
+                .as_ref()
+                .map(|_| crate::request_id::new_id()),
+            started: Instant::now(),
+            deadline: None,
+            records: 0,
+            search,
+        }
+    }
+}
+
+impl Client {
+    /// The url of the first page [Client::get] (or [Client::pages]) requests for `path`, with
+    /// the `pageid` and the `orderby` added by [Client::stable_pagination].  Use this to log
+    /// or link to a list the way it is really requested, [Client::url_for] is the url as given
+    ///
+    /// # Example
+    /// ```
+    /// use cwmanage::Client;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .build();
+    /// let url = client
+    ///     .list_url_for("/service/tickets", &[("fields", "id")])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     url.as_str(),
+    ///     "https://api-na.myconnectwise.net/v4_6_release/apis/3.0/service/tickets?pageid=1&fields=id&orderby=id+asc"
+    /// );
+    /// ```
+    pub fn list_url_for(&self, path: &str, query: &[(&str, &str)]) -> Result<Url> {
+        let (query, first) = self.list_query(path, query)?;
+        self.list_url(path, &query, &first)
+    }
+
+    /// the url of the page `page` of a list
+    fn list_url(&self, path: &str, query: &[(String, String)], page: &str) -> Result<Url> {
+        let req = self
+            .http_request(reqwest::Method::GET, path)?
+            .query(&[("pageid", page)])
+            .query(query)
+            .build()?;
+        Ok(req.url().clone())
+    }
+
+    /// the query the pages of a list are requested with and the `pageid` of the first page.
+    /// An `orderby=id asc` is added when there isn't an `orderby` and the client paginates
+    /// stably (or resumes after an expired cursor, which needs the results in id order)
+    fn list_query(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<(Vec<(String, String)>, String), Error> {
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let first = take_page_id(&mut query)?.unwrap_or_else(|| "1".to_string());
+        let ordered = query.iter().any(|(k, _)| k.eq_ignore_ascii_case("orderby"));
+        if (self.stable_pagination || self.resume_on_cursor_expiry) && !ordered {
+            tracing::debug!(path, "no orderby given, adding orderby=id asc");
+            query.push(("orderby".to_string(), "id asc".to_string()));
+        }
+        Ok((query, first))
+    }
+}
+
+/// removes a `pageid` from the query (to start from) and checks the other pagination keys
+fn take_page_id(query: &mut Vec<(String, String)>) -> Result<Option<String>, Error> {
+    let invalid = |key: &str, reason: &str| Error::InvalidQuery {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    };
+    let count = |query: &[(String, String)], key: &str| {
+        query
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(key))
+            .count()
+    };
+
+    if count(query, "page") > 0 {
+        return Err(invalid(
+            "page",
+            "this client uses forward only pagination, use pages (or a pageid to start from)",
+        ));
+    }
+    for key in ["pageid", "pagesize"] {
+        if count(query, key) > 1 {
+            return Err(invalid(key, "given more than once"));
+        }
+    }
+
+    let page_id = query
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("pageid"))
+        .map(|(_, v)| v.trim().to_string());
+    query.retain(|(k, _)| !k.eq_ignore_ascii_case("pageid"));
+    Ok(page_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MAX_CURSOR_RESUMES;
+    use crate::test_util::{fixtures, MockCw};
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_pages() {
+        let mut mock = MockCw::new();
+        let next = format!(
+            "<{}/v4_6_release/apis/3.0/system/members?pageId=2>; rel=\"next\"",
+            mock.url()
+        );
+        let first = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/members")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "pageid".to_string(),
+                "1".to_string(),
+            ))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}, {"id": 2}]"#)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/members")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "pageid".to_string(),
+                "2".to_string(),
+            ))
+            .with_body(r#"[{"id": 3}]"#)
+            .create();
+        let client = mock.client();
+
+        let mut pages = client.pages("/system/members", &[("fields", "id")]);
+        assert_eq!(
+            pages.next().unwrap().unwrap(),
+            vec![json!({"id": 1}), json!({"id": 2})]
+        );
+        first.assert();
+        assert!(!second.matched());
+
+        assert_eq!(pages.next().unwrap().unwrap(), vec![json!({"id": 3})]);
+        assert!(pages.next().is_none());
+        second.assert();
+
+        let all = client.get("/system/members", &[("fields", "id")]).unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_pages_user_pagination() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let started = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Exact(
+                "pageid=5&fields=id&pagesize=2&orderby=id+asc".to_string(),
+            ))
+            .with_body(r#"[{"id": 9}, {"id": 10}]"#)
+            .expect(1)
+            .create();
+
+        let result = mock
+            .client()
+            .get(
+                "/service/tickets",
+                &[("fields", "id"), ("PageId", "5"), ("pagesize", "2")],
+            )
+            .unwrap();
+
+        started.assert();
+        assert_eq!(result, vec![json!({"id": 9}), json!({"id": 10})]);
+    }
+
+    #[test]
+    fn test_stable_pagination() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let ordered = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Exact(
+                "pageid=1&fields=id&orderBy=summary+desc".to_string(),
+            ))
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let unordered = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Exact("pageid=1&fields=id".to_string()))
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        client
+            .get(
+                "/service/tickets",
+                &[("fields", "id"), ("orderBy", "summary desc")],
+            )
+            .unwrap();
+        client
+            .clone()
+            .stable_pagination(false)
+            .get("/service/tickets", &[("fields", "id")])
+            .unwrap();
+
+        ordered.assert();
+        unordered.assert();
+        assert_eq!(
+            client
+                .list_url_for("/service/tickets", &[("pageid", "3")])
+                .unwrap()
+                .as_str(),
+            format!("{}{}?pageid=3&orderby=id+asc", mock.url(), path)
+        );
+    }
+
+    #[test]
+    fn test_pages_invalid_pagination() {
+        let mut mock = MockCw::new();
+        let nothing = mock.server().mock("GET", Matcher::Any).expect(0).create();
+        let client = mock.client();
+
+        let err = client
+            .get("/service/tickets", &[("page", "2")])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidQuery { key, .. }) if key == "page"
+        ));
+
+        let err = client
+            .get("/service/tickets", &[("pageid", "2"), ("pageid", "3")])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::InvalidQuery {
+                key: "pageid".to_string(),
+                reason: "given more than once".to_string(),
+            })
+        );
+
+        let mut pages = client.pages("/service/tickets", &[("pagesize", "1"), ("pagesize", "2")]);
+        assert!(pages.next().unwrap().is_err());
+        assert!(pages.next().is_none());
+        nothing.assert();
+    }
+
+    #[test]
+    fn test_get_latest() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let newest = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Exact(
+                "pageid=1&conditions=board%2Fid+%3D+3&orderby=_info%2FlastUpdated+desc&pagesize=2"
+                    .to_string(),
+            ))
+            .with_header(
+                "link",
+                &format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path),
+            )
+            .with_body(r#"[{"id": 9}, {"id": 4}]"#)
+            .expect(1)
+            .create();
+        let entered = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/time/entries")
+            .match_query(Matcher::Exact(
+                "pageid=1&orderby=dateEntered+desc&pagesize=1000".to_string(),
+            ))
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let tickets = client
+            .get_latest("/service/tickets", &[("conditions", "board/id = 3")], 2, "")
+            .unwrap();
+        let entries = client
+            .get_latest("/time/entries", &[], 5000, "dateEntered")
+            .unwrap();
+
+        newest.assert();
+        entered.assert();
+        assert_eq!(tickets, vec![json!({"id": 9}), json!({"id": 4})]);
+        assert_eq!(entries, vec![json!({"id": 1})]);
+        assert_eq!(
+            client.get_latest("/service/tickets", &[], 0, "").unwrap(),
+            Vec::<serde_json::Value>::new()
+        );
+
+        let err = client
+            .get_latest("/service/tickets", &[("OrderBy", "id asc")], 2, "")
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidQuery { key, .. }) if key == "OrderBy"
+        ));
+    }
+
+    #[test]
+    fn test_get_page() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let url = |page: u32| format!("<{}{}?page={}&pageSize=2>", mock.url(), path, page);
+        let page = |page: &str| {
+            Matcher::Exact(format!(
+                "page={}&pageSize=2&conditions=closedFlag+%3D+false&orderby=id+asc",
+                page
+            ))
+        };
+        let first_link = format!("{}; rel=\"next\", {}; rel=\"last\"", url(2), url(2));
+        let last_link = format!("{}; rel=\"first\", {}; rel=\"prev\"", url(1), url(1));
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("1"))
+            .match_header("pagination-type", Matcher::Missing)
+            .with_header("link", &first_link)
+            .with_body(r#"[{"id": 4}, {"id": 5}]"#)
+            .expect(1)
+            .create();
+        let last = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("2"))
+            .match_header("pagination-type", Matcher::Missing)
+            .with_header("link", &last_link)
+            .with_body(r#"[{"id": 9}]"#)
+            .expect(1)
+            .create();
+        let past = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("3"))
+            .with_body("[]")
+            .expect(1)
+            .create();
+        let clamped = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/time/entries")
+            .match_query(Matcher::Exact(
+                "page=1&pageSize=1000&orderby=id+asc".to_string(),
+            ))
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        // each page is one request, asked for by number
+        let query = [("conditions", "closedFlag = false")];
+        let (tickets, next) = client.get_page("/service/tickets", &query, 1, 2).unwrap();
+        assert_eq!(tickets, vec![json!({"id": 4}), json!({"id": 5})]);
+        assert_eq!(next.as_deref(), Some("2"));
+        let (tickets, next) = client.get_page("/service/tickets", &query, 2, 2).unwrap();
+        assert_eq!(tickets, vec![json!({"id": 9})]);
+        assert_eq!(next, None);
+        let (tickets, next) = client.get_page("/service/tickets", &query, 3, 2).unwrap();
+        assert!(tickets.is_empty());
+        assert_eq!(next, None);
+        let (entries, _) = client.get_page("/time/entries", &[], 1, 5000).unwrap();
+        assert_eq!(entries, vec![json!({"id": 1})]);
+
+        for (query, page, size, key) in [
+            (vec![("pageSize", "10")], 1, 10, "pageSize"),
+            (vec![("pageId", "5")], 1, 10, "pageId"),
+            (vec![("page", "2")], 1, 10, "page"),
+            (vec![], 0, 10, "page"),
+            (vec![], 1, 0, "pagesize"),
+        ] {
+            let err = client
+                .get_page("/service/tickets", &query, page, size)
+                .unwrap_err();
+            assert!(
+                matches!(
+                    err.downcast_ref::<Error>(),
+                    Some(Error::InvalidQuery { key: k, .. }) if k == key
+                ),
+                "{}",
+                err
+            );
+        }
+
+        first.assert();
+        last.assert();
+        past.assert();
+        clamped.assert();
+    }
+
+    #[test]
+    fn test_get_one() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/company/companies";
+        let page = |conditions: &str, size: &str| {
+            Matcher::AllOf(vec![
+                Matcher::UrlEncoded("pageid".to_string(), "1".to_string()),
+                Matcher::UrlEncoded("conditions".to_string(), conditions.to_string()),
+                Matcher::UrlEncoded("pagesize".to_string(), size.to_string()),
+            ])
+        };
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("deletedFlag = false", "1"))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let unique = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("identifier = 'myco'", "2"))
+            .with_body(r#"[{"id": 7, "identifier": "myco"}]"#)
+            .expect(1)
+            .create();
+        let twice = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("name like 'my%'", "2"))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 7}, {"id": 8}]"#)
+            .expect(1)
+            .create();
+        let nothing = mock
+            .server()
+            .mock("GET", path)
+            .match_query(page("identifier = 'nope'", "1"))
+            .with_body("[]")
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let one =
+            |conditions: &str| client.get_one("/company/companies", &[("conditions", conditions)]);
+        let exactly_one = |conditions: &str| {
+            client.get_exactly_one("/company/companies", &[("conditions", conditions)])
+        };
+        // the next page isn't followed
+        assert_eq!(one("deletedFlag = false").unwrap(), Some(json!({"id": 1})));
+        assert_eq!(one("identifier = 'nope'").unwrap(), None);
+        assert_eq!(
+            exactly_one("identifier = 'myco'").unwrap(),
+            Some(json!({"id": 7, "identifier": "myco"}))
+        );
+        let err = exactly_one("name like 'my%'").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::NotUnique {
+                path: "/company/companies".to_string()
+            })
+        );
+        let err = client
+            .get_one("/company/companies", &[("pageSize", "5")])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidQuery { key, .. }) if key == "pageSize"
+        ));
+
+        first.assert();
+        unique.assert();
+        twice.assert();
+        nothing.assert();
+    }
+
+    #[test]
+    fn test_resume_on_cursor_expiry() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let link = |page: &str| format!("<{}{}?pageId={}>; rel=\"next\"", mock.url(), path, page);
+        let query = |page: &str, conditions: &str| {
+            Matcher::AllOf(vec![
+                Matcher::UrlEncoded("pageid".to_string(), page.to_string()),
+                Matcher::UrlEncoded("orderby".to_string(), "id asc".to_string()),
+                Matcher::UrlEncoded("conditions".to_string(), conditions.to_string()),
+            ])
+        };
+        let (link2, link3) = (link("2"), link("3"));
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(query("1", "board/id = 1"))
+            .with_header("link", &link2)
+            .with_body(r#"[{"id": 1}, {"id": 2}]"#)
+            .expect(1)
+            .create();
+        let second = mock
+            .server()
+            .mock("GET", path)
+            .match_query(query("2", "board/id = 1"))
+            .with_header("link", &link3)
+            .with_body(r#"[{"id": 3}, {"id": 4}]"#)
+            .expect(1)
+            .create();
+        let expired = mock
+            .server()
+            .mock("GET", path)
+            .match_query(query("3", "board/id = 1"))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "pageId is not valid"}"#)
+            .expect(1)
+            .create();
+        let resumed = mock
+            .server()
+            .mock("GET", path)
+            .match_query(query("1", "(board/id = 1) AND id > 4"))
+            .with_body(r#"[{"id": 5}, {"id": 6}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client().resume_on_cursor_expiry(true);
+
+        let all = client
+            .get("/service/tickets", &[("conditions", "board/id = 1")])
+            .unwrap();
+
+        first.assert();
+        second.assert();
+        expired.assert();
+        resumed.assert();
+        let ids: Vec<i64> = all.iter().map(|t| t["id"].as_i64().unwrap()).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_cursor_expiry_without_resume() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let _first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .create();
+        let _expired = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "pageId is not valid"}"#)
+            .create();
+
+        let err = mock
+            .client()
+            .get("/service/tickets", &[("", "")])
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::CursorExpired {
+                path: "/service/tickets".to_string(),
+                page: "2".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cursor_always_expired() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(2)
+            .create();
+        let expired = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "pageId is not valid"}"#)
+            .expect(2)
+            .create();
+        let client = mock.client().resume_on_cursor_expiry(true);
+
+        // the fresh list didn't get past id 1, so it isn't resumed again
+        let err = client.get("/service/tickets", &[]).unwrap_err();
+
+        first.assert();
+        expired.assert();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::CursorExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_cursor_resumes_are_capped() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let id = std::sync::atomic::AtomicI64::new(0);
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body_from_request(move |_| {
+                let id = id.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                json!([{ "id": id }]).to_string().into_bytes()
+            })
+            .expect(MAX_CURSOR_RESUMES as usize + 1)
+            .create();
+        let expired = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "pageId is not valid"}"#)
+            .expect(MAX_CURSOR_RESUMES as usize + 1)
+            .create();
+        let client = mock.client().resume_on_cursor_expiry(true);
+
+        let err = client.get("/service/tickets", &[]).unwrap_err();
+
+        first.assert();
+        expired.assert();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::CursorExpired { .. })
+        ));
+    }
+
+    #[test]
+    fn test_page_error_isnt_cursor_expiry() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let failed = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_status(400)
+            .with_body(fixtures::api_error("InvalidObject", "board/id is not valid").to_string())
+            .expect(1)
+            .create();
+
+        // only an error about the pageId is an expired cursor, this one isn't resumed
+        let err = mock
+            .client()
+            .resume_on_cursor_expiry(true)
+            .get("/service/tickets", &[("", "")])
+            .unwrap_err();
+
+        first.assert();
+        failed.assert();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Api {
+                status: 400, error, ..
+            }) => assert_eq!(error.message, "board/id is not valid"),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deadline_exceeded() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let _first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}, {"id": 2}]"#)
+            .create();
+        let _slow = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_body_from_request(|_| {
+                std::thread::sleep(Duration::from_secs(2));
+                b"[]".to_vec()
+            })
+            .create();
+        let client = mock.client().max_retries(3);
+
+        let started = Instant::now();
+        let mut pages = client
+            .pages("/service/tickets", &[("", "")])
+            .deadline(Duration::from_millis(500));
+        assert_eq!(pages.next().unwrap().unwrap().len(), 2);
+        let err = pages.next().unwrap().unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_millis(1500));
+        assert!(pages.next().is_none());
+        match err.downcast_ref::<Error>() {
+            Some(Error::DeadlineExceeded {
+                records_collected,
+                resume_cursor,
+                ..
+            }) => {
+                assert_eq!(*records_collected, 2);
+                assert_eq!(resume_cursor.as_deref(), Some("2"));
+            }
+            other => panic!("expected a deadline error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_deadline_checked_before_first_page() {
+        let mut mock = MockCw::new();
+        let nothing = mock.server().mock("GET", Matcher::Any).expect(0).create();
+
+        let err = mock
+            .client()
+            .get_with_deadline("/service/tickets", &[("", "")], Duration::ZERO)
+            .unwrap_err();
+
+        nothing.assert();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::DeadlineExceeded { records_collected: 0, resume_cursor: Some(page), .. })
+                if page == "1"
+        ));
+    }
+}
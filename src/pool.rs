@@ -0,0 +1,246 @@
+//! Clients for many connectwise instances (tenants) in one process.  See [ClientPool]
+use crate::{shared, Client, Region};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// The credentials and instance of one tenant of a [ClientPool]
+///
+/// ```
+/// use cwmanage::TenantConfig;
+///
+/// let config = r#"{"acme": {"company_id": "acme", "public_key": "pub",
+///     "private_key": "priv", "client_id": "id", "region": "EU"}}"#;
+/// let tenants: std::collections::HashMap<String, TenantConfig> =
+///     serde_json::from_str(config).unwrap();
+/// assert_eq!(tenants["acme"].region, Some(cwmanage::Region::Eu));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// the company id (the short name used to log in)
+    pub company_id: String,
+    /// the public key of the api member
+    pub public_key: String,
+    /// the private key of the api member
+    pub private_key: String,
+    /// the client id, see [Client::new]
+    pub client_id: String,
+    /// the cloud region of the instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+    /// the host (or url) of the instance when it isn't a cloud region, see [Client::api_url]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+#[derive(Debug)]
+struct Tenant {
+    config: TenantConfig,
+    client: Option<Arc<Client>>,
+}
+
+/// A [Client] per tenant, each made the first time it is asked for.  The clients share one
+/// http connection pool, and with [ClientPool::max_requests_per_second] each tenant has its
+/// own limit so a busy tenant doesn't slow the others down.  A tenant's keys can be swapped
+/// with [ClientPool::reload] while the pool is in use
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::{ClientPool, TenantConfig};
+/// use std::collections::HashMap;
+///
+/// let config = std::fs::read_to_string("tenants.json").unwrap();
+/// let tenants: HashMap<String, TenantConfig> = serde_json::from_str(&config).unwrap();
+/// let pool = ClientPool::new(tenants).unwrap().max_requests_per_second(5);
+///
+/// let acme = pool.client("acme").unwrap();
+/// let info = acme.get_single("/system/info", &[]).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ClientPool {
+    tenants: Mutex<BTreeMap<String, Tenant>>,
+    http: shared::Http,
+    per_second: u32,
+}
+
+impl ClientPool {
+    /// Creates a pool of the tenants in `tenants`, by their keys
+    pub fn new(tenants: HashMap<String, TenantConfig>) -> Result<ClientPool> {
+        let tenants = tenants
+            .into_iter()
+            .map(|(key, config)| {
+                let tenant = Tenant {
+                    config,
+                    client: None,
+                };
+                (key, tenant)
+            })
+            .collect();
+        let http = shared::Http::default();
+        http.get()?;
+        Ok(ClientPool {
+            tenants: Mutex::new(tenants),
+            http,
+            per_second: 0,
+        })
+    }
+
+    /// limits each tenant to `per_second` requests a second, see
+    /// [Client::max_requests_per_second].  No limit by default
+    pub fn max_requests_per_second(mut self, per_second: u32) -> ClientPool {
+        self.per_second = per_second;
+        self
+    }
+
+    /// The client of the tenant `key`, made from its config the first time.  The client
+    /// keeps the keys it was made with, after a [ClientPool::reload] ask for it again
+    pub fn client(&self, key: &str) -> Result<Arc<Client>> {
+        let mut tenants = self.tenants.lock().unwrap();
+        let tenant = tenants
+            .get_mut(key)
+            .ok_or_else(|| anyhow!("there is no tenant {:?} in the pool", key))?;
+        if let Some(client) = &tenant.client {
+            return Ok(client.clone());
+        }
+        let config = &tenant.config;
+        let client = Client::try_new(
+            config.company_id.clone(),
+            config.public_key.clone(),
+            config.private_key.clone(),
+            config.client_id.clone(),
+        )?
+        .http(self.http.clone())
+        .max_requests_per_second(self.per_second);
+        let client = match (&config.api_url, config.region) {
+            (Some(api_url), _) => client.api_url(api_url.clone()),
+            (None, Some(region)) => client.region(region),
+            (None, None) => client,
+        };
+        let client = Arc::new(client);
+        tenant.client = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Adds a tenant, or replaces its config (new keys, a new instance).  The next
+    /// [ClientPool::client] for it is made with the new config, clients already handed out
+    /// keep the old one
+    pub fn reload(&self, key: &str, config: TenantConfig) {
+        let tenant = Tenant {
+            config,
+            client: None,
+        };
+        self.tenants.lock().unwrap().insert(key.to_string(), tenant);
+    }
+
+    /// Removes a tenant, true if it was in the pool
+    pub fn remove(&self, key: &str) -> bool {
+        self.tenants.lock().unwrap().remove(key).is_some()
+    }
+
+    /// The keys of the tenants, sorted
+    pub fn tenants(&self) -> Vec<String> {
+        self.tenants.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use std::time::{Duration, Instant};
+
+    fn config(server: &mockito::Server, company_id: &str, public_key: &str) -> TenantConfig {
+        TenantConfig {
+            company_id: company_id.to_string(),
+            public_key: public_key.to_string(),
+            private_key: "private".to_string(),
+            client_id: "clientid".to_string(),
+            region: None,
+            api_url: Some(server.url()),
+        }
+    }
+
+    fn auth(company_id: &str, public_key: &str) -> String {
+        format!(
+            "Basic {}",
+            base64::encode(format!("{}+{}:private", company_id, public_key))
+        )
+    }
+
+    #[test]
+    fn test_client_pool() {
+        let mut server = mockito::Server::new();
+        let acme = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .match_header("authorization", auth("acme", "old").as_str())
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .expect(1)
+            .create();
+        let reloaded = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .match_header("authorization", auth("acme", "new").as_str())
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .expect(1)
+            .create();
+        let pool = ClientPool::new(HashMap::from([
+            ("acme".to_string(), config(&server, "acme", "old")),
+            ("globex".to_string(), config(&server, "globex", "pub")),
+        ]))
+        .unwrap();
+
+        let first = pool.client("acme").unwrap();
+        let again = pool.client("acme").unwrap();
+        first.get_single("/system/info", &[]).unwrap();
+        pool.reload("acme", config(&server, "acme", "new"));
+        pool.client("acme")
+            .unwrap()
+            .get_single("/system/info", &[])
+            .unwrap();
+
+        assert!(Arc::ptr_eq(&first, &again));
+        // every tenant sends with the pool's http client
+        let globex = pool.client("globex").unwrap();
+        assert!(Arc::ptr_eq(&first.http.0, &pool.http.0));
+        assert!(Arc::ptr_eq(&globex.http.0, &pool.http.0));
+        assert_eq!(pool.tenants(), vec!["acme", "globex"]);
+        assert!(pool.client("initech").is_err());
+        assert!(pool.remove("globex"));
+        assert!(pool.client("globex").is_err());
+        acme.assert();
+        reloaded.assert();
+    }
+
+    #[test]
+    fn test_client_pool_rate_limit() {
+        let mut server = mockito::Server::new();
+        let _info = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .expect(4)
+            .create();
+        let pool = ClientPool::new(HashMap::from([
+            ("acme".to_string(), config(&server, "acme", "pub")),
+            ("globex".to_string(), config(&server, "globex", "pub")),
+        ]))
+        .unwrap()
+        .max_requests_per_second(2);
+        let acme = pool.client("acme").unwrap();
+        let globex = pool.client("globex").unwrap();
+
+        let start = Instant::now();
+        acme.get_single("/system/info", &[]).unwrap();
+        globex.get_single("/system/info", &[]).unwrap();
+        let separate = start.elapsed();
+        acme.get_single("/system/info", &[]).unwrap();
+        acme.get_single("/system/info", &[]).unwrap();
+        let limited = start.elapsed();
+
+        assert!(separate < Duration::from_millis(450), "{:?}", separate);
+        assert!(limited >= Duration::from_millis(1000), "{:?}", limited);
+    }
+}
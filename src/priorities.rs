@@ -0,0 +1,160 @@
+//! Service priorities and the impact / severity levels of a ticket.  See
+//! [Client::get_priorities] and [crate::NewTicket::priority]
+use crate::reference::find_by_name;
+use crate::{Client, CwRef, Error};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const PRIORITIES_PATH: &str = "/service/priorities";
+const MATRIX_PATH: &str = "/service/priorityMatrix";
+
+/// A service priority (`/service/priorities`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Priority {
+    /// the id of the priority
+    pub id: i64,
+    /// the name of the priority (`Priority 2 - Quick Response`)
+    #[serde(default)]
+    pub name: String,
+    /// the color it is shown in (`Red`, `#FF0000`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// where it sorts, lower is more urgent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i64>,
+    /// true for the priority new tickets get
+    #[serde(default)]
+    pub default_flag: bool,
+}
+
+/// The impact or severity of a ticket, connectwise sends them as `Low`, `Medium` and `High`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicketLevel {
+    /// `Low`
+    Low,
+    /// `Medium`
+    Medium,
+    /// `High`
+    High,
+}
+
+/// One cell of the priority matrix: the priority a ticket gets for its impact and severity
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriorityMatrixEntry {
+    /// the impact of the ticket
+    pub impact: TicketLevel,
+    /// the severity of the ticket
+    pub severity: TicketLevel,
+    /// the priority it gets
+    pub priority: CwRef,
+}
+
+impl Client {
+    /// Gets every service priority, most urgent (lowest [Priority::sort_order]) first
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for priority in client.get_priorities().unwrap() {
+    ///     println!("{} ({:?})", priority.name, priority.color);
+    /// }
+    /// ```
+    pub fn get_priorities(&self) -> Result<Vec<Priority>> {
+        self.get_decoded(PRIORITIES_PATH, &[("orderby", "sortOrder asc")])
+    }
+
+    /// Finds the priority called `name`, ignoring case.  [crate::Error::UnknownName], with
+    /// the names of the priorities there are, if there isn't one
+    pub fn find_priority(&self, name: &str) -> Result<Priority> {
+        let priorities = self.get_priorities()?;
+        find_by_name(&priorities, PRIORITIES_PATH, name, |p| &p.name).cloned()
+    }
+
+    /// Gets the priority matrix, which priority a ticket gets for its impact and severity.
+    /// Empty when the instance doesn't use one
+    pub fn get_priority_matrix(&self) -> Result<Vec<PriorityMatrixEntry>> {
+        match self.get_decoded(MATRIX_PATH, &[("", "")]) {
+            Err(e)
+                if matches!(
+                    e.downcast_ref(),
+                    Some(Error::NotFound { .. } | Error::Api { status: 404, .. })
+                ) =>
+            {
+                Ok(Vec::new())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw};
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_priorities() {
+        let mock = MockCw::new()
+            .list(
+                "/service/priorities",
+                vec![vec![
+                    json!({"id": 1, "name": "Priority 1 - Emergency Response", "color": "Red", "sortOrder": 1}),
+                    json!({"id": 4, "name": "Priority 3 - Normal Response", "color": "Green", "sortOrder": 3, "defaultFlag": true}),
+                ]],
+            )
+            .list(
+                "/service/priorityMatrix",
+                vec![vec![
+                    json!({"impact": "High", "severity": "High", "priority": {"id": 1, "name": "Priority 1 - Emergency Response"}}),
+                ]],
+            );
+        let client = mock.client();
+
+        let priorities = client.get_priorities().unwrap();
+        assert_eq!(priorities[0].color.as_deref(), Some("Red"));
+        assert!(priorities[1].default_flag);
+        assert_eq!(
+            client
+                .find_priority("priority 3 - normal response")
+                .unwrap()
+                .id,
+            4
+        );
+        assert_eq!(
+            client.find_priority("Priority 2").unwrap_err().to_string(),
+            "no \"Priority 2\" in /service/priorities, it has Priority 1 - Emergency \
+             Response, Priority 3 - Normal Response"
+        );
+
+        let matrix = client.get_priority_matrix().unwrap();
+        assert_eq!(matrix[0].impact, TicketLevel::High);
+        assert_eq!(matrix[0].priority.id, 1);
+    }
+
+    #[test]
+    fn test_priority_matrix_not_enabled() {
+        let mock = MockCw::new().error(
+            "GET",
+            "/service/priorityMatrix",
+            404,
+            fixtures::api_error("NotFound", "not found"),
+        );
+
+        assert!(mock.client().get_priority_matrix().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,259 @@
+//! Receiving product against purchase orders.  See [Client::receive_po_line]
+use crate::{Client, CwRef, Error, PatchOp, PatchOperation};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A line of a purchase order (`/procurement/purchaseorders/{id}/lineitems`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoLineItem {
+    /// the id of the line
+    pub id: i64,
+    /// the purchase order the line is on
+    #[serde(default)]
+    pub purchase_order_id: i64,
+    /// the product ordered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub product: Option<CwRef>,
+    /// the description of the line
+    #[serde(default)]
+    pub description: String,
+    /// how many were ordered
+    #[serde(default)]
+    pub quantity: f64,
+    /// how many have been received so far
+    #[serde(default)]
+    pub received_quantity: f64,
+    /// how many were cancelled
+    #[serde(default)]
+    pub canceled_quantity: f64,
+    /// `Waiting`, `FullyReceived`, ...
+    #[serde(default)]
+    pub received_status: String,
+    /// the serial numbers received, separated by commas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_numbers: Option<String>,
+    /// true once the line is closed
+    #[serde(default)]
+    pub closed_flag: bool,
+}
+
+impl PoLineItem {
+    /// how many are still expected
+    pub fn remaining(&self) -> f64 {
+        (self.quantity - self.received_quantity - self.canceled_quantity).max(0.0)
+    }
+}
+
+fn line_items_path(po_id: i64) -> String {
+    format!("/procurement/purchaseorders/{}/lineitems", po_id)
+}
+
+impl Client {
+    /// Gets the lines of a purchase order that are still waiting for product
+    pub fn list_unreceived_lines(&self, po_id: i64) -> Result<Vec<PoLineItem>> {
+        let lines: Vec<PoLineItem> = self.get_decoded(&line_items_path(po_id), &[("", "")])?;
+        Ok(lines
+            .into_iter()
+            .filter(|l| !l.closed_flag && l.remaining() > 0.0)
+            .collect())
+    }
+
+    /// Receives `quantity` of a purchase order line (on top of what was already received)
+    /// with the serial numbers of what arrived, if it has them, and returns the line.  The
+    /// line is read first, receiving more than is still expected is [Error::OverReceived]
+    /// and nothing is sent.  When connectwise refuses the update the error is
+    /// [Error::Api] with its message
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for line in client.list_unreceived_lines(17).unwrap() {
+    ///     println!("{}: {} to go", line.description, line.remaining());
+    /// }
+    /// let line = client.receive_po_line(17, 3, 2.0, &["SN001", "SN002"]).unwrap();
+    /// println!("now {}", line.received_status);
+    /// ```
+    pub fn receive_po_line(
+        &self,
+        po_id: i64,
+        line_id: i64,
+        quantity: f64,
+        serial_numbers: &[&str],
+    ) -> Result<PoLineItem> {
+        if quantity <= 0.0 {
+            return Err(anyhow!("the quantity received must be more than 0"));
+        }
+        if !serial_numbers.is_empty() && serial_numbers.len() as f64 != quantity {
+            return Err(anyhow!(
+                "{} serial numbers given for a quantity of {}",
+                serial_numbers.len(),
+                quantity
+            ));
+        }
+        let path = format!("{}/{}", line_items_path(po_id), line_id);
+        let line: PoLineItem = self.decode(&path, self.get_single(&path, &[])?)?;
+        if quantity > line.remaining() {
+            return Err(Error::OverReceived {
+                line_id,
+                remaining: line.remaining(),
+                quantity,
+            }
+            .into());
+        }
+
+        let received = line.received_quantity + quantity;
+        let mut ops = vec![PatchOperation::new(
+            PatchOp::Replace,
+            "receivedQuantity",
+            json!(received),
+        )];
+        if quantity >= line.remaining() {
+            ops.push(PatchOperation::new(
+                PatchOp::Replace,
+                "receivedStatus",
+                json!("FullyReceived"),
+            ));
+        }
+        if !serial_numbers.is_empty() {
+            let serials = line
+                .serial_numbers
+                .iter()
+                .map(|s| s.as_str())
+                .filter(|s| !s.trim().is_empty())
+                .chain(serial_numbers.iter().copied())
+                .collect::<Vec<&str>>()
+                .join(",");
+            ops.push(PatchOperation::new(
+                PatchOp::Replace,
+                "serialNumbers",
+                json!(serials),
+            ));
+        }
+
+        let body = serde_json::to_string(&ops)?;
+        let line = self.write_checked(reqwest::Method::PATCH, &path, Some(body))?;
+        self.decode(&path, line)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    const LINE: &str = r#"{"id": 3, "purchaseOrderId": 17, "description": "laptop",
+        "quantity": 5, "receivedQuantity": 2, "receivedStatus": "Waiting",
+        "serialNumbers": "SN001,SN002"}"#;
+
+    #[test]
+    fn test_receive_po_line() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/procurement/purchaseorders/17/lineitems/3";
+        let _line = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Any)
+            .with_body(LINE)
+            .create();
+        let receive = mock
+            .server()
+            .mock("PATCH", path)
+            .match_body(Matcher::Json(json!([
+                {"op": "replace", "path": "receivedQuantity", "value": 5.0},
+                {"op": "replace", "path": "receivedStatus", "value": "FullyReceived"},
+                {"op": "replace", "path": "serialNumbers", "value": "SN001,SN002,SN003,SN004,SN005"}
+            ])))
+            .with_body(LINE.replace("\"Waiting\"", "\"FullyReceived\""))
+            .create();
+        let client = mock.client();
+
+        let line = client
+            .receive_po_line(17, 3, 3.0, &["SN003", "SN004", "SN005"])
+            .unwrap();
+
+        receive.assert();
+        assert_eq!(line.received_status, "FullyReceived");
+    }
+
+    #[test]
+    fn test_over_receive_and_api_errors() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/procurement/purchaseorders/17/lineitems/3";
+        let _line = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::Any)
+            .with_body(LINE)
+            .create();
+        let refused = mock
+            .server()
+            .mock("PATCH", path)
+            .with_status(400)
+            .with_body(
+                r#"{"code": "InvalidObject", "message": "purchaseOrderLineItem object is invalid",
+                    "errors": [{"code": "InvalidField", "message": "The warehouse bin is closed",
+                    "resource": "purchaseOrderLineItem", "field": "warehouseBin"}]}"#,
+            )
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let err = client.receive_po_line(17, 3, 4.0, &[]).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::OverReceived {
+                line_id: 3,
+                remaining: 3.0,
+                quantity: 4.0,
+            })
+        );
+        assert!(client.receive_po_line(17, 3, 1.0, &["A", "B"]).is_err());
+
+        let err = client.receive_po_line(17, 3, 1.0, &[]).unwrap_err();
+        refused.assert();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Api { error, .. }) => {
+                assert_eq!(error.errors[0].message, "The warehouse bin is closed")
+            }
+            other => panic!("expected an api error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_unreceived_lines() {
+        let mut mock = MockCw::new();
+        let _lines = mock
+            .server()
+            .mock(
+                "GET",
+                "/v4_6_release/apis/3.0/procurement/purchaseorders/17/lineitems",
+            )
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "quantity": 2, "receivedQuantity": 2},
+                    {"id": 2, "quantity": 2, "receivedQuantity": 1},
+                    {"id": 3, "quantity": 2, "canceledQuantity": 2},
+                    {"id": 4, "quantity": 2, "closedFlag": true}]"#,
+            )
+            .create();
+
+        let lines = mock.client().list_unreceived_lines(17).unwrap();
+
+        assert_eq!(lines.iter().map(|l| l.id).collect::<Vec<i64>>(), vec![2]);
+    }
+}
@@ -0,0 +1,484 @@
+//! Project work plans: phases and the tickets in them.  See [Client::get_project_work_plan]
+//! and [Client::get_project_hours_rollup]
+use crate::{Client, CwRef, TimeEntry};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A phase as `/project/projects/{id}/phases` returns it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectPhase {
+    /// the id of the phase
+    pub id: i64,
+    /// the name of the phase
+    #[serde(default)]
+    pub description: String,
+    /// the phase this one is under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_phase: Option<CwRef>,
+    /// the position in the work plan (`1.2`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wbs_code: Option<String>,
+    /// the status of the phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<CwRef>,
+    /// the hours budgeted for the phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_hours: Option<f64>,
+}
+
+/// A ticket on a project (`/project/tickets`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTicket {
+    /// the id of the ticket
+    pub id: i64,
+    /// the summary of the ticket
+    #[serde(default)]
+    pub summary: String,
+    /// the phase the ticket is in, if it is in one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<CwRef>,
+    /// the status of the ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<CwRef>,
+    /// the position in the work plan (`1.2.3`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wbs_code: Option<String>,
+    /// the hours budgeted for the ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_hours: Option<f64>,
+    /// true once the ticket is closed
+    #[serde(default)]
+    pub closed_flag: bool,
+}
+
+/// A phase in a [WorkPlan] with the phases under it and its tickets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    /// the phase
+    pub phase: ProjectPhase,
+    /// the phases under this one
+    pub children: Vec<Phase>,
+    /// the tickets in this phase (not the ones in the phases under it)
+    pub tickets: Vec<ProjectTicket>,
+}
+
+impl Phase {
+    /// This phase and every phase under it, parents before their children
+    pub fn iter(&self) -> Vec<&Phase> {
+        let mut phases = vec![self];
+        for child in self.children.iter() {
+            phases.extend(child.iter());
+        }
+        phases
+    }
+}
+
+/// The work plan of a project, from [Client::get_project_work_plan]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkPlan {
+    /// the project
+    pub project_id: i64,
+    /// the top level phases
+    pub phases: Vec<Phase>,
+    /// the tickets that aren't in a phase
+    pub unphased: Vec<ProjectTicket>,
+}
+
+impl WorkPlan {
+    /// Finds a phase anywhere in the plan
+    pub fn phase(&self, id: i64) -> Option<&Phase> {
+        self.phases
+            .iter()
+            .flat_map(|p| p.iter())
+            .find(|p| p.phase.id == id)
+    }
+}
+
+/// The hours of one phase in a [ProjectHoursRollup].  These are the phase's own figures, the
+/// phases under it aren't added in
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseHours {
+    /// the phase
+    pub phase_id: i64,
+    /// the name of the phase
+    pub description: String,
+    /// the phase this one is under
+    pub parent_phase_id: Option<i64>,
+    /// the hours budgeted
+    pub budget_hours: f64,
+    /// the hours entered against the phase and its tickets
+    pub actual_hours: f64,
+    /// budgeted less actual, negative when over budget
+    pub remaining_hours: f64,
+}
+
+/// Budgeted against actual hours of a project, from [Client::get_project_hours_rollup]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProjectHoursRollup {
+    /// the project
+    pub project_id: i64,
+    /// the budgets of the phases added up
+    pub budget_hours: f64,
+    /// every hour entered against the project, in a phase or not
+    pub actual_hours: f64,
+    /// budgeted less actual, negative when over budget
+    pub remaining_hours: f64,
+    /// each phase, in the order connectwise lists them
+    pub phases: Vec<PhaseHours>,
+    /// the hours that aren't in any phase
+    pub unphased_hours: f64,
+    /// the time entries that aren't in any phase
+    pub unphased_entries: Vec<TimeEntry>,
+}
+
+impl Client {
+    /// Gets the phases of a project, in the order connectwise lists them
+    pub fn get_project_phases(&self, project_id: i64) -> Result<Vec<ProjectPhase>> {
+        let path = format!("/project/projects/{}/phases", project_id);
+        self.get_decoded(&path, &[("orderby", "id asc")])
+    }
+
+    /// Gets the tickets of a project
+    pub fn get_project_tickets(&self, project_id: i64) -> Result<Vec<ProjectTicket>> {
+        let conditions = format!("project/id = {}", project_id);
+        self.get_decoded(
+            "/project/tickets",
+            &[("conditions", &conditions), ("orderby", "id asc")],
+        )
+    }
+
+    /// Gets the work plan of a project: its phases as a tree (from each phase's
+    /// `parentPhase`) with the tickets in each.  Tickets that aren't in a phase (or are in
+    /// one the project doesn't list) are in [WorkPlan::unphased], a phase whose parent isn't
+    /// listed is at the top.  Phases that are (through their parents) their own parent are an
+    /// error
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, Phase};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// fn show(phase: &Phase, depth: usize) {
+    ///     println!("{}{}", "  ".repeat(depth), phase.phase.description);
+    ///     for ticket in phase.tickets.iter() {
+    ///         println!("{}- {}", "  ".repeat(depth + 1), ticket.summary);
+    ///     }
+    ///     for child in phase.children.iter() {
+    ///         show(child, depth + 1);
+    ///     }
+    /// }
+    ///
+    /// let plan = client.get_project_work_plan(42).unwrap();
+    /// for phase in plan.phases.iter() {
+    ///     show(phase, 0);
+    /// }
+    /// ```
+    pub fn get_project_work_plan(&self, project_id: i64) -> Result<WorkPlan> {
+        let phases = self.get_project_phases(project_id)?;
+        let tickets = self.get_project_tickets(project_id)?;
+        work_plan(project_id, phases, tickets)
+    }
+
+    /// Gets the time entered against a project
+    pub fn get_project_time_entries(&self, project_id: i64) -> Result<Vec<TimeEntry>> {
+        let conditions = format!("project/id = {}", project_id);
+        self.get_decoded(
+            "/time/entries",
+            &[("conditions", &conditions), ("orderby", "id asc")],
+        )
+    }
+
+    /// Adds up the hours entered against a project, for each phase and for the whole project,
+    /// next to the hours budgeted for the phases.  Time against a ticket counts for the
+    /// ticket's phase.  Time that isn't in any phase is counted in the project's total and
+    /// listed in [ProjectHoursRollup::unphased_entries]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let rollup = client.get_project_hours_rollup(42).unwrap();
+    /// for phase in rollup.phases.iter() {
+    ///     println!(
+    ///         "{}: {} of {} hours",
+    ///         phase.description, phase.actual_hours, phase.budget_hours
+    ///     );
+    /// }
+    /// println!("{} hours outside any phase", rollup.unphased_hours);
+    /// ```
+    pub fn get_project_hours_rollup(&self, project_id: i64) -> Result<ProjectHoursRollup> {
+        let phases = self.get_project_phases(project_id)?;
+        let tickets = self.get_project_tickets(project_id)?;
+        let entries = self.get_project_time_entries(project_id)?;
+        Ok(hours_rollup(project_id, phases, tickets, entries))
+    }
+}
+
+fn hours_rollup(
+    project_id: i64,
+    phases: Vec<ProjectPhase>,
+    tickets: Vec<ProjectTicket>,
+    entries: Vec<TimeEntry>,
+) -> ProjectHoursRollup {
+    let mut actual: HashMap<i64, f64> = phases.iter().map(|p| (p.id, 0.0)).collect();
+    let phase_of_ticket: HashMap<i64, i64> = tickets
+        .iter()
+        .filter_map(|t| Some((t.id, t.phase.as_ref()?.id)))
+        .collect();
+
+    let mut rollup = ProjectHoursRollup {
+        project_id,
+        budget_hours: 0.0,
+        actual_hours: 0.0,
+        remaining_hours: 0.0,
+        phases: Vec::new(),
+        unphased_hours: 0.0,
+        unphased_entries: Vec::new(),
+    };
+    for entry in entries {
+        rollup.actual_hours += entry.actual_hours;
+        let ticket =
+            entry
+                .ticket
+                .as_ref()
+                .map(|t| t.id)
+                .or(match entry.charge_to_type.as_deref() {
+                    Some("ProjectTicket") => entry.charge_to_id,
+                    _ => None,
+                });
+        let phase = entry
+            .phase
+            .as_ref()
+            .map(|p| p.id)
+            .or_else(|| phase_of_ticket.get(&ticket?).copied());
+        match phase.and_then(|p| actual.get_mut(&p)) {
+            Some(hours) => *hours += entry.actual_hours,
+            None => {
+                rollup.unphased_hours += entry.actual_hours;
+                rollup.unphased_entries.push(entry);
+            }
+        }
+    }
+
+    for phase in phases {
+        let budget_hours = phase.budget_hours.unwrap_or_default();
+        let actual_hours = actual[&phase.id];
+        rollup.budget_hours += budget_hours;
+        rollup.phases.push(PhaseHours {
+            phase_id: phase.id,
+            description: phase.description,
+            parent_phase_id: phase.parent_phase.map(|p| p.id),
+            budget_hours,
+            actual_hours,
+            remaining_hours: budget_hours - actual_hours,
+        });
+    }
+    rollup.remaining_hours = rollup.budget_hours - rollup.actual_hours;
+    rollup
+}
+
+/// builds the tree, checking the parents don't go round in a circle first
+fn work_plan(
+    project_id: i64,
+    phases: Vec<ProjectPhase>,
+    tickets: Vec<ProjectTicket>,
+) -> Result<WorkPlan> {
+    let ids: HashSet<i64> = phases.iter().map(|p| p.id).collect();
+    let parent_of: HashMap<i64, i64> = phases
+        .iter()
+        .filter_map(|p| Some((p.id, p.parent_phase.as_ref()?.id)))
+        .filter(|(_, parent)| ids.contains(parent))
+        .collect();
+    for phase in phases.iter() {
+        let mut seen = vec![phase.id];
+        let mut current = phase.id;
+        while let Some(parent) = parent_of.get(&current) {
+            if seen.contains(parent) {
+                return Err(anyhow!(
+                    "the phases of project {} are in a cycle: {:?}",
+                    project_id,
+                    seen
+                ));
+            }
+            seen.push(*parent);
+            current = *parent;
+        }
+    }
+
+    let mut unphased = Vec::new();
+    let mut tickets_in: HashMap<i64, Vec<ProjectTicket>> = HashMap::new();
+    for ticket in tickets {
+        match ticket.phase.as_ref().map(|p| p.id) {
+            Some(phase) if ids.contains(&phase) => {
+                tickets_in.entry(phase).or_default().push(ticket)
+            }
+            _ => unphased.push(ticket),
+        }
+    }
+
+    let mut children_of: HashMap<Option<i64>, Vec<ProjectPhase>> = HashMap::new();
+    for phase in phases {
+        children_of
+            .entry(parent_of.get(&phase.id).copied())
+            .or_default()
+            .push(phase);
+    }
+
+    Ok(WorkPlan {
+        project_id,
+        phases: build_phases(None, &mut children_of, &mut tickets_in),
+        unphased,
+    })
+}
+
+fn build_phases(
+    parent: Option<i64>,
+    children_of: &mut HashMap<Option<i64>, Vec<ProjectPhase>>,
+    tickets_in: &mut HashMap<i64, Vec<ProjectTicket>>,
+) -> Vec<Phase> {
+    let phases = children_of.remove(&parent).unwrap_or_default();
+    phases
+        .into_iter()
+        .map(|phase| Phase {
+            children: build_phases(Some(phase.id), children_of, tickets_in),
+            tickets: tickets_in.remove(&phase.id).unwrap_or_default(),
+            phase,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_get_project_work_plan() {
+        let mut mock = MockCw::new();
+        let _phases = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/projects/42/phases")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "description": "Design"},
+                    {"id": 2, "description": "Build"},
+                    {"id": 3, "description": "Backend", "parentPhase": {"id": 2}},
+                    {"id": 4, "description": "Database", "parentPhase": {"id": 3}},
+                    {"id": 5, "description": "Stray", "parentPhase": {"id": 99}}]"#,
+            )
+            .create();
+        let _tickets = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/tickets")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "project/id = 42".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 10, "summary": "wireframes", "phase": {"id": 1}},
+                    {"id": 11, "summary": "schema", "phase": {"id": 4}},
+                    {"id": 12, "summary": "kickoff"},
+                    {"id": 13, "summary": "api", "phase": {"id": 3}}]"#,
+            )
+            .create();
+
+        let plan = mock.client().get_project_work_plan(42).unwrap();
+
+        let top: Vec<i64> = plan.phases.iter().map(|p| p.phase.id).collect();
+        assert_eq!(top, vec![1, 2, 5]);
+        let build = plan.phase(2).unwrap();
+        let under: Vec<i64> = build.iter().iter().map(|p| p.phase.id).collect();
+        assert_eq!(under, vec![2, 3, 4]);
+        assert_eq!(plan.phase(3).unwrap().tickets[0].summary, "api");
+        assert_eq!(plan.phase(4).unwrap().tickets[0].summary, "schema");
+        assert_eq!(plan.unphased[0].summary, "kickoff");
+    }
+
+    #[test]
+    fn test_phase_cycle() {
+        let phases: Vec<ProjectPhase> = serde_json::from_str(
+            r#"[{"id": 1, "description": "A", "parentPhase": {"id": 3}},
+                {"id": 2, "description": "B", "parentPhase": {"id": 1}},
+                {"id": 3, "description": "C", "parentPhase": {"id": 2}}]"#,
+        )
+        .unwrap();
+
+        assert!(work_plan(42, phases, vec![]).is_err());
+    }
+
+    #[test]
+    fn test_get_project_hours_rollup() {
+        let mut mock = MockCw::new();
+        let _phases = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/projects/42/phases")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "description": "Design", "budgetHours": 10},
+                    {"id": 2, "description": "Build", "budgetHours": 20.5}]"#,
+            )
+            .create();
+        let _tickets = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/project/tickets")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 10, "summary": "wireframes", "phase": {"id": 1}},
+                    {"id": 11, "summary": "schema", "phase": {"id": 2}},
+                    {"id": 12, "summary": "kickoff"}]"#,
+            )
+            .create();
+        let entries = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/time/entries")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "project/id = 42".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 1, "actualHours": 4, "ticket": {"id": 10}},
+                    {"id": 2, "actualHours": 12, "chargeToType": "ProjectTicket", "chargeToId": 10},
+                    {"id": 3, "actualHours": 2.5, "phase": {"id": 2}},
+                    {"id": 4, "actualHours": 1, "ticket": {"id": 12}},
+                    {"id": 5, "actualHours": 0.5}]"#,
+            )
+            .create();
+
+        let rollup = mock.client().get_project_hours_rollup(42).unwrap();
+
+        entries.assert();
+        assert_eq!(rollup.budget_hours, 30.5);
+        assert_eq!(rollup.actual_hours, 20.0);
+        assert_eq!(rollup.remaining_hours, 10.5);
+        assert_eq!(rollup.phases[0].actual_hours, 16.0);
+        assert_eq!(rollup.phases[0].remaining_hours, -6.0);
+        assert_eq!(rollup.phases[1].actual_hours, 2.5);
+        assert_eq!(rollup.unphased_hours, 1.5);
+        let unphased: Vec<i64> = rollup.unphased_entries.iter().map(|e| e.id).collect();
+        assert_eq!(unphased, vec![4, 5]);
+    }
+}
@@ -0,0 +1,519 @@
+//! Writes that are kept in a local journal when connectwise can't be reached, and sent
+//! later.  See [QueuedClient]
+use crate::{Client, Error, PatchOperation};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A write waiting in the journal of a [QueuedClient]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// the place of the write in the journal, the first is 1
+    pub seq: u64,
+    /// `POST`, `PATCH` or `DELETE`
+    pub method: String,
+    /// the path the write is for
+    pub path: String,
+    /// the body, the patch operations of a `PATCH`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// A line of the journal, a write or the mark that it is done
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum Line {
+    Entry(JournalEntry),
+    Done { done: u64 },
+}
+
+/// What became of a write given to a [QueuedClient]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Queued {
+    /// it was sent, with what connectwise returned (`null` for a delete)
+    Sent(Value),
+    /// connectwise couldn't be reached (or earlier writes to the same path are still
+    /// waiting), it is in the journal until [QueuedClient::flush] sends it
+    Journaled(JournalEntry),
+}
+
+/// What happened to an entry in a [QueuedClient::flush]
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlushOutcome {
+    /// it was sent and is marked done, with what connectwise returned
+    Sent(Value),
+    /// connectwise still can't be reached (or rate limited or unavailable), it stays in the
+    /// journal and the flush stopped
+    Unreachable(String),
+    /// connectwise refused it, it stays in the journal and the flush stopped.  Fix what it
+    /// points at or drop it with [QueuedClient::discard]
+    Rejected(String),
+    /// not tried because the flush stopped at an earlier entry
+    NotSent,
+}
+
+/// An entry of the journal and what a [QueuedClient::flush] did with it
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlushedEntry {
+    /// the entry
+    pub entry: JournalEntry,
+    /// what happened to it
+    pub outcome: FlushOutcome,
+}
+
+/// A [Client] whose writes are kept in a journal file when they fail because connectwise
+/// can't be reached (no connection, a timeout, or [Client::offline]), instead of being lost.
+/// [QueuedClient::flush] sends them later, in the order they were made
+///
+/// The journal is a file of json lines.  Each write and each mark that a write is done is
+/// one appended line, synced to disk before the call returns, so a write that succeeded is
+/// never sent again.  A crash after connectwise accepted a write but before it was marked
+/// done does send it again.  A write to a path that still has writes waiting goes straight
+/// to the journal so the writes to a record happen in order.  The journal is meant for one
+/// process at a time
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::{Client, FlushOutcome, Queued, QueuedClient};
+///
+/// // this example is using dotenv to load our settings from
+/// // the environment, you could also specify this manually
+/// use dotenv::dotenv;
+/// dotenv().ok();
+/// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+/// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+/// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+/// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+/// let client = Client::new(company_id, public_key, private_key, client_id).build();
+/// let queued = QueuedClient::new(client, "kiosk-journal.jsonl");
+///
+/// let entry = r#"{"chargeToType": "ServiceTicket", "chargeToId": 1, "timeStart": "2022-07-14T15:00:00Z"}"#;
+/// match queued.post("/time/entries", entry.to_string()).unwrap() {
+///     Queued::Sent(entry) => println!("saved {}", entry["id"]),
+///     Queued::Journaled(entry) => println!("saved for later as {}", entry.seq),
+/// }
+///
+/// // later, when the network is back
+/// for flushed in queued.flush().unwrap() {
+///     if let FlushOutcome::Rejected(why) = flushed.outcome {
+///         println!("{} {} was refused: {}", flushed.entry.method, flushed.entry.path, why);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct QueuedClient {
+    client: Client,
+    journal: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl QueuedClient {
+    /// Wraps `client`, keeping writes that can't be sent in the journal file at `journal`
+    /// (created when it is first needed)
+    pub fn new(client: Client, journal: impl Into<PathBuf>) -> QueuedClient {
+        QueuedClient {
+            client,
+            journal: journal.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The client the writes are sent with, for reads
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// [Client::post], or into the journal when connectwise can't be reached
+    pub fn post(&self, path: &str, body: String) -> Result<Queued> {
+        self.write("POST", path, Some(body))
+    }
+
+    /// Sends the patch operations, or puts them in the journal when connectwise can't be
+    /// reached
+    pub fn patch(&self, path: &str, ops: &[PatchOperation]) -> Result<Queued> {
+        if ops.is_empty() {
+            return Err(anyhow!("no patch operations given"));
+        }
+        self.write("PATCH", path, Some(serde_json::to_string(ops)?))
+    }
+
+    /// Deletes the record at `path`, or puts the delete in the journal when connectwise can't
+    /// be reached
+    pub fn delete(&self, path: &str) -> Result<Queued> {
+        self.write("DELETE", path, None)
+    }
+
+    /// The writes waiting in the journal, oldest first
+    pub fn pending(&self) -> Result<Vec<JournalEntry>> {
+        let _guard = self.lock()?;
+        self.read_pending()
+    }
+
+    /// Sends the writes in the journal, oldest first, marking each done as it succeeds.  Stops
+    /// at the first one that fails, which stays in the journal with the ones after it: the
+    /// result has an outcome for every entry that was pending.  The journal is compacted to
+    /// what is left afterwards
+    pub fn flush(&self) -> Result<Vec<FlushedEntry>> {
+        let _guard = self.lock()?;
+        let mut flushed = Vec::new();
+        let mut stopped = false;
+
+        for entry in self.read_pending()? {
+            if stopped {
+                flushed.push(FlushedEntry {
+                    entry,
+                    outcome: FlushOutcome::NotSent,
+                });
+                continue;
+            }
+            let outcome = match self.send(&entry.method, &entry.path, entry.body.as_deref()) {
+                Ok(value) => {
+                    self.append(&Line::Done { done: entry.seq })?;
+                    FlushOutcome::Sent(value)
+                }
+                Err(e) if is_unreachable(&e) || is_transient(&e) => {
+                    FlushOutcome::Unreachable(e.to_string())
+                }
+                Err(e) => FlushOutcome::Rejected(e.to_string()),
+            };
+            stopped = !matches!(outcome, FlushOutcome::Sent(_));
+            flushed.push(FlushedEntry { entry, outcome });
+        }
+
+        self.compact()?;
+        Ok(flushed)
+    }
+
+    /// Marks the entry `seq` done without sending it, for a write connectwise keeps refusing
+    pub fn discard(&self, seq: u64) -> Result<()> {
+        let _guard = self.lock()?;
+        if !self.read_pending()?.iter().any(|e| e.seq == seq) {
+            return Err(anyhow!("{} is not waiting in the journal", seq));
+        }
+        self.append(&Line::Done { done: seq })
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ()>> {
+        self.lock
+            .lock()
+            .map_err(|_| anyhow!("journal lock poisoned"))
+    }
+
+    fn write(&self, method: &str, path: &str, body: Option<String>) -> Result<Queued> {
+        let _guard = self.lock()?;
+        let pending = self.read_pending()?;
+
+        if !pending.iter().any(|e| e.path == path) {
+            match self.send(method, path, body.as_deref()) {
+                Err(e) if is_unreachable(&e) => {
+                    tracing::warn!(
+                        method,
+                        path,
+                        "connectwise can't be reached, journaling: {}",
+                        e
+                    )
+                }
+                result => return result.map(Queued::Sent),
+            }
+        }
+
+        let entry = JournalEntry {
+            seq: self.next_seq()?,
+            method: method.to_string(),
+            path: path.to_string(),
+            body,
+        };
+        self.append(&Line::Entry(entry.clone()))?;
+        Ok(Queued::Journaled(entry))
+    }
+
+    fn send(&self, method: &str, path: &str, body: Option<&str>) -> Result<Value> {
+        match method {
+            "POST" => self.client.post(path, body.unwrap_or_default().to_string()),
+            "PATCH" => {
+                let ops: Vec<PatchOperation> = serde_json::from_str(body.unwrap_or("[]"))?;
+                self.client.patch_ops(path, &ops)
+            }
+            "DELETE" => self.client.delete(path).map(|_| Value::Null),
+            _ => Err(anyhow!("{} can't be sent from the journal", method)),
+        }
+    }
+
+    fn read_lines(&self) -> Result<Vec<Line>> {
+        let raw = match fs::read_to_string(&self.journal) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow!("cannot read journal {:?}: {}", self.journal, e)),
+        };
+        // a line cut short by a crash while it was written was never acknowledged, skip it
+        Ok(raw
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn read_pending(&self) -> Result<Vec<JournalEntry>> {
+        let lines = self.read_lines()?;
+        let done: Vec<u64> = lines
+            .iter()
+            .filter_map(|l| match l {
+                Line::Done { done } => Some(*done),
+                Line::Entry(_) => None,
+            })
+            .collect();
+        Ok(lines
+            .into_iter()
+            .filter_map(|l| match l {
+                Line::Entry(e) if !done.contains(&e.seq) => Some(e),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn next_seq(&self) -> Result<u64> {
+        let last = self
+            .read_lines()?
+            .iter()
+            .map(|l| match l {
+                Line::Entry(e) => e.seq,
+                Line::Done { done } => *done,
+            })
+            .max();
+        Ok(last.unwrap_or(0) + 1)
+    }
+
+    /// appends a line and syncs it to disk
+    fn append(&self, line: &Line) -> Result<()> {
+        if let Some(parent) = self.journal.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.journal)
+            .map_err(|e| anyhow!("cannot open journal {:?}: {}", self.journal, e))?;
+        // a line torn by an earlier crash would swallow this one
+        if !ends_with_newline(&self.journal)? {
+            file.write_all(b"\n")?;
+        }
+        file.write_all(format!("{}\n", serde_json::to_string(line)?).as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// rewrites the journal with only the pending entries, through a file that replaces it
+    fn compact(&self) -> Result<()> {
+        if !self.journal.exists() {
+            return Ok(());
+        }
+        let mut rewritten = String::new();
+        for entry in self.read_pending()? {
+            rewritten.push_str(&serde_json::to_string(&Line::Entry(entry))?);
+            rewritten.push('\n');
+        }
+        let tmp = self.journal.with_extension("tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(rewritten.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.journal)
+            .map_err(|e| anyhow!("cannot replace journal {:?}: {}", self.journal, e))
+    }
+}
+
+fn ends_with_newline(path: &Path) -> Result<bool> {
+    let raw = fs::read(path)?;
+    Ok(raw.last().copied().unwrap_or(b'\n') == b'\n')
+}
+
+/// true when the request never got to connectwise
+fn is_unreachable(e: &anyhow::Error) -> bool {
+    if let Some(Error::Offline { .. }) = e.downcast_ref::<Error>() {
+        return true;
+    }
+    matches!(
+        e.downcast_ref::<reqwest::Error>(),
+        Some(e) if e.is_timeout() || e.is_connect() || e.is_request()
+    )
+}
+
+/// true for a failure that may go away, a 429 or a 502, 503 or 504
+fn is_transient(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<Error>(),
+        Some(Error::RateLimited { .. })
+            | Some(Error::Api {
+                status: 502..=504,
+                ..
+            })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use crate::PatchOp;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn outcomes(flushed: &[FlushedEntry]) -> Vec<(u64, &str)> {
+        flushed
+            .iter()
+            .map(|f| {
+                let outcome = match &f.outcome {
+                    FlushOutcome::Sent(_) => "sent",
+                    FlushOutcome::Unreachable(_) => "unreachable",
+                    FlushOutcome::Rejected(_) => "rejected",
+                    FlushOutcome::NotSent => "not sent",
+                };
+                (f.entry.seq, outcome)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_queued_client() {
+        let dir = std::env::temp_dir().join(format!("cwmanage-queue-{}", std::process::id()));
+        let journal = dir.join("journal.jsonl");
+        let first = json!({"chargeToId": 1});
+        let second = json!({"chargeToId": 2});
+
+        // nothing listens on port 1
+        let down = QueuedClient::new(
+            MockCw::new()
+                .client()
+                .api_url("http://127.0.0.1:1".to_string()),
+            &journal,
+        );
+        for body in [&first, &second].iter() {
+            match down.post("/time/entries", body.to_string()).unwrap() {
+                Queued::Journaled(_) => {}
+                other => panic!("{:?}", other),
+            }
+        }
+        let ops = [PatchOperation::new(
+            PatchOp::Replace,
+            "notes",
+            json!("fixed"),
+        )];
+        down.patch("/time/entries/5", &ops).unwrap();
+        let pending = down.pending().unwrap();
+        assert_eq!(
+            pending
+                .iter()
+                .map(|e| (e.seq, e.method.as_str(), e.path.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, "POST", "/time/entries"),
+                (2, "POST", "/time/entries"),
+                (3, "PATCH", "/time/entries/5")
+            ]
+        );
+
+        let mut mock = MockCw::new();
+        let created = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/entries")
+            .match_body(Matcher::Json(first.clone()))
+            .with_body(r#"{"id": 10}"#)
+            .expect(1)
+            .create();
+        let refused = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/entries")
+            .match_body(Matcher::Json(second.clone()))
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "timeStart is required"}"#)
+            .expect(2)
+            .create();
+        let patched = mock
+            .server()
+            .mock("PATCH", "/v4_6_release/apis/3.0/time/entries/5")
+            .with_body(r#"{"id": 5, "notes": "fixed"}"#)
+            .expect(2)
+            .create();
+        let up = QueuedClient::new(mock.client(), &journal);
+
+        // the write to a path with writes waiting goes behind them
+        let third = up.post("/time/entries", json!({"chargeToId": 3}).to_string());
+        match third.unwrap() {
+            Queued::Journaled(entry) => assert_eq!(entry.seq, 4),
+            other => panic!("{:?}", other),
+        }
+
+        let flushed = up.flush().unwrap();
+        assert_eq!(
+            outcomes(&flushed),
+            vec![
+                (1, "sent"),
+                (2, "rejected"),
+                (3, "not sent"),
+                (4, "not sent")
+            ]
+        );
+        assert_eq!(flushed[0].outcome, FlushOutcome::Sent(json!({"id": 10})));
+
+        // what succeeded isn't sent again
+        let flushed = up.flush().unwrap();
+        assert_eq!(
+            outcomes(&flushed),
+            vec![(2, "rejected"), (3, "not sent"), (4, "not sent")]
+        );
+
+        up.discard(2).unwrap();
+        assert!(up.discard(2).is_err());
+        let _fourth = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/entries")
+            .match_body(Matcher::Json(json!({"chargeToId": 3})))
+            .with_body(r#"{"id": 11}"#)
+            .create();
+        let flushed = up.flush().unwrap();
+        assert_eq!(outcomes(&flushed), vec![(3, "sent"), (4, "sent")]);
+        assert_eq!(up.pending().unwrap(), vec![]);
+        assert_eq!(fs::read_to_string(&journal).unwrap(), "");
+
+        // sent straight away when nothing is waiting
+        assert_eq!(
+            up.patch("/time/entries/5", &ops).unwrap(),
+            Queued::Sent(json!({"id": 5, "notes": "fixed"}))
+        );
+
+        created.assert();
+        refused.assert();
+        patched.assert();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_journal_line() {
+        let dir = std::env::temp_dir().join(format!("cwmanage-torn-{}", std::process::id()));
+        let journal = dir.join("journal.jsonl");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &journal,
+            "{\"seq\":1,\"method\":\"DELETE\",\"path\":\"/time/entries/1\"}\n{\"seq\":2,\"met",
+        )
+        .unwrap();
+        let down = QueuedClient::new(
+            MockCw::new()
+                .client()
+                .api_url("http://127.0.0.1:1".to_string()),
+            &journal,
+        );
+
+        down.delete("/time/entries/2").unwrap();
+
+        let pending = down.pending().unwrap();
+        assert_eq!(
+            pending.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
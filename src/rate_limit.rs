@@ -0,0 +1,216 @@
+//! The rate limit connectwise reports in response headers.  See [Client::last_rate_limit]
+use crate::{shared, Client};
+use reqwest::header::HeaderMap;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Gets the [RateLimitInfo] of a response that has few requests left, see
+/// [Client::on_rate_limit_low]
+pub type RateLimitHandler = dyn Fn(&RateLimitInfo) + Send + Sync;
+
+/// Reset values above this are a unix time, below it a number of seconds from now
+const EPOCH_RESET: u64 = 1_000_000_000;
+
+/// The rate limit headers of a response (`X-RateLimit-Limit`, `X-RateLimit-Remaining`,
+/// `X-RateLimit-Reset`, or the same without the `X-`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// the requests allowed in the window
+    pub limit: u64,
+    /// the requests left in the window
+    pub remaining: u64,
+    /// when the window starts over, when the response said
+    pub resets_at: Option<SystemTime>,
+}
+
+impl RateLimitInfo {
+    /// the rate limit in a response, `None` when it doesn't have the limit and remaining headers
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<RateLimitInfo> {
+        let header = |name: &str| {
+            [
+                format!("x-ratelimit-{}", name),
+                format!("ratelimit-{}", name),
+            ]
+            .iter()
+            .filter_map(|name| headers.get(name.as_str()))
+            .filter_map(|v| v.to_str().ok())
+            .find_map(|v| v.trim().parse::<u64>().ok())
+        };
+        let resets_at = header("reset").map(|reset| match reset > EPOCH_RESET {
+            true => UNIX_EPOCH + Duration::from_secs(reset),
+            false => SystemTime::now() + Duration::from_secs(reset),
+        });
+        Some(RateLimitInfo {
+            limit: header("limit")?,
+            remaining: header("remaining")?,
+            resets_at,
+        })
+    }
+
+    /// the time until the window starts over, `None` when it isn't known or has passed
+    fn reset_in(&self) -> Option<Duration> {
+        self.resets_at?.duration_since(SystemTime::now()).ok()
+    }
+}
+
+/// The hook of [Client::on_rate_limit_low] and when to call it
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RateLimitLow {
+    pub(crate) threshold: u64,
+    pub(crate) hook: shared::Hook<RateLimitHandler>,
+}
+
+impl Client {
+    /// The rate limit of the last response that reported one (the clones of a client share
+    /// it), `None` until then.  Responses without the headers leave it as it was
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    /// use dotenv::dotenv;
+    /// use std::env;
+    /// dotenv().ok();
+    /// let company_id: String = env::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = env::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = env::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = env::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// client.get_single("/system/info", &[]).unwrap();
+    /// if let Some(rate) = client.last_rate_limit() {
+    ///     println!("{} of {} requests left", rate.remaining, rate.limit);
+    /// }
+    /// ```
+    pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+        *self.shared.rate_limit.lock().unwrap()
+    }
+
+    /// calls `hook` after each response that says `threshold` or fewer requests are left
+    ///
+    /// ```
+    /// use cwmanage::{Client, RateLimitInfo};
+    /// use std::sync::Arc;
+    ///
+    /// let client = Client::new(
+    ///     "myco".to_string(),
+    ///     "public".to_string(),
+    ///     "private".to_string(),
+    ///     "clientid".to_string(),
+    /// )
+    /// .on_rate_limit_low(10, Arc::new(|rate: &RateLimitInfo| {
+    ///     eprintln!("only {} requests left", rate.remaining)
+    /// }))
+    /// .build();
+    /// ```
+    pub fn on_rate_limit_low(
+        mut self,
+        threshold: u64,
+        hook: std::sync::Arc<RateLimitHandler>,
+    ) -> Client {
+        self.on_rate_limit_low = Some(RateLimitLow {
+            threshold,
+            hook: shared::Hook(hook),
+        });
+        self
+    }
+
+    /// keeps the rate limit of a response, if it has one, and calls
+    /// [Client::on_rate_limit_low]
+    pub(crate) fn record_rate_limit(&self, headers: &HeaderMap) {
+        let rate = match RateLimitInfo::from_headers(headers) {
+            Some(rate) => rate,
+            None => return,
+        };
+        *self.shared.rate_limit.lock().unwrap() = Some(rate);
+        if let Some(low) = &self.on_rate_limit_low {
+            if rate.remaining <= low.threshold {
+                (low.hook.0)(&rate);
+            }
+        }
+    }
+
+    /// the wait between requests of [Client::max_requests_per_second], stretched so the
+    /// requests left last until the rate limit window starts over
+    pub(crate) fn tuned_interval(&self, interval: Duration) -> Duration {
+        let rate = match self.last_rate_limit() {
+            Some(rate) => rate,
+            None => return interval,
+        };
+        match rate.reset_in() {
+            Some(reset_in) => interval.max(reset_in / rate.remaining.max(1) as u32),
+            None => interval,
+        }
+    }
+
+    /// when the last response said no requests are left, the time the window starts over
+    pub(crate) fn rate_limit_turn(&self, now: Instant) -> Option<Instant> {
+        let rate = self.last_rate_limit()?;
+        match rate.remaining {
+            0 => Some(now + rate.reset_in()?),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_last_rate_limit() {
+        let mut mock = MockCw::new();
+        let _limited = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_header("x-ratelimit-limit", "100")
+            .with_header("x-ratelimit-remaining", "3")
+            .with_header("x-ratelimit-reset", "1700000000")
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .create();
+        let _plain = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .create();
+        let seen: Arc<Mutex<Vec<u64>>> = Default::default();
+        let collect = seen.clone();
+        let client = mock.client().on_rate_limit_low(
+            5,
+            Arc::new(move |rate: &RateLimitInfo| collect.lock().unwrap().push(rate.remaining)),
+        );
+
+        assert_eq!(client.last_rate_limit(), None);
+        client.get_single("/service/info", &[]).unwrap();
+        assert_eq!(client.last_rate_limit(), None);
+        client.get_single("/system/info", &[]).unwrap();
+        client.get_single("/service/info", &[]).unwrap();
+
+        assert_eq!(
+            client.last_rate_limit(),
+            Some(RateLimitInfo {
+                limit: 100,
+                remaining: 3,
+                resets_at: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            })
+        );
+        assert_eq!(*seen.lock().unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("ratelimit-limit", "50".parse().unwrap());
+        headers.insert("ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("ratelimit-reset", "30".parse().unwrap());
+        let rate = RateLimitInfo::from_headers(&headers).unwrap();
+        assert_eq!((rate.limit, rate.remaining), (50, 0));
+        assert!(rate.reset_in().unwrap() > Duration::from_secs(25));
+
+        headers.remove("ratelimit-remaining");
+        assert_eq!(RateLimitInfo::from_headers(&headers), None);
+    }
+}
@@ -0,0 +1,361 @@
+//! Masking the fields that hold personal data wherever the crate writes request and response
+//! data for someone to read (HAR captures, error messages), see [crate::Client::redact_fields].
+//! What is sent to and returned from the api is never changed
+use serde_json::Value;
+
+/// What is written instead of a redacted value
+pub(crate) const MASK: &str = "[redacted]";
+
+/// One step of a field path
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// a field of an object
+    Field(String),
+    /// any field of an object (`*`)
+    AnyField,
+    /// an element of an array (`[0]`)
+    Index(usize),
+    /// any element of an array (`[*]`)
+    AnyIndex,
+}
+
+/// The fields to mask, see [crate::Client::redact_fields]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Redaction {
+    rules: Vec<Vec<Segment>>,
+}
+
+/// `contact/email`, `contact.email` or `customFields[*].value` as segments.  In brackets `*`
+/// is any element and a number that element, anything else is part of the field name
+fn parse(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for part in path.split(['/', '.']).filter(|p| !p.is_empty()) {
+        let (name, mut rest) = match part.find('[') {
+            Some(at) => (&part[..at], &part[at..]),
+            None => (part, ""),
+        };
+        let mut indexes = Vec::new();
+        while let Some(end) = rest.find(']').filter(|_| rest.starts_with('[')) {
+            match &rest[1..end] {
+                "*" => indexes.push(Segment::AnyIndex),
+                index => match index.parse() {
+                    Ok(index) => indexes.push(Segment::Index(index)),
+                    Err(_) => break,
+                },
+            }
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            // not brackets we know, it is all the name
+            segments.push(Segment::Field(part.to_string()));
+            continue;
+        }
+        match name {
+            "" => {}
+            "*" => segments.push(Segment::AnyField),
+            // a patch operation path numbers the array elements
+            name if name.bytes().all(|b| b.is_ascii_digit()) && path.starts_with('/') => {
+                segments.push(Segment::Index(name.parse().unwrap_or_default()))
+            }
+            name => segments.push(Segment::Field(name.to_string())),
+        }
+        segments.extend(indexes);
+    }
+    segments
+}
+
+/// whether `path` (of a value in a record) is, or is inside, a field of `rule`.  A field name
+/// goes through arrays, `contact/email` is the email of every contact in a list
+fn matches(rule: &[Segment], path: &[Segment]) -> bool {
+    let (first, rest) = match (rule.split_first(), path.split_first()) {
+        (None, _) => return true,
+        (Some(_), None) => return false,
+        (Some((first, _)), Some(step)) => (first, step),
+    };
+    let (step, path_rest) = rest;
+    match (first, step) {
+        (Segment::AnyIndex, Segment::Index(_)) => matches(&rule[1..], path_rest),
+        (Segment::Index(want), Segment::Index(index)) => {
+            want == index && matches(&rule[1..], path_rest)
+        }
+        (_, Segment::Index(_)) => matches(rule, path_rest),
+        (Segment::AnyField, Segment::Field(_)) => matches(&rule[1..], path_rest),
+        (Segment::Field(want), Segment::Field(field)) => {
+            want == field && matches(&rule[1..], path_rest)
+        }
+        _ => false,
+    }
+}
+
+impl Redaction {
+    pub(crate) fn new(fields: &[&str]) -> Redaction {
+        Redaction {
+            rules: fields
+                .iter()
+                .map(|f| parse(f))
+                .filter(|rule| !rule.is_empty())
+                .collect(),
+        }
+    }
+
+    /// whether the value at `path` (`customFields[2].value`, `/contact/email`) is masked
+    pub(crate) fn covers(&self, path: &str) -> bool {
+        let path = parse(path);
+        self.rules.iter().any(|rule| matches(rule, &path))
+    }
+
+    /// `value` with the redacted fields masked.  The elements of a patch
+    /// (`[{"op": "replace", "path": "summary", "value": ...}]`) have their value masked when
+    /// their path is redacted
+    pub(crate) fn value(&self, value: &Value) -> Value {
+        let mut value = value.clone();
+        self.walk(&mut value, &mut Vec::new());
+        if let Value::Array(operations) = &mut value {
+            for operation in operations {
+                let path = match operation.get("path").and_then(Value::as_str) {
+                    Some(path) if operation.get("op").is_some() => path.to_string(),
+                    _ => continue,
+                };
+                if self.covers(&format!("/{}", path.trim_start_matches('/'))) {
+                    if let Some(value) = operation.get_mut("value") {
+                        mask(value);
+                    }
+                }
+            }
+        }
+        value
+    }
+
+    /// a body with the redacted fields masked, a body that isn't json is left as it is
+    pub(crate) fn text(&self, body: &str) -> String {
+        match serde_json::from_str::<Value>(body) {
+            Ok(value) => self.value(&value).to_string(),
+            Err(_) => body.to_string(),
+        }
+    }
+
+    fn walk(&self, value: &mut Value, path: &mut Vec<Segment>) {
+        if !path.is_empty() && self.rules.iter().any(|rule| matches(rule, path)) {
+            mask(value);
+            return;
+        }
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields.iter_mut() {
+                    path.push(Segment::Field(name.clone()));
+                    self.walk(field, path);
+                    path.pop();
+                }
+            }
+            Value::Array(elements) => {
+                for (index, element) in elements.iter_mut().enumerate() {
+                    path.push(Segment::Index(index));
+                    self.walk(element, path);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// every value in `value` masked, objects and arrays keep their shape.  `null` stays `null`
+fn mask(value: &mut Value) {
+    match value {
+        Value::Null => {}
+        Value::Object(fields) => fields.values_mut().for_each(mask),
+        Value::Array(elements) => elements.iter_mut().for_each(mask),
+        other => *other = Value::String(MASK.to_string()),
+    }
+}
+
+/// `body` masked by `redaction`, if there is one
+pub(crate) fn text(redaction: Option<&Redaction>, body: &str) -> String {
+    match redaction {
+        Some(redaction) => redaction.text(body),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("customFields[*].value"),
+            vec![
+                Segment::Field("customFields".to_string()),
+                Segment::AnyIndex,
+                Segment::Field("value".to_string()),
+            ]
+        );
+        assert_eq!(parse("contact/email"), parse("contact.email"));
+        assert_eq!(
+            parse("[2].items[0][*]"),
+            vec![
+                Segment::Index(2),
+                Segment::Field("items".to_string()),
+                Segment::Index(0),
+                Segment::AnyIndex,
+            ]
+        );
+        assert_eq!(
+            parse("odd[name]"),
+            vec![Segment::Field("odd[name]".to_string())]
+        );
+        assert_eq!(
+            parse("/customFields/0/value"),
+            vec![
+                Segment::Field("customFields".to_string()),
+                Segment::Index(0),
+                Segment::Field("value".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redact_ticket() {
+        let redaction = Redaction::new(&["summary", "contact/email", "customFields[*].value"]);
+        let ticket = json!({
+            "id": 123,
+            "summary": "Jane Doe's laptop is stolen",
+            "contact": {"id": 7, "name": "Jane Doe", "email": null},
+            "contactEmailAddress": "jane@example.com",
+            "customFields": [
+                {"id": 1, "caption": "Serial", "value": "ABC123"},
+                {"id": 2, "caption": "Seats", "value": 4},
+            ],
+        });
+
+        assert_eq!(
+            redaction.value(&ticket),
+            json!({
+                "id": 123,
+                "summary": "[redacted]",
+                "contact": {"id": 7, "name": "Jane Doe", "email": null},
+                "contactEmailAddress": "jane@example.com",
+                "customFields": [
+                    {"id": 1, "caption": "Serial", "value": "[redacted]"},
+                    {"id": 2, "caption": "Seats", "value": "[redacted]"},
+                ],
+            })
+        );
+        // every record of a list
+        let list = json!([{"id": 1, "summary": "a"}, {"id": 2, "summary": "b"}]);
+        assert_eq!(
+            redaction.value(&list),
+            json!([{"id": 1, "summary": "[redacted]"}, {"id": 2, "summary": "[redacted]"}])
+        );
+        // the value of a patch of a redacted field
+        let patch = json!([
+            {"op": "replace", "path": "summary", "value": "Jane Doe's laptop"},
+            {"op": "replace", "path": "/customFields/0/value", "value": "ABC123"},
+            {"op": "replace", "path": "status/name", "value": "Closed"},
+        ]);
+        assert_eq!(
+            redaction.value(&patch),
+            json!([
+                {"op": "replace", "path": "summary", "value": "[redacted]"},
+                {"op": "replace", "path": "/customFields/0/value", "value": "[redacted]"},
+                {"op": "replace", "path": "status/name", "value": "Closed"},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_redact_contact() {
+        let redaction = Redaction::new(&[
+            "firstName",
+            "lastName",
+            "communicationItems[*].value",
+            "site.*",
+            "addressLine1",
+        ]);
+        let contact = json!({
+            "id": 7,
+            "firstName": "Jane",
+            "lastName": "Doe",
+            "company": {"id": 2, "identifier": "myco"},
+            "site": {"id": 3, "name": "Main"},
+            "communicationItems": [
+                {"type": {"id": 1, "name": "Email"}, "value": "jane@example.com"},
+                {"type": {"id": 2, "name": "Direct"}, "value": "555-1234"},
+            ],
+            "addressLine1": "1 Main St",
+        });
+
+        assert_eq!(
+            redaction.value(&contact),
+            json!({
+                "id": 7,
+                "firstName": "[redacted]",
+                "lastName": "[redacted]",
+                "company": {"id": 2, "identifier": "myco"},
+                "site": {"id": "[redacted]", "name": "[redacted]"},
+                "communicationItems": [
+                    {"type": {"id": 1, "name": "Email"}, "value": "[redacted]"},
+                    {"type": {"id": 2, "name": "Direct"}, "value": "[redacted]"},
+                ],
+                "addressLine1": "[redacted]",
+            })
+        );
+        assert!(redaction.covers("[3].communicationItems[0].value"));
+        assert!(!redaction.covers("[3].company.identifier"));
+        assert_eq!(redaction.text("<html>oops</html>"), "<html>oops</html>");
+    }
+
+    #[test]
+    fn test_redact_fields() {
+        let mut server = mockito::Server::new();
+        let created = server
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .match_body(r#"{"summary": "Jane Doe's laptop", "board": {"id": 1}}"#)
+            .with_body(r#"{"id": 1, "summary": "Jane Doe's laptop", "board": {"id": 1}}"#)
+            .create();
+        let _failed = server
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/1")
+            .with_status(500)
+            .with_body(r#"{"summary": "Jane Doe's laptop"}"#)
+            .create();
+        let dir = std::env::temp_dir().join(format!("cwmanage-redact-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let har = dir.join("capture.har");
+        let client = crate::Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .redact_fields(&["summary", "notes"])
+        .har_capture(&har)
+        .build();
+
+        // what is sent and returned isn't changed
+        let body = r#"{"summary": "Jane Doe's laptop", "board": {"id": 1}}"#;
+        let ticket = client.post("/service/tickets", body.to_string()).unwrap();
+        created.assert();
+        assert_eq!(ticket["summary"], "Jane Doe's laptop");
+
+        let err = client.get_single("/service/tickets/1", &[]).unwrap_err();
+        assert!(err.to_string().contains("[redacted]"), "{}", err);
+        assert!(!err.to_string().contains("Jane"), "{}", err);
+
+        let captured = std::fs::read_to_string(&har).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!captured.contains("Jane"));
+        assert!(captured.contains(r#"\"board\":{\"id\":1}"#));
+
+        let err = client
+            .decode::<crate::TicketTask>("/service/tickets/1/tasks/2", json!({"id": 2, "notes": 5}))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "can't decode notes of /service/tickets/1/tasks/2 (id 2): invalid value, expected a string"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! The `{id, name, _info}` reference shape connectwise uses whenever one record points at
+//! another (a ticket's `status`, `board`, `company` etc)
+use crate::Error;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A reference to another record.  When deserializing everything but the `id` is optional,
+/// when serializing (for a write) only the fields that are set are sent so [CwRef::id]
+/// produces `{"id": 16}`
+///
+/// ```
+/// use cwmanage::CwRef;
+/// use serde_json::json;
+///
+/// let status: CwRef = serde_json::from_value(json!({
+///     "id": 16,
+///     "name": "Closed",
+///     "_info": {"status_href": "https://na.myconnectwise.net/v4_6_release/apis/3.0/service/boards/1/statuses/16"}
+/// }))
+/// .unwrap();
+/// assert_eq!(status.name.as_deref(), Some("Closed"));
+///
+/// assert_eq!(serde_json::to_value(CwRef::id(16)).unwrap(), json!({"id": 16}));
+/// assert_eq!(CwRef::patch_path("status"), "status/id");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CwRef {
+    /// The id of the referenced record
+    pub id: i64,
+    /// The name, most references have this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The identifier, used by companies and members instead of a name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// The `_info` map, mostly `*_href` links to the referenced record
+    #[serde(rename = "_info", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<HashMap<String, String>>,
+}
+
+impl CwRef {
+    /// A reference with only the id set, this is what a write body needs
+    pub fn id(id: i64) -> CwRef {
+        CwRef {
+            id,
+            name: None,
+            identifier: None,
+            info: None,
+        }
+    }
+
+    /// The patch path that updates the reference in `field`, `status` is `status/id`
+    pub fn patch_path(field: &str) -> String {
+        format!("{}/id", field)
+    }
+}
+
+/// A reference given by id, or by name to be looked up (case insensitive) before it is sent.
+/// Names are handy when the ids differ between environments
+///
+/// ```
+/// use cwmanage::IdOrName;
+///
+/// assert_eq!(IdOrName::from(3), IdOrName::Id(3));
+/// assert_eq!(IdOrName::from("Scheduled"), IdOrName::Name("Scheduled".to_string()));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum IdOrName {
+    /// the id of the record
+    Id(i64),
+    /// the name of the record
+    Name(String),
+}
+
+impl From<i64> for IdOrName {
+    fn from(id: i64) -> Self {
+        IdOrName::Id(id)
+    }
+}
+
+impl From<&str> for IdOrName {
+    fn from(name: &str) -> Self {
+        IdOrName::Name(name.to_string())
+    }
+}
+
+/// finds the one item called `name` (ignoring case and surrounding whitespace) in the lookup
+/// list from `path`.  [Error::UnknownName] (with the names there are) if there isn't one, an
+/// error if there are several
+pub(crate) fn find_by_name<'a, T>(
+    items: &'a [T],
+    path: &str,
+    name: &str,
+    name_of: impl Fn(&T) -> &str,
+) -> Result<&'a T> {
+    let wanted = name.trim().to_lowercase();
+    let mut found = items
+        .iter()
+        .filter(|item| name_of(item).trim().to_lowercase() == wanted);
+    match (found.next(), found.next()) {
+        (Some(item), None) => Ok(item),
+        (Some(_), Some(_)) => Err(anyhow!("{:?} is ambiguous in {}", name, path)),
+        (None, _) => Err(Error::UnknownName {
+            path: path.to_string(),
+            name: name.to_string(),
+            valid: items.iter().map(|item| name_of(item).to_string()).collect(),
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_deserialize_identifier() {
+        let raw = json!({
+            "id": 250,
+            "identifier": "ACME",
+            "name": "Acme Inc",
+            "_info": {"company_href": "https://na.myconnectwise.net/v4_6_release/apis/3.0/company/companies/250"}
+        });
+
+        let result: CwRef = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(result.identifier.as_deref(), Some("ACME"));
+        assert_eq!(serde_json::to_value(&result).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_deserialize_id_only() {
+        let result: CwRef = serde_json::from_value(json!({"id": 16})).unwrap();
+
+        assert_eq!(result, CwRef::id(16));
+    }
+}
@@ -0,0 +1,104 @@
+//! The connectwise cloud regions and their api hosts.  See [crate::Client::region]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The domain of the connectwise cloud instances
+const CLOUD_DOMAIN: &str = ".myconnectwise.net";
+
+/// A connectwise cloud region.  Api traffic goes to the region's `api-` host
+/// (`api-na.myconnectwise.net`), not the host people log in to (`na.myconnectwise.net`)
+///
+/// ```
+/// use cwmanage::Region;
+///
+/// assert_eq!(Region::Eu.host(), "api-eu.myconnectwise.net");
+/// assert_eq!("AU".parse::<Region>().unwrap(), Region::Au);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Region {
+    /// north america
+    Na,
+    /// europe
+    Eu,
+    /// australia
+    Au,
+    /// south africa
+    Za,
+}
+
+impl Region {
+    /// every region
+    pub const ALL: [Region; 4] = [Region::Na, Region::Eu, Region::Au, Region::Za];
+
+    /// the region code, the same as the `cloudRegion` of `/system/info` (`NA`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Region::Na => "NA",
+            Region::Eu => "EU",
+            Region::Au => "AU",
+            Region::Za => "ZA",
+        }
+    }
+
+    /// the host api requests for the region go to
+    pub fn host(&self) -> &'static str {
+        match self {
+            Region::Na => "api-na.myconnectwise.net",
+            Region::Eu => "api-eu.myconnectwise.net",
+            Region::Au => "api-au.myconnectwise.net",
+            Region::Za => "api-za.myconnectwise.net",
+        }
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl std::str::FromStr for Region {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Region::ALL
+            .iter()
+            .copied()
+            .find(|r| r.code().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| anyhow::anyhow!("unknown region {:?}", s))
+    }
+}
+
+/// the api host for a cloud login host (`na.myconnectwise.net` is `api-na.myconnectwise.net`),
+/// `None` for any other host
+pub(crate) fn api_host(host: &str) -> Option<&'static str> {
+    let prefix = host.to_lowercase();
+    let prefix = prefix.strip_suffix(CLOUD_DOMAIN)?;
+    Region::ALL
+        .iter()
+        .find(|r| r.code().eq_ignore_ascii_case(prefix))
+        .map(|r| r.host())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_api_host() {
+        assert_eq!(
+            api_host("na.myconnectwise.net"),
+            Some("api-na.myconnectwise.net")
+        );
+        assert_eq!(
+            api_host("EU.myconnectwise.net"),
+            Some("api-eu.myconnectwise.net")
+        );
+        assert_eq!(api_host("api-na.myconnectwise.net"), None);
+        assert_eq!(api_host("cw.example.com"), None);
+        assert_eq!(api_host("xx.myconnectwise.net"), None);
+        assert!("mars".parse::<Region>().is_err());
+    }
+}
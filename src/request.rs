@@ -0,0 +1,219 @@
+//! A request to any endpoint, for the ones the other methods don't cover, see [Client::request]
+use crate::{api_error, Client};
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Method;
+use serde_json::Value;
+
+/// A request being built, from [Client::request].  It is sent with the same `Authorization`,
+/// `clientid`, `Content-Type` and `pagination-type` headers (and the same retries, rate limit
+/// tracking and read only check) as every other request, a header set with
+/// [CwRequestBuilder::header] replaces the one the client would send
+///
+/// ```no_run
+/// use cwmanage::Client;
+/// use reqwest::Method;
+///
+/// let client = Client::new(
+///     "myco".to_string(),
+///     "public".to_string(),
+///     "private".to_string(),
+///     "clientid".to_string(),
+/// )
+/// .build();
+/// let response = client
+///     .request(Method::PATCH, "/service/tickets/1")
+///     .query("updateView", "true")
+///     .header("x-cw-usertype", "member")
+///     .body(r#"[{"op": "replace", "path": "summary", "value": "printer is on fire"}]"#)
+///     .send()
+///     .unwrap();
+/// assert_eq!(response.status, 200);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CwRequestBuilder<'a> {
+    client: &'a Client,
+    method: Method,
+    path: String,
+    query: Vec<(String, String)>,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+}
+
+/// What [CwRequestBuilder::send] got back
+#[derive(Debug, Clone, PartialEq)]
+pub struct CwResponse {
+    /// the http status (`200`, `201`, `204`)
+    pub status: u16,
+    /// the parsed body, `Value::Null` when it was empty
+    pub body: Value,
+}
+
+impl Client {
+    /// Starts a `method` request to `path` (`/service/tickets/1`), for endpoints and flags the
+    /// other methods don't cover.  See [CwRequestBuilder]
+    pub fn request(&self, method: Method, path: &str) -> CwRequestBuilder<'_> {
+        CwRequestBuilder {
+            client: self,
+            method,
+            path: path.to_string(),
+            query: Vec::new(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+impl<'a> CwRequestBuilder<'a> {
+    /// adds `key=value` to the query
+    pub fn query(mut self, key: &str, value: &str) -> CwRequestBuilder<'a> {
+        self.query.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// sets the header `name`, replacing the one the client sends if it is one of its own
+    pub fn header(mut self, name: &str, value: &str) -> CwRequestBuilder<'a> {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// sets the body, json unless the `Content-Type` header is changed
+    pub fn body(mut self, body: impl Into<String>) -> CwRequestBuilder<'a> {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sends the request.  A status that isn't a success is [crate::Error::Api] (or
+    /// [crate::Error::Forbidden]) as for the other methods, a body that isn't json is an error
+    pub fn send(self) -> Result<CwResponse> {
+        let client = self.client;
+        let req = match self.method {
+            Method::GET | Method::HEAD | Method::OPTIONS => {
+                client.http_request(self.method.clone(), &self.path)?
+            }
+            _ => client.write_request(self.method.clone(), &self.path)?,
+        };
+        let req = match self.query.is_empty() {
+            true => req,
+            false => req.query(&self.query),
+        };
+        let req = req.headers(header_map(&self.headers)?);
+        let req = match self.body {
+            Some(body) => req.body(body),
+            None => req,
+        };
+
+        let (method, path) = (&self.method, self.path.as_str());
+        client.call(req, |res| {
+            if !res.status.is_success() {
+                return Err(api_error(&res, path));
+            }
+            let body = match res.body.trim().is_empty() {
+                true => Value::Null,
+                false => serde_json::from_str(&res.body).map_err(|e| {
+                    anyhow!("{} {} returned a body that isn't json: {}", method, path, e)
+                })?,
+            };
+            Ok(CwResponse {
+                status: res.status.as_u16(),
+                body,
+            })
+        })
+    }
+}
+
+fn header_map(headers: &[(String, String)]) -> Result<HeaderMap> {
+    let mut map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| anyhow!("{:?} isn't a valid header name", name))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| anyhow!("the value of the header {} isn't valid", name))?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw, API_PREFIX};
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_request() {
+        let mut mock = MockCw::new();
+        let path = format!("{}/service/tickets/1", API_PREFIX);
+        let patched = mock
+            .server()
+            .mock("PATCH", path.as_str())
+            .match_query(Matcher::UrlEncoded(
+                "updateView".to_string(),
+                "true".to_string(),
+            ))
+            .match_header("authorization", Matcher::Regex("^Basic ".to_string()))
+            .match_header("clientid", "clientid")
+            .match_header("pagination-type", "forward-only")
+            .match_header("content-type", "application/json-patch+json")
+            .match_body(r#"[{"op":"replace","path":"summary","value":"x"}]"#)
+            .with_body(r#"{"id": 1, "summary": "x"}"#)
+            .create();
+        let removed = mock
+            .server()
+            .mock("DELETE", path.as_str())
+            .with_status(204)
+            .create();
+        let missing = mock
+            .server()
+            .mock("GET", format!("{}/service/tickets/9", API_PREFIX).as_str())
+            .with_status(404)
+            .with_body(fixtures::not_found("Ticket", 9).to_string())
+            .create();
+        let client = mock.client();
+
+        let response = client
+            .request(Method::PATCH, "/service/tickets/1")
+            .query("updateView", "true")
+            .header("Content-Type", "application/json-patch+json")
+            .body(r#"[{"op":"replace","path":"summary","value":"x"}]"#)
+            .send()
+            .unwrap();
+        assert_eq!(
+            response,
+            CwResponse {
+                status: 200,
+                body: json!({"id": 1, "summary": "x"}),
+            }
+        );
+        let response = client
+            .request(Method::DELETE, "/service/tickets/1")
+            .send()
+            .unwrap();
+        assert_eq!(response.status, 204);
+        assert_eq!(response.body, Value::Null);
+        let err = client
+            .request(Method::GET, "/service/tickets/9")
+            .send()
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(Error::Api { status: 404, .. })
+        ));
+
+        // writes are refused by a read only client, reads aren't
+        let err = client
+            .clone()
+            .read_only(true)
+            .request(Method::DELETE, "/service/tickets/1")
+            .send()
+            .unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&Error::ReadOnly));
+
+        patched.assert();
+        removed.assert();
+        missing.assert();
+    }
+}
@@ -0,0 +1,117 @@
+//! Ids sent with each request so a failed call can be found on both sides.  See
+//! [crate::Client::request_id_header]
+use std::fmt;
+
+/// The id of a request, attached to every error the request produces when request ids are
+/// on.  Get it back with [anyhow::Error::downcast_ref]
+///
+/// ```
+/// use cwmanage::RequestId;
+///
+/// fn request_id(err: &anyhow::Error) -> Option<&str> {
+///     err.downcast_ref::<RequestId>().map(|r| r.id.as_str())
+/// }
+/// # let err = anyhow::anyhow!("oops").context(RequestId { id: "abc".to_string(), parent: None });
+/// # assert_eq!(request_id(&err), Some("abc"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId {
+    /// the id sent with the request
+    pub id: String,
+    /// the id of the whole operation when the request is one page of a [crate::Client::get]
+    pub parent: Option<String>,
+}
+
+impl RequestId {
+    pub(crate) fn new(parent: Option<&str>) -> RequestId {
+        RequestId {
+            id: new_id(),
+            parent: parent.map(|p| p.to_string()),
+        }
+    }
+}
+
+/// a new random (v4) uuid
+pub(crate) fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.parent {
+            Some(parent) => write!(f, "request id {} (part of {})", self.id, parent),
+            None => write!(f, "request id {}", self.id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_request_id_in_error() {
+        let mut mock = MockCw::new();
+        let uuid = Matcher::Regex("^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-".to_string());
+        let m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/1")
+            .match_query(Matcher::Any)
+            .match_header("x-request-id", uuid)
+            .with_status(404)
+            .expect(1)
+            .create();
+
+        let err = mock
+            .client()
+            .request_id_header("X-Request-Id")
+            .get_single("/service/tickets/1", &[])
+            .unwrap_err();
+
+        m.assert();
+        let request_id = err.downcast_ref::<RequestId>().unwrap();
+        assert_eq!(request_id.id.len(), 36);
+        assert_eq!(request_id.parent, None);
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_request_id_pages() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets";
+        let next = format!("<{}{}?pageId=2>; rel=\"next\"", mock.url(), path);
+        let _first = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .match_header("x-request-id", Matcher::Any)
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .create();
+        let _second = mock
+            .server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "2".to_string()))
+            .with_status(500)
+            .with_body("oops")
+            .create();
+
+        let err = mock
+            .client()
+            .request_id_header("X-Request-Id")
+            .get("/service/tickets", &[])
+            .unwrap_err();
+
+        let request_id = err.downcast_ref::<RequestId>().unwrap();
+        let parent = request_id.parent.as_ref().unwrap();
+        assert_ne!(&request_id.id, parent);
+        assert!(format!("{:#}", err).contains(&format!("(part of {})", parent)));
+    }
+}
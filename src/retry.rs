@@ -0,0 +1,373 @@
+//! Retrying requests that failed for a reason that may go away (a dropped connection, a 503
+//! from a load balancer, rate limiting).  See [Client::max_retries]
+use crate::{api_error, ApiError, CancelToken, Client, Error, RawResponse};
+use anyhow::{anyhow, Result};
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// statuses that are worth trying again
+const TRANSIENT_STATUS: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// Decides whether a failed request is sent again, see [Client::retry_if]
+pub type RetryPredicate = dyn Fn(&RetryContext) -> bool + Send + Sync;
+
+/// What a [Client::retry_if] predicate gets to decide with
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryContext {
+    /// the request method (`GET`, `POST`, ...)
+    pub method: String,
+    /// the response status, `None` when there was no response (the connection failed or
+    /// timed out)
+    pub status: Option<u16>,
+    /// the error connectwise returned, if the response has one
+    pub api_error: Option<ApiError>,
+    /// the attempt that failed, 1 for the first
+    pub attempt: u32,
+    /// true if the request can be sent twice safely (GET, PUT, DELETE and a PATCH with a
+    /// `test` operation)
+    pub idempotent: bool,
+    retry_non_idempotent: bool,
+}
+
+impl RetryContext {
+    /// true if the failure may go away: no response, a 429 or a 502, 503 or 504
+    pub fn is_transient(&self) -> bool {
+        match self.status {
+            None => true,
+            Some(status) => status == 429 || TRANSIENT_STATUS.iter().any(|s| s.as_u16() == status),
+        }
+    }
+
+    /// the default predicate, a transient failure of an idempotent request (or of any request
+    /// with [Client::retry_non_idempotent])
+    pub fn default_policy(&self) -> bool {
+        self.is_transient() && (self.idempotent || self.retry_non_idempotent)
+    }
+}
+
+impl Client {
+    /// sends a request, retrying it up to `max_retries` times when the retry predicate says to
+    /// and there is time before `deadline`
+    pub(crate) fn send_retrying(
+        &self,
+        http: &reqwest::blocking::Client,
+        req: reqwest::blocking::Request,
+        deadline: Option<Instant>,
+    ) -> Result<RawResponse> {
+        let idempotent = is_idempotent(&req);
+        let mut attempt: u32 = 0;
+
+        loop {
+            if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                return Err(cancelled(&req));
+            }
+            attempt += 1;
+            let mut this = req
+                .try_clone()
+                .ok_or_else(|| anyhow!("the request body can't be sent again"))?;
+            if let Some(deadline) = deadline {
+                let left = deadline.saturating_duration_since(Instant::now());
+                let timeout = this.timeout_mut();
+                *timeout = Some(timeout.map_or(left, |t| t.min(left)));
+            }
+            let result = self.send_once(http, this);
+
+            if self.max_retries == 0 {
+                return result;
+            }
+            let ctx = match retry_context(&req, &result, attempt, idempotent) {
+                Some(ctx) => RetryContext {
+                    retry_non_idempotent: self.retry_non_idempotent,
+                    ..ctx
+                },
+                None => return result,
+            };
+            let retry = match &self.retry_if {
+                Some(retry_if) => (retry_if.0)(&ctx),
+                None => ctx.default_policy(),
+            };
+            if !retry {
+                return match self.retry_if.is_none() && ctx.is_transient() {
+                    true => not_retried(&req, result),
+                    false => result,
+                };
+            }
+            if attempt > self.max_retries {
+                return result;
+            }
+
+            let backoff = self.retry_backoff * 2u32.saturating_pow(attempt - 1);
+            let wait = retry_after(&result).unwrap_or(backoff);
+            if deadline.is_some_and(|deadline| Instant::now() + wait >= deadline) {
+                return result;
+            }
+            match &self.cancel {
+                Some(token) if token.sleep(wait) => return Err(cancelled(&req)),
+                Some(_) => {}
+                None => std::thread::sleep(wait),
+            }
+        }
+    }
+}
+
+fn cancelled(req: &reqwest::blocking::Request) -> anyhow::Error {
+    Error::Cancelled {
+        method: req.method().to_string(),
+        path: req.url().path().to_string(),
+    }
+    .into()
+}
+
+/// GET, PUT and DELETE can be sent twice without changing the outcome, as can a PATCH that
+/// tests the current value first.  A POST can't, a second one is a second record
+fn is_idempotent(req: &reqwest::blocking::Request) -> bool {
+    match *req.method() {
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE => true,
+        Method::PATCH => req
+            .body()
+            .and_then(|b| b.as_bytes())
+            .and_then(|b| serde_json::from_slice::<Value>(b).ok())
+            .and_then(|ops| {
+                ops.as_array()
+                    .map(|ops| ops.iter().any(|op| op["op"] == "test"))
+            })
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// what a retry predicate is asked about a result, `None` if it succeeded or failed in a way
+/// that sending again can't fix
+fn retry_context(
+    req: &reqwest::blocking::Request,
+    result: &Result<RawResponse>,
+    attempt: u32,
+    idempotent: bool,
+) -> Option<RetryContext> {
+    let (status, api_error) = match result {
+        Ok(res) if res.status.is_success() => return None,
+        Ok(res) => (Some(res.status.as_u16()), ApiError::from_body(&res.body)),
+        Err(e) => match e.downcast_ref() {
+            Some(Error::RateLimited { .. }) => (Some(429), None),
+            _ => match e.downcast_ref::<reqwest::Error>() {
+                Some(e) if e.is_timeout() || e.is_connect() || e.is_request() => (None, None),
+                _ => return None,
+            },
+        },
+    };
+    Some(RetryContext {
+        method: req.method().to_string(),
+        status,
+        api_error,
+        attempt,
+        idempotent,
+        retry_non_idempotent: false,
+    })
+}
+
+fn retry_after(result: &Result<RawResponse>) -> Option<Duration> {
+    match result.as_ref().err()?.downcast_ref() {
+        Some(Error::RateLimited { retry_after }) => *retry_after,
+        _ => None,
+    }
+}
+
+fn not_retried(
+    req: &reqwest::blocking::Request,
+    result: Result<RawResponse>,
+) -> Result<RawResponse> {
+    let why = format!(
+        "{} {} was not retried because it isn't idempotent, a retry could create a duplicate \
+         (see Client::retry_non_idempotent)",
+        req.method(),
+        req.url().path()
+    );
+    match result {
+        Ok(res) => Err(api_error(&res, req.url().path()).context(why)),
+        Err(e) => Err(e.context(why)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw};
+    use crate::PatchOp;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_is_retried() {
+        let mut mock = MockCw::new();
+        let unavailable = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(503)
+            .expect(2)
+            .create();
+        let ok = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"isCloud": true}"#)
+            .expect(1)
+            .create();
+
+        let result = mock
+            .client()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(0))
+            .get_single("/system/info", &[])
+            .unwrap();
+
+        unavailable.assert();
+        ok.assert();
+        assert_eq!(result, json!({"isCloud": true}));
+    }
+
+    #[test]
+    fn test_post_is_not_retried() {
+        let mut mock = MockCw::new();
+        let unavailable = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(503)
+            .with_body(
+                fixtures::api_error("ServiceUnavailable", "down for maintenance").to_string(),
+            )
+            .expect(1)
+            .create();
+
+        let err = mock
+            .client()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(0))
+            .post("/service/tickets", "{}".to_string())
+            .unwrap_err();
+
+        unavailable.assert();
+        assert!(err
+            .to_string()
+            .contains("POST /v4_6_release/apis/3.0/service/tickets was not retried"));
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Api { status: 503, .. })
+        ));
+    }
+
+    #[test]
+    fn test_retry_non_idempotent() {
+        let mut mock = MockCw::new();
+        let unavailable = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let ok = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets")
+            .with_body(r#"{"id": 1}"#)
+            .expect(1)
+            .create();
+
+        let result = mock
+            .client()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(0))
+            .retry_non_idempotent(true)
+            .post("/service/tickets", "{}".to_string())
+            .unwrap();
+
+        unavailable.assert();
+        ok.assert();
+        assert_eq!(result, json!({"id": 1}));
+    }
+
+    #[test]
+    fn test_retry_if() {
+        let mut mock = MockCw::new();
+        let quirky = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(500)
+            .with_body(r#"{"code": "DeadlockVictim", "message": "try again"}"#)
+            .expect(1)
+            .create();
+        let broken = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_status(500)
+            .with_body(r#"{"code": "Unexpected", "message": "boom"}"#)
+            .expect(1)
+            .create();
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let record = seen.clone();
+        let client = mock
+            .client()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(0))
+            .retry_if(std::sync::Arc::new(move |ctx: &RetryContext| {
+                record.lock().unwrap().push(ctx.clone());
+                ctx.default_policy()
+                    || ctx.api_error.as_ref().map(|e| e.code.as_str()) == Some("DeadlockVictim")
+            }));
+
+        let _ = client.get_single("/system/info", &[]);
+
+        quirky.assert();
+        broken.assert();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].method, "GET");
+        assert_eq!(seen[0].status, Some(500));
+        assert_eq!(seen[0].attempt, 1);
+        assert_eq!(seen[1].attempt, 2);
+        assert_eq!(seen[1].api_error.as_ref().unwrap().code, "Unexpected");
+    }
+
+    #[test]
+    fn test_is_idempotent() {
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        );
+        let patch = |ops: Value| {
+            client
+                .http_request(Method::PATCH, "/service/tickets/1")
+                .unwrap()
+                .body(ops.to_string())
+                .build()
+                .unwrap()
+        };
+        let method = |method: Method| {
+            client
+                .http_request(method, "/service/tickets/1")
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        assert!(is_idempotent(&method(Method::GET)));
+        assert!(is_idempotent(&method(Method::PUT)));
+        assert!(is_idempotent(&method(Method::DELETE)));
+        assert!(!is_idempotent(&method(Method::POST)));
+        assert!(!is_idempotent(&patch(json!([
+            {"op": PatchOp::Replace, "path": "summary", "value": "x"}
+        ]))));
+        assert!(is_idempotent(&patch(json!([
+            {"op": PatchOp::Test, "path": "summary", "value": "old"},
+            {"op": PatchOp::Replace, "path": "summary", "value": "x"}
+        ]))));
+    }
+}
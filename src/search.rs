@@ -0,0 +1,312 @@
+//! The POST `search` routes, for conditions too long to fit in a url.  See [Client::search]
+use crate::{parse_page, ApiError, Client, Error, RawResponse};
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::time::Instant;
+
+/// The lists with a POST `{path}/search` route that [Client::get] switches to when the url
+/// would be longer than [Client::max_url_length], more are added with [Client::search_route]
+const SEARCH_ROUTES: [&str; 2] = ["/service/tickets", "/project/tickets"];
+
+/// The query keys that go in the body of a search (as they are named there), the rest stay
+/// in the query
+const BODY_KEYS: [&str; 4] = [
+    "conditions",
+    "childConditions",
+    "customFieldConditions",
+    "orderBy",
+];
+
+/// splits a query into what stays in the query and the body of a search
+fn search_query<'a>(query: &[(&'a str, &'a str)]) -> (Vec<(&'a str, &'a str)>, Value) {
+    let mut body = Map::new();
+    let mut rest = Vec::new();
+    for (k, v) in query {
+        match BODY_KEYS.iter().find(|b| b.eq_ignore_ascii_case(k)) {
+            Some(key) => {
+                body.insert(key.to_string(), Value::String(v.to_string()));
+            }
+            None => rest.push((*k, *v)),
+        }
+    }
+    (rest, Value::Object(body))
+}
+
+/// a page of a search, a search connectwise can't run can come back as a 200 with the error
+/// (`code`, `message` and `errors`) instead of a list, that is [Error::Api] as for a post
+fn parse_search_page(
+    res: RawResponse,
+    path: &str,
+    page: &str,
+) -> Result<(Vec<Value>, Option<String>)> {
+    if res.status.is_success() && res.body.trim_start().starts_with('{') {
+        if let Some(error) = ApiError::from_body(&res.body) {
+            return Err(Error::Api {
+                path: path.to_string(),
+                status: res.status.as_u16(),
+                error,
+            }
+            .into());
+        }
+    }
+    parse_page(res, path, page)
+}
+
+impl Client {
+    /// Like [Client::get] but POSTs the `conditions` (and `childConditions`,
+    /// `customFieldConditions` and `orderBy`) to the `search` route of `path`
+    /// (`/service/tickets/search`) instead of putting them in the url, so they can be as long
+    /// as needed.  The other query keys (`fields`, `pagesize`, ...) stay in the query and the
+    /// pages are followed the same way as [Client::get].  Only some lists have a search route
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ids: Vec<String> = (1..=2000).map(|id| id.to_string()).collect();
+    /// let conditions = format!("id in ({})", ids.join(","));
+    /// let tickets = client
+    ///     .search("/service/tickets", &[("conditions", &conditions), ("fields", "id")])
+    ///     .unwrap();
+    /// ```
+    pub fn search(&self, path: &str, query: &[(&str, &str)]) -> Result<Vec<Value>> {
+        let mut collected_res: Vec<Value> = Vec::new();
+
+        for page in self.list_pages(path, query, true) {
+            collected_res.append(&mut page?);
+        }
+
+        Ok(collected_res)
+    }
+
+    /// true when `path` has a search route, one of [SEARCH_ROUTES] or added with
+    /// [Client::search_route]
+    pub(crate) fn has_search_route(&self, path: &str) -> bool {
+        let path = format!("/{}", path.trim_matches('/'));
+        SEARCH_ROUTES
+            .iter()
+            .copied()
+            .chain(self.search_routes.iter().map(String::as_str))
+            .any(|r| r.eq_ignore_ascii_case(&path))
+    }
+
+    /// gets one page of a search, see [Client::fetch_page]
+    pub(crate) fn fetch_search_page(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        page: &str,
+        parent: Option<&str>,
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<Value>, Option<String>)> {
+        let (query, body) = search_query(query);
+        let search_path = format!("{}/search", path.trim_end_matches('/'));
+        let req = self
+            .http_request(reqwest::Method::POST, &search_path)?
+            .query(&[("pageid", page)])
+            .query(&query)
+            .body(body.to_string());
+
+        self.call_in(parent, deadline, req, |res| {
+            parse_search_page(res, &search_path, page)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn long_conditions() -> String {
+        let ids: Vec<String> = (1..=500).map(|id| id.to_string()).collect();
+        format!("id in ({})", ids.join(","))
+    }
+
+    #[test]
+    fn test_search() {
+        let mut mock = MockCw::new();
+        let conditions = long_conditions();
+        let next = format!(
+            "<{}/v4_6_release/apis/3.0/service/tickets/search?pageId=2>; rel=\"next\"",
+            mock.url()
+        );
+        let body = Matcher::Json(json!({"conditions": conditions, "orderBy": "id asc"}));
+        let first = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/search")
+            .match_query(Matcher::Exact("pageid=1&fields=id".to_string()))
+            .match_body(body.clone())
+            .with_header("link", &next)
+            .with_body(r#"[{"id": 1}]"#)
+            .expect(1)
+            .create();
+        let second = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/search")
+            .match_query(Matcher::Exact("pageid=2&fields=id".to_string()))
+            .match_body(body)
+            .with_body(r#"[{"id": 2}]"#)
+            .expect(1)
+            .create();
+
+        // too long for a url, so get switches to the search route
+        let tickets = mock
+            .client()
+            .get(
+                "/service/tickets",
+                &[("conditions", &conditions), ("fields", "id")],
+            )
+            .unwrap();
+
+        first.assert();
+        second.assert();
+        assert_eq!(tickets, vec![json!({"id": 1}), json!({"id": 2})]);
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let mut mock = MockCw::new();
+        let companies = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/company/companies/search")
+            .match_query(Matcher::Any)
+            .match_body(Matcher::PartialJson(
+                json!({"conditions": "name like 'a%'"}),
+            ))
+            .with_body(r#"[{"id": 1, "name": "acme"}]"#)
+            .create();
+        let invalid = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/search")
+            .match_query(Matcher::Any)
+            .match_body(Matcher::PartialJson(json!({"conditions": "nope = 1"})))
+            .with_status(400)
+            .with_body(
+                fixtures::validation_error("ticket", "conditions", "nope is not a field")
+                    .to_string(),
+            )
+            .create();
+        let answered = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/search")
+            .match_query(Matcher::Any)
+            .match_body(Matcher::PartialJson(json!({"conditions": "id = x"})))
+            .with_body(fixtures::api_error("InvalidConditions", "x is not a number").to_string())
+            .create();
+        let client = mock.client();
+
+        let found = client
+            .search("/company/companies", &[("conditions", "name like 'a%'")])
+            .unwrap();
+        assert_eq!(found, vec![json!({"id": 1, "name": "acme"})]);
+        let err = client
+            .search("/service/tickets", &[("conditions", "nope = 1")])
+            .unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Api {
+                status: 400, error, ..
+            }) => assert_eq!(error.errors[0].field, "conditions"),
+            other => panic!("{:?}", other),
+        }
+        let err = client
+            .search("/service/tickets", &[("conditions", "id = x")])
+            .unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Api {
+                path,
+                status: 200,
+                error,
+            }) => {
+                assert_eq!(path, "/service/tickets/search");
+                assert_eq!(error.message, "x is not a number");
+            }
+            other => panic!("{:?}", other),
+        }
+
+        companies.assert();
+        invalid.assert();
+        answered.assert();
+    }
+
+    #[test]
+    fn test_query_too_long() {
+        let mut mock = MockCw::new();
+        let conditions = long_conditions();
+        let companies = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/company/companies")
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        let too_long = client
+            .get("/company/companies", &[("conditions", &conditions)])
+            .unwrap_err();
+        client
+            .clone()
+            .max_url_length(10_000)
+            .get("/company/companies", &[("conditions", &conditions)])
+            .unwrap();
+
+        match too_long.downcast_ref::<Error>() {
+            Some(Error::QueryTooLong { path, length, max }) => {
+                assert_eq!(path, "/company/companies");
+                assert!(*length > 2048);
+                assert_eq!(*max, 2048);
+            }
+            other => panic!("{:?}", other),
+        }
+        companies.assert();
+    }
+
+    #[test]
+    fn test_search_route_fallback() {
+        let mut mock = MockCw::new();
+        let conditions = long_conditions();
+        let searched = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/company/companies/search")
+            .match_query(Matcher::UrlEncoded("pageid".to_string(), "1".to_string()))
+            .match_body(Matcher::PartialJson(json!({"conditions": conditions})))
+            .with_body(r#"[{"id": 250}]"#)
+            .expect(1)
+            .create();
+        let client = mock.client().search_route("/company/companies");
+
+        // too long for a url, and the list was given a search route
+        let companies = client
+            .get("/company/companies", &[("conditions", &conditions)])
+            .unwrap();
+
+        searched.assert();
+        assert_eq!(companies, vec![json!({"id": 250})]);
+    }
+
+    #[test]
+    fn test_search_route() {
+        let client = MockCw::new().client();
+        let companies = client.clone().search_route("company/companies/");
+
+        assert!(client.has_search_route("service/tickets/"));
+        assert!(!client.has_search_route("/service/tickets/1"));
+        assert!(!client.has_search_route("/company/companies"));
+        assert!(companies.has_search_route("/Company/Companies"));
+        assert!(companies.has_search_route("/project/tickets"));
+    }
+}
@@ -0,0 +1,89 @@
+//! State shared by a client and its clones (caches and the like)
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+/// What the clones of a client share
+#[derive(Debug, Default)]
+pub(crate) struct State {
+    /// the instance time zone, see [crate::Client::timezone]
+    #[cfg(feature = "chrono-tz")]
+    pub(crate) timezone: Mutex<Option<chrono_tz::Tz>>,
+    /// the earliest the next request may be sent, see [crate::Client::max_requests_per_second]
+    pub(crate) next_request: Mutex<Option<Instant>>,
+    /// the rate limit of the last response that had one, see [crate::Client::last_rate_limit]
+    pub(crate) rate_limit: Mutex<Option<crate::RateLimitInfo>>,
+    /// the service boards, see [crate::Client::get_boards]
+    pub(crate) boards: Mutex<Option<Vec<crate::Board>>>,
+    /// the statuses of each board, see [crate::Client::get_board_statuses]
+    pub(crate) board_statuses: Mutex<HashMap<i64, Vec<crate::BoardStatus>>>,
+}
+
+/// [State] behind an [Arc].  It doesn't take part in comparing clients
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Shared(Arc<State>);
+
+impl PartialEq for Shared {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Deref for Shared {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        &self.0
+    }
+}
+
+/// A callback set on a client.  Clients compare equal when they hold the same callback
+pub(crate) struct Hook<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Hook(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> PartialEq for Hook<F> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(Arc::as_ptr(&self.0), Arc::as_ptr(&other.0))
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// The http client (and its connection pool) requests are sent with.  It is made once, by
+/// [crate::Client::build] or else by the first request, and shared by the clones of a client
+/// and the clients of a [crate::ClientPool].  Clients compare equal whichever one they hold
+#[derive(Clone, Default)]
+pub(crate) struct Http(pub(crate) Arc<OnceLock<reqwest::blocking::Client>>);
+
+impl Http {
+    /// the http client, made the first time it is needed
+    pub(crate) fn get(&self) -> anyhow::Result<reqwest::blocking::Client> {
+        if let Some(http) = self.0.get() {
+            return Ok(http.clone());
+        }
+        let http = crate::http_client()?;
+        Ok(self.0.get_or_init(|| http).clone())
+    }
+}
+
+impl PartialEq for Http {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl std::fmt::Debug for Http {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Http")
+    }
+}
@@ -0,0 +1,287 @@
+//! Where a ticket is against its SLA.  See [Client::get_ticket_sla]
+use crate::{Client, CwValueExt};
+use anyhow::Result;
+use serde_json::Value;
+
+/// The SLA stage a ticket is in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlaStage {
+    /// the ticket's board has no SLA, nothing else in the [TicketSla] is set
+    NoSla,
+    /// waiting for the first response
+    Respond,
+    /// responded, waiting for a plan
+    Plan,
+    /// planned, waiting to be resolved
+    Resolve,
+    /// resolved
+    Resolved,
+    /// the SLA clock is stopped (the ticket is in a waiting status)
+    Waiting,
+}
+
+/// The SLA of a ticket, from [Client::get_ticket_sla]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TicketSla {
+    /// the ticket
+    pub ticket_id: i64,
+    /// the name of the SLA, `None` when there is no SLA
+    pub sla_name: Option<String>,
+    /// the stage the ticket is in
+    pub stage: SlaStage,
+    /// when the ticket has to be responded to
+    #[cfg(feature = "chrono-tz")]
+    pub respond_by: Option<chrono::DateTime<chrono::Utc>>,
+    /// when the ticket has to have a plan
+    #[cfg(feature = "chrono-tz")]
+    pub plan_by: Option<chrono::DateTime<chrono::Utc>>,
+    /// when the ticket has to be resolved
+    #[cfg(feature = "chrono-tz")]
+    pub resolve_by: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl TicketSla {
+    /// false when the ticket's board has no SLA
+    pub fn has_sla(&self) -> bool {
+        self.stage != SlaStage::NoSla
+    }
+
+    /// The deadline of the current stage, `None` once resolved, while waiting or without an
+    /// SLA
+    #[cfg(feature = "chrono-tz")]
+    pub fn deadline(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.stage {
+            SlaStage::Respond => self.respond_by,
+            SlaStage::Plan => self.plan_by,
+            SlaStage::Resolve => self.resolve_by,
+            _ => None,
+        }
+    }
+
+    /// The minutes from `now` to the deadline of the current stage, negative once it has
+    /// passed
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use cwmanage::{SlaStage, TicketSla};
+    ///
+    /// let sla = TicketSla {
+    ///     ticket_id: 123,
+    ///     sla_name: Some("Standard".to_string()),
+    ///     stage: SlaStage::Respond,
+    ///     respond_by: Some(Utc.with_ymd_and_hms(2022, 7, 14, 15, 0, 0).unwrap()),
+    ///     plan_by: None,
+    ///     resolve_by: None,
+    /// };
+    /// let now = Utc.with_ymd_and_hms(2022, 7, 14, 14, 15, 0).unwrap();
+    /// assert_eq!(sla.minutes_remaining(now), Some(45));
+    /// ```
+    #[cfg(feature = "chrono-tz")]
+    pub fn minutes_remaining(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+        Some((self.deadline()? - now).num_minutes())
+    }
+}
+
+/// where each deadline is, the ticket field on current versions and the `_info` entry older
+/// versions put it in
+#[cfg(feature = "chrono-tz")]
+const DEADLINES: [(&str, &str); 3] = [
+    ("respondByDate", "respondBy"),
+    ("planByDate", "planBy"),
+    ("resolveByDate", "resolveBy"),
+];
+
+/// the stage from `slaStatus` (`Respond by Thu 07/14 3:00 PM`, `Resolved`, ...), or from which
+/// of the stages have been done when there isn't one
+fn sla_stage(ticket: &Value) -> SlaStage {
+    let status = ticket.get_str("slaStatus").unwrap_or_default().trim();
+    let first = status.split_whitespace().next().unwrap_or_default();
+    match first.to_lowercase().as_str() {
+        "respond" => return SlaStage::Respond,
+        "plan" => return SlaStage::Plan,
+        "resolve" => return SlaStage::Resolve,
+        "resolved" => return SlaStage::Resolved,
+        "waiting" => return SlaStage::Waiting,
+        _ => {}
+    }
+    let done = |key: &str| ticket.get_str(key).is_some_and(|d| !d.is_empty());
+    if done("dateResolved") || ticket["closedFlag"] == Value::Bool(true) {
+        SlaStage::Resolved
+    } else if done("dateResplan") {
+        SlaStage::Resolve
+    } else if done("dateResponded") {
+        SlaStage::Plan
+    } else {
+        SlaStage::Respond
+    }
+}
+
+impl Client {
+    /// Gets where a ticket is against its SLA: the stage and the respond, plan and resolve
+    /// deadlines (with the `chrono-tz` feature).  Deadlines without an offset are in the
+    /// instance's time zone and converted with [Client::to_utc].  A ticket on a board without
+    /// an SLA isn't an error, its stage is [SlaStage::NoSla]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let sla = client.get_ticket_sla(123).unwrap();
+    /// match sla.has_sla() {
+    ///     true => println!("{:?} on {:?}", sla.stage, sla.sla_name),
+    ///     false => println!("no sla"),
+    /// }
+    /// ```
+    pub fn get_ticket_sla(&self, ticket_id: i64) -> Result<TicketSla> {
+        let ticket = self.get_single(&format!("/service/tickets/{}", ticket_id), &[])?;
+        let sla_name = ticket
+            .ref_id("sla")
+            .map(|_| ticket.ref_name("sla").unwrap_or_default().to_string());
+        let stage = match sla_name {
+            Some(_) => sla_stage(&ticket),
+            None => SlaStage::NoSla,
+        };
+
+        #[cfg(feature = "chrono-tz")]
+        let [respond_by, plan_by, resolve_by] = match stage {
+            SlaStage::NoSla => [None, None, None],
+            _ => {
+                let mut deadlines = [None, None, None];
+                for (deadline, (field, info)) in deadlines.iter_mut().zip(DEADLINES) {
+                    let value = ticket
+                        .get_str(field)
+                        .or_else(|| ticket.get("_info")?.get_str(info));
+                    if let Some(value) = value {
+                        *deadline = Some(self.sla_time(value)?);
+                    }
+                }
+                deadlines
+            }
+        };
+
+        Ok(TicketSla {
+            ticket_id,
+            sla_name,
+            stage,
+            #[cfg(feature = "chrono-tz")]
+            respond_by,
+            #[cfg(feature = "chrono-tz")]
+            plan_by,
+            #[cfg(feature = "chrono-tz")]
+            resolve_by,
+        })
+    }
+
+    /// a deadline, in utc when it has an offset and otherwise in the instance's time zone
+    #[cfg(feature = "chrono-tz")]
+    fn sla_time(&self, value: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+        if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+            return Ok(time.with_timezone(&chrono::Utc));
+        }
+        let local = chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+            .map_err(|e| anyhow::anyhow!("couldn't read the sla time {:?}: {}", value, e))?;
+        self.to_utc(local)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_no_sla() {
+        let mut mock = MockCw::new();
+        let _ticket = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 123, "summary": "printer on fire", "_info": {}}"#)
+            .create();
+
+        let sla = mock.client().get_ticket_sla(123).unwrap();
+
+        assert!(!sla.has_sla());
+        assert_eq!(sla.stage, SlaStage::NoSla);
+        assert_eq!(sla.sla_name, None);
+    }
+
+    #[test]
+    fn test_sla_stage() {
+        let sla = json!({"id": 1, "name": "Standard"});
+
+        assert_eq!(
+            sla_stage(&json!({"sla": sla, "slaStatus": "Plan by Thu 07/14 3:00 PM"})),
+            SlaStage::Plan
+        );
+        assert_eq!(
+            sla_stage(&json!({"sla": sla, "slaStatus": "Waiting"})),
+            SlaStage::Waiting
+        );
+        assert_eq!(sla_stage(&json!({"sla": sla})), SlaStage::Respond);
+        assert_eq!(
+            sla_stage(&json!({"sla": sla, "dateResponded": "2022-07-14T14:00:00Z"})),
+            SlaStage::Plan
+        );
+        assert_eq!(
+            sla_stage(&json!({"sla": sla, "closedFlag": true})),
+            SlaStage::Resolved
+        );
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_get_ticket_sla() {
+        use chrono::{TimeZone, Utc};
+        let mut mock = MockCw::new();
+        let _ticket = mock.server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"{"id": 123, "sla": {"id": 1, "name": "Standard"},
+                    "slaStatus": "Respond by Thu 07/14 3:00 PM",
+                    "respondByDate": "2022-07-14T15:00:00Z",
+                    "_info": {"planBy": "2022-07-14T17:30:00", "resolveBy": "2022-07-15T17:00:00"}}"#,
+            )
+            .create();
+        let info = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"{"version": "v2019.1", "isCloud": false,
+                    "serverTimeZone": "Central Standard Time"}"#,
+            )
+            .expect(1)
+            .create();
+
+        let sla = mock.client().get_ticket_sla(123).unwrap();
+
+        info.assert();
+        assert_eq!(sla.sla_name.as_deref(), Some("Standard"));
+        assert_eq!(sla.stage, SlaStage::Respond);
+        assert_eq!(
+            sla.plan_by,
+            Some(Utc.with_ymd_and_hms(2022, 7, 14, 22, 30, 0).unwrap())
+        );
+        assert_eq!(
+            sla.resolve_by,
+            Some(Utc.with_ymd_and_hms(2022, 7, 15, 22, 0, 0).unwrap())
+        );
+        let now = Utc.with_ymd_and_hms(2022, 7, 14, 16, 0, 0).unwrap();
+        assert_eq!(sla.minutes_remaining(now), Some(-60));
+    }
+}
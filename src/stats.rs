@@ -0,0 +1,221 @@
+//! Open ticket counts for dashboards, see [Client::ticket_stats]
+use crate::bulk::run_each;
+use crate::{BulkOptions, Client};
+use anyhow::{anyhow, Result};
+
+/// how many count queries [Client::ticket_stats] sends at the same time
+const STATS_CONCURRENCY: usize = 4;
+
+/// What [Client::ticket_stats] counts tickets by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsGroup {
+    /// the service board (`board/id`), one bucket per active board
+    Board,
+    /// the status (`status/id`), one bucket per active status of each active board.  Every
+    /// board has its own statuses, so the names are `Help Desk / New`
+    Status,
+}
+
+/// The count of one group from [Client::ticket_stats]
+#[derive(Debug)]
+pub struct StatBucket {
+    /// the name of the board or status
+    pub group_name: String,
+    /// the id of the board or status
+    pub group_id: i64,
+    /// how many tickets match, or why they couldn't be counted
+    pub count: Result<u64>,
+}
+
+impl Client {
+    /// Counts the tickets matching `base_conditions` (all of them when it is empty) in each
+    /// board or status.  The boards and statuses come from [Client::get_boards] and
+    /// [Client::get_board_statuses], so they are only asked for once, and the counts
+    /// (`/service/tickets/count`) are sent a few at a time.  A count that fails is an `Err`
+    /// in its bucket, the others are still returned
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, StatsGroup};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for bucket in client.ticket_stats(StatsGroup::Board, "closedFlag = false").unwrap() {
+    ///     match bucket.count {
+    ///         Ok(count) => println!("{}: {}", bucket.group_name, count),
+    ///         Err(e) => println!("{}: unavailable ({})", bucket.group_name, e),
+    ///     }
+    /// }
+    /// ```
+    pub fn ticket_stats(
+        &self,
+        group_by: StatsGroup,
+        base_conditions: &str,
+    ) -> Result<Vec<StatBucket>> {
+        let boards = self.get_boards()?.into_iter().filter(|b| !b.inactive_flag);
+        let mut groups = Vec::new();
+        for board in boards {
+            match group_by {
+                StatsGroup::Board => groups.push((board.name, "board/id", board.id)),
+                StatsGroup::Status => {
+                    for status in self.get_board_statuses(board.id)? {
+                        if !status.inactive {
+                            let name = format!("{} / {}", board.name, status.name);
+                            groups.push((name, "status/id", status.id));
+                        }
+                    }
+                }
+            }
+        }
+
+        let options = BulkOptions::default().concurrency(STATS_CONCURRENCY);
+        let counts = run_each(&groups, &options, false, |(_, field, id)| {
+            let conditions = match base_conditions.trim() {
+                "" => format!("{} = {}", field, id),
+                base => format!("({}) AND {} = {}", base, field, id),
+            };
+            self.get_count("/service/tickets", &[("conditions", &conditions)])
+        });
+
+        Ok(groups
+            .into_iter()
+            .zip(counts)
+            .map(|((group_name, _, group_id), count)| StatBucket {
+                group_name,
+                group_id,
+                count: count.unwrap_or_else(|| Err(anyhow!("not counted"))),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw, API_PREFIX};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn mock_count(mock: &mut MockCw, conditions: &str, body: &str, hits: usize) -> mockito::Mock {
+        mock.server()
+            .mock(
+                "GET",
+                format!("{}/service/tickets/count", API_PREFIX).as_str(),
+            )
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                conditions.to_string(),
+            ))
+            .with_body(body)
+            .expect(hits)
+            .create()
+    }
+
+    fn counts(buckets: &[StatBucket]) -> Vec<(String, i64, Option<u64>)> {
+        buckets
+            .iter()
+            .map(|b| {
+                (
+                    b.group_name.clone(),
+                    b.group_id,
+                    b.count.as_ref().ok().copied(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ticket_stats_by_board() {
+        let mut mock = MockCw::new().list(
+            "/service/boards",
+            vec![vec![
+                json!({"id": 1, "name": "Help Desk"}),
+                json!({"id": 2, "name": "Old", "inactiveFlag": true}),
+                json!({"id": 3, "name": "Projects"}),
+            ]],
+        );
+        let _projects = mock
+            .server()
+            .mock(
+                "GET",
+                format!("{}/service/tickets/count", API_PREFIX).as_str(),
+            )
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "(closedFlag = false) AND board/id = 3".to_string(),
+            ))
+            .with_status(400)
+            .with_body(fixtures::api_error("InvalidObject", "boom").to_string())
+            .create();
+        let help_desk = mock_count(
+            &mut mock,
+            "(closedFlag = false) AND board/id = 1",
+            r#"{"count": 12}"#,
+            1,
+        );
+        let client = mock.client();
+
+        let buckets = client
+            .ticket_stats(StatsGroup::Board, "closedFlag = false")
+            .unwrap();
+
+        help_desk.assert();
+        assert_eq!(
+            counts(&buckets),
+            vec![
+                ("Help Desk".to_string(), 1, Some(12)),
+                ("Projects".to_string(), 3, None),
+            ]
+        );
+        assert!(buckets[1]
+            .count
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("boom"));
+    }
+
+    #[test]
+    fn test_ticket_stats_by_status() {
+        let mut mock = MockCw::new()
+            .list(
+                "/service/boards",
+                vec![vec![json!({"id": 1, "name": "Help Desk"})]],
+            )
+            .list(
+                "/service/boards/1/statuses",
+                vec![vec![
+                    json!({"id": 16, "name": "New"}),
+                    json!({"id": 17, "name": "Waiting", "inactive": true}),
+                    json!({"id": 18, "name": "Completed", "closedStatus": true}),
+                ]],
+            );
+        let new = mock_count(&mut mock, "status/id = 16", r#"{"count": 4}"#, 2);
+        let completed = mock_count(&mut mock, "status/id = 18", r#"{"count": 0}"#, 2);
+        let client = mock.client();
+
+        let buckets = client.ticket_stats(StatsGroup::Status, " ").unwrap();
+        // the boards and statuses are remembered
+        client.ticket_stats(StatsGroup::Status, "").unwrap();
+
+        assert_eq!(
+            counts(&buckets),
+            vec![
+                ("Help Desk / New".to_string(), 16, Some(4)),
+                ("Help Desk / Completed".to_string(), 18, Some(0)),
+            ]
+        );
+        mock.assert();
+        new.assert();
+        completed.assert();
+    }
+}
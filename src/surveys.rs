@@ -0,0 +1,151 @@
+//! Customer satisfaction surveys and their results.  See [Client::get_survey_results]
+use crate::{Client, CwRef};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// A survey (`/service/surveys`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Survey {
+    /// the id of the survey
+    pub id: i64,
+    /// the name of the survey
+    #[serde(default)]
+    pub name: String,
+    /// what the survey is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// true if the survey is no longer sent
+    #[serde(default)]
+    pub inactive_flag: bool,
+}
+
+/// One completed survey (`/service/surveys/{id}/results`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurveyResult {
+    /// the id of the result
+    pub id: i64,
+    /// the survey that was answered
+    #[serde(default)]
+    pub survey_id: i64,
+    /// the ticket the survey was sent for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket_id: Option<i64>,
+    /// who answered, `None` when the result is anonymous
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<CwRef>,
+    /// the total score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<f64>,
+    /// the answer to each question
+    #[serde(default)]
+    pub answers: Vec<SurveyAnswer>,
+}
+
+/// The answer to one question of a survey
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SurveyAnswer {
+    /// the id of the question
+    #[serde(default)]
+    pub question_id: i64,
+    /// the text of the question
+    #[serde(default, alias = "questionText")]
+    pub question: String,
+    /// the score given, `None` for a free text question
+    #[serde(default, alias = "points", skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// what was written in, if anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+impl Client {
+    /// Gets the surveys, `query` is passed on as with [Client::get]
+    pub fn list_surveys(&self, query: &[(&str, &str)]) -> Result<Vec<Survey>> {
+        self.get_decoded("/service/surveys", query)
+    }
+
+    /// Gets every result of a survey (all of the pages), `query` is passed on as with
+    /// [Client::get]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for result in client.get_survey_results(1, &[("", "")]).unwrap() {
+    ///     for answer in result.answers.iter() {
+    ///         println!("{}: {:?} {:?}", answer.question, answer.score, answer.comment);
+    ///     }
+    /// }
+    /// ```
+    pub fn get_survey_results(
+        &self,
+        survey_id: i64,
+        query: &[(&str, &str)],
+    ) -> Result<Vec<SurveyResult>> {
+        let path = format!("/service/surveys/{}/results", survey_id);
+        self.get_decoded(&path, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_survey_results() {
+        let mock = MockCw::new().list(
+            "/service/surveys/4/results",
+            vec![
+                vec![json!({"id": 1, "surveyId": 4, "ticketId": 123, "points": 9,
+                "contact": {"id": 7, "name": "Jane"},
+                "answers": [
+                    {"questionId": 1, "question": "How did we do?", "score": 9},
+                    {"questionId": 2, "question": "Anything else?", "comment": "fast!"}
+                ]})],
+                vec![json!({"id": 2, "surveyId": 4, "answers": []})],
+            ],
+        );
+
+        let results = mock.client().get_survey_results(4, &[("", "")]).unwrap();
+
+        mock.assert();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].contact.as_ref().map(|c| c.id), Some(7));
+        assert_eq!(results[0].answers[0].score, Some(9.0));
+        assert_eq!(results[0].answers[1].comment.as_deref(), Some("fast!"));
+        assert_eq!(results[1].contact, None);
+        assert_eq!(results[1].ticket_id, None);
+    }
+
+    #[test]
+    fn test_list_surveys() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/surveys")
+            .match_query(Matcher::Any)
+            .with_body(r#"[{"id": 4, "name": "After ticket", "inactiveFlag": false}]"#)
+            .create();
+
+        let surveys = mock.client().list_surveys(&[("", "")]).unwrap();
+
+        assert_eq!(surveys[0].name, "After ticket");
+        assert_eq!(surveys[0].description, None);
+    }
+}
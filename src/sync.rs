@@ -0,0 +1,227 @@
+//! Getting what changed in a list since the last sync.  See [Client::get_updated_since]
+use crate::{Client, Error, LATEST_ORDER_FIELD};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// The records [Client::get_updated_since] got, and where the next sync starts from
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdatedSince {
+    /// the records updated since, oldest update first
+    pub records: Vec<Value>,
+    /// the latest `_info/lastUpdated` of the records, `None` when there are none.  Keep it
+    /// and pass it as `since` next time
+    pub last_updated: Option<DateTime<Utc>>,
+}
+
+impl Client {
+    /// The records of `path` updated at or after `since`, ordered by when they were updated
+    /// (`_info/lastUpdated asc`) and with the latest update seen, the `since` of the next sync.
+    /// The date is sent the way connectwise expects (`[2024-01-01T00:00:00Z]`) and `AND`ed
+    /// with the `conditions` in `extra_query`
+    ///
+    /// A record updated at exactly `since` is returned again by the next sync, so nothing
+    /// updated in the same second as the last record is missed; store the records by id.  The
+    /// order is set here, an `orderby` in `extra_query` is [Error::InvalidQuery].  When
+    /// `extra_query` has `fields`, `_info/lastUpdated` is added to them
+    ///
+    /// # Example
+    /// ```no_run
+    /// use chrono::{TimeZone, Utc};
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let since = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    /// let updated = client
+    ///     .get_updated_since("/service/tickets", since, &[("conditions", "board/id = 3")])
+    ///     .unwrap();
+    /// println!("{} tickets changed", updated.records.len());
+    /// let next_since = updated.last_updated.unwrap_or(since);
+    /// ```
+    pub fn get_updated_since(
+        &self,
+        path: &str,
+        since: DateTime<Utc>,
+        extra_query: &[(&str, &str)],
+    ) -> Result<UpdatedSince> {
+        if let Some((key, _)) = extra_query
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("orderby"))
+        {
+            return Err(Error::InvalidQuery {
+                key: key.to_string(),
+                reason: "get_updated_since orders by when the records were updated".to_string(),
+            }
+            .into());
+        }
+
+        let updated = format!(
+            "{} >= [{}]",
+            LATEST_ORDER_FIELD,
+            crate::dates::format(&since)
+        );
+        let orderby = format!("{} asc", LATEST_ORDER_FIELD);
+        let mut conditions = updated.clone();
+        let mut fields = None;
+        let mut query: Vec<(&str, &str)> = Vec::new();
+        for (key, value) in extra_query {
+            if key.eq_ignore_ascii_case("conditions") {
+                if !value.trim().is_empty() {
+                    conditions = format!("({}) AND {}", value, updated);
+                }
+            } else if key.eq_ignore_ascii_case("fields") {
+                let has_last_updated = value.split(',').any(|f| {
+                    let f = f.trim();
+                    f.eq_ignore_ascii_case("_info") || f.eq_ignore_ascii_case(LATEST_ORDER_FIELD)
+                });
+                fields = match has_last_updated {
+                    true => Some(value.to_string()),
+                    false => Some(format!("{},{}", value, LATEST_ORDER_FIELD)),
+                };
+            } else {
+                query.push((key, value));
+            }
+        }
+        query.push(("conditions", &conditions));
+        query.push(("orderby", &orderby));
+        if let Some(fields) = &fields {
+            query.push(("fields", fields));
+        }
+
+        let records = self.get(path, &query)?;
+        let last_updated = records
+            .iter()
+            .filter_map(|r| r["_info"]["lastUpdated"].as_str())
+            .filter_map(|d| crate::dates::parse(d).ok())
+            .max();
+        Ok(UpdatedSince {
+            records,
+            last_updated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{MockCw, API_PREFIX};
+    use chrono::TimeZone;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_get_updated_since() {
+        let mut mock = MockCw::new();
+        let tickets = mock
+            .server()
+            .mock("GET", format!("{}/service/tickets", API_PREFIX).as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded(
+                    "conditions".to_string(),
+                    "(board/id = 3) AND _info/lastUpdated >= [2024-01-01T00:00:00Z]".to_string(),
+                ),
+                Matcher::UrlEncoded("orderby".to_string(), "_info/lastUpdated asc".to_string()),
+                Matcher::UrlEncoded(
+                    "fields".to_string(),
+                    "id,summary,_info/lastUpdated".to_string(),
+                ),
+                Matcher::UrlEncoded("pagesize".to_string(), "500".to_string()),
+            ]))
+            .with_body(
+                json!([
+                    {"id": 2, "_info": {"lastUpdated": "2024-01-02T08:00:00Z"}},
+                    {"id": 1, "_info": {"lastUpdated": "2024-01-03T09:30:00Z"}},
+                ])
+                .to_string(),
+            )
+            .create();
+        let companies = mock
+            .server()
+            .mock("GET", format!("{}/company/companies", API_PREFIX).as_str())
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "_info/lastUpdated >= [2024-01-01T00:00:00Z]".to_string(),
+            ))
+            .with_body("[]")
+            .create();
+        let contracts = mock
+            .server()
+            .mock("GET", format!("{}/service/contracts", API_PREFIX).as_str())
+            .match_query(Matcher::UrlEncoded(
+                "fields".to_string(),
+                "id,_info/enteredBy,_info/lastUpdated".to_string(),
+            ))
+            .with_body(
+                json!([{"id": 3, "_info": {"enteredBy": "zpeters",
+                    "lastUpdated": "2024-01-02T08:00:00Z"}}])
+                .to_string(),
+            )
+            .create();
+        let client = mock.client();
+        let since = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+
+        let updated = client
+            .get_updated_since(
+                "/service/tickets",
+                since,
+                &[
+                    ("conditions", "board/id = 3"),
+                    ("fields", "id,summary"),
+                    ("pagesize", "500"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            updated
+                .records
+                .iter()
+                .map(|r| r["id"].as_i64().unwrap())
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        assert_eq!(
+            updated.last_updated,
+            Some(Utc.with_ymd_and_hms(2024, 1, 3, 9, 30, 0).unwrap())
+        );
+        let none = client
+            .get_updated_since("/company/companies", since, &[])
+            .unwrap();
+        assert_eq!(none.records, Vec::<Value>::new());
+        assert_eq!(none.last_updated, None);
+
+        // another `_info` field still gets `_info/lastUpdated`, the next sync needs it
+        let updated = client
+            .get_updated_since(
+                "/service/contracts",
+                since,
+                &[("fields", "id,_info/enteredBy")],
+            )
+            .unwrap();
+        assert_eq!(
+            updated.last_updated,
+            Some(Utc.with_ymd_and_hms(2024, 1, 2, 8, 0, 0).unwrap())
+        );
+
+        let err = client
+            .get_updated_since("/service/tickets", since, &[("orderBy", "id")])
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::InvalidQuery { key, .. }) if key == "orderBy"
+        ));
+
+        tickets.assert();
+        companies.assert();
+        contracts.assert();
+    }
+}
@@ -0,0 +1,174 @@
+//! Information about the connectwise instance itself.  See [Client::system_info]
+use crate::Client;
+#[cfg(feature = "chrono-tz")]
+use crate::Error;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// What `/system/info` returns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    /// the version of manage (`v2022.1.12345`)
+    pub version: String,
+    /// true for a cloud (hosted) instance
+    pub is_cloud: bool,
+    /// the windows name of the time zone the server runs in (`Eastern Standard Time`)
+    pub server_time_zone: String,
+}
+
+#[cfg(feature = "chrono-tz")]
+impl SystemInfo {
+    /// The IANA time zone for [SystemInfo::server_time_zone], or [Error::UnknownTimeZone]
+    ///
+    /// ```
+    /// use cwmanage::SystemInfo;
+    ///
+    /// let info = SystemInfo {
+    ///     version: "v2022.1".to_string(),
+    ///     is_cloud: true,
+    ///     server_time_zone: "Eastern Standard Time".to_string(),
+    /// };
+    /// assert_eq!(info.timezone().unwrap(), chrono_tz::America::New_York);
+    /// ```
+    pub fn timezone(&self) -> Result<chrono_tz::Tz> {
+        crate::timezone::windows_to_iana(&self.server_time_zone).ok_or_else(|| {
+            Error::UnknownTimeZone {
+                name: self.server_time_zone.to_string(),
+            }
+            .into()
+        })
+    }
+}
+
+impl Client {
+    /// Gets `/system/info` as a [SystemInfo]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let info = client.system_info().unwrap();
+    /// println!("running {}", info.version);
+    /// ```
+    pub fn system_info(&self) -> Result<SystemInfo> {
+        self.decode("/system/info", self.get_single("/system/info", &[])?)
+    }
+
+    /// The time zone of the instance.  It is asked for once (with [Client::system_info]) and
+    /// then remembered by this client and its clones
+    #[cfg(feature = "chrono-tz")]
+    pub fn timezone(&self) -> Result<chrono_tz::Tz> {
+        let mut cached = self.shared.timezone.lock().unwrap();
+        if let Some(tz) = *cached {
+            return Ok(tz);
+        }
+        let tz = self.system_info()?.timezone()?;
+        *cached = Some(tz);
+        Ok(tz)
+    }
+
+    /// Converts a time in the instance's local time (see [Client::timezone]) to utc.  A time
+    /// that happens twice when the clocks go back is the first of the two.  A time that
+    /// doesn't exist because the clocks went forward is moved forward by the change, 2:30 on
+    /// the morning the clocks go from 2:00 to 3:00 is 3:30
+    #[cfg(feature = "chrono-tz")]
+    pub fn to_utc(
+        &self,
+        naive_local: chrono::NaiveDateTime,
+    ) -> Result<chrono::DateTime<chrono::Utc>> {
+        let tz = self.timezone()?;
+        Ok(crate::timezone::local_to_utc(&tz, &naive_local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_system_info() {
+        let mut mock = MockCw::new();
+        let _m = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{"version": "v2022.1.12345", "isCloud": true,
+                    "serverTimeZone": "Eastern Standard Time", "licenseBits": []}"#,
+            )
+            .create();
+
+        let info = mock.client().system_info().unwrap();
+
+        assert_eq!(
+            info,
+            SystemInfo {
+                version: "v2022.1.12345".to_string(),
+                is_cloud: true,
+                server_time_zone: "Eastern Standard Time".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "chrono-tz")]
+    fn test_to_utc_caches_timezone() {
+        use chrono::{NaiveDate, TimeZone, Utc};
+
+        let mut mock = MockCw::new();
+        let info = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(
+                r#"{"version": "v2022.1", "isCloud": true,
+                    "serverTimeZone": "Central Standard Time"}"#,
+            )
+            .expect(1)
+            .create();
+        let client = mock.client();
+        let local = NaiveDate::from_ymd_opt(2022, 7, 14)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+
+        let first = client.to_utc(local).unwrap();
+        let second = client.clone().to_utc(local).unwrap();
+
+        info.assert();
+        assert_eq!(first, Utc.with_ymd_and_hms(2022, 7, 14, 15, 0, 0).unwrap());
+        assert_eq!(second, first);
+    }
+
+    #[test]
+    #[cfg(feature = "chrono-tz")]
+    fn test_unknown_timezone() {
+        let info = SystemInfo {
+            version: "v2022.1".to_string(),
+            is_cloud: false,
+            server_time_zone: "Mars Standard Time".to_string(),
+        };
+
+        let err = info.timezone().unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::UnknownTimeZone {
+                name: "Mars Standard Time".to_string()
+            })
+        );
+    }
+}
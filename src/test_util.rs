@@ -0,0 +1,334 @@
+//! Helpers for testing code that uses this crate (the `test-util` feature): a mock connectwise
+//! server that pages lists the way connectwise does ([MockCw]) and records shaped like the
+//! ones it returns ([fixtures])
+//!
+//! # Example
+//! ```
+//! use cwmanage::test_util::{fixtures, MockCw};
+//!
+//! let mock = MockCw::new().list(
+//!     "/service/tickets",
+//!     vec![
+//!         vec![fixtures::ticket(1), fixtures::ticket(2)],
+//!         vec![fixtures::ticket(3)],
+//!     ],
+//! );
+//! let tickets = mock.client().get("/service/tickets", &[]).unwrap();
+//! assert_eq!(tickets.len(), 3);
+//! ```
+use crate::Client;
+use mockito::{Matcher, Mock, ServerGuard};
+use serde_json::Value;
+use std::fmt;
+
+/// The path the api is under for a [Client] with the default codebase and version
+pub const API_PREFIX: &str = "/v4_6_release/apis/3.0";
+
+/// A mock connectwise server, with a [Client] pointed at it from [MockCw::client]
+pub struct MockCw {
+    server: ServerGuard,
+    mocks: Vec<Mock>,
+}
+
+impl fmt::Debug for MockCw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockCw")
+            .field("url", &self.server.url())
+            .field("mocks", &self.mocks.len())
+            .finish()
+    }
+}
+
+impl Default for MockCw {
+    fn default() -> Self {
+        MockCw::new()
+    }
+}
+
+impl MockCw {
+    /// Starts a mock server with nothing mounted
+    pub fn new() -> MockCw {
+        MockCw {
+            server: mockito::Server::new(),
+            mocks: Vec::new(),
+        }
+    }
+
+    /// The url of the server, what [Client::api_url] is set to
+    pub fn url(&self) -> String {
+        self.server.url()
+    }
+
+    /// The server, to mount mocks the helpers don't cover.  Their paths start with
+    /// [API_PREFIX]
+    pub fn server(&mut self) -> &mut ServerGuard {
+        &mut self.server
+    }
+
+    /// A client pointed at the server, with made up credentials
+    pub fn client(&self) -> Client {
+        Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(self.server.url())
+        .build()
+    }
+
+    /// Mounts the list endpoint `path` (`/service/tickets`) returning `pages`, each page
+    /// linking to the next with a forward only `link` header (`<...?pageId=2>; rel="next"`) as
+    /// connectwise does.  No pages is one empty page
+    pub fn list(mut self, path: &str, pages: Vec<Vec<Value>>) -> MockCw {
+        let pages = match pages.is_empty() {
+            true => vec![Vec::new()],
+            false => pages,
+        };
+        let url = format!("{}{}{}", self.server.url(), API_PREFIX, path);
+        let count = pages.len();
+        for (index, page) in pages.into_iter().enumerate() {
+            let number = index + 1;
+            let mut mock = self
+                .server
+                .mock("GET", format!("{}{}", API_PREFIX, path).as_str())
+                .match_query(Matcher::UrlEncoded(
+                    "pageid".to_string(),
+                    number.to_string(),
+                ))
+                .with_header("content-type", "application/json")
+                .with_body(Value::Array(page).to_string());
+            if number < count {
+                let next = format!("<{}?pageId={}>; rel=\"next\"", url, number + 1);
+                mock = mock.with_header("link", &next);
+            }
+            self.mocks.push(mock.create());
+        }
+        self
+    }
+
+    /// Mounts `GET path` (`/service/tickets/1`) returning `record`
+    pub fn single(mut self, path: &str, record: Value) -> MockCw {
+        let mock = self
+            .server
+            .mock("GET", format!("{}{}", API_PREFIX, path).as_str())
+            .match_query(Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(record.to_string())
+            .create();
+        self.mocks.push(mock);
+        self
+    }
+
+    /// Mounts `method path` failing with `status` and `body` (see [fixtures::api_error])
+    pub fn error(mut self, method: &str, path: &str, status: usize, body: Value) -> MockCw {
+        let mock = self
+            .server
+            .mock(method, format!("{}{}", API_PREFIX, path).as_str())
+            .match_query(Matcher::Any)
+            .with_status(status)
+            .with_header("content-type", "application/json")
+            .with_body(body.to_string())
+            .create();
+        self.mocks.push(mock);
+        self
+    }
+
+    /// Panics unless every page and record mounted was requested exactly once
+    pub fn assert(&self) {
+        self.mocks.iter().for_each(Mock::assert);
+    }
+}
+
+/// an empty directory for a test, `cwmanage-{name}-{pid}` in the temp dir
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("cwmanage-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Records shaped like the ones connectwise returns, with the references and `_info` links
+/// it adds
+pub mod fixtures {
+    use serde_json::{json, Value};
+
+    /// the `_info` connectwise adds to every record
+    fn info(href: &str) -> Value {
+        json!({
+            "lastUpdated": "2022-07-14T15:34:22Z",
+            "updatedBy": "zach",
+            "dateEntered": "2022-07-01T09:00:00Z",
+            "enteredBy": "zach",
+            "href": format!("https://api-na.myconnectwise.net/v4_6_release/apis/3.0{}", href),
+        })
+    }
+
+    /// a reference to another record, `{id, name, _info}`
+    fn reference(id: i64, name: &str, href: &str) -> Value {
+        json!({"id": id, "name": name, "_info": {"href": href}})
+    }
+
+    /// A service ticket (`/service/tickets/{id}`)
+    pub fn ticket(id: i64) -> Value {
+        json!({
+            "id": id,
+            "summary": format!("printer {} is on fire", id),
+            "recordType": "ServiceTicket",
+            "board": reference(1, "Service", "/service/boards/1"),
+            "status": reference(16, "New", "/service/boards/1/statuses/16"),
+            "company": {
+                "id": 250,
+                "identifier": "myco",
+                "name": "My Company",
+                "_info": {"company_href": "/company/companies/250"},
+            },
+            "contact": reference(7, "Jane Doe", "/company/contacts/7"),
+            "priority": reference(4, "Priority 3 - Normal Response", "/service/priorities/4"),
+            "severity": "Medium",
+            "impact": "Medium",
+            "closedFlag": false,
+            "actualHours": 1.5,
+            "customFields": [
+                {"id": 1, "caption": "Serial", "type": "Text", "entryMethod": "EntryField", "numberOfDecimals": 0, "value": null},
+            ],
+            "_info": info(&format!("/service/tickets/{}", id)),
+        })
+    }
+
+    /// A member (`/system/members/{id}`)
+    pub fn member(id: i64) -> Value {
+        json!({
+            "id": id,
+            "identifier": format!("member{}", id),
+            "firstName": "Zach",
+            "lastName": "Peters",
+            "officeEmail": format!("member{}@example.com", id),
+            "inactiveFlag": false,
+            "securityRole": reference(63, "Admin", "/system/securityroles/63"),
+            "defaultLocation": reference(2, "Main", "/system/locations/2"),
+            "defaultDepartment": reference(10, "Service", "/system/departments/10"),
+            "_info": info(&format!("/system/members/{}", id)),
+        })
+    }
+
+    /// A company (`/company/companies/{id}`)
+    pub fn company(id: i64) -> Value {
+        json!({
+            "id": id,
+            "identifier": format!("company{}", id),
+            "name": format!("Company {}", id),
+            "status": reference(1, "Active", "/company/companies/statuses/1"),
+            "types": [reference(1, "Client", "/company/companies/types/1")],
+            "site": reference(3, "Main", &format!("/company/companies/{}/sites/3", id)),
+            "phoneNumber": "555-1234",
+            "deletedFlag": false,
+            "_info": info(&format!("/company/companies/{}", id)),
+        })
+    }
+
+    /// The body of a failed request
+    pub fn api_error(code: &str, message: &str) -> Value {
+        json!({"code": code, "message": message, "errors": null})
+    }
+
+    /// The body of a request that failed validation, with the field that is invalid
+    pub fn validation_error(resource: &str, field: &str, message: &str) -> Value {
+        json!({
+            "code": "InvalidObject",
+            "message": format!("{} object is invalid", resource),
+            "errors": [
+                {"code": "NullValue", "message": message, "resource": resource, "field": field},
+            ],
+        })
+    }
+
+    /// The body of a 404 for a record that doesn't exist
+    pub fn not_found(resource: &str, id: i64) -> Value {
+        api_error("NotFound", &format!("{} Id {} not found", resource, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ApiError, Error, Member};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_mock_cw_pages() {
+        let mock = MockCw::new()
+            .list(
+                "/service/tickets",
+                vec![
+                    vec![fixtures::ticket(1), fixtures::ticket(2)],
+                    vec![fixtures::ticket(3)],
+                    vec![fixtures::ticket(4)],
+                ],
+            )
+            .list("/service/boards", vec![]);
+        let client = mock.client();
+
+        let ids: Vec<i64> = client
+            .get("/service/tickets", &[])
+            .unwrap()
+            .iter()
+            .filter_map(|t| t["id"].as_i64())
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert!(client.get("/service/boards", &[]).unwrap().is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let mock = MockCw::new()
+            .single("/system/members/5", fixtures::member(5))
+            .error(
+                "GET",
+                "/service/tickets/9",
+                404,
+                fixtures::not_found("Ticket", 9),
+            )
+            .error(
+                "POST",
+                "/service/tickets",
+                400,
+                fixtures::validation_error("ticket", "summary", "Summary is required"),
+            );
+        let client = mock.client();
+
+        let member: Member = serde_json::from_value(fixtures::member(5)).unwrap();
+        assert_eq!(member.identifier, "member5");
+        assert_eq!(
+            client.get_single("/system/members/5", &[]).unwrap()["id"],
+            5
+        );
+        assert!(matches!(
+            client
+                .get_single("/service/tickets/9", &[])
+                .unwrap_err()
+                .downcast_ref(),
+            Some(Error::NotFound { .. })
+        ));
+        let err = client
+            .post("/service/tickets", "{}".to_string())
+            .unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Api {
+                status: 400, error, ..
+            }) => {
+                assert_eq!(error.errors[0].field, "summary");
+                assert_eq!(
+                    ApiError::from_body(&fixtures::api_error("x", "y").to_string())
+                        .unwrap()
+                        .code,
+                    "x"
+                );
+            }
+            other => panic!("{:?}", other),
+        }
+        mock.assert();
+    }
+}
@@ -0,0 +1,132 @@
+//! The checklist (tasks) of a service ticket.  See [Client::get_ticket_tasks]
+use crate::{Client, PatchOp};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// One task on a ticket (`/service/tickets/{id}/tasks`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketTask {
+    /// the id of the task
+    pub id: i64,
+    /// the ticket the task is on
+    #[serde(default)]
+    pub ticket_id: i64,
+    /// what needs doing
+    #[serde(default)]
+    pub notes: String,
+    /// true once the task is done
+    #[serde(default)]
+    pub closed_flag: bool,
+    /// the position of the task in the list, connectwise numbers these itself
+    #[serde(default)]
+    pub priority: i64,
+    /// how the task was resolved, if it says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+}
+
+fn tasks_path(ticket_id: i64) -> String {
+    format!("/service/tickets/{}/tasks", ticket_id)
+}
+
+impl Client {
+    /// Gets the tasks of a ticket, in the order they are listed on the ticket (by priority)
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// for task in client.get_ticket_tasks(123).unwrap() {
+    ///     if !task.closed_flag {
+    ///         client.close_ticket_task(123, task.id).unwrap();
+    ///     }
+    /// }
+    /// ```
+    pub fn get_ticket_tasks(&self, ticket_id: i64) -> Result<Vec<TicketTask>> {
+        self.get_decoded(
+            &tasks_path(ticket_id),
+            &[("orderby", "priority asc, id asc")],
+        )
+    }
+
+    /// Adds a task to the end of a ticket's list.  Connectwise picks the priority (and may
+    /// number it differently than the tasks before it), the created task is returned so you
+    /// can see what it got
+    pub fn add_ticket_task(&self, ticket_id: i64, notes: &str) -> Result<TicketTask> {
+        let body = json!({ "notes": notes }).to_string();
+        let path = tasks_path(ticket_id);
+        self.decode(&path, self.post(&path, body)?)
+    }
+
+    /// Ticks a task off (sets `closedFlag`) and returns it
+    pub fn close_ticket_task(&self, ticket_id: i64, task_id: i64) -> Result<TicketTask> {
+        let path = format!("{}/{}", tasks_path(ticket_id), task_id);
+        let task = self.patch(&path, PatchOp::Replace, "closedFlag", json!(true))?;
+        self.decode(&path, task)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::MockCw;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_ticket_tasks() {
+        let mut mock = MockCw::new();
+        let path = "/v4_6_release/apis/3.0/service/tickets/123/tasks";
+        let list = mock.server()
+            .mock("GET", path)
+            .match_query(Matcher::UrlEncoded(
+                "orderby".to_string(),
+                "priority asc, id asc".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 1, "ticketId": 123, "notes": "unbox", "closedFlag": true, "priority": 1},
+                    {"id": 2, "ticketId": 123, "notes": "plug in", "closedFlag": false, "priority": 2}]"#,
+            )
+            .create();
+        let add = mock
+            .server()
+            .mock("POST", path)
+            .match_body(Matcher::Json(json!({"notes": "test print"})))
+            .with_body(r#"{"id": 3, "ticketId": 123, "notes": "test print", "priority": 3}"#)
+            .create();
+        let close = mock.server()
+            .mock("PATCH", format!("{}/2", path).as_str())
+            .match_body(Matcher::Json(
+                json!([{"op": "replace", "path": "closedFlag", "value": true}]),
+            ))
+            .with_body(r#"{"id": 2, "ticketId": 123, "notes": "plug in", "closedFlag": true, "priority": 2}"#)
+            .create();
+        let client = mock.client();
+
+        let tasks = client.get_ticket_tasks(123).unwrap();
+        let added = client.add_ticket_task(123, "test print").unwrap();
+        let closed = client.close_ticket_task(123, 2).unwrap();
+
+        list.assert();
+        add.assert();
+        close.assert();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].notes, "plug in");
+        assert!(!tasks[1].closed_flag);
+        assert_eq!(added.priority, 3);
+        assert!(!added.closed_flag);
+        assert!(closed.closed_flag);
+    }
+}
@@ -0,0 +1,734 @@
+//! Service tickets and what hangs off them.  See [Client::get_ticket_bundle] and
+//! [Client::close_ticket]
+use crate::reference::find_by_name;
+use crate::{Client, CwRef, Error, IdOrName, PatchOp, TicketLevel};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+const BOARDS_PATH: &str = "/service/boards";
+
+/// A note on a ticket (`/service/tickets/{id}/notes`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketNote {
+    /// the id of the note
+    pub id: i64,
+    /// the ticket the note is on
+    #[serde(default)]
+    pub ticket_id: i64,
+    /// the note itself
+    #[serde(default)]
+    pub text: String,
+    /// true for a note in the discussion (detail description)
+    #[serde(default)]
+    pub detail_description_flag: bool,
+    /// true for an internal note
+    #[serde(default)]
+    pub internal_analysis_flag: bool,
+    /// true for the resolution
+    #[serde(default)]
+    pub resolution_flag: bool,
+    /// the member who wrote the note, if a member did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<CwRef>,
+    /// the contact who wrote the note, if a contact did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contact: Option<CwRef>,
+    /// when the note was written (`2022-07-14T15:34:22Z`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_created: Option<String>,
+}
+
+#[cfg(feature = "chrono")]
+impl TicketNote {
+    /// [TicketNote::date_created] as a date, see [crate::dates].  `None` when it isn't set (or isn't a date)
+    pub fn date_created_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.date_created.as_deref())
+    }
+}
+
+/// Time entered against a ticket or project (`/time/entries`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    /// the id of the entry
+    pub id: i64,
+    /// who the time is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member: Option<CwRef>,
+    /// when the work started (`2022-07-14T15:00:00Z`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_start: Option<String>,
+    /// when the work ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_end: Option<String>,
+    /// the hours worked
+    #[serde(default)]
+    pub actual_hours: f64,
+    /// the notes of the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// what the time is against (`ServiceTicket`, `ProjectTicket`, `ChargeCode`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charge_to_type: Option<String>,
+    /// the id of what the time is against
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub charge_to_id: Option<i64>,
+    /// the ticket, for time against a ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<CwRef>,
+    /// the project, for time against a project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<CwRef>,
+    /// the project phase, for time against a phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<CwRef>,
+}
+
+#[cfg(feature = "chrono")]
+impl TimeEntry {
+    /// [TimeEntry::time_start] as a date, see [crate::dates].  `None` when it isn't set (or isn't a date)
+    pub fn time_start_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.time_start.as_deref())
+    }
+
+    /// [TimeEntry::time_end] as a date, see [crate::dates].  `None` when it isn't set (or isn't a date)
+    pub fn time_end_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.time_end.as_deref())
+    }
+}
+
+/// A configuration attached to a ticket (`/service/tickets/{id}/configurations`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TicketConfiguration {
+    /// the id of the configuration
+    pub id: i64,
+    /// the name of the configuration
+    #[serde(default)]
+    pub name: String,
+    /// the device identifier, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_identifier: Option<String>,
+}
+
+/// A ticket with its notes, time entries and configurations, from
+/// [Client::get_ticket_bundle].  Each part is fetched on its own, one that failed doesn't
+/// stop the others
+#[derive(Debug)]
+pub struct TicketBundle {
+    /// the ticket
+    pub ticket: Value,
+    /// the notes, oldest first
+    pub notes: Result<Vec<TicketNote>>,
+    /// the time entries, oldest first
+    pub time_entries: Result<Vec<TimeEntry>>,
+    /// the attached configurations
+    pub configurations: Result<Vec<TicketConfiguration>>,
+}
+
+/// A status of a service board (`/service/boards/{id}/statuses`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardStatus {
+    /// the id of the status
+    pub id: i64,
+    /// the name of the status (`New`, `Completed`)
+    #[serde(default)]
+    pub name: String,
+    /// true for the status new tickets on the board get
+    #[serde(default)]
+    pub default_flag: bool,
+    /// true if the status can no longer be used
+    #[serde(default)]
+    pub inactive: bool,
+    /// true if a ticket with this status is closed
+    #[serde(default)]
+    pub closed_status: bool,
+}
+
+/// A service board (`/service/boards`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Board {
+    /// the id of the board
+    pub id: i64,
+    /// the name of the board (`Help Desk`)
+    #[serde(default)]
+    pub name: String,
+    /// true if the board can no longer be used
+    #[serde(default)]
+    pub inactive_flag: bool,
+}
+
+/// A service ticket to create with [Client::create_ticket].  The board and priority can be
+/// given by name, they are looked up when the ticket is created
+///
+/// ```
+/// use cwmanage::{NewTicket, TicketLevel};
+///
+/// let ticket = NewTicket::new("printer is on fire")
+///     .board("Help Desk")
+///     .company(250)
+///     .priority("Priority 2 - Quick Response")
+///     .impact(TicketLevel::High)
+///     .severity(TicketLevel::Medium);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NewTicket {
+    summary: String,
+    board: Option<IdOrName>,
+    company_id: Option<i64>,
+    contact_id: Option<i64>,
+    priority: Option<IdOrName>,
+    impact: Option<TicketLevel>,
+    severity: Option<TicketLevel>,
+    description: Option<String>,
+}
+
+impl NewTicket {
+    /// a ticket with a summary
+    pub fn new(summary: &str) -> NewTicket {
+        NewTicket {
+            summary: summary.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// the service board, by id or name
+    pub fn board(mut self, board: impl Into<IdOrName>) -> NewTicket {
+        self.board = Some(board.into());
+        self
+    }
+
+    /// the company the ticket is for
+    pub fn company(mut self, company_id: i64) -> NewTicket {
+        self.company_id = Some(company_id);
+        self
+    }
+
+    /// the contact the ticket is for
+    pub fn contact(mut self, contact_id: i64) -> NewTicket {
+        self.contact_id = Some(contact_id);
+        self
+    }
+
+    /// the priority, by id or name (see [Client::get_priorities])
+    pub fn priority(mut self, priority: impl Into<IdOrName>) -> NewTicket {
+        self.priority = Some(priority.into());
+        self
+    }
+
+    /// the impact, for boards that use the priority matrix
+    pub fn impact(mut self, impact: TicketLevel) -> NewTicket {
+        self.impact = Some(impact);
+        self
+    }
+
+    /// the severity, for boards that use the priority matrix
+    pub fn severity(mut self, severity: TicketLevel) -> NewTicket {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// the initial description, the first note of the ticket
+    pub fn description(mut self, description: &str) -> NewTicket {
+        self.description = Some(description.to_string());
+        self
+    }
+}
+
+/// How [Client::close_ticket] closes a ticket
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CloseOptions {
+    /// the name of the closed status to use, needed when the board has more than one
+    pub status: Option<String>,
+    /// a resolution note to add before closing, for boards that need one
+    pub resolution: Option<String>,
+}
+
+impl Client {
+    /// Gets a ticket with its notes, time entries and configurations.  The four are fetched
+    /// at the same time.  Only the ticket itself has to succeed, a part that fails is an
+    /// `Err` in the bundle
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let bundle = client.get_ticket_bundle(123).unwrap();
+    /// println!("{}", bundle.ticket["summary"]);
+    /// match &bundle.notes {
+    ///     Ok(notes) => println!("{} notes", notes.len()),
+    ///     Err(e) => println!("notes unavailable: {}", e),
+    /// }
+    /// ```
+    pub fn get_ticket_bundle(&self, ticket_id: i64) -> Result<TicketBundle> {
+        let ticket_path = format!("/service/tickets/{}", ticket_id);
+        let (ticket, notes, time_entries, configurations) = std::thread::scope(|scope| {
+            let notes = scope.spawn(|| self.get_ticket_notes(ticket_id));
+            let time_entries = scope.spawn(|| self.get_ticket_time_entries(ticket_id));
+            let configurations = scope.spawn(|| self.get_ticket_configurations(ticket_id));
+            let ticket = self.get_single(&ticket_path, &[]);
+            (
+                ticket,
+                notes.join().unwrap(),
+                time_entries.join().unwrap(),
+                configurations.join().unwrap(),
+            )
+        });
+
+        Ok(TicketBundle {
+            ticket: ticket?,
+            notes,
+            time_entries,
+            configurations,
+        })
+    }
+
+    /// Gets the notes of a ticket, oldest first
+    pub fn get_ticket_notes(&self, ticket_id: i64) -> Result<Vec<TicketNote>> {
+        let path = format!("/service/tickets/{}/notes", ticket_id);
+        self.get_decoded(&path, &[("orderby", "id asc")])
+    }
+
+    /// Gets the time entered against a ticket, oldest first
+    pub fn get_ticket_time_entries(&self, ticket_id: i64) -> Result<Vec<TimeEntry>> {
+        let conditions = format!(
+            "chargeToType = 'ServiceTicket' AND chargeToId = {}",
+            ticket_id
+        );
+        self.get_decoded(
+            "/time/entries",
+            &[("conditions", &conditions), ("orderby", "timeStart asc")],
+        )
+    }
+
+    /// Gets the configurations attached to a ticket
+    pub fn get_ticket_configurations(&self, ticket_id: i64) -> Result<Vec<TicketConfiguration>> {
+        let path = format!("/service/tickets/{}/configurations", ticket_id);
+        self.get_decoded(&path, &[("", "")])
+    }
+
+    /// Gets the statuses of a service board, inactive ones included.  They are asked for
+    /// once per board and then remembered by this client and its clones
+    pub fn get_board_statuses(&self, board_id: i64) -> Result<Vec<BoardStatus>> {
+        if let Some(statuses) = self.shared.board_statuses.lock().unwrap().get(&board_id) {
+            return Ok(statuses.clone());
+        }
+        let path = format!("/service/boards/{}/statuses", board_id);
+        let statuses: Vec<BoardStatus> = self.get_decoded(&path, &[("", "")])?;
+        self.shared
+            .board_statuses
+            .lock()
+            .unwrap()
+            .insert(board_id, statuses.clone());
+        Ok(statuses)
+    }
+
+    /// Gets the service boards, inactive ones included.  They are asked for once and then
+    /// remembered by this client and its clones
+    pub fn get_boards(&self) -> Result<Vec<Board>> {
+        if let Some(boards) = self.shared.boards.lock().unwrap().as_ref() {
+            return Ok(boards.clone());
+        }
+        let boards: Vec<Board> = self.get_decoded(BOARDS_PATH, &[("orderby", "name asc")])?;
+        *self.shared.boards.lock().unwrap() = Some(boards.clone());
+        Ok(boards)
+    }
+
+    /// Creates a service ticket and returns it.  A board or priority given by name is looked
+    /// up first (an active board, see [Client::get_boards], and [Client::find_priority]), a
+    /// name that isn't one is [Error::UnknownName] with the names there are
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, NewTicket};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let ticket = NewTicket::new("printer is on fire")
+    ///     .board("Help Desk")
+    ///     .company(250)
+    ///     .priority("Priority 2 - Quick Response");
+    /// let created = client.create_ticket(&ticket).unwrap();
+    /// println!("created ticket {}", created["id"]);
+    /// ```
+    pub fn create_ticket(&self, ticket: &NewTicket) -> Result<Value> {
+        let mut body = Map::new();
+        body.insert("summary".to_string(), json!(ticket.summary));
+        if let Some(board) = &ticket.board {
+            let id = match board {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => {
+                    let boards: Vec<Board> = self
+                        .get_boards()?
+                        .into_iter()
+                        .filter(|b| !b.inactive_flag)
+                        .collect();
+                    find_by_name(&boards, BOARDS_PATH, name, |b| &b.name)?.id
+                }
+            };
+            body.insert("board".to_string(), json!({ "id": id }));
+        }
+        if let Some(company_id) = ticket.company_id {
+            body.insert("company".to_string(), json!({ "id": company_id }));
+        }
+        if let Some(contact_id) = ticket.contact_id {
+            body.insert("contact".to_string(), json!({ "id": contact_id }));
+        }
+        if let Some(priority) = &ticket.priority {
+            let id = match priority {
+                IdOrName::Id(id) => *id,
+                IdOrName::Name(name) => self.find_priority(name)?.id,
+            };
+            body.insert("priority".to_string(), json!({ "id": id }));
+        }
+        if let Some(impact) = ticket.impact {
+            body.insert("impact".to_string(), json!(impact));
+        }
+        if let Some(severity) = ticket.severity {
+            body.insert("severity".to_string(), json!(severity));
+        }
+        if let Some(description) = &ticket.description {
+            body.insert("initialDescription".to_string(), json!(description));
+        }
+
+        self.post("/service/tickets", Value::Object(body).to_string())
+    }
+
+    /// Closes a ticket and returns it as it is afterwards
+    ///
+    /// The status is one of the active closed statuses of the ticket's board (see
+    /// [Client::get_board_statuses]), the only one or the one named in
+    /// [CloseOptions::status].  A board without one is [Error::NoClosedStatus] and a name
+    /// that isn't one of them is [Error::UnknownName].  The [CloseOptions::resolution] note is
+    /// added before the status is changed, connectwise sets the closed date itself
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::{Client, CloseOptions};
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let options = CloseOptions {
+    ///     status: Some("Completed".to_string()),
+    ///     resolution: Some("replaced the toner".to_string()),
+    /// };
+    /// let ticket = client.close_ticket(123, options).unwrap();
+    /// assert_eq!(ticket["closedFlag"], true);
+    /// ```
+    pub fn close_ticket(&self, ticket_id: i64, options: CloseOptions) -> Result<Value> {
+        self.check_writable()?;
+        let path = format!("/service/tickets/{}", ticket_id);
+        let ticket = self.get_single(&path, &[("fields", "id,board")])?;
+        let board_id = ticket["board"]["id"]
+            .as_i64()
+            .ok_or_else(|| anyhow!("ticket {} has no board", ticket_id))?;
+        let closed: Vec<BoardStatus> = self
+            .get_board_statuses(board_id)?
+            .into_iter()
+            .filter(|s| s.closed_status && !s.inactive)
+            .collect();
+        let statuses_path = format!("/service/boards/{}/statuses", board_id);
+        let status = match (&options.status, closed.as_slice()) {
+            (_, []) => return Err(Error::NoClosedStatus { board_id }.into()),
+            (Some(name), closed) => find_by_name(closed, &statuses_path, name, |s| &s.name)?,
+            (None, [only]) => only,
+            (None, closed) => {
+                let names: Vec<&str> = closed.iter().map(|s| s.name.as_str()).collect();
+                return Err(anyhow!(
+                    "board {} has several closed statuses ({}), pick one with CloseOptions::status",
+                    board_id,
+                    names.join(", ")
+                ));
+            }
+        };
+
+        if let Some(resolution) = &options.resolution {
+            let note = json!({
+                "text": resolution,
+                "resolutionFlag": true,
+            });
+            self.post(&format!("{}/notes", path), note.to_string())?;
+        }
+        self.patch(&path, PatchOp::Replace, "status/id", json!(status.id))?;
+        self.get_single(&path, &[])
+    }
+
+    /// Closes a ticket with a resolution note, [Client::close_ticket] with only
+    /// [CloseOptions::resolution] set
+    pub fn resolve_ticket(&self, ticket_id: i64, resolution: &str) -> Result<Value> {
+        let options = CloseOptions {
+            status: None,
+            resolution: Some(resolution.to_string()),
+        };
+        self.close_ticket(ticket_id, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{fixtures, MockCw, API_PREFIX};
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_get_ticket_bundle() {
+        let mut mock = MockCw::new();
+        let ticket = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 123, "summary": "printer on fire"}"#)
+            .create();
+        let notes = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123/notes")
+            .match_query(Matcher::Any)
+            .with_body(
+                r#"[{"id": 1, "ticketId": 123, "text": "on it", "internalAnalysisFlag": true}]"#,
+            )
+            .create();
+        let entries = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/time/entries")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "chargeToType = 'ServiceTicket' AND chargeToId = 123".to_string(),
+            ))
+            .with_body(
+                r#"[{"id": 9, "member": {"id": 1, "identifier": "zpeters"}, "actualHours": 0.5}]"#,
+            )
+            .create();
+        let configurations = mock
+            .server()
+            .mock(
+                "GET",
+                "/v4_6_release/apis/3.0/service/tickets/123/configurations",
+            )
+            .match_query(Matcher::Any)
+            .with_status(500)
+            .create();
+
+        let bundle = mock.client().get_ticket_bundle(123).unwrap();
+
+        ticket.assert();
+        notes.assert();
+        entries.assert();
+        configurations.assert();
+        assert_eq!(bundle.ticket["summary"], "printer on fire");
+        let notes = bundle.notes.unwrap();
+        assert_eq!(notes[0].text, "on it");
+        assert!(notes[0].internal_analysis_flag);
+        assert_eq!(bundle.time_entries.unwrap()[0].actual_hours, 0.5);
+        assert!(bundle.configurations.is_err());
+    }
+
+    #[test]
+    fn test_bundle_needs_ticket() {
+        let mut mock = MockCw::new();
+        let _ticket = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/404")
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .create();
+        let _rest = mock
+            .server()
+            .mock(
+                "GET",
+                Matcher::Regex("/(notes|entries|configurations)$".to_string()),
+            )
+            .match_query(Matcher::Any)
+            .with_body("[]")
+            .create();
+
+        assert!(mock.client().get_ticket_bundle(404).is_err());
+    }
+
+    fn mock_board(server: &mut mockito::Server, statuses: &str) -> mockito::Mock {
+        let _ticket = server
+            .mock("GET", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_query(Matcher::Any)
+            .with_body(r#"{"id": 123, "board": {"id": 1, "name": "Help Desk"}}"#)
+            .create();
+        server
+            .mock("GET", "/v4_6_release/apis/3.0/service/boards/1/statuses")
+            .match_query(Matcher::Any)
+            .with_body(statuses)
+            .create()
+    }
+
+    #[test]
+    fn test_close_ticket() {
+        let mut mock = MockCw::new();
+        let statuses = mock_board(
+            mock.server(),
+            r#"[{"id": 1, "name": "New", "defaultFlag": true},
+                {"id": 2, "name": "Completed", "closedStatus": true},
+                {"id": 3, "name": "Cancelled", "closedStatus": true},
+                {"id": 4, "name": "Closed", "closedStatus": true, "inactive": true}]"#,
+        )
+        .expect(1);
+        let note = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/service/tickets/123/notes")
+            .match_body(Matcher::Json(
+                json!({"text": "replaced the toner", "resolutionFlag": true}),
+            ))
+            .with_body(r#"{"id": 9}"#)
+            .expect(1)
+            .create();
+        let closed = mock
+            .server()
+            .mock("PATCH", "/v4_6_release/apis/3.0/service/tickets/123")
+            .match_body(Matcher::Json(
+                json!([{"op": "replace", "path": "status/id", "value": 2}]),
+            ))
+            .with_body(r#"{"id": 123}"#)
+            .expect(1)
+            .create();
+        let client = mock.client();
+
+        client
+            .close_ticket(
+                123,
+                CloseOptions {
+                    status: Some("completed".to_string()),
+                    resolution: Some("replaced the toner".to_string()),
+                },
+            )
+            .unwrap();
+        let unnamed = client.close_ticket(123, CloseOptions::default());
+        let inactive = client.close_ticket(
+            123,
+            CloseOptions {
+                status: Some("Closed".to_string()),
+                resolution: None,
+            },
+        );
+
+        statuses.assert();
+        note.assert();
+        closed.assert();
+        assert!(unnamed
+            .unwrap_err()
+            .to_string()
+            .contains("Completed, Cancelled"));
+        assert_eq!(
+            inactive.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::UnknownName {
+                path: "/service/boards/1/statuses".to_string(),
+                name: "Closed".to_string(),
+                valid: vec!["Completed".to_string(), "Cancelled".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_close_ticket_no_closed_status() {
+        let mut mock = MockCw::new();
+        let _statuses = mock_board(mock.server(), r#"[{"id": 1, "name": "New"}]"#);
+
+        let result = mock.client().resolve_ticket(123, "fixed");
+
+        assert_eq!(
+            result.unwrap_err().downcast_ref::<Error>(),
+            Some(&Error::NoClosedStatus { board_id: 1 })
+        );
+    }
+
+    #[test]
+    fn test_create_ticket_by_name() {
+        let mut mock = MockCw::new()
+            .list(
+                "/service/boards",
+                vec![vec![
+                    json!({"id": 1, "name": "Help Desk"}),
+                    json!({"id": 2, "name": "Projects", "inactiveFlag": true}),
+                ]],
+            )
+            .list(
+                "/service/priorities",
+                vec![vec![
+                    json!({"id": 1, "name": "Priority 1 - Emergency Response"}),
+                    json!({"id": 2, "name": "Priority 2 - Quick Response"}),
+                ]],
+            );
+        let create = mock
+            .server()
+            .mock("POST", format!("{}/service/tickets", API_PREFIX).as_str())
+            .match_body(Matcher::Json(json!({
+                "summary": "printer is on fire",
+                "board": {"id": 1},
+                "company": {"id": 250},
+                "priority": {"id": 2},
+                "impact": "High",
+                "severity": "Low",
+            })))
+            .with_body(fixtures::ticket(9).to_string())
+            .expect(1)
+            .create();
+        let client = mock.client();
+        let ticket = NewTicket::new("printer is on fire")
+            .board("help desk")
+            .company(250)
+            .priority("Priority 2 - Quick Response")
+            .impact(TicketLevel::High)
+            .severity(TicketLevel::Low);
+
+        assert_eq!(client.create_ticket(&ticket).unwrap()["id"], 9);
+        create.assert();
+
+        let err = client
+            .create_ticket(&ticket.clone().priority("Urgent"))
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Error>(),
+            Some(&Error::UnknownName {
+                path: "/service/priorities".to_string(),
+                name: "Urgent".to_string(),
+                valid: vec![
+                    "Priority 1 - Emergency Response".to_string(),
+                    "Priority 2 - Quick Response".to_string(),
+                ],
+            })
+        );
+        let err = client
+            .create_ticket(&NewTicket::new("x").board("Projects"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no \"Projects\" in /service/boards, it has Help Desk"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Entering time, and a [Timer] that turns the time spent on a ticket into a time entry.
+//! See [Client::create_time_entry]
+use crate::har::cw_timestamp;
+use crate::{Client, CwRef, TimeEntry};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const TIME_ENTRIES_PATH: &str = "/time/entries";
+
+/// Where the time comes from, so the rounding of a [Timer] can be tested
+pub trait Clock {
+    /// the current time
+    fn now(&self) -> SystemTime;
+}
+
+/// The system clock, what [Timer::start] and [Timer::stop] use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Whether the time is billed (`billableOption`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BillableOption {
+    /// billed to the customer
+    Billable,
+    /// not billed
+    DoNotBill,
+    /// billed at no charge, shown on the invoice
+    NoCharge,
+    /// the default of the work type
+    NoDefault,
+}
+
+/// How [Timer::stop] rounds the time worked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// to the nearest increment, half way goes up
+    Nearest,
+    /// up to the next increment
+    Up,
+    /// down to the last increment
+    Down,
+}
+
+/// A time entry to create with [Client::create_time_entry]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTimeEntry {
+    /// what the time is against (`ServiceTicket`, `ProjectTicket`, `ChargeCode`, ...)
+    pub charge_to_type: String,
+    /// the id of what the time is against
+    pub charge_to_id: i64,
+    /// who the time is for
+    pub member: CwRef,
+    /// when the work started (`2022-07-14T15:00:00Z`)
+    pub time_start: String,
+    /// when the work ended
+    pub time_end: String,
+    /// the notes of the entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// whether the time is billed, by default what the work type says
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billable_option: Option<BillableOption>,
+}
+
+/// How [Timer::stop] makes the time entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimerOptions {
+    /// what the time worked is rounded to, 15 minutes by default.  Zero doesn't round
+    pub increment: Duration,
+    /// the least time entered, 15 minutes by default
+    pub minimum: Duration,
+    /// how the time is rounded, [Rounding::Nearest] by default
+    pub rounding: Rounding,
+    /// the notes of the entry
+    pub notes: Option<String>,
+    /// whether the time is billed, by default what the work type says
+    pub billable: Option<BillableOption>,
+}
+
+impl Default for TimerOptions {
+    fn default() -> Self {
+        TimerOptions {
+            increment: Duration::from_secs(15 * 60),
+            minimum: Duration::from_secs(15 * 60),
+            rounding: Rounding::Nearest,
+            notes: None,
+            billable: None,
+        }
+    }
+}
+
+/// The clock running on a ticket.  Serialize it to keep it over a restart
+///
+/// # Example
+/// ```no_run
+/// use cwmanage::{Client, Timer, TimerOptions};
+///
+/// // this example is using dotenv to load our settings from
+/// // the environment, you could also specify this manually
+/// use dotenv::dotenv;
+/// dotenv().ok();
+/// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+/// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+/// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+/// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+/// let client = Client::new(company_id, public_key, private_key, client_id).build();
+///
+/// let timer = Timer::start(123, 7);
+/// std::fs::write("timer.json", serde_json::to_string(&timer).unwrap()).unwrap();
+///
+/// // later, maybe after a restart
+/// let timer: Timer = serde_json::from_str(&std::fs::read_to_string("timer.json").unwrap()).unwrap();
+/// let options = TimerOptions {
+///     notes: Some("replaced the toner".to_string()),
+///     ..Default::default()
+/// };
+/// let entry = timer.stop(&client, &options).unwrap();
+/// println!("entered {} hours", entry.actual_hours);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timer {
+    /// the ticket the time is on
+    pub ticket_id: i64,
+    /// the member doing the work
+    pub member_id: i64,
+    /// when the clock started, in seconds since the unix epoch
+    pub started_at: u64,
+}
+
+impl Timer {
+    /// Starts the clock on `ticket_id` for `member_id`
+    pub fn start(ticket_id: i64, member_id: i64) -> Timer {
+        Timer::start_at(ticket_id, member_id, &SystemClock)
+    }
+
+    /// [Timer::start] with the time from `clock`
+    pub fn start_at(ticket_id: i64, member_id: i64, clock: &dyn Clock) -> Timer {
+        Timer {
+            ticket_id,
+            member_id,
+            started_at: unix_seconds(clock.now()),
+        }
+    }
+
+    /// How long the clock has been running
+    pub fn elapsed(&self, clock: &dyn Clock) -> Duration {
+        let now = unix_seconds(clock.now());
+        Duration::from_secs(now.saturating_sub(self.started_at))
+    }
+
+    /// Stops the clock and enters the time on the ticket, see [Timer::entry]
+    pub fn stop(&self, client: &Client, options: &TimerOptions) -> Result<TimeEntry> {
+        self.stop_at(client, options, &SystemClock)
+    }
+
+    /// [Timer::stop] with the time from `clock`
+    pub fn stop_at(
+        &self,
+        client: &Client,
+        options: &TimerOptions,
+        clock: &dyn Clock,
+    ) -> Result<TimeEntry> {
+        client.create_time_entry(&self.entry(options, clock))
+    }
+
+    /// The time entry for the clock stopped now: from the start (to the minute) for the time
+    /// worked rounded as the options say, and never less than the minimum
+    pub fn entry(&self, options: &TimerOptions, clock: &dyn Clock) -> NewTimeEntry {
+        let worked = round(self.elapsed(clock), options.increment, options.rounding);
+        let start = self.started_at - self.started_at % 60;
+        let end = start + worked.max(options.minimum).as_secs();
+        NewTimeEntry {
+            charge_to_type: "ServiceTicket".to_string(),
+            charge_to_id: self.ticket_id,
+            member: CwRef::id(self.member_id),
+            time_start: cw_timestamp(UNIX_EPOCH + Duration::from_secs(start)),
+            time_end: cw_timestamp(UNIX_EPOCH + Duration::from_secs(end)),
+            notes: options.notes.clone(),
+            billable_option: options.billable,
+        }
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// `worked` rounded to a whole number of `increment`s
+fn round(worked: Duration, increment: Duration, rounding: Rounding) -> Duration {
+    let (worked, increment) = (worked.as_secs(), increment.as_secs());
+    if increment == 0 {
+        return Duration::from_secs(worked);
+    }
+    let increments = match rounding {
+        Rounding::Nearest => (worked + increment / 2) / increment,
+        Rounding::Up => worked.div_ceil(increment),
+        Rounding::Down => worked / increment,
+    };
+    Duration::from_secs(increments * increment)
+}
+
+impl Client {
+    /// Creates a time entry and returns it
+    pub fn create_time_entry(&self, entry: &NewTimeEntry) -> Result<TimeEntry> {
+        let created = self.post(TIME_ENTRIES_PATH, serde_json::to_string(entry)?)?;
+        self.decode(TIME_ENTRIES_PATH, created)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+    use std::cell::Cell;
+
+    /// 2022-07-14T15:00:20Z
+    const START: u64 = 1_657_810_820;
+
+    /// a clock that is wherever the test puts it
+    struct TestClock(Cell<u64>);
+
+    impl TestClock {
+        fn after(&self, minutes: u64, seconds: u64) {
+            self.0.set(START + minutes * 60 + seconds);
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(self.0.get())
+        }
+    }
+
+    #[test]
+    fn test_timer_rounding() {
+        let clock = TestClock(Cell::new(START));
+        let timer = Timer::start_at(123, 7, &clock);
+        let options = TimerOptions::default();
+        let end = |minutes, seconds, options: &TimerOptions| {
+            clock.after(minutes, seconds);
+            timer.entry(options, &clock).time_end
+        };
+
+        assert_eq!(
+            timer.entry(&options, &clock).time_start,
+            "2022-07-14T15:00:00Z"
+        );
+        // never less than the minimum
+        assert_eq!(end(0, 0, &options), "2022-07-14T15:15:00Z");
+        assert_eq!(end(3, 0, &options), "2022-07-14T15:15:00Z");
+        // to the nearest 15 minutes
+        assert_eq!(end(22, 29, &options), "2022-07-14T15:15:00Z");
+        assert_eq!(end(22, 30, &options), "2022-07-14T15:30:00Z");
+        assert_eq!(end(95, 0, &options), "2022-07-14T16:30:00Z");
+
+        let up = TimerOptions {
+            rounding: Rounding::Up,
+            ..Default::default()
+        };
+        assert_eq!(end(31, 0, &up), "2022-07-14T15:45:00Z");
+        let down = TimerOptions {
+            rounding: Rounding::Down,
+            minimum: Duration::from_secs(0),
+            ..Default::default()
+        };
+        assert_eq!(end(14, 0, &down), "2022-07-14T15:00:00Z");
+        let exact = TimerOptions {
+            increment: Duration::from_secs(0),
+            minimum: Duration::from_secs(60),
+            ..Default::default()
+        };
+        assert_eq!(end(7, 10, &exact), "2022-07-14T15:07:10Z");
+    }
+
+    #[test]
+    fn test_timer_stop() {
+        let mut server = mockito::Server::new();
+        let created = server
+            .mock("POST", "/v4_6_release/apis/3.0/time/entries")
+            .match_body(Matcher::Json(json!({
+                "chargeToType": "ServiceTicket",
+                "chargeToId": 123,
+                "member": {"id": 7},
+                "timeStart": "2022-07-14T15:00:00Z",
+                "timeEnd": "2022-07-14T15:45:00Z",
+                "notes": "replaced the toner",
+                "billableOption": "DoNotBill"
+            })))
+            .with_body(
+                r#"{"id": 88, "member": {"id": 7}, "timeStart": "2022-07-14T15:00:00Z",
+                "timeEnd": "2022-07-14T15:45:00Z", "actualHours": 0.75}"#,
+            )
+            .expect(1)
+            .create();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .build();
+        let clock = TestClock(Cell::new(START));
+
+        // kept over a restart
+        let timer = Timer::start_at(123, 7, &clock);
+        let saved = serde_json::to_string(&timer).unwrap();
+        assert_eq!(
+            saved,
+            r#"{"ticketId":123,"memberId":7,"startedAt":1657810820}"#
+        );
+        let timer: Timer = serde_json::from_str(&saved).unwrap();
+
+        clock.after(41, 0);
+        let options = TimerOptions {
+            notes: Some("replaced the toner".to_string()),
+            billable: Some(BillableOption::DoNotBill),
+            ..Default::default()
+        };
+        let entry = timer.stop_at(&client, &options, &clock).unwrap();
+
+        created.assert();
+        assert_eq!(entry.id, 88);
+        assert_eq!(entry.actual_hours, 0.75);
+    }
+}
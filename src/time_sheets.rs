@@ -0,0 +1,210 @@
+//! Time sheets (`/time/sheets`).  See [Client::list_time_sheets]
+use crate::{Client, CwRef};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const SHEETS_PATH: &str = "/time/sheets";
+
+/// A member's time sheet for one period
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeSheet {
+    /// the id of the time sheet
+    pub id: i64,
+    /// whose time sheet it is
+    pub member: CwRef,
+    /// the year of the period
+    #[serde(default)]
+    pub year: i64,
+    /// the number of the period in the year
+    #[serde(default)]
+    pub period: i64,
+    /// the first day of the period (`2022-07-01T00:00:00Z`)
+    #[serde(default)]
+    pub date_start: String,
+    /// the last day of the period
+    #[serde(default)]
+    pub date_end: String,
+    /// where the sheet is (`Open`, `Submitted`, `Approved`, `Rejected`, ...)
+    #[serde(default)]
+    pub status: String,
+    /// the total hours entered
+    #[serde(default)]
+    pub hours: f64,
+    /// when the sheet is due
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<String>,
+}
+
+#[cfg(feature = "chrono")]
+impl TimeSheet {
+    /// [TimeSheet::date_start] as a date, see [crate::dates].  `None` when it isn't set
+    pub fn date_start_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(Some(&self.date_start))
+    }
+
+    /// [TimeSheet::date_end] as a date, see [crate::dates].  `None` when it isn't set
+    pub fn date_end_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(Some(&self.date_end))
+    }
+
+    /// [TimeSheet::deadline] as a date, see [crate::dates].  `None` when it isn't set
+    pub fn deadline_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        crate::dates::parse_field(self.deadline.as_deref())
+    }
+
+    /// The `conditions` for the time sheets whose period overlaps `start` to `end`
+    /// (inclusive)
+    ///
+    /// ```
+    /// use chrono::NaiveDate;
+    /// use cwmanage::TimeSheet;
+    ///
+    /// let start = NaiveDate::from_ymd_opt(2022, 7, 1).unwrap();
+    /// let end = NaiveDate::from_ymd_opt(2022, 7, 31).unwrap();
+    /// assert_eq!(
+    ///     TimeSheet::period_conditions(start, end),
+    ///     "dateStart <= [2022-07-31T00:00:00Z] AND dateEnd >= [2022-07-01T00:00:00Z]"
+    /// );
+    /// ```
+    pub fn period_conditions(start: chrono::NaiveDate, end: chrono::NaiveDate) -> String {
+        let day = |d: chrono::NaiveDate| {
+            let midnight = d.and_time(chrono::NaiveTime::MIN).and_utc();
+            crate::date_value(&midnight)
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        };
+        format!(
+            "dateStart <= [{}] AND dateEnd >= [{}]",
+            day(end),
+            day(start)
+        )
+    }
+}
+
+impl Client {
+    /// Gets the time sheets, `query` is passed on as with [Client::get]
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cwmanage::Client;
+    ///
+    /// // this example is using dotenv to load our settings from
+    /// // the environment, you could also specify this manually
+    /// use dotenv::dotenv;
+    /// dotenv().ok();
+    /// let company_id: String = dotenv::var("CWMANAGE_COMPANY_ID").unwrap();
+    /// let public_key: String = dotenv::var("CWMANAGE_PUBLIC_KEY").unwrap();
+    /// let private_key: String = dotenv::var("CWMANAGE_PRIVATE_KEY").unwrap();
+    /// let client_id: String = dotenv::var("CWMANAGE_CLIENT_ID").unwrap();
+    /// let client = Client::new(company_id, public_key, private_key, client_id).build();
+    ///
+    /// let query = [("conditions", "member/identifier = 'zpeters' AND year = 2022")];
+    /// for sheet in client.list_time_sheets(&query).unwrap() {
+    ///     println!("period {}: {} hours ({})", sheet.period, sheet.hours, sheet.status);
+    /// }
+    /// ```
+    pub fn list_time_sheets(&self, query: &[(&str, &str)]) -> Result<Vec<TimeSheet>> {
+        self.get_decoded(SHEETS_PATH, query)
+    }
+
+    /// Gets one time sheet
+    pub fn get_time_sheet(&self, id: i64) -> Result<TimeSheet> {
+        let path = format!("{}/{}", SHEETS_PATH, id);
+        self.decode(&path, self.get_single(&path, &[])?)
+    }
+
+    /// Submits a time sheet for approval and returns it.  When the member the client
+    /// authenticates as can't submit it the error is [crate::Error::Forbidden], any other
+    /// refusal is [crate::Error::Api]
+    pub fn submit_time_sheet(&self, id: i64) -> Result<TimeSheet> {
+        self.time_sheet_action(id, "submit")
+    }
+
+    /// Approves a submitted time sheet and returns it, the errors are the same as
+    /// [Client::submit_time_sheet]
+    pub fn approve_time_sheet(&self, id: i64) -> Result<TimeSheet> {
+        self.time_sheet_action(id, "approve")
+    }
+
+    fn time_sheet_action(&self, id: i64, action: &str) -> Result<TimeSheet> {
+        let path = format!("{}/{}/{}", SHEETS_PATH, id, action);
+        let sheet = self.write_checked(reqwest::Method::POST, &path, None)?;
+        self.decode(&path, sheet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::MockCw;
+    use crate::Error;
+    use mockito::Matcher;
+    use pretty_assertions::assert_eq;
+
+    const SHEET: &str = r#"{"id": 7, "member": {"id": 1, "identifier": "zpeters"},
+        "year": 2022, "period": 14, "dateStart": "2022-07-04T00:00:00Z",
+        "dateEnd": "2022-07-10T00:00:00Z", "status": "Open", "hours": 38.5}"#;
+
+    #[test]
+    fn test_time_sheets() {
+        let mut mock = MockCw::new();
+        let _list = mock
+            .server()
+            .mock("GET", "/v4_6_release/apis/3.0/time/sheets")
+            .match_query(Matcher::UrlEncoded(
+                "conditions".to_string(),
+                "year = 2022".to_string(),
+            ))
+            .with_body(format!("[{}]", SHEET))
+            .create();
+        let submit = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/sheets/7/submit")
+            .with_body(SHEET.replace("Open", "Submitted"))
+            .create();
+        let client = mock.client();
+
+        let sheets = client
+            .list_time_sheets(&[("conditions", "year = 2022")])
+            .unwrap();
+        let submitted = client.submit_time_sheet(7).unwrap();
+
+        submit.assert();
+        assert_eq!(sheets[0].member.identifier.as_deref(), Some("zpeters"));
+        assert_eq!(sheets[0].hours, 38.5);
+        assert_eq!(submitted.status, "Submitted");
+    }
+
+    #[test]
+    fn test_approve_forbidden() {
+        let mut mock = MockCw::new();
+        let _approve = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/sheets/7/approve")
+            .with_status(403)
+            .with_body(r#"{"code": "Forbidden", "message": "you are not an approver"}"#)
+            .create();
+        let _submit = mock
+            .server()
+            .mock("POST", "/v4_6_release/apis/3.0/time/sheets/7/submit")
+            .with_status(400)
+            .with_body(r#"{"code": "InvalidObject", "message": "already submitted"}"#)
+            .create();
+        let client = mock.client();
+
+        let err = client.approve_time_sheet(7).unwrap_err();
+        match err.downcast_ref::<Error>() {
+            Some(Error::Forbidden { path, error }) => {
+                assert_eq!(path, "/time/sheets/7/approve");
+                assert_eq!(error.message, "you are not an approver");
+            }
+            other => panic!("expected forbidden, got {:?}", other),
+        }
+        let err = client.submit_time_sheet(7).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::Api { status: 400, error, .. }) if error.code == "InvalidObject"
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+//! Mapping the windows time zone names connectwise reports (`serverTimeZone`) to IANA zones.
+//! The table is the CLDR `windowsZones` mapping for the names Manage offers
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+const WINDOWS_ZONES: &[(&str, Tz)] = &[
+    ("Dateline Standard Time", Tz::Etc__GMTPlus12),
+    ("UTC-11", Tz::Etc__GMTPlus11),
+    ("Aleutian Standard Time", Tz::America__Adak),
+    ("Hawaiian Standard Time", Tz::Pacific__Honolulu),
+    ("Marquesas Standard Time", Tz::Pacific__Marquesas),
+    ("Alaskan Standard Time", Tz::America__Anchorage),
+    ("UTC-09", Tz::Etc__GMTPlus9),
+    ("Pacific Standard Time (Mexico)", Tz::America__Tijuana),
+    ("UTC-08", Tz::Etc__GMTPlus8),
+    ("Pacific Standard Time", Tz::America__Los_Angeles),
+    ("US Mountain Standard Time", Tz::America__Phoenix),
+    ("Mountain Standard Time (Mexico)", Tz::America__Mazatlan),
+    ("Mountain Standard Time", Tz::America__Denver),
+    ("Yukon Standard Time", Tz::America__Whitehorse),
+    ("Central America Standard Time", Tz::America__Guatemala),
+    ("Central Standard Time", Tz::America__Chicago),
+    ("Easter Island Standard Time", Tz::Pacific__Easter),
+    ("Central Standard Time (Mexico)", Tz::America__Mexico_City),
+    ("Canada Central Standard Time", Tz::America__Regina),
+    ("SA Pacific Standard Time", Tz::America__Bogota),
+    ("Eastern Standard Time (Mexico)", Tz::America__Cancun),
+    ("Eastern Standard Time", Tz::America__New_York),
+    ("Haiti Standard Time", Tz::America__PortauPrince),
+    ("Cuba Standard Time", Tz::America__Havana),
+    (
+        "US Eastern Standard Time",
+        Tz::America__Indiana__Indianapolis,
+    ),
+    ("Turks And Caicos Standard Time", Tz::America__Grand_Turk),
+    ("Paraguay Standard Time", Tz::America__Asuncion),
+    ("Atlantic Standard Time", Tz::America__Halifax),
+    ("Venezuela Standard Time", Tz::America__Caracas),
+    ("Central Brazilian Standard Time", Tz::America__Cuiaba),
+    ("SA Western Standard Time", Tz::America__La_Paz),
+    ("Pacific SA Standard Time", Tz::America__Santiago),
+    ("Newfoundland Standard Time", Tz::America__St_Johns),
+    ("Tocantins Standard Time", Tz::America__Araguaina),
+    ("E. South America Standard Time", Tz::America__Sao_Paulo),
+    ("SA Eastern Standard Time", Tz::America__Cayenne),
+    (
+        "Argentina Standard Time",
+        Tz::America__Argentina__Buenos_Aires,
+    ),
+    ("Greenland Standard Time", Tz::America__Godthab),
+    ("Montevideo Standard Time", Tz::America__Montevideo),
+    ("Magallanes Standard Time", Tz::America__Punta_Arenas),
+    ("Saint Pierre Standard Time", Tz::America__Miquelon),
+    ("Bahia Standard Time", Tz::America__Bahia),
+    ("UTC-02", Tz::Etc__GMTPlus2),
+    ("Mid-Atlantic Standard Time", Tz::Etc__GMTPlus2),
+    ("Azores Standard Time", Tz::Atlantic__Azores),
+    ("Cape Verde Standard Time", Tz::Atlantic__Cape_Verde),
+    ("UTC", Tz::Etc__UTC),
+    ("Coordinated Universal Time", Tz::Etc__UTC),
+    ("GMT Standard Time", Tz::Europe__London),
+    ("Greenwich Standard Time", Tz::Atlantic__Reykjavik),
+    ("Sao Tome Standard Time", Tz::Africa__Sao_Tome),
+    ("Morocco Standard Time", Tz::Africa__Casablanca),
+    ("W. Europe Standard Time", Tz::Europe__Berlin),
+    ("Central Europe Standard Time", Tz::Europe__Budapest),
+    ("Romance Standard Time", Tz::Europe__Paris),
+    ("Central European Standard Time", Tz::Europe__Warsaw),
+    ("W. Central Africa Standard Time", Tz::Africa__Lagos),
+    ("Jordan Standard Time", Tz::Asia__Amman),
+    ("GTB Standard Time", Tz::Europe__Bucharest),
+    ("Middle East Standard Time", Tz::Asia__Beirut),
+    ("Egypt Standard Time", Tz::Africa__Cairo),
+    ("E. Europe Standard Time", Tz::Europe__Chisinau),
+    ("Syria Standard Time", Tz::Asia__Damascus),
+    ("West Bank Standard Time", Tz::Asia__Hebron),
+    ("South Africa Standard Time", Tz::Africa__Johannesburg),
+    ("FLE Standard Time", Tz::Europe__Kiev),
+    ("Israel Standard Time", Tz::Asia__Jerusalem),
+    ("South Sudan Standard Time", Tz::Africa__Juba),
+    ("Kaliningrad Standard Time", Tz::Europe__Kaliningrad),
+    ("Sudan Standard Time", Tz::Africa__Khartoum),
+    ("Libya Standard Time", Tz::Africa__Tripoli),
+    ("Namibia Standard Time", Tz::Africa__Windhoek),
+    ("Arabic Standard Time", Tz::Asia__Baghdad),
+    ("Turkey Standard Time", Tz::Europe__Istanbul),
+    ("Arab Standard Time", Tz::Asia__Riyadh),
+    ("Belarus Standard Time", Tz::Europe__Minsk),
+    ("Russian Standard Time", Tz::Europe__Moscow),
+    ("E. Africa Standard Time", Tz::Africa__Nairobi),
+    ("Volgograd Standard Time", Tz::Europe__Volgograd),
+    ("Iran Standard Time", Tz::Asia__Tehran),
+    ("Arabian Standard Time", Tz::Asia__Dubai),
+    ("Astrakhan Standard Time", Tz::Europe__Astrakhan),
+    ("Azerbaijan Standard Time", Tz::Asia__Baku),
+    ("Russia Time Zone 3", Tz::Europe__Samara),
+    ("Mauritius Standard Time", Tz::Indian__Mauritius),
+    ("Saratov Standard Time", Tz::Europe__Saratov),
+    ("Georgian Standard Time", Tz::Asia__Tbilisi),
+    ("Caucasus Standard Time", Tz::Asia__Yerevan),
+    ("Afghanistan Standard Time", Tz::Asia__Kabul),
+    ("West Asia Standard Time", Tz::Asia__Tashkent),
+    ("Ekaterinburg Standard Time", Tz::Asia__Yekaterinburg),
+    ("Pakistan Standard Time", Tz::Asia__Karachi),
+    ("Qyzylorda Standard Time", Tz::Asia__Qyzylorda),
+    ("India Standard Time", Tz::Asia__Kolkata),
+    ("Sri Lanka Standard Time", Tz::Asia__Colombo),
+    ("Nepal Standard Time", Tz::Asia__Kathmandu),
+    ("Central Asia Standard Time", Tz::Asia__Almaty),
+    ("Bangladesh Standard Time", Tz::Asia__Dhaka),
+    ("Omsk Standard Time", Tz::Asia__Omsk),
+    ("Myanmar Standard Time", Tz::Asia__Yangon),
+    ("SE Asia Standard Time", Tz::Asia__Bangkok),
+    ("Altai Standard Time", Tz::Asia__Barnaul),
+    ("W. Mongolia Standard Time", Tz::Asia__Hovd),
+    ("North Asia Standard Time", Tz::Asia__Krasnoyarsk),
+    ("N. Central Asia Standard Time", Tz::Asia__Novosibirsk),
+    ("Tomsk Standard Time", Tz::Asia__Tomsk),
+    ("China Standard Time", Tz::Asia__Shanghai),
+    ("North Asia East Standard Time", Tz::Asia__Irkutsk),
+    ("Singapore Standard Time", Tz::Asia__Singapore),
+    ("W. Australia Standard Time", Tz::Australia__Perth),
+    ("Taipei Standard Time", Tz::Asia__Taipei),
+    ("Ulaanbaatar Standard Time", Tz::Asia__Ulaanbaatar),
+    ("Aus Central W. Standard Time", Tz::Australia__Eucla),
+    ("Transbaikal Standard Time", Tz::Asia__Chita),
+    ("Tokyo Standard Time", Tz::Asia__Tokyo),
+    ("North Korea Standard Time", Tz::Asia__Pyongyang),
+    ("Korea Standard Time", Tz::Asia__Seoul),
+    ("Yakutsk Standard Time", Tz::Asia__Yakutsk),
+    ("Cen. Australia Standard Time", Tz::Australia__Adelaide),
+    ("AUS Central Standard Time", Tz::Australia__Darwin),
+    ("E. Australia Standard Time", Tz::Australia__Brisbane),
+    ("AUS Eastern Standard Time", Tz::Australia__Sydney),
+    ("West Pacific Standard Time", Tz::Pacific__Port_Moresby),
+    ("Tasmania Standard Time", Tz::Australia__Hobart),
+    ("Vladivostok Standard Time", Tz::Asia__Vladivostok),
+    ("Lord Howe Standard Time", Tz::Australia__Lord_Howe),
+    ("Bougainville Standard Time", Tz::Pacific__Bougainville),
+    ("Russia Time Zone 10", Tz::Asia__Srednekolymsk),
+    ("Magadan Standard Time", Tz::Asia__Magadan),
+    ("Norfolk Standard Time", Tz::Pacific__Norfolk),
+    ("Sakhalin Standard Time", Tz::Asia__Sakhalin),
+    ("Central Pacific Standard Time", Tz::Pacific__Guadalcanal),
+    ("Russia Time Zone 11", Tz::Asia__Kamchatka),
+    ("New Zealand Standard Time", Tz::Pacific__Auckland),
+    ("UTC+12", Tz::Etc__GMTMinus12),
+    ("Fiji Standard Time", Tz::Pacific__Fiji),
+    ("Chatham Islands Standard Time", Tz::Pacific__Chatham),
+    ("UTC+13", Tz::Etc__GMTMinus13),
+    ("Tonga Standard Time", Tz::Pacific__Tongatapu),
+    ("Samoa Standard Time", Tz::Pacific__Apia),
+    ("Line Islands Standard Time", Tz::Pacific__Kiritimati),
+];
+
+/// The IANA zone for a windows time zone name (case is ignored).  IANA names
+/// (`America/Chicago`) are accepted as well
+pub(crate) fn windows_to_iana(name: &str) -> Option<Tz> {
+    let name = name.trim();
+    WINDOWS_ZONES
+        .iter()
+        .find(|(windows, _)| windows.eq_ignore_ascii_case(name))
+        .map(|(_, tz)| *tz)
+        .or_else(|| name.parse().ok())
+}
+
+/// Converts a local time to utc.  Times that happen twice (when the clocks go back) are the
+/// first of the two, times that don't exist (when the clocks go forward) are read with the
+/// offset from before the change, so they land that much after it (2:30 on the morning the
+/// clocks go from 2:00 to 3:00 is 3:30)
+pub(crate) fn local_to_utc(tz: &Tz, local: &NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(local) {
+        LocalResult::Single(d) => d.with_timezone(&Utc),
+        LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+        LocalResult::None => {
+            // no zone moves its clocks by more than a few hours
+            let before = tz
+                .offset_from_local_datetime(&(*local - Duration::hours(6)))
+                .earliest()
+                .unwrap_or_else(|| tz.offset_from_utc_datetime(local));
+            Utc.from_utc_datetime(&(*local - before.fix()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&local(y, m, d, h, min))
+    }
+
+    #[test]
+    fn test_windows_to_iana() {
+        assert_eq!(
+            windows_to_iana("Eastern Standard Time"),
+            Some(Tz::America__New_York)
+        );
+        assert_eq!(
+            windows_to_iana(" central standard time "),
+            Some(Tz::America__Chicago)
+        );
+        assert_eq!(
+            windows_to_iana("Australia/Sydney"),
+            Some(Tz::Australia__Sydney)
+        );
+        assert_eq!(windows_to_iana("Mars Standard Time"), None);
+    }
+
+    #[test]
+    fn test_local_to_utc() {
+        let tz = Tz::America__New_York;
+        // winter and summer
+        assert_eq!(
+            local_to_utc(&tz, &local(2022, 1, 10, 9, 0)),
+            utc(2022, 1, 10, 14, 0)
+        );
+        assert_eq!(
+            local_to_utc(&tz, &local(2022, 7, 10, 9, 0)),
+            utc(2022, 7, 10, 13, 0)
+        );
+        // 1:30 happens twice on 2022-11-06, the first (EDT) is used
+        assert_eq!(
+            local_to_utc(&tz, &local(2022, 11, 6, 1, 30)),
+            utc(2022, 11, 6, 5, 30)
+        );
+        // 2:30 doesn't exist on 2022-03-13, read as EST it is 3:30 EDT
+        assert_eq!(
+            local_to_utc(&tz, &local(2022, 3, 13, 2, 30)),
+            utc(2022, 3, 13, 7, 30)
+        );
+    }
+}
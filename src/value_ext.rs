@@ -0,0 +1,127 @@
+//! Helpers for pulling values out of the untyped [Value] results without a chain of
+//! `as_str().unwrap_or_default()`.  See [CwValueExt]
+use serde_json::Value;
+
+/// Typed getters for the shapes connectwise returns.  Every getter returns `None` when the key
+/// is missing or holds the wrong type
+///
+/// ```
+/// use cwmanage::CwValueExt;
+/// use serde_json::json;
+///
+/// let ticket = json!({
+///     "id": 123,
+///     "summary": "printer on fire",
+///     "status": {"id": 16, "name": "Closed", "_info": {}}
+/// });
+///
+/// assert_eq!(ticket.get_i64("id"), Some(123));
+/// assert_eq!(ticket.get_str("summary"), Some("printer on fire"));
+/// assert_eq!(ticket.ref_id("status"), Some(16));
+/// assert_eq!(ticket.ref_name("status"), Some("Closed"));
+/// assert_eq!(ticket.ref_name("board"), None);
+/// ```
+pub trait CwValueExt {
+    /// The string value of `key`
+    fn get_str(&self, key: &str) -> Option<&str>;
+    /// The integer value of `key`
+    fn get_i64(&self, key: &str) -> Option<i64>;
+    /// The `id` of the reference object (`{id, name, _info}`) in `key`
+    fn ref_id(&self, key: &str) -> Option<i64>;
+    /// The `name` of the reference object (`{id, name, _info}`) in `key`
+    fn ref_name(&self, key: &str) -> Option<&str>;
+    /// The named href from the `_info` map, `info_href("board")` finds `board_href`.
+    /// Use with [crate::Client::follow_link]
+    fn info_href(&self, name: &str) -> Option<&str>;
+    /// The date value of `key`, parsed from connectwise's ISO 8601 format
+    #[cfg(feature = "chrono")]
+    fn get_date(&self, key: &str) -> Option<chrono::DateTime<chrono::Utc>>;
+}
+
+impl CwValueExt for Value {
+    fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key)?.as_str()
+    }
+
+    fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key)?.as_i64()
+    }
+
+    fn ref_id(&self, key: &str) -> Option<i64> {
+        self.get(key)?.get_i64("id")
+    }
+
+    fn ref_name(&self, key: &str) -> Option<&str> {
+        self.get(key)?.get_str("name")
+    }
+
+    fn info_href(&self, name: &str) -> Option<&str> {
+        let info = self.get("_info")?;
+        match name.ends_with("_href") {
+            true => info.get_str(name),
+            false => info.get_str(&format!("{}_href", name)),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    fn get_date(&self, key: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::parse_from_rfc3339(self.get_str(key)?)
+            .ok()
+            .map(|d| d.with_timezone(&chrono::Utc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    #[test]
+    fn test_wrong_types() {
+        let ticket = json!({"id": "123", "summary": 5, "status": "Closed"});
+
+        assert_eq!(ticket.get_i64("id"), None);
+        assert_eq!(ticket.get_str("summary"), None);
+        assert_eq!(ticket.ref_id("status"), None);
+        assert_eq!(ticket.ref_name("status"), None);
+    }
+
+    #[test]
+    fn test_not_an_object() {
+        let v = json!([1, 2, 3]);
+
+        assert_eq!(v.get_i64("id"), None);
+        assert_eq!(v.ref_name("status"), None);
+    }
+
+    #[test]
+    fn test_info_href() {
+        let href = "https://na.myconnectwise.net/v4_6_release/apis/3.0/service/boards/1";
+        let ticket =
+            json!({"board": {"id": 1, "name": "Help Desk", "_info": {"board_href": href}}});
+
+        assert_eq!(ticket["board"].info_href("board"), Some(href));
+        assert_eq!(ticket["board"].info_href("board_href"), Some(href));
+        assert_eq!(ticket["board"].info_href("status"), None);
+        assert_eq!(ticket.info_href("board"), None);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_get_date() {
+        use chrono::TimeZone;
+        let ticket = json!({"_info": {}, "lastUpdated": "2022-07-14T15:34:22Z", "bad": "today"});
+
+        assert_eq!(
+            ticket.get_date("lastUpdated"),
+            Some(
+                chrono::Utc
+                    .with_ymd_and_hms(2022, 7, 14, 15, 34, 22)
+                    .unwrap()
+            )
+        );
+        assert_eq!(ticket.get_date("bad"), None);
+        assert_eq!(ticket.get_date("missing"), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Deprecation and sunset warnings sent with responses, so an endpoint going away is noticed
+//! before it breaks.  See [Client::on_warning]
+use crate::{Client, RawResponse};
+use reqwest::header::HeaderMap;
+
+/// Gets each [ApiWarning], see [Client::on_warning]
+pub type WarningHandler = dyn Fn(&ApiWarning) + Send + Sync;
+
+/// A response said the endpoint is deprecated or going away
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiWarning {
+    /// the request method (`GET`, `POST`, ...)
+    pub method: String,
+    /// the path that was requested
+    pub path: String,
+    /// the `Deprecation` header, `true` or when it was deprecated
+    pub deprecation: Option<String>,
+    /// the `Sunset` header, when the endpoint stops working (an http date)
+    pub sunset: Option<String>,
+    /// the text of the `Warning` headers
+    pub messages: Vec<String>,
+    /// the `Link` with the details (`rel="deprecation"` or `rel="sunset"`)
+    pub link: Option<String>,
+}
+
+impl ApiWarning {
+    /// the warning in a response, `None` when there isn't one
+    pub(crate) fn from_headers(
+        method: &str,
+        path: &str,
+        headers: &HeaderMap,
+    ) -> Option<ApiWarning> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.trim().to_string())
+        };
+        let messages: Vec<String> = headers
+            .get_all("warning")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .map(warning_text)
+            .collect();
+        let link = headers
+            .get_all("link")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .find(|l| l.contains("rel=\"deprecation\"") || l.contains("rel=\"sunset\""))
+            .and_then(|l| Some(l.split('<').nth(1)?.split('>').next()?.to_string()));
+
+        let warning = ApiWarning {
+            method: method.to_string(),
+            path: path.to_string(),
+            deprecation: header("deprecation"),
+            sunset: header("sunset"),
+            messages,
+            link,
+        };
+        match warning.deprecation.is_some()
+            || warning.sunset.is_some()
+            || !warning.messages.is_empty()
+        {
+            true => Some(warning),
+            false => None,
+        }
+    }
+}
+
+/// the text of a `Warning` header (`299 - "going away"`), or all of it when it isn't in that
+/// form
+fn warning_text(value: &str) -> String {
+    match (value.find('"'), value.rfind('"')) {
+        (Some(start), Some(end)) if end > start => value[start + 1..end].to_string(),
+        _ => value.trim().to_string(),
+    }
+}
+
+impl Client {
+    /// calls [Client::on_warning] and logs the warning in a response, if it has one
+    pub(crate) fn check_warnings(&self, method: &str, path: &str, res: &RawResponse) {
+        let warning = match ApiWarning::from_headers(method, path, &res.headers) {
+            Some(warning) => warning,
+            None => return,
+        };
+        tracing::warn!(
+            method,
+            path,
+            deprecation = warning.deprecation.as_deref(),
+            sunset = warning.sunset.as_deref(),
+            link = warning.link.as_deref(),
+            messages = ?warning.messages,
+            "connectwise says the endpoint is deprecated"
+        );
+        if let Some(on_warning) = &self.on_warning {
+            (on_warning.0)(&warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_on_warning() {
+        let mut server = mockito::Server::new();
+        let _old = server
+            .mock("GET", "/v4_6_release/apis/3.0/system/info")
+            .match_query(mockito::Matcher::Any)
+            .with_header("deprecation", "true")
+            .with_header("sunset", "Sat, 01 Jun 2024 00:00:00 GMT")
+            .with_header(
+                "link",
+                "<https://developer.connectwise.com/changes>; rel=\"sunset\"",
+            )
+            .with_header("warning", "299 - \"use /system/info/v2\"")
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .create();
+        let _current = server
+            .mock("GET", "/v4_6_release/apis/3.0/service/info")
+            .match_query(mockito::Matcher::Any)
+            .with_body(r#"{"version": "v2022.1"}"#)
+            .create();
+        let seen: Arc<Mutex<Vec<ApiWarning>>> = Default::default();
+        let collect = seen.clone();
+        let client = Client::new(
+            "myco".to_string(),
+            "public".to_string(),
+            "private".to_string(),
+            "clientid".to_string(),
+        )
+        .api_url(server.url())
+        .on_warning(Arc::new(move |w: &ApiWarning| {
+            collect.lock().unwrap().push(w.clone())
+        }))
+        .build();
+
+        let info = client.get_single("/system/info", &[]).unwrap();
+        client.get_single("/service/info", &[]).unwrap();
+
+        assert_eq!(info["version"], "v2022.1");
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![ApiWarning {
+                method: "GET".to_string(),
+                path: "/v4_6_release/apis/3.0/system/info".to_string(),
+                deprecation: Some("true".to_string()),
+                sunset: Some("Sat, 01 Jun 2024 00:00:00 GMT".to_string()),
+                messages: vec!["use /system/info/v2".to_string()],
+                link: Some("https://developer.connectwise.com/changes".to_string()),
+            }]
+        );
+    }
+}
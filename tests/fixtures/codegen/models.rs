@@ -0,0 +1,116 @@
+// Generated by cwmanage::codegen from the connectwise OpenAPI definitions, do not edit
+
+/// The `Company` entity
+#[derive(Debug, Clone, PartialEq, Default, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Company {
+    /// `_info`
+    #[serde(rename = "_info", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<::std::collections::HashMap<String, String>>,
+    /// `addressLine1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_line1: Option<String>,
+    /// `annualRevenue`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annual_revenue: Option<f64>,
+    /// `dateAcquired`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "cwmanage::dates::option")]
+    pub date_acquired: Option<chrono::DateTime<chrono::Utc>>,
+    /// `deletedFlag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_flag: Option<bool>,
+    /// `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// Max length: 25;
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// Max length: 50;
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// `status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<cwmanage::CwRef>,
+    /// `types`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub types: Option<Vec<cwmanage::CwRef>>,
+}
+
+/// A member of the company
+/// with a login
+#[derive(Debug, Clone, PartialEq, Default, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Member {
+    /// `_info`
+    #[serde(rename = "_info", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<::std::collections::HashMap<String, String>>,
+    /// `defaultLocation`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_location: Option<cwmanage::CwRef>,
+    /// `firstName`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_name: Option<String>,
+    /// `hireDate`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "cwmanage::dates::option")]
+    pub hire_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// `identifier`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    /// `inactiveFlag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inactive_flag: Option<bool>,
+    /// `lastName`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_name: Option<String>,
+    /// `mobileGuid`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mobile_guid: Option<String>,
+    /// `officeEmail`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub office_email: Option<String>,
+}
+
+/// The `Ticket` entity
+#[derive(Debug, Clone, PartialEq, Default, ::serde::Serialize, ::serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticket {
+    /// `_info`
+    #[serde(rename = "_info", default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<::std::collections::HashMap<String, String>>,
+    /// `board`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<cwmanage::CwRef>,
+    /// `budgetHours`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_hours: Option<f64>,
+    /// `closedFlag`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_flag: Option<bool>,
+    /// `company`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub company: Option<cwmanage::CwRef>,
+    /// `customFields`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_fields: Option<Vec<::serde_json::Value>>,
+    /// `dateEntered`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "cwmanage::dates::option")]
+    pub date_entered: Option<chrono::DateTime<chrono::Utc>>,
+    /// `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    /// `recordType`, one of `ProjectIssue`, `ProjectTicket`, `ServiceTicket`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_type: Option<String>,
+    /// `status`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<cwmanage::CwRef>,
+    /// Max length: 100;
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// `type`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<cwmanage::CwRef>,
+}